@@ -73,6 +73,10 @@ async fn main(spawner: Spawner) {
         uart1: peripherals.UART1,
         uart1_rx: peripherals.GPIO1,
         uart1_tx: peripherals.GPIO10,
+        i2c0: peripherals.I2C0,
+        i2c0_sda: peripherals.GPIO3,
+        i2c0_scl: peripherals.GPIO0,
+        microphone: None,
     };
 
     let altruist = Altruist::new(hardware).await;
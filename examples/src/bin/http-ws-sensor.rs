@@ -0,0 +1,120 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! ROHI example that streams Altruist sensor readings to WebSocket clients
+//! and exposes them for Prometheus.
+//!
+//! Connect to `hello_rohi_net` access point and open `ws://192.168.42.1/ws`,
+//! metrics are available on `http://192.168.42.1/metrics`.
+#![no_std]
+#![no_main]
+#![deny(
+    clippy::mem_forget,
+    reason = "mem::forget is generally not safe to do with esp_hal types, especially those \
+    holding buffers for the duration of a data transfer."
+)]
+
+use embassy_executor::Spawner;
+use embassy_time::{Instant, Timer};
+use esp_hal::clock::CpuClock;
+use esp_hal::interrupt::software::SoftwareInterruptControl;
+use esp_hal::timer::timg::TimerGroup;
+use heapless::String;
+use log::info;
+
+use rohi_hal::{
+    board::{Altruist, altruist},
+    sensor::*,
+};
+use rohi_net::http::{
+    HttpServer, Metrics, MetricsHandler, NotFound, Route, WsReadingsHandler, ws::ReadingsChannel,
+};
+use rohi_net::{Network, WifiConfig};
+
+use esp_backtrace as _;
+
+// This creates a default app-descriptor required by the esp-idf bootloader.
+// For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
+esp_bootloader_esp_idf::esp_app_desc!();
+
+static READINGS: ReadingsChannel = ReadingsChannel::new();
+static METRICS: Metrics = Metrics::new();
+
+#[embassy_executor::task]
+async fn measure_task(mut sensors: altruist::Sensors) {
+    let publisher = READINGS.immediate_publisher();
+    loop {
+        let mut measurement = Measurement::new(Instant::now().as_millis());
+        measurement.pm10 = sensors.pm10().await;
+        measurement.pm25 = sensors.pm25().await;
+        if measurement.pm10.is_none() {
+            METRICS.sensor_error();
+        }
+        METRICS.update(measurement);
+        publisher.publish_immediate(measurement);
+        Timer::after_secs(5).await;
+    }
+}
+
+#[embassy_executor::task]
+async fn http_task(server: HttpServer) {
+    let handler = Route::new(
+        "/ws",
+        WsReadingsHandler::new(&READINGS),
+        Route::new("/metrics", MetricsHandler::new(&METRICS), NotFound),
+    );
+    server.run(handler).await
+}
+
+#[esp_rtos::main]
+async fn main(spawner: Spawner) {
+    esp_println::logger::init_logger_from_env();
+    esp_alloc::heap_allocator!(#[unsafe(link_section = ".dram2_uninit")] size: 66320);
+
+    let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
+    let peripherals = esp_hal::init(config);
+
+    let timg0 = TimerGroup::new(peripherals.TIMG0);
+    #[cfg(target_arch = "riscv32")]
+    let sw_int = SoftwareInterruptControl::new(peripherals.SW_INTERRUPT);
+    esp_rtos::start(
+        timg0.timer0,
+        #[cfg(target_arch = "riscv32")]
+        sw_int.software_interrupt0,
+    );
+    info!("Embassy execution engine ready");
+
+    let hardware = altruist::Hardware {
+        uart1: peripherals.UART1,
+        uart1_rx: peripherals.GPIO1,
+        uart1_tx: peripherals.GPIO10,
+        i2c0: peripherals.I2C0,
+        i2c0_sda: peripherals.GPIO3,
+        i2c0_scl: peripherals.GPIO0,
+        microphone: None,
+    };
+    let altruist = Altruist::new(hardware).await;
+    spawner.spawn(measure_task(altruist.sensors)).ok();
+
+    let ssid: String<32> = String::try_from("hello_rohi_net").unwrap();
+    let ip = "192.168.42.1/24".parse().unwrap();
+    let wifi_config = WifiConfig::Ap { ssid, ip };
+
+    let network = Network::new(peripherals.WIFI);
+    let stack = network.start_wifi(wifi_config, &spawner);
+    spawner.spawn(http_task(HttpServer::new(stack, 80))).ok();
+}
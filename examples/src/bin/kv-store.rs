@@ -0,0 +1,92 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! ROHI example of persistent key-value store, counts device boots.
+//!
+//! Store lives in `config` partition, flash example with firmware partition table:
+//! `cargo run --bin example-kv-store -- --partition-table ../firmwares/altruist-sensors-social/partitions.csv`
+#![no_std]
+#![no_main]
+#![deny(
+    clippy::mem_forget,
+    reason = "mem::forget is generally not safe to do with esp_hal types, especially those \
+    holding buffers for the duration of a data transfer."
+)]
+
+use log::{info, warn};
+
+use embassy_executor::Spawner;
+use esp_hal::clock::CpuClock;
+use esp_hal::interrupt::software::SoftwareInterruptControl;
+use esp_hal::timer::timg::TimerGroup;
+use heapless::String;
+use static_cell::StaticCell;
+
+use rohi_hal::flash::{Flash, Partition, SharedFlash};
+use rohi_hal::storage::{Key, KvStore};
+
+use esp_backtrace as _;
+
+// This creates a default app-descriptor required by the esp-idf bootloader.
+// For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
+esp_bootloader_esp_idf::esp_app_desc!();
+
+const BOOT_COUNT: Key<u32> = Key::new(0x100);
+const DEVICE_NAME: Key<String<32>> = Key::new(0x101);
+
+static FLASH: StaticCell<SharedFlash> = StaticCell::new();
+
+#[esp_rtos::main]
+async fn main(_spawner: Spawner) {
+    esp_println::logger::init_logger_from_env();
+
+    let config = esp_hal::Config::default().with_cpu_clock(CpuClock::max());
+    let peripherals = esp_hal::init(config);
+
+    let timg0 = TimerGroup::new(peripherals.TIMG0);
+    #[cfg(target_arch = "riscv32")]
+    let sw_int = SoftwareInterruptControl::new(peripherals.SW_INTERRUPT);
+    esp_rtos::start(
+        timg0.timer0,
+        #[cfg(target_arch = "riscv32")]
+        sw_int.software_interrupt0,
+    );
+    info!("Embassy execution engine ready");
+
+    let flash = FLASH.init(SharedFlash::new(Flash::new(peripherals.FLASH)));
+    let partition = match Partition::find(flash, "config").await {
+        Ok(partition) => partition,
+        Err(e) => {
+            warn!("No config partition: {:?}", e);
+            return;
+        }
+    };
+    info!("Config partition: {:x?}", partition.range());
+    let mut store = KvStore::new(partition);
+
+    let name = match store.get(&DEVICE_NAME).await {
+        Ok(Some(name)) => name,
+        _ => {
+            let name = String::try_from("altruist").unwrap();
+            store.set(&DEVICE_NAME, &name).await.unwrap();
+            name
+        }
+    };
+    let count = store.get(&BOOT_COUNT).await.unwrap().unwrap_or(0) + 1;
+    store.set(&BOOT_COUNT, &count).await.unwrap();
+    info!("Device {} booted {} times", name, count);
+}
@@ -1,3 +1,56 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::path::Path;
+use std::{env, fs};
+
+use flate2::{Compression, write::GzEncoder};
+
+/// Web dashboard files embedded into firmware image.
+const WEB_ASSETS: [&str; 1] = ["index.html"];
+
+/// Hex encoded ed25519 key firmware updates should be signed with.
+const OTA_PUBLIC_KEY: &str = "OTA_PUBLIC_KEY";
+
 fn main() {
     println!("cargo:rustc-link-arg=-Tlinkall.x");
+
+    let out_dir = env::var("OUT_DIR").unwrap();
+    for asset in WEB_ASSETS {
+        let source = Path::new("web").join(asset);
+        println!("cargo:rerun-if-changed={}", source.display());
+        let content = fs::read(&source).unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(&content).unwrap();
+        let gzip = encoder.finish().unwrap();
+        fs::write(Path::new(&out_dir).join(format!("{asset}.gz")), gzip).unwrap();
+
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let etag = format!("\"{:016x}\"", hasher.finish());
+        fs::write(Path::new(&out_dir).join(format!("{asset}.etag")), etag).unwrap();
+    }
+
+    // Public key is written empty when not given, firmware refuses updates then.
+    println!("cargo:rerun-if-env-changed={OTA_PUBLIC_KEY}");
+    let public_key = match env::var(OTA_PUBLIC_KEY) {
+        Ok(hex) => parse_public_key(&hex),
+        Err(_) => {
+            println!("cargo:warning={OTA_PUBLIC_KEY} is not set, firmware updates are disabled");
+            Vec::new()
+        }
+    };
+    fs::write(Path::new(&out_dir).join("ota.pub"), public_key).unwrap();
+}
+
+fn parse_public_key(hex: &str) -> Vec<u8> {
+    let hex = hex.trim();
+    if hex.len() != 64 {
+        panic!("{OTA_PUBLIC_KEY} should be 32 bytes hex encoded");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .unwrap_or_else(|_| panic!("{OTA_PUBLIC_KEY} should be 32 bytes hex encoded"))
 }
@@ -23,16 +23,47 @@
     holding buffers for the duration of a data transfer."
 )]
 
-use log::info;
+use log::{info, warn};
 
 use embassy_executor::Spawner;
-use embassy_time::Timer;
+use embassy_futures::{
+    join::join,
+    select::{Either, select},
+};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel, mutex::Mutex};
+use embassy_time::{Duration, Instant, Timer};
 use esp_hal::clock::CpuClock;
 use esp_hal::interrupt::software::SoftwareInterruptControl;
+use esp_hal::rng::Trng;
 use esp_hal::timer::timg::TimerGroup;
+use static_cell::StaticCell;
 
-use rohi_hal::board::{Altruist, altruist};
+use rohi_hal::board::{Altruist, ChipId, altruist};
+use rohi_hal::config::DeviceConfig;
+use rohi_hal::flash::{Flash, Partition, SharedFlash};
+use rohi_hal::identity::{Keypair, SEED_KEY, Scheme, Seed};
+use rohi_hal::ota::{BootStatus, Ota, UpdatePolicy};
+use rohi_hal::reset::{
+    BootButton, Command, Console, LONG_PRESS, RESET_THRESHOLD, RESET_WINDOW, ResetCounter,
+};
 use rohi_hal::sensor::*;
+use rohi_hal::storage::buffer::DrainError;
+use rohi_hal::storage::{KvStore, MeasurementBuffer};
+use rohi_net::datalog::{DatalogError, Format, SharedAggregate};
+use rohi_net::http::client::ClientError;
+use rohi_net::http::{
+    ApiHandler, Asset, AssetHandler, Authorize, HttpClient, HttpServer, Metrics, MetricsHandler,
+    OtaHandler, Route, WsReadingsHandler,
+    api::{Device, UploadStatus},
+    auth,
+    ws::ReadingsChannel,
+};
+use rohi_net::sensor_community::Service;
+use rohi_net::sensors_social::Station;
+use rohi_net::{
+    DatalogPublisher, Exporter, NETWORK_STATS, Network, RpcClient, SensorCommunityUploader,
+    SensorsSocialUploader, WALL_CLOCK, WifiConfig, sntp::sntp_task,
+};
 
 use esp_backtrace as _;
 
@@ -40,8 +71,399 @@ use esp_backtrace as _;
 // For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
 esp_bootloader_esp_idf::esp_app_desc!();
 
+/// Web dashboard compressed by build script.
+static DASHBOARD: [Asset; 1] = [Asset {
+    path: "/index.html",
+    content_type: "text/html; charset=utf-8",
+    gzip: include_bytes!(concat!(env!("OUT_DIR"), "/index.html.gz")),
+    etag: include_str!(concat!(env!("OUT_DIR"), "/index.html.etag")),
+}];
+
+/// Anti-rollback counter, increment it together with `rohi-sign --security-version`
+/// when release fixes security issue.
+const SECURITY_VERSION: u32 = 1;
+
+/// Key firmware updates are signed with, empty when not given at build time.
+static OTA_PUBLIC_KEY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/ota.pub"));
+
+/// Firmware version reported to sensors.social.
+const SOFTWARE_VERSION: &str = concat!("ROHI-altruist-", env!("CARGO_PKG_VERSION"));
+
+static READINGS: ReadingsChannel = ReadingsChannel::new();
+static METRICS: Metrics = Metrics::new();
+static AGGREGATE: SharedAggregate = Mutex::new(Aggregate::new());
+/// Averaged measurements waiting for [`datalog_task`].
+static DATALOG: Channel<CriticalSectionRawMutex, Measurement, 4> = Channel::new();
+static FLASH: StaticCell<SharedFlash> = StaticCell::new();
+static DEVICE: StaticCell<AltruistDevice> = StaticCell::new();
+
+type ConfigStore = KvStore<Partition<'static>>;
+
+/// Device state exposed through REST API.
+struct AltruistDevice {
+    config: Mutex<CriticalSectionRawMutex, DeviceConfig>,
+    store: Mutex<CriticalSectionRawMutex, Option<ConfigStore>>,
+}
+
+impl Device for AltruistDevice {
+    type Config = DeviceConfig;
+
+    fn firmware(&self) -> &str {
+        concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION"))
+    }
+
+    fn measurement(&self) -> Option<Measurement> {
+        METRICS.measurement()
+    }
+
+    async fn config(&self) -> DeviceConfig {
+        self.config.lock().await.clone()
+    }
+
+    async fn set_config(&self, config: DeviceConfig) -> Result<(), &'static str> {
+        config.validate()?;
+        if !config.network.ssid.is_empty() && config.admin_password.is_empty() {
+            return Err("device password should be set before joining WiFi network");
+        }
+        let Some(store) = &mut *self.store.lock().await else {
+            return Err("no config partition");
+        };
+        config
+            .save(store)
+            .await
+            .map_err(|_| "unable to save config")?;
+        *self.config.lock().await = config;
+        Ok(())
+    }
+
+    async fn factory_reset(&self) {
+        if let Some(store) = &mut *self.store.lock().await {
+            wipe_config(store).await;
+        }
+    }
+
+    fn uploads(&self) -> Option<UploadStatus> {
+        Some(METRICS.upload_status())
+    }
+}
+
+impl Authorize for AltruistDevice {
+    async fn authorize(&self, token: Option<&str>) -> bool {
+        let config = self.config.lock().await;
+        if config.admin_password.is_empty() {
+            // Device without password is reachable only through own access point.
+            return config.network.ssid.is_empty();
+        }
+        token.is_some_and(|token| auth::verify(token, &config.admin_password))
+    }
+}
+
+/// Wipe config store, device seed is kept so account stays the same.
+async fn wipe_config(store: &mut ConfigStore) {
+    let seed = store.get(&SEED_KEY).await.ok().flatten();
+    if let Err(e) = store.clear().await {
+        warn!("Unable to wipe config: {:?}", e);
+    }
+    if let Some(seed) = seed
+        && let Err(e) = seed.save(store).await
+    {
+        warn!("Unable to restore device seed: {:?}", e);
+    }
+}
+
+/// Device account keys, seed is generated on first start.
+async fn load_identity(device: &AltruistDevice) -> Option<Keypair> {
+    let Some(store) = &mut *device.store.lock().await else {
+        warn!("No config partition, device has no account");
+        return None;
+    };
+    // Radio is up, so hardware RNG is fed by true entropy source.
+    let mut rng = Trng::try_new().ok()?;
+    match Seed::load_or_generate(store, &mut rng).await {
+        Ok(seed) => Some(Keypair::from_seed(Scheme::Sr25519, &seed)),
+        Err(e) => {
+            warn!("Unable to load device seed: {:?}", e);
+            None
+        }
+    }
+}
+
+/// Open config store and load device configuration, factory defaults on failure.
+///
+/// Store is wiped first when device was reset several times in a row.
+async fn load_config(flash: &'static SharedFlash) -> (DeviceConfig, Option<ConfigStore>) {
+    let mut store = match Partition::find(flash, "config").await {
+        Ok(partition) => KvStore::new(partition),
+        Err(e) => {
+            warn!("No config partition: {:?}", e);
+            return (DeviceConfig::default(), None);
+        }
+    };
+    let wipe = ResetCounter::register_boot(&mut store, RESET_THRESHOLD)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Unable to count reset: {:?}", e);
+            false
+        });
+    if wipe {
+        warn!("Factory reset: wiping config");
+        wipe_config(&mut store).await;
+    }
+    let config = DeviceConfig::load(&mut store).await.unwrap_or_else(|e| {
+        warn!("Unable to load config, using defaults: {:?}", e);
+        DeviceConfig::default()
+    });
+    (config, Some(store))
+}
+
+#[embassy_executor::task]
+async fn http_task(
+    server: HttpServer,
+    device: &'static AltruistDevice,
+    flash: &'static SharedFlash,
+    client: HttpClient,
+) {
+    let handler = Route::new(
+        "/ws",
+        WsReadingsHandler::new(&READINGS),
+        Route::new(
+            "/metrics",
+            MetricsHandler::new(&METRICS),
+            Route::new(
+                "/api",
+                ApiHandler::new(device),
+                Route::new(
+                    "/ota",
+                    OtaHandler::new(flash, client, update_policy(), device),
+                    AssetHandler::new(&DASHBOARD),
+                ),
+            ),
+        ),
+    );
+    server.run(handler).await
+}
+
+/// Every upload interval send averaged measurements to sensors.social, datalog,
+/// sensor.community, Madavi and own server, datalog records are passed to [`datalog_task`].
+///
+/// sensors.social uploads go through flash buffer, so readings taken offline are
+/// delivered later. Other uploads are best effort, sensor.community doesn't accept
+/// old readings and datalog account could have no funds.
+#[embassy_executor::task]
+async fn upload_task(
+    client: HttpClient,
+    device: &'static AltruistDevice,
+    flash: &'static SharedFlash,
+    datalog: bool,
+) {
+    let mut buffer = match Partition::find(flash, "buffer").await {
+        Ok(partition) => {
+            let capacity = partition.range().len() as u32;
+            Some(MeasurementBuffer::new(partition, capacity))
+        }
+        Err(e) => {
+            warn!("No buffer partition, offline readings are lost: {:?}", e);
+            None
+        }
+    };
+    let chip_id = ChipId::read();
+    info!("Sensor ID: {}", chip_id);
+    loop {
+        let config = device.config().await;
+        Timer::after(Duration::from_secs(config.intervals.upload_secs.into())).await;
+        let config = device.config().await;
+        let Some(measurement) = AGGREGATE.lock().await.take() else {
+            continue;
+        };
+        let station = Station::new(chip_id, SOFTWARE_VERSION, config.location);
+
+        let url = &config.upload.sensors_social;
+        if !url.is_empty() {
+            let uploader = SensorsSocialUploader::new(client, url, station);
+            let time = WALL_CLOCK.unix_time(measurement.timestamp);
+            // Readings are buffered only with wall clock time, otherwise they
+            // would be delivered later as live ones.
+            let result = match (&mut buffer, time) {
+                (Some(buffer), Some(time)) => {
+                    if let Err(e) = buffer.push(time, &measurement).await {
+                        warn!("Unable to buffer readings: {:?}", e);
+                    }
+                    buffer
+                        .drain(async |r| uploader.upload(&r.measurement, Some(r.time)).await)
+                        .await
+                        .map_err(|e| match e {
+                            DrainError::Upload(e) => upload_error(&e),
+                            DrainError::Store(_) => "buffer failure",
+                        })
+                }
+                _ => uploader
+                    .upload(&measurement, time)
+                    .await
+                    .map(|_| 1)
+                    .map_err(|e| upload_error(&e)),
+            };
+            match result {
+                Ok(count) => {
+                    info!("{} readings sent to sensors.social", count);
+                    METRICS.upload(true);
+                }
+                Err(reason) => {
+                    warn!("sensors.social upload failed: {}", reason);
+                    METRICS.upload_error(reason);
+                }
+            }
+        }
+
+        let services = [
+            (config.upload.sensor_community, Service::SensorCommunity),
+            (config.upload.madavi, Service::Madavi),
+        ];
+        for (_, service) in services.into_iter().filter(|(enabled, _)| *enabled) {
+            let uploader = SensorCommunityUploader::new(client, service, chip_id, SOFTWARE_VERSION);
+            match uploader.upload(&measurement).await {
+                Ok(_) => METRICS.upload(true),
+                Err(e) => {
+                    warn!("{:?} upload failed: {:?}", service, e);
+                    METRICS.upload_error(upload_error(&e));
+                }
+            }
+        }
+
+        let exporter = Exporter::new(client, &config.export, &config.name);
+        if exporter.is_enabled() {
+            match exporter.export(&measurement).await {
+                Ok(()) => METRICS.upload(true),
+                Err(e) => {
+                    warn!("Export failed: {:?}", e);
+                    METRICS.upload_error(upload_error(&e));
+                }
+            }
+        }
+
+        if datalog && !config.upload.robonomics.is_empty() && DATALOG.try_send(measurement).is_err()
+        {
+            warn!("Datalog publishing is behind, readings dropped");
+            METRICS.upload_error("datalog publishing is behind");
+        }
+    }
+}
+
+/// Publish averaged measurements into datalog of device account.
+///
+/// Publisher is kept while node URL and station are the same, so chain parameters
+/// and nonce are not fetched for every record. Records go through `datalog` flash
+/// partition, devices updated over the air from older partition table publish
+/// readings only live.
+#[embassy_executor::task]
+async fn datalog_task(
+    client: HttpClient,
+    device: &'static AltruistDevice,
+    flash: &'static SharedFlash,
+    pair: Keypair,
+) {
+    let mut buffer = match Partition::find(flash, "datalog").await {
+        Ok(partition) => {
+            let capacity = partition.range().len() as u32;
+            Some(MeasurementBuffer::new(partition, capacity))
+        }
+        Err(e) => {
+            warn!("No datalog partition, offline records are lost: {:?}", e);
+            None
+        }
+    };
+    let chip_id = ChipId::read();
+    let mut next = None;
+    loop {
+        let config = device.config().await;
+        let url = config.upload.robonomics;
+        let format =
+            Format::SensorsSocial(Station::new(chip_id, SOFTWARE_VERSION, config.location));
+        let mut publisher =
+            DatalogPublisher::new(RpcClient::new(client, &url), pair.clone()).with_format(format);
+        loop {
+            let measurement = match next.take() {
+                Some(measurement) => measurement,
+                None => DATALOG.receive().await,
+            };
+            let config = device.config().await;
+            let station = Station::new(chip_id, SOFTWARE_VERSION, config.location);
+            if config.upload.robonomics != url || Format::SensorsSocial(station) != format {
+                // Settings changed, measurement goes to new publisher.
+                next = Some(measurement);
+                break;
+            }
+            if url.is_empty() {
+                continue;
+            }
+            let result = match &mut buffer {
+                Some(buffer) => publisher.publish(&measurement, buffer).await,
+                None => publisher
+                    .record(&measurement)
+                    .await
+                    .map(|_| 1)
+                    .map_err(DrainError::Upload),
+            };
+            match result {
+                Ok(count) => {
+                    info!("{} records published to datalog", count);
+                    METRICS.upload(true);
+                }
+                Err(DrainError::Upload(DatalogError::NoFunds)) => {
+                    warn!("Datalog account {} can't pay fees", pair.public());
+                    METRICS.upload_error("datalog account can't pay fees");
+                }
+                Err(e) => {
+                    warn!("Datalog record failed: {:?}", e);
+                    METRICS.upload_error("datalog record failed");
+                }
+            }
+        }
+    }
+}
+
+/// Short failure reason shown in device status.
+fn upload_error(e: &ClientError) -> &'static str {
+    match e {
+        ClientError::InvalidUrl => "unsupported URL, only http:// is supported",
+        ClientError::Dns => "unable to resolve host",
+        ClientError::Status(_) => "rejected by server",
+        ClientError::BufferOverflow | ClientError::TooManyHeaders => "payload is too large",
+        ClientError::Http(_) => "connection failed",
+    }
+}
+
+#[embassy_executor::task]
+async fn reset_task(mut button: BootButton, mut console: Console, device: &'static AltruistDevice) {
+    let triggers = async {
+        match select(button.wait_long_press(LONG_PRESS), console.command()).await {
+            Either::Second(Command::Reboot) => device.reboot(),
+            Either::First(_) | Either::Second(Command::FactoryReset) => {
+                warn!("Factory reset requested");
+                device.factory_reset().await;
+                device.reboot()
+            }
+        }
+    };
+    let window = async {
+        Timer::after(RESET_WINDOW).await;
+        if let Some(store) = &mut *device.store.lock().await
+            && let Err(e) = ResetCounter::clear(store).await
+        {
+            warn!("Unable to clear reset counter: {:?}", e);
+        }
+    };
+    join(window, triggers).await;
+}
+
+fn update_policy() -> Option<UpdatePolicy> {
+    Some(UpdatePolicy {
+        public_key: OTA_PUBLIC_KEY.try_into().ok()?,
+        security_version: SECURITY_VERSION,
+    })
+}
+
 #[esp_rtos::main]
-async fn main(_spawner: Spawner) {
+async fn main(spawner: Spawner) {
     esp_println::logger::init_logger_from_env();
     esp_alloc::heap_allocator!(#[unsafe(link_section = ".dram2_uninit")] size: 66320);
 
@@ -58,16 +480,114 @@ async fn main(_spawner: Spawner) {
     );
     info!("Embassy execution engine ready");
 
+    let flash = FLASH.init(SharedFlash::new(Flash::new(peripherals.FLASH)));
+    match Ota::new(&mut *flash.lock().await).boot_check() {
+        Ok(BootStatus::RolledBack) => esp_hal::system::software_reset(),
+        Ok(status) => info!("Firmware image: {:?}", status),
+        Err(e) => warn!("Unable to check firmware image: {:?}", e),
+    }
+
     let hardware = altruist::Hardware {
         uart1: peripherals.UART1,
         uart1_rx: peripherals.GPIO1,
         uart1_tx: peripherals.GPIO10,
+        i2c0: peripherals.I2C0,
+        i2c0_sda: peripherals.GPIO3,
+        i2c0_scl: peripherals.GPIO0,
+        // I2S wiring of ICS-43434 isn't described for the board yet.
+        microphone: None,
     };
 
     let mut altruist = Altruist::new(hardware).await;
 
+    let (config, store) = load_config(flash).await;
+    // Own access point is started until upstream network is configured.
+    let wifi = if config.network.ssid.is_empty() {
+        WifiConfig::Ap {
+            ssid: config.name.clone(),
+            ip: "192.168.42.1/24".parse().unwrap(),
+        }
+    } else {
+        WifiConfig::Sta {
+            ssid: config.network.ssid.clone(),
+            password: config.network.password.clone(),
+        }
+    };
+    let device = DEVICE.init(AltruistDevice {
+        config: Mutex::new(config),
+        store: Mutex::new(store),
+    });
+
+    let button = BootButton::new(peripherals.GPIO9);
+    let console = Console::new(peripherals.USB_DEVICE);
+    spawner.spawn(reset_task(button, console, device)).ok();
+
+    let network = Network::new(peripherals.WIFI);
+    let pair = load_identity(device).await;
+    if let Some(pair) = &pair {
+        info!("Robonomics account: {}", pair.public());
+    }
+    let upstream = matches!(wifi, WifiConfig::Sta { .. });
+    let stack = network.start_wifi(wifi, &spawner);
+    let client = HttpClient::new(stack);
+    spawner
+        .spawn(http_task(HttpServer::new(stack, 80), device, flash, client))
+        .ok();
+    spawner
+        .spawn(upload_task(client, device, flash, pair.is_some()))
+        .ok();
+    if let Some(pair) = pair {
+        spawner
+            .spawn(datalog_task(client, device, flash, pair))
+            .ok();
+    }
+    if upstream {
+        spawner.spawn(sntp_task(stack)).ok();
+    }
+
+    let publisher = READINGS.immediate_publisher();
+    let mut confirmed = false;
     loop {
-        info!("PM10 measure: {:?}", altruist.sensors.pm10().await);
-        Timer::after_secs(10).await;
+        let sensors = device.config.lock().await.sensors.clone();
+        let mut measurement = Measurement::new(Instant::now().as_millis());
+        if sensors.pm {
+            measurement.pm10 = altruist.sensors.pm10().await;
+            measurement.pm25 = altruist.sensors.pm25().await;
+            if measurement.pm10.is_none() {
+                METRICS.sensor_error();
+            }
+        }
+        if sensors.climate {
+            measurement.temperature = altruist
+                .sensors
+                .temperature()
+                .await
+                .map(|t| t.saturating_add(sensors.temperature_offset));
+            measurement.humidity = altruist.sensors.humidity().await;
+            measurement.pressure = altruist.sensors.pressure().await;
+            if measurement.temperature.is_none() {
+                METRICS.sensor_error();
+            }
+        }
+        if sensors.noise {
+            measurement.noise = altruist.sensors.noise().await;
+            if measurement.noise.is_none() {
+                METRICS.sensor_error();
+            }
+        }
+        info!("Measurement: {:?}", measurement);
+        METRICS.update(measurement);
+        AGGREGATE.lock().await.add(&measurement);
+        publisher.publish_immediate(measurement);
+
+        // Image is confirmed once sensors loop is up and network is proven to work.
+        if !confirmed && NETWORK_STATS.is_ready(stack) {
+            confirmed = true;
+            if let Err(e) = Ota::new(&mut *flash.lock().await).mark_healthy() {
+                warn!("Unable to confirm firmware image: {:?}", e);
+            }
+        }
+        let interval = device.config.lock().await.intervals.measure_secs;
+        Timer::after_secs(interval.into()).await;
     }
 }
@@ -18,6 +18,33 @@
 //! Robonomics Open Hardware development board collection.
 //! Device list available on https://robonomics.network/devices/
 
+use core::fmt;
+
 /// Altruist Air Quality Sensor HAL.
+#[cfg(feature = "esp32c3")]
 pub mod altruist;
+#[cfg(feature = "esp32c3")]
 pub use altruist::Altruist;
+
+/// Chip identifier derived from factory MAC address, the same as `ESP.getEfuseMac()`
+/// based ID of airrohr firmware, e.g. `7a5b3c71bf10`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChipId(pub [u8; 6]);
+
+impl ChipId {
+    /// Identifier of running chip.
+    #[cfg(feature = "esp32c3")]
+    pub fn read() -> Self {
+        Self(esp_hal::efuse::Efuse::mac_address())
+    }
+}
+
+/// Lower hex of high 16 and low 32 bits of little endian MAC, without padding.
+impl fmt::Display for ChipId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [m0, m1, m2, m3, m4, m5] = self.0;
+        let high = u16::from_le_bytes([m4, m5]);
+        let low = u32::from_le_bytes([m0, m1, m2, m3]);
+        write!(f, "{:x}{:x}", high, low)
+    }
+}
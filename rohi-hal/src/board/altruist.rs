@@ -26,14 +26,21 @@
 
 use embassy_time::Delay;
 use esp_hal::Async;
-//use esp_hal::i2c::master::I2c;
-use esp_hal::peripherals::{GPIO1, GPIO10, UART1};
+use esp_hal::gpio::AnyPin;
+use esp_hal::i2c::master::I2c;
+use esp_hal::i2s::master::{Channels, Config as I2sConfig, DataFormat, I2s, I2sRx};
+use esp_hal::peripherals::{DMA_CH0, GPIO0, GPIO1, GPIO3, GPIO10, I2C0, I2S0, UART1};
+use esp_hal::time::Rate;
 use esp_hal::uart::{self, RxConfig, Uart};
 use log::{info, warn};
 use sds011::{SDS011, sensor_state::Polling};
 
+use crate::sensor::ics43434::{SAMPLE_RATE, SoundMeter};
 use crate::sensor::*;
 
+/// Bytes of one DMA read of microphone, 10 ms of 32-bit samples.
+const MICROPHONE_BLOCK: usize = 4 * SAMPLE_RATE as usize / 100;
+
 /// Air-quality sensor board Altruist.
 ///
 /// - ESP32-C3FH4 — high-performance 32-bit single-core RISC-V CPU, up to 160 MHz
@@ -49,6 +56,20 @@ pub struct Hardware {
     pub uart1: UART1<'static>,
     pub uart1_tx: GPIO10<'static>,
     pub uart1_rx: GPIO1<'static>,
+    pub i2c0: I2C0<'static>,
+    pub i2c0_sda: GPIO3<'static>,
+    pub i2c0_scl: GPIO0<'static>,
+    /// Noise sensor, the board works without it when `None`.
+    pub microphone: Option<Microphone>,
+}
+
+/// I2S bus of ICS-43434 microphone, it is read by DMA.
+pub struct Microphone {
+    pub i2s0: I2S0<'static>,
+    pub dma: DMA_CH0<'static>,
+    pub bclk: AnyPin<'static>,
+    pub ws: AnyPin<'static>,
+    pub din: AnyPin<'static>,
 }
 
 impl Altruist {
@@ -80,17 +101,16 @@ impl Altruist {
             }
         };
 
-        /*
-        let i2c = I2c::new(peripherals.I2C0, Default::default())
+        let i2c = I2c::new(hardware.i2c0, Default::default())
             .unwrap()
-            .with_sda(peripherals.GPIO3)
-            .with_scl(peripherals.GPIO0)
+            .with_sda(hardware.i2c0_sda)
+            .with_scl(hardware.i2c0_scl)
             .into_async();
 
         // Create BME280 instance and save it in case of successful init.
-        let mut bme280_device = BME280::new_primary(i2c);
-        let bme280 = match bme280_device.init(&mut Delay).await {
-            Ok(_) => {
+        let mut bme280_device = Bme280::new(i2c);
+        let bme280 = match bme280_device.init().await {
+            Ok(()) => {
                 info!("[Altruist] BME280 init complete");
                 Some(bme280_device)
             }
@@ -99,10 +119,38 @@ impl Altruist {
                 None
             }
         };
-        */
+
+        let microphone = hardware.microphone.and_then(|microphone| {
+            let config = I2sConfig::new_tdm_philips()
+                .with_sample_rate(Rate::from_hz(SAMPLE_RATE))
+                .with_data_format(DataFormat::Data32Channel32)
+                .with_channels(Channels::MONO);
+            match I2s::new(microphone.i2s0, microphone.dma, config) {
+                Ok(i2s) => {
+                    let (descriptors, _) = esp_hal::dma_descriptors!(MICROPHONE_BLOCK, 0);
+                    info!("[Altruist] ICS-43434 init complete");
+                    Some(
+                        i2s.into_async()
+                            .i2s_rx
+                            .with_bclk(microphone.bclk)
+                            .with_ws(microphone.ws)
+                            .with_din(microphone.din)
+                            .build(descriptors),
+                    )
+                }
+                Err(e) => {
+                    warn!("[Altruist] ICS-43434 init failure: {:?}", e);
+                    None
+                }
+            }
+        });
 
         Self {
-            sensors: Sensors { sds011 },
+            sensors: Sensors {
+                sds011,
+                bme280,
+                microphone,
+            },
         }
     }
 }
@@ -117,15 +165,20 @@ impl Altruist {
 ///
 pub struct Sensors {
     sds011: Option<SDS011<Uart<'static, Async>, Polling>>,
-    // TODO: use embedded-devices implementation when it ready
-    //bme280: Option<BME280<I2c<'static, Async>>>,
+    bme280: Option<Bme280<I2c<'static, Async>>>,
+    microphone: Option<I2sRx<'static, Async>>,
 }
 
 impl ParticulateMatter for Sensors {
     async fn pm10(&mut self) -> Option<u16> {
         if let Some(sds011) = &mut self.sds011 {
-            let data = sds011.measure(&mut Delay).await.unwrap();
-            Some(data.pm10())
+            match sds011.measure(&mut Delay).await {
+                Ok(data) => Some(data.pm10()),
+                Err(e) => {
+                    warn!("[Altruist] SDS011 measure failure: {}", e);
+                    None
+                }
+            }
         } else {
             None
         }
@@ -133,35 +186,54 @@ impl ParticulateMatter for Sensors {
 
     async fn pm25(&mut self) -> Option<u16> {
         if let Some(sds011) = &mut self.sds011 {
-            let data = sds011.measure(&mut Delay).await.unwrap();
-            Some(data.pm25())
+            match sds011.measure(&mut Delay).await {
+                Ok(data) => Some(data.pm25()),
+                Err(e) => {
+                    warn!("[Altruist] SDS011 measure failure: {}", e);
+                    None
+                }
+            }
         } else {
             None
         }
     }
 }
 
-/*
-impl Temperature for Altruist {
+impl Temperature for Sensors {
     async fn temperature(&mut self) -> Option<i16> {
-        if let Some(bme280) = &mut self.bme280 {
-            let data = bme280.measure(&mut Delay).await.unwrap();
-            let temp_tenths = data.temperature * 10.0;
-            Some(temp_tenths as i16)
-        } else {
-            None
-        }
+        self.bme280.as_mut()?.temperature().await
     }
 }
 
-impl Pressure for Altruist {
+impl Humidity for Sensors {
+    async fn humidity(&mut self) -> Option<u16> {
+        self.bme280.as_mut()?.humidity().await
+    }
+}
+
+impl Pressure for Sensors {
     async fn pressure(&mut self) -> Option<u32> {
-        if let Some(bme280) = &mut self.bme280 {
-            let data = bme280.measure(&mut Delay).await.unwrap();
-            Some(data.pressure as u32)
-        } else {
-            None
+        self.bme280.as_mut()?.pressure().await
+    }
+}
+
+impl Noise for Sensors {
+    /// Equivalent level of one second.
+    async fn noise(&mut self) -> Option<u16> {
+        let microphone = self.microphone.as_mut()?;
+        let mut block = [0u8; MICROPHONE_BLOCK];
+        let mut samples = [0i32; MICROPHONE_BLOCK / 4];
+        let mut meter = SoundMeter::new();
+        while meter.count() < SAMPLE_RATE {
+            if let Err(e) = microphone.read_dma_async(&mut block).await {
+                warn!("[Altruist] ICS-43434 read failure: {:?}", e);
+                return None;
+            }
+            for (sample, bytes) in samples.iter_mut().zip(block.chunks_exact(4)) {
+                *sample = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            }
+            meter.push(&samples);
         }
+        meter.level()
     }
 }
-*/
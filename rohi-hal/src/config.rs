@@ -0,0 +1,1001 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Typed device configuration persisted in key-value store.
+//!
+//! [`DeviceConfig`] is kept in a single [`KvStore`] item, so it is always replaced
+//! atomically. Item holds schema version together with postcard encoded config
+//! of that version, older layouts are upgraded by [`migrate`] when loaded.
+//!
+//! Changing config schema:
+//! 1. copy current structs as they are into `v<N>` module, where `N` is [`CONFIG_VERSION`];
+//! 2. change structs, bump [`CONFIG_VERSION`];
+//! 3. decode `v<N>` layout in [`migrate`] and convert it into new one.
+
+use embedded_storage_async::nor_flash::MultiwriteNorFlash;
+use heapless::{String, Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::{Key, KvStore, StoreError};
+
+/// Current config schema version.
+pub const CONFIG_VERSION: u16 = 5;
+
+/// Maximal size of encoded config.
+pub const CONFIG_MAX_SIZE: usize = 896;
+
+/// Stored config: schema version and encoded config.
+pub type StoredConfig = (u16, Vec<u8, CONFIG_MAX_SIZE>);
+
+/// Key of device configuration in store.
+pub const CONFIG_KEY: Key<StoredConfig> = Key::new(1);
+
+/// Value shown to clients instead of secret which is set, stored secret is
+/// kept when client sends it back or omits secret.
+pub const SECRET_PLACEHOLDER: &str = "********";
+
+/// Configuration loading and saving errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError<E> {
+    /// Store access failure.
+    Store(StoreError<E>),
+    /// Config is written by newer firmware or damaged.
+    UnsupportedVersion(u16),
+    /// Config doesn't pass validation.
+    Invalid(&'static str),
+}
+
+impl<E> From<StoreError<E>> for ConfigError<E> {
+    fn from(e: StoreError<E>) -> Self {
+        Self::Store(e)
+    }
+}
+
+/// Device configuration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DeviceConfig {
+    /// Device name, also used as SSID of own access point.
+    pub name: String<32>,
+    pub network: NetworkConfig,
+    pub sensors: SensorsConfig,
+    pub upload: UploadConfig,
+    pub location: Location,
+    pub intervals: Intervals,
+    pub export: ExportConfig,
+    /// Password of local API changing device state and of firmware updates,
+    /// it is required to join upstream network.
+    #[serde(default = "kept_secret")]
+    pub admin_password: String<64>,
+}
+
+/// WiFi settings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct NetworkConfig {
+    /// Upstream access point, device runs own access point only when empty.
+    pub ssid: String<32>,
+    #[serde(default = "kept_secret")]
+    pub password: String<64>,
+}
+
+/// Enabled sensors and their calibration.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SensorsConfig {
+    /// Particulate matter sensor.
+    pub pm: bool,
+    /// Temperature, humidity and pressure sensor.
+    pub climate: bool,
+    /// Noise level sensor.
+    pub noise: bool,
+    /// Correction added to measured temperature in tenths of degree Celsius.
+    pub temperature_offset: i16,
+}
+
+/// Measurements upload targets.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct UploadConfig {
+    /// sensors.social gateway URL, upload is disabled when empty.
+    pub sensors_social: String<96>,
+    /// Robonomics node RPC URL for datalog records, publishing is disabled when empty.
+    pub robonomics: String<96>,
+    /// Upload to sensor.community map.
+    pub sensor_community: bool,
+    /// Upload to Madavi graphs.
+    pub madavi: bool,
+}
+
+/// Export of measurements to own server, disabled by default.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExportConfig {
+    pub kind: ExportKind,
+    /// InfluxDB base URL, e.g. `http://influx.local:8086`, or webhook endpoint.
+    pub url: String<96>,
+    /// InfluxDB v1 database or v2 bucket.
+    pub database: String<32>,
+    /// InfluxDB v2 organization.
+    pub org: String<32>,
+    /// InfluxDB v2 API token or v1 `user:password`, no authentication when empty.
+    #[serde(default = "kept_secret")]
+    pub token: String<96>,
+    /// Webhook JSON body with `{pm10}`-like placeholders, default body when empty.
+    pub template: String<192>,
+}
+
+/// Format and protocol of export.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    /// Export is disabled.
+    #[default]
+    None,
+    /// InfluxDB 1.x `/write` endpoint.
+    InfluxV1,
+    /// InfluxDB 2.x `/api/v2/write` endpoint.
+    InfluxV2,
+    /// JSON body posted to any URL.
+    Webhook,
+}
+
+/// Geographic location of device, `0, 0` when not set.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct Location {
+    /// Latitude in degrees, `-90..=90`.
+    pub latitude: f32,
+    /// Longitude in degrees, `-180..=180`.
+    pub longitude: f32,
+    /// Altitude above sea level in meters.
+    pub altitude: f32,
+}
+
+impl Location {
+    /// Location is set by user.
+    pub fn is_set(&self) -> bool {
+        self.latitude != 0.0 || self.longitude != 0.0
+    }
+}
+
+/// Periods of device activities.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Intervals {
+    /// Sensors measurement period in seconds.
+    pub measure_secs: u16,
+    /// Measurements upload period in seconds.
+    pub upload_secs: u16,
+}
+
+/// Factory defaults.
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        Self {
+            name: String::try_from("altruist").unwrap(),
+            network: NetworkConfig {
+                ssid: String::new(),
+                password: String::new(),
+            },
+            sensors: SensorsConfig {
+                pm: true,
+                climate: true,
+                noise: true,
+                temperature_offset: 0,
+            },
+            upload: UploadConfig {
+                sensors_social: String::new(),
+                robonomics: String::new(),
+                sensor_community: false,
+                madavi: false,
+            },
+            location: Location::default(),
+            intervals: Intervals {
+                measure_secs: 10,
+                upload_secs: 300,
+            },
+            export: ExportConfig::default(),
+            admin_password: String::new(),
+        }
+    }
+}
+
+impl DeviceConfig {
+    /// Replace secrets which are set by [`SECRET_PLACEHOLDER`].
+    pub fn redact(&mut self) {
+        redact(&mut self.network.password);
+        redact(&mut self.export.token);
+        redact(&mut self.admin_password);
+    }
+
+    /// Take secrets left as [`SECRET_PLACEHOLDER`] by client from `current` config.
+    pub fn keep_secrets(&mut self, current: &Self) {
+        keep(&mut self.network.password, &current.network.password);
+        keep(&mut self.export.token, &current.export.token);
+        keep(&mut self.admin_password, &current.admin_password);
+    }
+
+    /// Check that values are in range, error message is shown to user.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.name.is_empty() {
+            return Err("name should not be empty");
+        }
+        if !self.network.ssid.is_empty() && !valid_password(&self.network.password) {
+            return Err("WiFi password should be empty or 8..63 characters");
+        }
+        if !valid_password(&self.admin_password) {
+            return Err("device password should be empty or 8..63 characters");
+        }
+        if !(-500..=500).contains(&self.sensors.temperature_offset) {
+            return Err("temperature offset should be within ±50 °C");
+        }
+        for url in [&self.upload.sensors_social, &self.upload.robonomics] {
+            if !url.is_empty() && !url.starts_with("http://") {
+                return Err("upload URL should start with http://");
+            }
+        }
+        let location = &self.location;
+        if !(-90.0..=90.0).contains(&location.latitude)
+            || !(-180.0..=180.0).contains(&location.longitude)
+        {
+            return Err("location is out of range");
+        }
+        if self.intervals.measure_secs == 0 {
+            return Err("measure interval should be positive");
+        }
+        if self.intervals.upload_secs < self.intervals.measure_secs {
+            return Err("upload interval should not be shorter than measure interval");
+        }
+        let export = &self.export;
+        if export.kind != ExportKind::None && !export.url.starts_with("http://") {
+            return Err("export URL should start with http://");
+        }
+        match export.kind {
+            ExportKind::InfluxV1 if export.database.is_empty() => {
+                Err("InfluxDB database should not be empty")
+            }
+            ExportKind::InfluxV2 if export.database.is_empty() || export.org.is_empty() => {
+                Err("InfluxDB bucket and organization should not be empty")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Load config from store, factory defaults are returned when nothing is stored.
+    pub async fn load<S: MultiwriteNorFlash>(
+        store: &mut KvStore<S>,
+    ) -> Result<Self, ConfigError<S::Error>> {
+        match store.get(&CONFIG_KEY).await? {
+            Some((version, data)) => migrate(version, &data),
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Validate and save config into store.
+    pub async fn save<S: MultiwriteNorFlash>(
+        &self,
+        store: &mut KvStore<S>,
+    ) -> Result<(), ConfigError<S::Error>> {
+        self.validate().map_err(ConfigError::Invalid)?;
+        let mut data = [0u8; CONFIG_MAX_SIZE];
+        let data = postcard::to_slice(self, &mut data)
+            .map_err(|_| ConfigError::Store(StoreError::TooLarge))?;
+        let data = Vec::from_slice(data).map_err(|_| ConfigError::Store(StoreError::TooLarge))?;
+        Ok(store.set(&CONFIG_KEY, &(CONFIG_VERSION, data)).await?)
+    }
+}
+
+/// Decode config stored with given schema version and upgrade it to current one.
+pub fn migrate<E>(version: u16, data: &[u8]) -> Result<DeviceConfig, ConfigError<E>> {
+    let config = match version {
+        CONFIG_VERSION => postcard::from_bytes(data).ok(),
+        4 => postcard::from_bytes::<v4::DeviceConfig>(data)
+            .ok()
+            .map(Into::into),
+        3 => postcard::from_bytes::<v3::DeviceConfig>(data)
+            .ok()
+            .map(|c| v4::DeviceConfig::from(c).into()),
+        2 => postcard::from_bytes::<v2::DeviceConfig>(data)
+            .ok()
+            .map(|c| v4::DeviceConfig::from(v3::DeviceConfig::from(c)).into()),
+        1 => postcard::from_bytes::<v1::DeviceConfig>(data)
+            .ok()
+            .map(|c| {
+                v4::DeviceConfig::from(v3::DeviceConfig::from(v2::DeviceConfig::from(c))).into()
+            }),
+        _ => None,
+    };
+    let config: DeviceConfig = config.ok_or(ConfigError::UnsupportedVersion(version))?;
+    config.validate().map_err(ConfigError::Invalid)?;
+    Ok(config)
+}
+
+fn kept_secret<const N: usize>() -> String<N> {
+    String::try_from(SECRET_PLACEHOLDER).unwrap()
+}
+
+fn redact<const N: usize>(secret: &mut String<N>) {
+    if !secret.is_empty() {
+        *secret = kept_secret();
+    }
+}
+
+fn keep<const N: usize>(secret: &mut String<N>, stored: &String<N>) {
+    if secret == SECRET_PLACEHOLDER {
+        secret.clone_from(stored);
+    }
+}
+
+fn valid_password(password: &str) -> bool {
+    password.is_empty() || (8..=63).contains(&password.len())
+}
+
+/// Schema version 1, before Robonomics node URL.
+mod v1 {
+    use heapless::String;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct DeviceConfig {
+        pub name: String<32>,
+        pub network: NetworkConfig,
+        pub sensors: SensorsConfig,
+        pub upload: UploadConfig,
+        pub location: Location,
+        pub intervals: Intervals,
+    }
+
+    #[derive(Deserialize)]
+    pub struct NetworkConfig {
+        pub ssid: String<32>,
+        pub password: String<64>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SensorsConfig {
+        pub pm: bool,
+        pub climate: bool,
+        pub noise: bool,
+        pub temperature_offset: i16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct UploadConfig {
+        pub sensors_social: String<96>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Location {
+        pub latitude: f32,
+        pub longitude: f32,
+        pub altitude: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Intervals {
+        pub measure_secs: u16,
+        pub upload_secs: u16,
+    }
+
+    impl From<DeviceConfig> for super::v2::DeviceConfig {
+        fn from(c: DeviceConfig) -> Self {
+            use super::v2::*;
+            Self {
+                name: c.name,
+                network: NetworkConfig {
+                    ssid: c.network.ssid,
+                    password: c.network.password,
+                },
+                sensors: SensorsConfig {
+                    pm: c.sensors.pm,
+                    climate: c.sensors.climate,
+                    noise: c.sensors.noise,
+                    temperature_offset: c.sensors.temperature_offset,
+                },
+                upload: UploadConfig {
+                    sensors_social: c.upload.sensors_social,
+                    robonomics: String::new(),
+                },
+                location: Location {
+                    latitude: c.location.latitude,
+                    longitude: c.location.longitude,
+                    altitude: c.location.altitude,
+                },
+                intervals: Intervals {
+                    measure_secs: c.intervals.measure_secs,
+                    upload_secs: c.intervals.upload_secs,
+                },
+            }
+        }
+    }
+}
+
+/// Schema version 2, before sensor.community uploads.
+mod v2 {
+    use heapless::String;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct DeviceConfig {
+        pub name: String<32>,
+        pub network: NetworkConfig,
+        pub sensors: SensorsConfig,
+        pub upload: UploadConfig,
+        pub location: Location,
+        pub intervals: Intervals,
+    }
+
+    #[derive(Deserialize)]
+    pub struct NetworkConfig {
+        pub ssid: String<32>,
+        pub password: String<64>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SensorsConfig {
+        pub pm: bool,
+        pub climate: bool,
+        pub noise: bool,
+        pub temperature_offset: i16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct UploadConfig {
+        pub sensors_social: String<96>,
+        pub robonomics: String<96>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Location {
+        pub latitude: f32,
+        pub longitude: f32,
+        pub altitude: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Intervals {
+        pub measure_secs: u16,
+        pub upload_secs: u16,
+    }
+
+    impl From<DeviceConfig> for super::v3::DeviceConfig {
+        fn from(c: DeviceConfig) -> Self {
+            use super::v3::*;
+            Self {
+                name: c.name,
+                network: NetworkConfig {
+                    ssid: c.network.ssid,
+                    password: c.network.password,
+                },
+                sensors: SensorsConfig {
+                    pm: c.sensors.pm,
+                    climate: c.sensors.climate,
+                    noise: c.sensors.noise,
+                    temperature_offset: c.sensors.temperature_offset,
+                },
+                upload: UploadConfig {
+                    sensors_social: c.upload.sensors_social,
+                    robonomics: c.upload.robonomics,
+                    sensor_community: false,
+                    madavi: false,
+                },
+                location: Location {
+                    latitude: c.location.latitude,
+                    longitude: c.location.longitude,
+                    altitude: c.location.altitude,
+                },
+                intervals: Intervals {
+                    measure_secs: c.intervals.measure_secs,
+                    upload_secs: c.intervals.upload_secs,
+                },
+            }
+        }
+    }
+}
+
+/// Schema version 3, before export to own server.
+mod v3 {
+    use heapless::String;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct DeviceConfig {
+        pub name: String<32>,
+        pub network: NetworkConfig,
+        pub sensors: SensorsConfig,
+        pub upload: UploadConfig,
+        pub location: Location,
+        pub intervals: Intervals,
+    }
+
+    #[derive(Deserialize)]
+    pub struct NetworkConfig {
+        pub ssid: String<32>,
+        pub password: String<64>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SensorsConfig {
+        pub pm: bool,
+        pub climate: bool,
+        pub noise: bool,
+        pub temperature_offset: i16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct UploadConfig {
+        pub sensors_social: String<96>,
+        pub robonomics: String<96>,
+        pub sensor_community: bool,
+        pub madavi: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Location {
+        pub latitude: f32,
+        pub longitude: f32,
+        pub altitude: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Intervals {
+        pub measure_secs: u16,
+        pub upload_secs: u16,
+    }
+
+    impl From<DeviceConfig> for super::v4::DeviceConfig {
+        fn from(c: DeviceConfig) -> Self {
+            use super::v4::*;
+            Self {
+                name: c.name,
+                network: NetworkConfig {
+                    ssid: c.network.ssid,
+                    password: c.network.password,
+                },
+                sensors: SensorsConfig {
+                    pm: c.sensors.pm,
+                    climate: c.sensors.climate,
+                    noise: c.sensors.noise,
+                    temperature_offset: c.sensors.temperature_offset,
+                },
+                upload: UploadConfig {
+                    sensors_social: c.upload.sensors_social,
+                    robonomics: c.upload.robonomics,
+                    sensor_community: c.upload.sensor_community,
+                    madavi: c.upload.madavi,
+                },
+                location: Location {
+                    latitude: c.location.latitude,
+                    longitude: c.location.longitude,
+                    altitude: c.location.altitude,
+                },
+                intervals: Intervals {
+                    measure_secs: c.intervals.measure_secs,
+                    upload_secs: c.intervals.upload_secs,
+                },
+                export: ExportConfig {
+                    kind: ExportKind::None,
+                    url: String::new(),
+                    database: String::new(),
+                    org: String::new(),
+                    token: String::new(),
+                    template: String::new(),
+                },
+            }
+        }
+    }
+}
+
+/// Schema version 4, before device password.
+mod v4 {
+    use heapless::String;
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    pub struct DeviceConfig {
+        pub name: String<32>,
+        pub network: NetworkConfig,
+        pub sensors: SensorsConfig,
+        pub upload: UploadConfig,
+        pub location: Location,
+        pub intervals: Intervals,
+        pub export: ExportConfig,
+    }
+
+    #[derive(Deserialize)]
+    pub struct NetworkConfig {
+        pub ssid: String<32>,
+        pub password: String<64>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SensorsConfig {
+        pub pm: bool,
+        pub climate: bool,
+        pub noise: bool,
+        pub temperature_offset: i16,
+    }
+
+    #[derive(Deserialize)]
+    pub struct UploadConfig {
+        pub sensors_social: String<96>,
+        pub robonomics: String<96>,
+        pub sensor_community: bool,
+        pub madavi: bool,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ExportConfig {
+        pub kind: ExportKind,
+        pub url: String<96>,
+        pub database: String<32>,
+        pub org: String<32>,
+        pub token: String<96>,
+        pub template: String<192>,
+    }
+
+    #[derive(Deserialize)]
+    pub enum ExportKind {
+        None,
+        InfluxV1,
+        InfluxV2,
+        Webhook,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Location {
+        pub latitude: f32,
+        pub longitude: f32,
+        pub altitude: f32,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Intervals {
+        pub measure_secs: u16,
+        pub upload_secs: u16,
+    }
+
+    impl From<DeviceConfig> for super::DeviceConfig {
+        fn from(c: DeviceConfig) -> Self {
+            use super::*;
+            Self {
+                name: c.name,
+                network: NetworkConfig {
+                    ssid: c.network.ssid,
+                    password: c.network.password,
+                },
+                sensors: SensorsConfig {
+                    pm: c.sensors.pm,
+                    climate: c.sensors.climate,
+                    noise: c.sensors.noise,
+                    temperature_offset: c.sensors.temperature_offset,
+                },
+                upload: plain_http(UploadConfig {
+                    sensors_social: c.upload.sensors_social,
+                    robonomics: c.upload.robonomics,
+                    sensor_community: c.upload.sensor_community,
+                    madavi: c.upload.madavi,
+                }),
+                location: Location {
+                    latitude: c.location.latitude,
+                    longitude: c.location.longitude,
+                    altitude: c.location.altitude,
+                },
+                intervals: Intervals {
+                    measure_secs: c.intervals.measure_secs,
+                    upload_secs: c.intervals.upload_secs,
+                },
+                export: ExportConfig {
+                    kind: match c.export.kind {
+                        self::ExportKind::None => ExportKind::None,
+                        self::ExportKind::InfluxV1 => ExportKind::InfluxV1,
+                        self::ExportKind::InfluxV2 => ExportKind::InfluxV2,
+                        self::ExportKind::Webhook => ExportKind::Webhook,
+                    },
+                    url: c.export.url,
+                    database: c.export.database,
+                    org: c.export.org,
+                    token: c.export.token,
+                    template: c.export.template,
+                },
+                admin_password: String::new(),
+            }
+        }
+    }
+
+    /// Uploads to `https://` were accepted but never worked, such URLs are
+    /// dropped instead of failing validation of the whole config.
+    fn plain_http(mut upload: super::UploadConfig) -> super::UploadConfig {
+        for url in [&mut upload.sensors_social, &mut upload.robonomics] {
+            if !url.starts_with("http://") {
+                url.clear();
+            }
+        }
+        upload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_futures::block_on;
+
+    use super::*;
+    use crate::flash::{RamFlash, SECTOR_SIZE};
+
+    fn configured() -> DeviceConfig {
+        let mut config = DeviceConfig::default();
+        config.network.ssid = String::try_from("home").unwrap();
+        config.network.password = String::try_from("wifi-secret").unwrap();
+        config.admin_password = String::try_from("admin-secret").unwrap();
+        config
+    }
+
+    #[test]
+    fn redacted_secrets_are_kept() {
+        let stored = configured();
+        let mut shown = stored.clone();
+        shown.redact();
+        assert_eq!(shown.network.password, SECRET_PLACEHOLDER);
+        assert_eq!(shown.admin_password, SECRET_PLACEHOLDER);
+        // Secret which isn't set stays empty.
+        assert_eq!(shown.export.token, "");
+
+        shown.keep_secrets(&stored);
+        assert_eq!(shown, stored);
+    }
+
+    #[test]
+    fn changed_and_omitted_secrets() {
+        let stored = configured();
+        let mut shown = stored.clone();
+        shown.redact();
+        let mut json = [0u8; 1024];
+        let len = serde_json_core::to_slice(&shown, &mut json).unwrap();
+        let json = core::str::from_utf8(&json[..len]).unwrap();
+
+        // Omitted secret is kept, changed one is replaced, cleared one is removed.
+        let json = json
+            .replace(r#","admin_password":"********""#, "")
+            .replace(r#""password":"********""#, r#""password":"""#);
+        let (mut config, _) = serde_json_core::from_str::<DeviceConfig>(&json).unwrap();
+        config.keep_secrets(&stored);
+        assert_eq!(config.admin_password, stored.admin_password);
+        assert_eq!(config.network.password, "");
+    }
+
+    #[test]
+    fn upload_urls_are_plain_http() {
+        let mut config = DeviceConfig::default();
+        config.upload.robonomics =
+            String::try_from("https://kusama.rpc.robonomics.network").unwrap();
+        assert_eq!(
+            config.validate(),
+            Err("upload URL should start with http://")
+        );
+        config.upload.robonomics = String::try_from("http://127.0.0.1:9944").unwrap();
+        assert_eq!(config.validate(), Ok(()));
+    }
+
+    #[test]
+    fn migrate_v4() {
+        let mut config = configured();
+        config.admin_password.clear();
+        let v4 = (
+            &config.name,
+            &config.network,
+            &config.sensors,
+            &config.upload,
+            &config.location,
+            &config.intervals,
+            &config.export,
+        );
+        let mut data = [0u8; CONFIG_MAX_SIZE];
+        let data = postcard::to_slice(&v4, &mut data).unwrap();
+        assert_eq!(migrate::<()>(4, data), Ok(config));
+    }
+
+    /// Concatenate encoded fields.
+    fn blob(fields: &[&[u8]]) -> Vec<u8, CONFIG_MAX_SIZE> {
+        let mut blob = Vec::new();
+        for field in fields {
+            blob.extend_from_slice(field).unwrap();
+        }
+        blob
+    }
+
+    /// Fields common for all versions, in postcard encoding.
+    const NAME: &[u8] = b"\x0abalcony-01";
+    const NETWORK: &[u8] = b"\x04home\x0bwifi-secret";
+    /// PM and noise enabled, temperature offset -1.5 °C in zigzag varint.
+    const SENSORS: &[u8] = b"\x01\x00\x01\x1d";
+    /// 55.75, 37.625, 150.0 as little endian `f32`.
+    const LOCATION: &[u8] = b"\x00\x00\x5f\x42\x00\x80\x16\x42\x00\x00\x16\x43";
+    /// 10 and 300 seconds in varint.
+    const INTERVALS: &[u8] = b"\x0a\xac\x02";
+
+    /// 64 characters.
+    const LONG_PASSWORD: &str = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    const SENSORS_SOCIAL: &str = "http://gw.sensors.social";
+
+    fn migrated() -> DeviceConfig {
+        let mut config = DeviceConfig {
+            name: String::try_from("balcony-01").unwrap(),
+            ..Default::default()
+        };
+        config.network.ssid = String::try_from("home").unwrap();
+        config.network.password = String::try_from("wifi-secret").unwrap();
+        config.sensors.climate = false;
+        config.sensors.temperature_offset = -15;
+        config.upload.sensors_social = String::try_from(SENSORS_SOCIAL).unwrap();
+        config.location = Location {
+            latitude: 55.75,
+            longitude: 37.625,
+            altitude: 150.0,
+        };
+        config
+    }
+
+    #[test]
+    fn migrate_v1() {
+        let upload = b"\x18http://gw.sensors.social";
+        let v1 = blob(&[NAME, NETWORK, SENSORS, upload, LOCATION, INTERVALS]);
+        assert_eq!(migrate::<()>(1, &v1), Ok(migrated()));
+    }
+
+    #[test]
+    fn migrate_v2() {
+        let upload = b"\x18http://gw.sensors.social\x15http://127.0.0.1:9944";
+        let v2 = blob(&[NAME, NETWORK, SENSORS, upload, LOCATION, INTERVALS]);
+        let mut config = migrated();
+        config.upload.robonomics = String::try_from("http://127.0.0.1:9944").unwrap();
+        assert_eq!(migrate::<()>(2, &v2), Ok(config));
+    }
+
+    #[test]
+    fn migrate_v3() {
+        // Secure URL never worked, it is dropped.
+        let upload = b"\x18http://gw.sensors.social\x1fhttps://polkadot.rpc.robonomics\x01\x01";
+        let v3 = blob(&[NAME, NETWORK, SENSORS, upload, LOCATION, INTERVALS]);
+        let mut config = migrated();
+        config.upload.sensor_community = true;
+        config.upload.madavi = true;
+        assert_eq!(migrate::<()>(3, &v3), Ok(config));
+    }
+
+    #[test]
+    fn unsupported_versions() {
+        let v1 = blob(&[NAME, NETWORK, SENSORS, b"\x00", LOCATION, INTERVALS]);
+        assert_eq!(
+            migrate::<()>(CONFIG_VERSION + 1, &v1),
+            Err(ConfigError::UnsupportedVersion(CONFIG_VERSION + 1))
+        );
+        assert_eq!(
+            migrate::<()>(0, &v1),
+            Err(ConfigError::UnsupportedVersion(0))
+        );
+        // Truncated blob.
+        assert_eq!(
+            migrate::<()>(1, &v1[..v1.len() - 1]),
+            Err(ConfigError::UnsupportedVersion(1))
+        );
+        // Stored config should be valid too.
+        let zero_intervals = blob(&[NAME, NETWORK, SENSORS, b"\x00", LOCATION, b"\x00\x00"]);
+        assert_eq!(
+            migrate::<()>(1, &zero_intervals),
+            Err(ConfigError::Invalid("measure interval should be positive"))
+        );
+    }
+
+    #[test]
+    fn load_save() {
+        block_on(async {
+            let mut store = KvStore::new(RamFlash::<{ 4 * SECTOR_SIZE }>::new());
+            assert_eq!(
+                DeviceConfig::load(&mut store).await,
+                Ok(DeviceConfig::default())
+            );
+
+            let mut config = configured();
+            config.export.kind = ExportKind::InfluxV2;
+            config.export.url = String::try_from("http://influx.local:8086").unwrap();
+            config.export.database = String::try_from("air").unwrap();
+            config.export.org = String::try_from("home").unwrap();
+            config.save(&mut store).await.unwrap();
+
+            let mut store = KvStore::new(store.into_inner());
+            assert_eq!(DeviceConfig::load(&mut store).await, Ok(config.clone()));
+            let (version, _) = store.get(&CONFIG_KEY).await.unwrap().unwrap();
+            assert_eq!(version, CONFIG_VERSION);
+
+            // Invalid config isn't saved.
+            let mut invalid = config.clone();
+            invalid.name.clear();
+            assert_eq!(
+                invalid.save(&mut store).await,
+                Err(ConfigError::Invalid("name should not be empty"))
+            );
+            assert_eq!(DeviceConfig::load(&mut store).await, Ok(config));
+        });
+    }
+
+    #[test]
+    fn validate_ranges() {
+        let rejected = |change: fn(&mut DeviceConfig)| {
+            let mut config = configured();
+            change(&mut config);
+            config.validate().unwrap_err()
+        };
+        assert_eq!(configured().validate(), Ok(()));
+        assert_eq!(
+            rejected(|c| c.network.password = String::try_from("short").unwrap()),
+            "WiFi password should be empty or 8..63 characters"
+        );
+        assert_eq!(
+            rejected(|c| c.admin_password = String::try_from(LONG_PASSWORD).unwrap()),
+            "device password should be empty or 8..63 characters"
+        );
+        assert_eq!(
+            rejected(|c| c.sensors.temperature_offset = 501),
+            "temperature offset should be within ±50 °C"
+        );
+        assert_eq!(
+            rejected(|c| c.sensors.temperature_offset = -501),
+            "temperature offset should be within ±50 °C"
+        );
+        assert_eq!(
+            rejected(|c| c.location.latitude = 90.5),
+            "location is out of range"
+        );
+        assert_eq!(
+            rejected(|c| c.location.longitude = -180.5),
+            "location is out of range"
+        );
+        assert_eq!(
+            rejected(|c| c.intervals.measure_secs = 0),
+            "measure interval should be positive"
+        );
+        assert_eq!(
+            rejected(|c| c.intervals.upload_secs = 5),
+            "upload interval should not be shorter than measure interval"
+        );
+        assert_eq!(
+            rejected(|c| c.export.kind = ExportKind::Webhook),
+            "export URL should start with http://"
+        );
+        assert_eq!(
+            rejected(|c| {
+                c.export.kind = ExportKind::InfluxV1;
+                c.export.url = String::try_from("http://influx.local:8086").unwrap();
+            }),
+            "InfluxDB database should not be empty"
+        );
+        assert_eq!(
+            rejected(|c| {
+                c.export.kind = ExportKind::InfluxV2;
+                c.export.url = String::try_from("http://influx.local:8086").unwrap();
+                c.export.database = String::try_from("air").unwrap();
+            }),
+            "InfluxDB bucket and organization should not be empty"
+        );
+
+        // Bounds are inclusive, WiFi password isn't checked without network.
+        let mut config = configured();
+        config.sensors.temperature_offset = -500;
+        config.location.latitude = -90.0;
+        config.location.longitude = 180.0;
+        config.intervals.upload_secs = config.intervals.measure_secs;
+        assert_eq!(config.validate(), Ok(()));
+        config.network.ssid.clear();
+        config.network.password = String::try_from("short").unwrap();
+        assert_eq!(config.validate(), Ok(()));
+    }
+}
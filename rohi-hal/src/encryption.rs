@@ -0,0 +1,283 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Payload encryption for device owner.
+//!
+//! Shared key is agreed with X25519 between device ed25519 key and owner's
+//! public key, both converted to Montgomery form the same way as libsodium
+//! `crypto_sign_ed25519_*_to_curve25519` does. Payloads are sealed with NaCl
+//! `crypto_box`:
+//!
+//! - [`Cipher::XChaCha20Poly1305`] is libsodium
+//!   `crypto_box_curve25519xchacha20poly1305`;
+//! - [`Cipher::XSalsa20Poly1305`] is classic `crypto_box`, used by
+//!   `Keypair.encrypt_message` of Robonomics Python tooling.
+//!
+//! Sealed payload is `nonce (24) | tag (16) | ciphertext`. Owner opens it with
+//! own secret key and device account, e.g. with `tools/rohi-decrypt`.
+//!
+//! sr25519 keys have no X25519 form, so device account should be ed25519.
+//!
+//! ```ignore
+//! let envelope = Envelope::new(&pair, &OwnerKey::from_account(&owner)?, Cipher::default())?;
+//! let len = envelope.seal(&mut rng, payload, &mut sealed)?;
+//! ```
+
+use crypto_box::aead::AeadInPlace;
+use crypto_box::{ChaChaBox, PublicKey, SalsaBox, SecretKey};
+use ed25519_dalek::VerifyingKey;
+use rand_core::RngCore;
+
+use crate::identity::{AccountId, Keypair};
+
+/// Length of random nonce prepended to sealed payload.
+pub const NONCE_LEN: usize = 24;
+/// Length of authentication tag.
+pub const TAG_LEN: usize = 16;
+/// Sealed payload is longer than plain one by this count of bytes.
+pub const OVERHEAD: usize = NONCE_LEN + TAG_LEN;
+
+/// Encryption errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EncryptionError {
+    /// Key pair is not ed25519.
+    Scheme,
+    /// Owner account is not a valid ed25519 public key.
+    PublicKey,
+    /// Output buffer is too small.
+    Overflow,
+    /// Payload is corrupted or sealed for other keys.
+    Decryption,
+}
+
+/// Authenticated cipher of sealed payloads.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Cipher {
+    /// XChaCha20-Poly1305 `crypto_box`.
+    #[default]
+    XChaCha20Poly1305,
+    /// XSalsa20-Poly1305 `crypto_box`, compatible with Robonomics tooling.
+    XSalsa20Poly1305,
+}
+
+/// X25519 public key of payload recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OwnerKey(PublicKey);
+
+impl OwnerKey {
+    /// Key of ed25519 Robonomics account.
+    pub fn from_account(account: &AccountId) -> Result<Self, EncryptionError> {
+        let key = VerifyingKey::from_bytes(&account.0).map_err(|_| EncryptionError::PublicKey)?;
+        Ok(Self(PublicKey::from(key.to_montgomery().to_bytes())))
+    }
+
+    /// Raw X25519 public key.
+    pub fn from_x25519(key: [u8; 32]) -> Self {
+        Self(PublicKey::from(key))
+    }
+}
+
+/// Precomputed shared key of device and owner.
+pub enum Envelope {
+    XChaCha20Poly1305(ChaChaBox),
+    XSalsa20Poly1305(SalsaBox),
+}
+
+impl Envelope {
+    /// Agree on shared key between device `pair` and `owner`.
+    pub fn new(pair: &Keypair, owner: &OwnerKey, cipher: Cipher) -> Result<Self, EncryptionError> {
+        let Keypair::Ed25519(key) = pair else {
+            return Err(EncryptionError::Scheme);
+        };
+        let secret = SecretKey::from(key.to_scalar_bytes());
+        Ok(match cipher {
+            Cipher::XChaCha20Poly1305 => Self::XChaCha20Poly1305(ChaChaBox::new(&owner.0, &secret)),
+            Cipher::XSalsa20Poly1305 => Self::XSalsa20Poly1305(SalsaBox::new(&owner.0, &secret)),
+        })
+    }
+
+    /// Seal `payload` into `out` with random nonce, returns length of sealed payload.
+    ///
+    /// Nonce is public and only has to be unique, so hardware RNG is enough.
+    pub fn seal(
+        &self,
+        rng: &mut impl RngCore,
+        payload: &[u8],
+        out: &mut [u8],
+    ) -> Result<usize, EncryptionError> {
+        let len = payload.len() + OVERHEAD;
+        let out = out.get_mut(..len).ok_or(EncryptionError::Overflow)?;
+        let (nonce, rest) = out.split_at_mut(NONCE_LEN);
+        let (tag, ciphertext) = rest.split_at_mut(TAG_LEN);
+        rng.fill_bytes(nonce);
+        ciphertext.copy_from_slice(payload);
+
+        let nonce = (&*nonce).into();
+        let sealed = match self {
+            Self::XChaCha20Poly1305(b) => b.encrypt_in_place_detached(nonce, b"", ciphertext),
+            Self::XSalsa20Poly1305(b) => b.encrypt_in_place_detached(nonce, b"", ciphertext),
+        };
+        tag.copy_from_slice(&sealed.map_err(|_| EncryptionError::Overflow)?);
+        Ok(len)
+    }
+
+    /// Open `sealed` payload into `out`, returns length of plain payload.
+    pub fn open(&self, sealed: &[u8], out: &mut [u8]) -> Result<usize, EncryptionError> {
+        let len = sealed
+            .len()
+            .checked_sub(OVERHEAD)
+            .ok_or(EncryptionError::Decryption)?;
+        let out = out.get_mut(..len).ok_or(EncryptionError::Overflow)?;
+        let (nonce, rest) = sealed.split_at(NONCE_LEN);
+        let (tag, ciphertext) = rest.split_at(TAG_LEN);
+        out.copy_from_slice(ciphertext);
+
+        let (nonce, tag) = (nonce.into(), tag.into());
+        match self {
+            Self::XChaCha20Poly1305(b) => b.decrypt_in_place_detached(nonce, b"", out, tag),
+            Self::XSalsa20Poly1305(b) => b.decrypt_in_place_detached(nonce, b"", out, tag),
+        }
+        .map_err(|_| EncryptionError::Decryption)?;
+        Ok(len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Scheme;
+
+    // Test vector of `tools/rohi-decrypt`, produced by libsodium.
+    const DEVICE: &str = "0x202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f";
+    const OWNER: &str = "0x000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const PAYLOAD: &[u8] = b"{\"pm25\":12.3,\"temperature\":21.5}";
+    const NONCE: u8 = 7;
+    const XCHACHA20: &str = "070707070707070707070707070707070707070707070707\
+                             b17ef4fc70af68ff1793dadd5e749a9105db20a98519417b\
+                             dfc0240e9c498cf2667a6e4a5e2f5edb2ad77a2abc6fe95d";
+    const XSALSA20: &str = "070707070707070707070707070707070707070707070707\
+                            fd7ebc185d5cd5fd66e9ee879d88006049336732ea67dee6\
+                            ce6f9c309678fa2deaf8c33c35c76e86f4148b29d1c75195";
+
+    /// Nonce source of test vector.
+    struct Fill(u8);
+
+    impl RngCore for Fill {
+        fn next_u32(&mut self) -> u32 {
+            u32::from_ne_bytes([self.0; 4])
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::from_ne_bytes([self.0; 8])
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(self.0)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            dest.fill(self.0);
+            Ok(())
+        }
+    }
+
+    fn unhex(hex: &str) -> heapless::Vec<u8, 96> {
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    fn pair(uri: &str) -> Keypair {
+        Keypair::from_uri(Scheme::Ed25519, uri).unwrap()
+    }
+
+    fn device_envelope(cipher: Cipher) -> Envelope {
+        let owner = OwnerKey::from_account(&pair(OWNER).public()).unwrap();
+        Envelope::new(&pair(DEVICE), &owner, cipher).unwrap()
+    }
+
+    #[test]
+    fn seal_matches_libsodium() {
+        for (cipher, vector) in [
+            (Cipher::XChaCha20Poly1305, XCHACHA20),
+            (Cipher::XSalsa20Poly1305, XSALSA20),
+        ] {
+            let mut sealed = [0u8; 96];
+            let len = device_envelope(cipher)
+                .seal(&mut Fill(NONCE), PAYLOAD, &mut sealed)
+                .unwrap();
+            assert_eq!(sealed[..len], unhex(vector)[..], "{cipher:?}");
+        }
+    }
+
+    #[test]
+    fn open_libsodium_vectors() {
+        for (cipher, vector) in [
+            (Cipher::XChaCha20Poly1305, XCHACHA20),
+            (Cipher::XSalsa20Poly1305, XSALSA20),
+        ] {
+            let sealed = unhex(vector);
+            let mut plain = [0u8; 96];
+            // Shared key is the same on both sides.
+            let device = OwnerKey::from_account(&pair(DEVICE).public()).unwrap();
+            let owner = Envelope::new(&pair(OWNER), &device, cipher).unwrap();
+            let len = owner.open(&sealed, &mut plain).unwrap();
+            assert_eq!(&plain[..len], PAYLOAD, "{cipher:?}");
+            let len = device_envelope(cipher).open(&sealed, &mut plain).unwrap();
+            assert_eq!(&plain[..len], PAYLOAD, "{cipher:?}");
+        }
+    }
+
+    #[test]
+    fn corrupted_payload() {
+        let envelope = device_envelope(Cipher::XChaCha20Poly1305);
+        let mut plain = [0u8; 96];
+        for i in [0, NONCE_LEN, OVERHEAD] {
+            let mut sealed = unhex(XCHACHA20);
+            sealed[i] ^= 1;
+            assert_eq!(
+                envelope.open(&sealed, &mut plain),
+                Err(EncryptionError::Decryption)
+            );
+        }
+        // Sealed by other cipher.
+        let sealed = unhex(XSALSA20);
+        assert_eq!(
+            envelope.open(&sealed, &mut plain),
+            Err(EncryptionError::Decryption)
+        );
+        assert_eq!(
+            envelope.open(&sealed[..OVERHEAD - 1], &mut plain),
+            Err(EncryptionError::Decryption)
+        );
+        assert_eq!(
+            envelope.open(&sealed, &mut plain[..PAYLOAD.len() - 1]),
+            Err(EncryptionError::Overflow)
+        );
+    }
+
+    #[test]
+    fn sr25519_is_refused() {
+        let owner = OwnerKey::from_x25519([9; 32]);
+        let pair = Keypair::from_uri(Scheme::Sr25519, DEVICE).unwrap();
+        assert!(matches!(
+            Envelope::new(&pair, &owner, Cipher::default()),
+            Err(EncryptionError::Scheme)
+        ));
+    }
+}
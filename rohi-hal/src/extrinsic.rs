@@ -0,0 +1,529 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Signed extrinsics of Robonomics parachain.
+//!
+//! Extrinsics are built in version 4 format with transaction extensions of
+//! Robonomics runtime: spec and transaction version, genesis hash, era, nonce,
+//! tip and disabled metadata hash check. Chain parameters in [`ChainInfo`]
+//! and account nonce are fetched from node before signing.
+//!
+//! ```ignore
+//! let call = Call::Datalog(b"{\"pm25\":12.5}");
+//! let extrinsic = ExtrinsicBuilder::new(&chain, nonce)
+//!     .with_mortal_era(64, block_number, block_hash)
+//!     .sign(&pair, &call)?;
+//! ```
+//!
+//! Commands sent to device are found in block extrinsics by [`Launch::decode`].
+
+use heapless::Vec;
+
+use crate::identity::{AccountId, Keypair, Signature};
+use crate::ipfs::{Cid, DAG_PB, Version};
+use crate::scale::{Compact, Encode, Input, Output, Overflow, blake2_256};
+
+/// Index of `Datalog` pallet in Robonomics runtime.
+pub const DATALOG_PALLET: u8 = 51;
+/// Index of `Launch` pallet in Robonomics runtime.
+pub const LAUNCH_PALLET: u8 = 52;
+/// Index of `RWS` pallet in Robonomics runtime.
+pub const RWS_PALLET: u8 = 55;
+
+/// Maximal length of datalog record accepted by runtime.
+pub const RECORD_MAX_LEN: usize = 512;
+
+/// Maximal length of encoded extrinsic.
+pub const EXTRINSIC_MAX_LEN: usize = 1024;
+
+/// Encoded extrinsic ready for submission.
+pub type Extrinsic = Vec<u8, EXTRINSIC_MAX_LEN>;
+
+/// Extrinsic format version 4.
+const EXTRINSIC_VERSION: u8 = 4;
+/// Version byte flag of signed extrinsic.
+const SIGNED_FLAG: u8 = 0b1000_0000;
+/// Signed payloads longer than this are hashed before signing.
+const PAYLOAD_HASH_THRESHOLD: usize = 256;
+
+/// Runtime call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Call<'a> {
+    /// `datalog.record(record)`: save data on chain, up to [`RECORD_MAX_LEN`] bytes.
+    Datalog(&'a [u8]),
+    /// `launch.launch(robot, param)`: send command with 32 bytes parameter to robot.
+    Launch { robot: AccountId, param: [u8; 32] },
+    /// `rws.call(subscription_id, call)`: dispatch call paid by RWS subscription.
+    Rws {
+        subscription: AccountId,
+        call: &'a Call<'a>,
+    },
+    /// Already encoded call of any other pallet.
+    Raw(&'a [u8]),
+}
+
+impl Encode for Call<'_> {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        match self {
+            Self::Datalog(record) => (DATALOG_PALLET, 0u8, *record).encode_to(out),
+            Self::Launch { robot, param } => (LAUNCH_PALLET, 0u8, robot.0, param).encode_to(out),
+            Self::Rws { subscription, call } => {
+                (RWS_PALLET, 0u8, subscription.0, call).encode_to(out)
+            }
+            Self::Raw(call) => out.write(call),
+        }
+    }
+}
+
+/// Transaction lifetime.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Era {
+    /// Valid forever, replay is protected by nonce only.
+    #[default]
+    Immortal,
+    /// Valid for `period` blocks since block with number `phase` modulo `period`.
+    Mortal { period: u64, phase: u64 },
+}
+
+impl Era {
+    /// Era starting at `current` block, `period` is rounded up to power of two within `4..=65536`.
+    pub fn mortal(period: u64, current: u64) -> Self {
+        let period = period
+            .checked_next_power_of_two()
+            .unwrap_or(1 << 16)
+            .clamp(4, 1 << 16);
+        let phase = current % period;
+        let quantize_factor = (period >> 12).max(1);
+        Self::Mortal {
+            period,
+            phase: phase / quantize_factor * quantize_factor,
+        }
+    }
+
+    /// Number of the first block of era containing `current` block.
+    pub fn birth(&self, current: u64) -> u64 {
+        match *self {
+            Self::Immortal => 0,
+            Self::Mortal { period, phase } => {
+                (current.max(phase) - phase) / period * period + phase
+            }
+        }
+    }
+}
+
+impl Encode for Era {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        match *self {
+            Self::Immortal => out.write(&[0]),
+            Self::Mortal { period, phase } => {
+                let quantize_factor = (period >> 12).max(1);
+                let encoded = (period.trailing_zeros() - 1).clamp(1, 15) as u16
+                    | (((phase / quantize_factor) as u16) << 4);
+                encoded.encode_to(out)
+            }
+        }
+    }
+}
+
+/// Chain parameters signed with every extrinsic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChainInfo {
+    pub genesis_hash: [u8; 32],
+    pub spec_version: u32,
+    pub transaction_version: u32,
+}
+
+/// Builder of signed extrinsic.
+#[derive(Clone, Copy, Debug)]
+pub struct ExtrinsicBuilder<'a> {
+    chain: &'a ChainInfo,
+    nonce: u32,
+    tip: u128,
+    era: Era,
+    era_hash: [u8; 32],
+}
+
+impl<'a> ExtrinsicBuilder<'a> {
+    /// Immortal extrinsic with given account nonce and no tip.
+    pub fn new(chain: &'a ChainInfo, nonce: u32) -> Self {
+        Self {
+            chain,
+            nonce,
+            tip: 0,
+            era: Era::Immortal,
+            era_hash: chain.genesis_hash,
+        }
+    }
+
+    /// Tip to block author in planck units.
+    pub fn with_tip(mut self, tip: u128) -> Self {
+        self.tip = tip;
+        self
+    }
+
+    /// Extrinsic valid for `period` blocks since block with `number` and `hash`.
+    ///
+    /// Periods above 4096 blocks quantize era start, so `hash` should be
+    /// hash of [`Era::birth`] block in that case.
+    pub fn with_mortal_era(mut self, period: u64, number: u64, hash: [u8; 32]) -> Self {
+        self.era = Era::mortal(period, number);
+        self.era_hash = hash;
+        self
+    }
+
+    /// Transaction extensions included into extrinsic.
+    fn extra(&self) -> impl Encode {
+        // Era, nonce, tip and metadata hash check mode.
+        (self.era, Compact(self.nonce.into()), Compact(self.tip), 0u8)
+    }
+
+    /// Transaction extensions implied by runtime, signed but not included.
+    fn implicit(&self) -> impl Encode {
+        (
+            self.chain.spec_version,
+            self.chain.transaction_version,
+            (self.chain.genesis_hash, self.era_hash),
+            // No metadata hash.
+            None::<[u8; 32]>,
+        )
+    }
+
+    /// Payload to be signed by account.
+    pub fn payload(&self, call: &Call<'_>) -> Result<Vec<u8, EXTRINSIC_MAX_LEN>, Overflow> {
+        (call, self.extra(), self.implicit()).encode()
+    }
+
+    /// Sign call and encode extrinsic.
+    pub fn sign(&self, pair: &Keypair, call: &Call<'_>) -> Result<Extrinsic, Overflow> {
+        let payload = self.payload(call)?;
+        let signature = if payload.len() > PAYLOAD_HASH_THRESHOLD {
+            pair.sign(&blake2_256(&payload))
+        } else {
+            pair.sign(&payload)
+        };
+
+        let mut body: Vec<u8, EXTRINSIC_MAX_LEN> = Vec::new();
+        (EXTRINSIC_VERSION | SIGNED_FLAG).encode_to(&mut body)?;
+        // `MultiAddress::Id`.
+        (0u8, pair.public().0).encode_to(&mut body)?;
+        signature.encode_to(&mut body)?;
+        self.extra().encode_to(&mut body)?;
+        call.encode_to(&mut body)?;
+
+        let mut extrinsic = Extrinsic::new();
+        body[..].encode_to(&mut extrinsic)?;
+        Ok(extrinsic)
+    }
+}
+
+/// `MultiSignature` encoding.
+impl Encode for Signature {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        match self {
+            Self::Ed25519(bytes) => (0u8, bytes).encode_to(out),
+            Self::Sr25519(bytes) => (1u8, bytes).encode_to(out),
+        }
+    }
+}
+
+/// `launch.launch` call found in block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Launch {
+    /// Signer of extrinsic.
+    pub sender: AccountId,
+    /// Target of command.
+    pub robot: AccountId,
+    /// Command parameter: IPFS content hash or raw payload.
+    pub param: [u8; 32],
+    /// Owner of RWS subscription for calls through `rws.call`.
+    pub subscription: Option<AccountId>,
+}
+
+impl Launch {
+    /// Find launch call in encoded extrinsic, sent directly or through `rws.call`.
+    ///
+    /// Only signed version 4 extrinsics with transaction extensions of Robonomics
+    /// runtime are recognized, see [`ExtrinsicBuilder`].
+    pub fn decode(extrinsic: &[u8]) -> Option<Self> {
+        let mut input = Input::new(Input::new(extrinsic).byte_string()?);
+        if input.u8()? != EXTRINSIC_VERSION | SIGNED_FLAG {
+            return None;
+        }
+        // `MultiAddress::Id` only, accounts are never referenced by index.
+        if input.u8()? != 0 {
+            return None;
+        }
+        let sender = AccountId(input.array()?);
+        let signature_len = match input.u8()? {
+            0 | 1 => 64,
+            2 => 65,
+            _ => return None,
+        };
+        input.bytes(signature_len)?;
+        // Era, nonce, tip and metadata hash check mode.
+        if input.u8()? != 0 {
+            input.u8()?;
+        }
+        input.compact()?;
+        input.compact()?;
+        input.u8()?;
+
+        let (subscription, robot, param) = decode_launch_call(&mut input)?;
+        Some(Self {
+            sender,
+            robot,
+            param,
+            subscription,
+        })
+    }
+
+    /// Account command is dispatched on behalf of: subscription owner for calls
+    /// through RWS, signer otherwise.
+    pub fn origin(&self) -> AccountId {
+        self.subscription.unwrap_or(self.sender)
+    }
+
+    /// Parameter as IPFS CIDv0, by Robonomics convention it holds SHA-256 digest of content.
+    pub fn param_cid(&self) -> Cid {
+        Cid::new(Version::V0, DAG_PB, self.param)
+    }
+}
+
+/// Decode launch call, returns RWS subscription owner when it is dispatched by `rws.call`.
+fn decode_launch_call(input: &mut Input<'_>) -> Option<(Option<AccountId>, AccountId, [u8; 32])> {
+    match (input.u8()?, input.u8()?) {
+        (LAUNCH_PALLET, 0) => Some((None, AccountId(input.array()?), input.array()?)),
+        (RWS_PALLET, 0) => {
+            // Launch is in call dispatched on behalf of subscription owner.
+            let owner = AccountId(input.array()?);
+            let (_, robot, param) = decode_launch_call(input)?;
+            Some((Some(owner), robot, param))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity::Scheme;
+
+    // Fixtures are built by `sp_runtime::generic::UncheckedExtrinsic` with
+    // the same call and extensions, ed25519 signatures are deterministic.
+    const URI: &str =
+        "0x9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60//robonomics";
+    const CHAIN: ChainInfo = ChainInfo {
+        genesis_hash: [0x63; 32],
+        spec_version: 41,
+        transaction_version: 2,
+    };
+    const ROBOT: AccountId = AccountId([0x11; 32]);
+    const PARAM: [u8; 32] = [0x33; 32];
+    const LAUNCH: Call = Call::Launch {
+        robot: ROBOT,
+        param: PARAM,
+    };
+
+    fn unhex<const N: usize>(hex: &str) -> Vec<u8, N> {
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    fn pair() -> Keypair {
+        Keypair::from_uri(Scheme::Ed25519, URI).unwrap()
+    }
+
+    #[test]
+    fn datalog_immortal() {
+        let call = Call::Datalog(b"{\"pm25\":12.5}");
+        let builder = ExtrinsicBuilder::new(&CHAIN, 0);
+        assert_eq!(
+            builder.payload(&call).unwrap(),
+            unhex::<128>(concat!(
+                "3300347b22706d3235223a31322e357d",
+                "00000000",
+                "2900000002000000",
+                "6363636363636363636363636363636363636363636363636363636363636363",
+                "6363636363636363636363636363636363636363636363636363636363636363",
+                "00",
+            ))
+        );
+        assert_eq!(
+            builder.sign(&pair(), &call).unwrap(),
+            unhex::<256>(concat!(
+                "dd018400",
+                "e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff",
+                "00ff3d0757b4446d4e320832312d950ce6eae43f3e2bfa04e1a455e742253326",
+                "9431a19f977b765784699ff64fce09c66131eeabf9b8d08be35be046c6351835",
+                "0200000000",
+                "3300347b22706d3235223a31322e357d",
+            ))
+        );
+    }
+
+    #[test]
+    fn launch_mortal_with_tip() {
+        let builder = ExtrinsicBuilder::new(&CHAIN, 7)
+            .with_tip(1_000_000)
+            .with_mortal_era(64, 1000, [0x44; 32]);
+        assert_eq!(
+            builder.payload(&LAUNCH).unwrap(),
+            unhex::<256>(concat!(
+                "3400",
+                "1111111111111111111111111111111111111111111111111111111111111111",
+                "3333333333333333333333333333333333333333333333333333333333333333",
+                "85021c02093d0000",
+                "2900000002000000",
+                "6363636363636363636363636363636363636363636363636363636363636363",
+                "4444444444444444444444444444444444444444444444444444444444444444",
+                "00",
+            ))
+        );
+        let extrinsic = builder.sign(&pair(), &LAUNCH).unwrap();
+        assert_eq!(
+            extrinsic,
+            unhex::<256>(concat!(
+                "b5028400",
+                "e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff",
+                "00023d65646d3e6bff343263adf6ce037803b93b3cae26a8b7958a6f7c61b762",
+                "6ecc6172905978894eb2c143dba5cf44425aff8f94c5056c733ba33174e6bf340b",
+                "85021c02093d0000",
+                "3400",
+                "1111111111111111111111111111111111111111111111111111111111111111",
+                "3333333333333333333333333333333333333333333333333333333333333333",
+            ))
+        );
+        assert_eq!(
+            Launch::decode(&extrinsic),
+            Some(Launch {
+                sender: pair().public(),
+                robot: ROBOT,
+                param: PARAM,
+                subscription: None,
+            })
+        );
+    }
+
+    #[test]
+    fn rws_call() {
+        let call = Call::Rws {
+            subscription: AccountId([0x22; 32]),
+            call: &LAUNCH,
+        };
+        let builder = ExtrinsicBuilder::new(&CHAIN, 8).with_mortal_era(64, 1000, [0x44; 32]);
+        assert_eq!(
+            builder.payload(&call).unwrap(),
+            unhex::<256>(concat!(
+                "3700",
+                "2222222222222222222222222222222222222222222222222222222222222222",
+                "3400",
+                "1111111111111111111111111111111111111111111111111111111111111111",
+                "3333333333333333333333333333333333333333333333333333333333333333",
+                "8502200000",
+                "2900000002000000",
+                "6363636363636363636363636363636363636363636363636363636363636363",
+                "4444444444444444444444444444444444444444444444444444444444444444",
+                "00",
+            ))
+        );
+        let extrinsic = builder.sign(&pair(), &call).unwrap();
+        assert_eq!(
+            extrinsic,
+            unhex::<256>(concat!(
+                "31038400",
+                "e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff",
+                "00017817914959fa6871a78fedc47f9d61048e36589a0e60676771d7411c3811",
+                "b04eb390236f3d3bd2f596b405a7f06b558f6fa26c49c5208122cb1ae4583c7002",
+                "8502200000",
+                "3700",
+                "2222222222222222222222222222222222222222222222222222222222222222",
+                "3400",
+                "1111111111111111111111111111111111111111111111111111111111111111",
+                "3333333333333333333333333333333333333333333333333333333333333333",
+            ))
+        );
+        let launch = Launch::decode(&extrinsic).unwrap();
+        assert_eq!(
+            launch,
+            Launch {
+                sender: pair().public(),
+                robot: ROBOT,
+                param: PARAM,
+                subscription: Some(AccountId([0x22; 32])),
+            }
+        );
+        assert_eq!(launch.origin(), AccountId([0x22; 32]));
+    }
+
+    #[test]
+    fn long_payload_is_hashed() {
+        let record: Vec<u8, 300> = (0..300).map(|i| b'a' + (i % 26) as u8).collect();
+        let call = Call::Datalog(&record);
+        let builder = ExtrinsicBuilder::new(&CHAIN, 1);
+        assert_eq!(builder.payload(&call).unwrap().len(), 381);
+
+        let mut expected: Vec<u8, 512> = unhex(concat!(
+            "5d068400",
+            "e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff",
+            "0030ec92f17dd597bb936bea7aa4e54591401be783216eb3767c4b54fc41937c",
+            "5df9cfc9a357b5693f856c9db06ef9edc032d6955acae386d6a02d0b47fbfdaf02",
+            "00040000",
+            "3300b104",
+        ));
+        expected.extend_from_slice(&record).unwrap();
+        assert_eq!(builder.sign(&pair(), &call).unwrap()[..], expected[..]);
+    }
+
+    #[test]
+    fn mortal_era() {
+        // `sp_runtime::generic::Era` tests and its encoding of the same eras.
+        for (period, current, expected, encoded) in [
+            (64, 42, (64, 42), [0xa5, 0x02]),
+            (32768, 20000, (32768, 20000), [0x4e, 0x9c]),
+            (200, 513, (256, 1), [0x17, 0x00]),
+            (2, 1, (4, 1), [0x11, 0x00]),
+            (4, 5, (4, 1), [0x11, 0x00]),
+            (
+                1000000,
+                1000001,
+                (65536, 1000001 % 65536 / 4 * 4),
+                [0x4f, 0x42],
+            ),
+        ] {
+            let era = Era::mortal(period, current);
+            let (period, phase) = expected;
+            assert_eq!(era, Era::Mortal { period, phase });
+            assert_eq!(era.encode::<2>().unwrap()[..], encoded);
+        }
+        assert_eq!(Era::Immortal.encode::<1>().unwrap()[..], [0]);
+    }
+
+    #[test]
+    fn era_birth() {
+        let era = Era::mortal(4, 6);
+        for current in 6..10 {
+            assert_eq!(era.birth(current), 6);
+        }
+        let era = Era::mortal(32768, 20000);
+        assert_eq!(era.birth(20000), 20000);
+        assert_eq!(era.birth(40000), 20000);
+        assert_eq!(era.birth(60000), 52768);
+        assert_eq!(Era::Immortal.birth(1000), 0);
+    }
+}
@@ -0,0 +1,79 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! On-chip SPI flash memory access through ESP32-C3 ROM functions.
+//!
+//! [`Flash`] implements [`NorFlash`](embedded_storage::nor_flash::NorFlash) for
+//! wear-aware users like key-value store and [`Storage`](embedded_storage::Storage)
+//! with sector read-modify-write for partition table and OTA.
+//!
+//! Without `esp32c3` feature only [`RamFlash`] is available, flash data
+//! structures are tested on top of it.
+
+/// Named flash partitions shared between tasks.
+#[cfg(feature = "esp32c3")]
+pub mod partition;
+#[cfg(feature = "esp32c3")]
+pub use partition::{Partition, SharedFlash};
+
+/// RAM-backed flash memory stand-in.
+pub mod ram;
+pub use ram::RamFlash;
+
+/// Flash access through ROM functions.
+#[cfg(feature = "esp32c3")]
+mod rom;
+#[cfg(feature = "esp32c3")]
+pub use rom::Flash;
+
+use embedded_storage::nor_flash::{NorFlashError, NorFlashErrorKind};
+
+/// Minimal erasable unit of flash memory.
+pub const SECTOR_SIZE: usize = 4096;
+
+/// Flash memory capacity of ESP32-C3FH4.
+pub const FLASH_SIZE: usize = 4 * 1024 * 1024;
+
+/// Flash operation errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlashError {
+    /// Address or length is not aligned to read, write or erase unit.
+    NotAligned,
+    /// Operation is out of flash memory bounds.
+    OutOfBounds,
+    /// ROM function returned error or timeout.
+    Rom(i32),
+}
+
+impl NorFlashError for FlashError {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Self::NotAligned => NorFlashErrorKind::NotAligned,
+            Self::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            Self::Rom(_) => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl From<NorFlashErrorKind> for FlashError {
+    fn from(kind: NorFlashErrorKind) -> Self {
+        match kind {
+            NorFlashErrorKind::NotAligned => Self::NotAligned,
+            _ => Self::OutOfBounds,
+        }
+    }
+}
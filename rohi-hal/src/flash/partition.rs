@@ -0,0 +1,109 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Flash partitions shared between tasks.
+//!
+//! Flash memory is owned by [`SharedFlash`] mutex, so OTA updates, key-value store
+//! and measurement log could use it concurrently. [`Partition`] locks flash for
+//! every single operation and restricts access to the partition bounds, addresses
+//! are relative to partition start.
+
+use core::ops::Range;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::Mutex};
+use embedded_storage::nor_flash::{ErrorType, NorFlash as _, ReadNorFlash as _};
+use embedded_storage_async::nor_flash::{MultiwriteNorFlash, NorFlash, ReadNorFlash};
+use esp_bootloader_esp_idf::partitions::{self, PARTITION_TABLE_MAX_LEN};
+
+use super::{Flash, FlashError, SECTOR_SIZE};
+
+/// Flash memory shared between firmware subsystems.
+pub type SharedFlash = Mutex<CriticalSectionRawMutex, Flash>;
+
+/// Flash partition found by label in partition table.
+#[derive(Clone, Copy)]
+pub struct Partition<'a> {
+    flash: &'a SharedFlash,
+    offset: u32,
+    size: u32,
+}
+
+impl<'a> Partition<'a> {
+    /// Find partition with given label, e.g. `config`.
+    pub async fn find(flash: &'a SharedFlash, label: &str) -> Result<Self, partitions::Error> {
+        let mut buf = [0u8; PARTITION_TABLE_MAX_LEN];
+        let mut guard = flash.lock().await;
+        let table = partitions::read_partition_table(&mut *guard, &mut buf)?;
+        let entry = table
+            .iter()
+            .find(|entry| entry.label_as_str() == label)
+            .ok_or(partitions::Error::Invalid)?;
+        Ok(Self {
+            flash,
+            offset: entry.offset(),
+            size: entry.len(),
+        })
+    }
+
+    /// Absolute flash address range of partition.
+    pub fn range(&self) -> Range<u32> {
+        self.offset..self.offset + self.size
+    }
+
+    fn check(&self, offset: u32, len: usize) -> Result<u32, FlashError> {
+        match offset.checked_add(len as u32) {
+            Some(end) if end <= self.size => Ok(self.offset + offset),
+            _ => Err(FlashError::OutOfBounds),
+        }
+    }
+}
+
+impl ErrorType for Partition<'_> {
+    type Error = FlashError;
+}
+
+impl ReadNorFlash for Partition<'_> {
+    const READ_SIZE: usize = Flash::READ_SIZE;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let address = self.check(offset, bytes.len())?;
+        self.flash.lock().await.read(address, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        self.size as usize
+    }
+}
+
+impl NorFlash for Partition<'_> {
+    const WRITE_SIZE: usize = Flash::WRITE_SIZE;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        let address = self.check(from, to.saturating_sub(from) as usize)?;
+        self.flash
+            .lock()
+            .await
+            .erase(address, address + (to - from))
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        let address = self.check(offset, bytes.len())?;
+        self.flash.lock().await.write(address, bytes)
+    }
+}
+
+impl MultiwriteNorFlash for Partition<'_> {}
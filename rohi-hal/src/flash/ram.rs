@@ -0,0 +1,218 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! RAM-backed flash memory with NOR semantics.
+//!
+//! Writes could only clear bits and erase sets whole sector to `0xFF`, exactly
+//! as on-chip flash does. Useful to exercise flash data structures on the host
+//! or in RAM without wearing real memory.
+//!
+//! [`Storage`] writes do sector read-modify-write like on-chip flash, so partition
+//! table and OTA data could be kept here too.
+//!
+//! Power loss is simulated by [`RamFlash::lose_power_after`]: write in progress
+//! is left partially programmed and memory is inaccessible until power is back.
+
+use embedded_storage::nor_flash::{
+    self as blocking, ErrorType, NorFlashErrorKind, check_erase, check_read, check_write,
+};
+use embedded_storage::{ReadStorage, Storage};
+use embedded_storage_async::nor_flash::{MultiwriteNorFlash, NorFlash, ReadNorFlash};
+
+use super::SECTOR_SIZE;
+
+/// Flash memory stand-in of `SIZE` bytes, should be a multiple of sector size.
+#[derive(Clone)]
+pub struct RamFlash<const SIZE: usize> {
+    memory: [u8; SIZE],
+    erases: u32,
+    /// Bytes left to write before power loss.
+    budget: Option<usize>,
+    powered: bool,
+}
+
+impl<const SIZE: usize> RamFlash<SIZE> {
+    /// New erased memory.
+    pub const fn new() -> Self {
+        Self {
+            memory: [0xFF; SIZE],
+            erases: 0,
+            budget: None,
+            powered: true,
+        }
+    }
+
+    /// Lose power once given count of bytes is written, all operations fail after that.
+    pub fn lose_power_after(&mut self, bytes: usize) {
+        self.budget = Some(bytes);
+    }
+
+    /// Power memory again, written data is kept.
+    pub fn restore_power(&mut self) {
+        self.budget = None;
+        self.powered = true;
+    }
+
+    /// Power is not lost.
+    pub fn is_powered(&self) -> bool {
+        self.powered
+    }
+
+    fn check_power(&self) -> Result<(), NorFlashErrorKind> {
+        if self.powered {
+            Ok(())
+        } else {
+            Err(NorFlashErrorKind::Other)
+        }
+    }
+
+    /// Raw memory content.
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    /// Count of sector erases performed, to estimate wear.
+    pub fn erases(&self) -> u32 {
+        self.erases
+    }
+}
+
+impl<const SIZE: usize> Default for RamFlash<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SIZE: usize> ErrorType for RamFlash<SIZE> {
+    type Error = NorFlashErrorKind;
+}
+
+impl<const SIZE: usize> blocking::ReadNorFlash for RamFlash<SIZE> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_power()?;
+        check_read(self, offset, bytes.len())?;
+        let offset = offset as usize;
+        bytes.copy_from_slice(&self.memory[offset..offset + bytes.len()]);
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+impl<const SIZE: usize> blocking::NorFlash for RamFlash<SIZE> {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        self.check_power()?;
+        check_erase(self, from, to)?;
+        self.memory[from as usize..to as usize].fill(0xFF);
+        self.erases += (to - from) / SECTOR_SIZE as u32;
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_power()?;
+        check_write(self, offset, bytes.len())?;
+        let len = match self.budget {
+            Some(budget) => budget.min(bytes.len()),
+            None => bytes.len(),
+        };
+        let offset = offset as usize;
+        for (cell, byte) in self.memory[offset..offset + len].iter_mut().zip(bytes) {
+            *cell &= *byte;
+        }
+        if let Some(budget) = &mut self.budget {
+            *budget -= len;
+            if len < bytes.len() {
+                self.powered = false;
+                return Err(NorFlashErrorKind::Other);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<const SIZE: usize> blocking::MultiwriteNorFlash for RamFlash<SIZE> {}
+
+impl<const SIZE: usize> ReadNorFlash for RamFlash<SIZE> {
+    const READ_SIZE: usize = 1;
+
+    async fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        blocking::ReadNorFlash::read(self, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+impl<const SIZE: usize> NorFlash for RamFlash<SIZE> {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    async fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        blocking::NorFlash::erase(self, from, to)
+    }
+
+    async fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        blocking::NorFlash::write(self, offset, bytes)
+    }
+}
+
+impl<const SIZE: usize> MultiwriteNorFlash for RamFlash<SIZE> {}
+
+impl<const SIZE: usize> ReadStorage for RamFlash<SIZE> {
+    type Error = NorFlashErrorKind;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        blocking::ReadNorFlash::read(self, offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        SIZE
+    }
+}
+
+impl<const SIZE: usize> Storage for RamFlash<SIZE> {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_power()?;
+        if offset as usize + bytes.len() > SIZE {
+            return Err(NorFlashErrorKind::OutOfBounds);
+        }
+        let mut written = 0;
+        while written < bytes.len() {
+            let address = offset as usize + written;
+            let sector_start = address - address % SECTOR_SIZE;
+            let in_sector = address - sector_start;
+            let len = (SECTOR_SIZE - in_sector).min(bytes.len() - written);
+
+            let mut sector = [0u8; SECTOR_SIZE];
+            sector.copy_from_slice(&self.memory[sector_start..sector_start + SECTOR_SIZE]);
+            sector[in_sector..in_sector + len].copy_from_slice(&bytes[written..written + len]);
+            let sector_start = sector_start as u32;
+            blocking::NorFlash::erase(self, sector_start, sector_start + SECTOR_SIZE as u32)?;
+            blocking::NorFlash::write(self, sector_start, &sector)?;
+            written += len;
+        }
+        Ok(())
+    }
+}
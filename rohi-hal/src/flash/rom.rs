@@ -0,0 +1,195 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! ESP32-C3 ROM flash functions, they are called in critical section from RAM.
+
+use embedded_storage::nor_flash::{
+    ErrorType, MultiwriteNorFlash, NorFlash, ReadNorFlash, check_erase, check_read, check_write,
+};
+use embedded_storage::{ReadStorage, Storage};
+use esp_hal::peripherals::FLASH;
+use esp_rom_sys::rom::spiflash::{
+    ESP_ROM_SPIFLASH_RESULT_OK, esp_rom_spiflash_erase_sector, esp_rom_spiflash_read,
+    esp_rom_spiflash_unlock, esp_rom_spiflash_write,
+};
+
+use super::{FLASH_SIZE, FlashError, SECTOR_SIZE};
+
+/// Words copied through aligned buffer per single ROM call.
+const CHUNK_WORDS: usize = 64;
+
+fn rom_result(code: i32) -> Result<(), FlashError> {
+    if code == ESP_ROM_SPIFLASH_RESULT_OK {
+        Ok(())
+    } else {
+        Err(FlashError::Rom(code))
+    }
+}
+
+// Flash is not accessible by cache during ROM operations, so callers should live in RAM.
+
+#[esp_hal::ram]
+fn rom_read(address: u32, words: &mut [u32]) -> Result<(), FlashError> {
+    let len = (words.len() * 4) as u32;
+    rom_result(critical_section::with(|_| unsafe {
+        esp_rom_spiflash_read(address, words.as_mut_ptr(), len)
+    }))
+}
+
+#[esp_hal::ram]
+fn rom_write(address: u32, words: &[u32]) -> Result<(), FlashError> {
+    let len = (words.len() * 4) as u32;
+    rom_result(critical_section::with(|_| unsafe {
+        esp_rom_spiflash_unlock();
+        esp_rom_spiflash_write(address, words.as_ptr(), len)
+    }))
+}
+
+#[esp_hal::ram]
+fn rom_erase_sector(sector: u32) -> Result<(), FlashError> {
+    rom_result(critical_section::with(|_| unsafe {
+        esp_rom_spiflash_unlock();
+        esp_rom_spiflash_erase_sector(sector)
+    }))
+}
+
+/// Read any amount of bytes from any address through aligned word buffer.
+fn read_bytes(offset: u32, bytes: &mut [u8]) -> Result<(), FlashError> {
+    let end = offset + bytes.len() as u32;
+    let mut words = [0u32; CHUNK_WORDS];
+    let mut position = offset & !3;
+    while position < end {
+        let count = ((end - position).div_ceil(4) as usize).min(CHUNK_WORDS);
+        rom_read(position, &mut words[..count])?;
+        for (i, byte) in words[..count]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .enumerate()
+        {
+            let address = position + i as u32;
+            if (offset..end).contains(&address) {
+                bytes[(address - offset) as usize] = byte;
+            }
+        }
+        position += (count * 4) as u32;
+    }
+    Ok(())
+}
+
+/// Write word aligned bytes to word aligned address.
+fn write_bytes(offset: u32, bytes: &[u8]) -> Result<(), FlashError> {
+    let mut words = [0u32; CHUNK_WORDS];
+    for (i, chunk) in bytes.chunks(CHUNK_WORDS * 4).enumerate() {
+        let count = chunk.len() / 4;
+        for (word, src) in words.iter_mut().zip(chunk.chunks_exact(4)) {
+            *word = u32::from_le_bytes([src[0], src[1], src[2], src[3]]);
+        }
+        rom_write(offset + (i * CHUNK_WORDS * 4) as u32, &words[..count])?;
+    }
+    Ok(())
+}
+
+/// On-chip flash memory.
+///
+/// Keeps one sector buffer for [`Storage`] writes, so better to be allocated statically.
+pub struct Flash {
+    sector: [u8; SECTOR_SIZE],
+}
+
+impl Flash {
+    /// Take flash memory peripheral.
+    pub fn new(_flash: FLASH<'static>) -> Self {
+        Self {
+            sector: [0; SECTOR_SIZE],
+        }
+    }
+}
+
+impl ErrorType for Flash {
+    type Error = FlashError;
+}
+
+impl ReadNorFlash for Flash {
+    const READ_SIZE: usize = 4;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        check_read(self, offset, bytes.len())?;
+        read_bytes(offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        FLASH_SIZE
+    }
+}
+
+impl NorFlash for Flash {
+    const WRITE_SIZE: usize = 4;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        check_erase(self, from, to)?;
+        for sector in from / SECTOR_SIZE as u32..to / SECTOR_SIZE as u32 {
+            rom_erase_sector(sector)?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        check_write(self, offset, bytes.len())?;
+        write_bytes(offset, bytes)
+    }
+}
+
+/// NOR flash bits could be cleared by several writes without erase.
+impl MultiwriteNorFlash for Flash {}
+
+impl ReadStorage for Flash {
+    type Error = FlashError;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > FLASH_SIZE {
+            return Err(FlashError::OutOfBounds);
+        }
+        read_bytes(offset, bytes)
+    }
+
+    fn capacity(&self) -> usize {
+        FLASH_SIZE
+    }
+}
+
+impl Storage for Flash {
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        if offset as usize + bytes.len() > FLASH_SIZE {
+            return Err(FlashError::OutOfBounds);
+        }
+        let mut written = 0;
+        while written < bytes.len() {
+            let address = offset as usize + written;
+            let sector_start = address - address % SECTOR_SIZE;
+            let in_sector = address - sector_start;
+            let len = (SECTOR_SIZE - in_sector).min(bytes.len() - written);
+
+            read_bytes(sector_start as u32, &mut self.sector)?;
+            self.sector[in_sector..in_sector + len].copy_from_slice(&bytes[written..written + len]);
+            rom_erase_sector((sector_start / SECTOR_SIZE) as u32)?;
+            write_bytes(sector_start as u32, &self.sector)?;
+            written += len;
+        }
+        Ok(())
+    }
+}
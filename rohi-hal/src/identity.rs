@@ -0,0 +1,505 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Robonomics account of device.
+//!
+//! Device identity is a 32 bytes [`Seed`] kept in config partition under
+//! [`SEED_KEY`]. It is generated on first start or imported from `subkey`
+//! output, mnemonic phrases are not supported. Account keys are derived from
+//! seed the same way as Substrate does:
+//!
+//! - sr25519 keys are expanded in ed25519 mode and sign in `substrate` context,
+//!   both `//hard` and `/soft` junctions are supported;
+//! - ed25519 keys use seed as secret key and support `//hard` junctions only.
+//!
+//! ```ignore
+//! let seed = Seed::load_or_generate(&mut store, &mut Trng::try_new()?).await?;
+//! let pair = Keypair::from_seed(Scheme::Sr25519, &seed).derive("//robonomics")?;
+//! info!("Account: {}", pair.public());
+//! let signature = pair.sign(payload);
+//! ```
+//!
+//! Secret URIs like `0x<seed>//robonomics//0` printed by `subkey inspect` are
+//! accepted by [`Keypair::from_uri`].
+
+/// SS58 address format.
+pub mod ss58;
+pub use ss58::{AddressError, SS58_PREFIX};
+
+use core::fmt;
+use embedded_storage_async::nor_flash::MultiwriteNorFlash;
+use heapless::String;
+use log::info;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
+use schnorrkel::derive::{ChainCode, Derivation};
+use schnorrkel::{ExpansionMode, MiniSecretKey};
+use serde::{Deserialize, Serialize};
+
+use crate::scale::{Encode, Overflow};
+use crate::storage::{Key, KvStore, StoreError};
+
+/// Store key of device seed.
+pub const SEED_KEY: Key<Seed> = Key::new(2);
+
+/// Signing context of sr25519 signatures in Substrate.
+pub const SIGNING_CONTEXT: &[u8] = b"substrate";
+
+/// Identity errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdentityError {
+    /// Seed is not a 32 bytes hex string.
+    InvalidSeed,
+    /// Derivation path is malformed.
+    InvalidPath,
+    /// Soft derivation is not possible for ed25519 keys.
+    SoftDerivation,
+}
+
+/// Signature scheme of account keys.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scheme {
+    #[default]
+    Sr25519,
+    Ed25519,
+}
+
+/// Secret seed of device keys.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Seed([u8; 32]);
+
+impl Seed {
+    /// Seed from raw bytes.
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Seed from hex string, with or without `0x` prefix.
+    pub fn from_hex(hex: &str) -> Result<Self, IdentityError> {
+        let hex = hex.strip_prefix("0x").unwrap_or(hex).as_bytes();
+        if hex.len() != 64 {
+            return Err(IdentityError::InvalidSeed);
+        }
+        let mut bytes = [0u8; 32];
+        for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+            let pair = core::str::from_utf8(pair).map_err(|_| IdentityError::InvalidSeed)?;
+            *byte = u8::from_str_radix(pair, 16).map_err(|_| IdentityError::InvalidSeed)?;
+        }
+        Ok(Self(bytes))
+    }
+
+    /// Random seed, `rng` should be a true random source like `esp_hal::rng::Trng`.
+    pub fn generate(rng: &mut impl CryptoRngCore) -> Self {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    /// Raw seed bytes.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Read device seed from store, new one is generated and saved when missing.
+    pub async fn load_or_generate<S: MultiwriteNorFlash>(
+        store: &mut KvStore<S>,
+        rng: &mut impl CryptoRngCore,
+    ) -> Result<Self, StoreError<S::Error>> {
+        if let Some(seed) = store.get(&SEED_KEY).await? {
+            return Ok(seed);
+        }
+        let seed = Self::generate(rng);
+        seed.save(store).await?;
+        info!("[Identity] New device seed generated");
+        Ok(seed)
+    }
+
+    /// Write seed to store, e.g. imported one.
+    pub async fn save<S: MultiwriteNorFlash>(
+        &self,
+        store: &mut KvStore<S>,
+    ) -> Result<(), StoreError<S::Error>> {
+        store.set(&SEED_KEY, self).await
+    }
+}
+
+/// Seed never goes to logs.
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Seed(..)")
+    }
+}
+
+/// Account public key, displayed as Robonomics SS58 address.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AccountId(pub [u8; 32]);
+
+impl AccountId {
+    /// Parse SS58 address of any network.
+    pub fn from_ss58(address: &str) -> Result<Self, AddressError> {
+        ss58::decode(address).map(|(_, public)| Self(public))
+    }
+
+    /// SS58 address with given network prefix.
+    pub fn to_ss58(&self, prefix: u16) -> String<{ ss58::ADDRESS_MAX_LEN }> {
+        ss58::encode(prefix, &self.0)
+    }
+}
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_ss58(SS58_PREFIX))
+    }
+}
+
+/// Signature made by [`Keypair`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signature {
+    Sr25519([u8; 64]),
+    Ed25519([u8; 64]),
+}
+
+impl Signature {
+    /// Raw signature bytes.
+    pub fn as_bytes(&self) -> &[u8; 64] {
+        match self {
+            Self::Sr25519(bytes) | Self::Ed25519(bytes) => bytes,
+        }
+    }
+
+    /// Check signature of `message` made by `account`.
+    pub fn verify(&self, message: &[u8], account: &AccountId) -> bool {
+        match self {
+            Self::Sr25519(bytes) => {
+                let (Ok(public), Ok(signature)) = (
+                    schnorrkel::PublicKey::from_bytes(&account.0),
+                    schnorrkel::Signature::from_bytes(bytes),
+                ) else {
+                    return false;
+                };
+                public
+                    .verify_simple(SIGNING_CONTEXT, message, &signature)
+                    .is_ok()
+            }
+            Self::Ed25519(bytes) => {
+                let Ok(public) = ed25519_dalek::VerifyingKey::from_bytes(&account.0) else {
+                    return false;
+                };
+                let signature = ed25519_dalek::Signature::from_bytes(bytes);
+                public.verify_strict(message, &signature).is_ok()
+            }
+        }
+    }
+}
+
+/// Account key pair.
+#[derive(Clone)]
+pub enum Keypair {
+    Sr25519(schnorrkel::Keypair),
+    Ed25519(ed25519_dalek::SigningKey),
+}
+
+impl Keypair {
+    /// Root key pair of seed.
+    pub fn from_seed(scheme: Scheme, seed: &Seed) -> Self {
+        match scheme {
+            Scheme::Sr25519 => Self::Sr25519(sr25519_from_seed(&seed.0)),
+            Scheme::Ed25519 => Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(&seed.0)),
+        }
+    }
+
+    /// Key pair from secret URI `0x<hex seed>[//hard][/soft]...`.
+    pub fn from_uri(scheme: Scheme, uri: &str) -> Result<Self, IdentityError> {
+        let (seed, path) = uri.split_at(uri.find('/').unwrap_or(uri.len()));
+        Self::from_seed(scheme, &Seed::from_hex(seed)?).derive(path)
+    }
+
+    /// Derive key pair by path like `//robonomics//0`.
+    pub fn derive(&self, path: &str) -> Result<Self, IdentityError> {
+        let mut pair = self.clone();
+        let mut rest = path;
+        while !rest.is_empty() {
+            let junction = rest.strip_prefix('/').ok_or(IdentityError::InvalidPath)?;
+            let (hard, junction) = match junction.strip_prefix('/') {
+                Some(junction) => (true, junction),
+                None => (false, junction),
+            };
+            let (name, tail) = junction.split_at(junction.find('/').unwrap_or(junction.len()));
+            if name.is_empty() {
+                return Err(IdentityError::InvalidPath);
+            }
+            pair = pair.derive_junction(hard, chain_code(name))?;
+            rest = tail;
+        }
+        Ok(pair)
+    }
+
+    fn derive_junction(&self, hard: bool, code: [u8; 32]) -> Result<Self, IdentityError> {
+        match (self, hard) {
+            (Self::Sr25519(pair), true) => {
+                let (mini, _) = pair
+                    .secret
+                    .hard_derive_mini_secret_key(Some(ChainCode(code)), b"");
+                Ok(Self::Sr25519(
+                    mini.expand_to_keypair(ExpansionMode::Ed25519),
+                ))
+            }
+            (Self::Sr25519(pair), false) => Ok(Self::Sr25519(
+                pair.derived_key_simple_rng(ChainCode(code), b"", NoEntropy)
+                    .0,
+            )),
+            (Self::Ed25519(pair), true) => {
+                let seed = (ED25519_HDKD, pair.as_bytes(), code).blake2_256();
+                Ok(Self::Ed25519(ed25519_dalek::SigningKey::from_bytes(&seed)))
+            }
+            (Self::Ed25519(_), false) => Err(IdentityError::SoftDerivation),
+        }
+    }
+
+    /// Signature scheme of key pair.
+    pub fn scheme(&self) -> Scheme {
+        match self {
+            Self::Sr25519(_) => Scheme::Sr25519,
+            Self::Ed25519(_) => Scheme::Ed25519,
+        }
+    }
+
+    /// Account of key pair.
+    pub fn public(&self) -> AccountId {
+        match self {
+            Self::Sr25519(pair) => AccountId(pair.public.to_bytes()),
+            Self::Ed25519(pair) => AccountId(pair.verifying_key().to_bytes()),
+        }
+    }
+
+    /// Sign message.
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        match self {
+            Self::Sr25519(pair) => {
+                let transcript = schnorrkel::signing_context(SIGNING_CONTEXT).bytes(message);
+                let transcript = schnorrkel::context::attach_rng(transcript, NoEntropy);
+                Signature::Sr25519(pair.sign(transcript).to_bytes())
+            }
+            Self::Ed25519(pair) => {
+                use ed25519_dalek::Signer;
+                Signature::Ed25519(pair.sign(message).to_bytes())
+            }
+        }
+    }
+}
+
+/// Secrets never go to logs.
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("scheme", &self.scheme())
+            .field("public", &self.public())
+            .finish()
+    }
+}
+
+/// Ed25519 hard derivation domain separator.
+const ED25519_HDKD: &str = "Ed25519HDKD";
+
+fn sr25519_from_seed(seed: &[u8; 32]) -> schnorrkel::Keypair {
+    // Any 32 bytes are valid mini secret key.
+    MiniSecretKey::from_bytes(seed)
+        .expect("mini secret key length")
+        .expand_to_keypair(ExpansionMode::Ed25519)
+}
+
+/// Chain code of derivation junction: SCALE encoded number or string,
+/// hashed when longer than 32 bytes.
+fn chain_code(name: &str) -> [u8; 32] {
+    let mut code = [0u8; 32];
+    if let Ok(index) = name.parse::<u64>() {
+        code[..8].copy_from_slice(&index.to_le_bytes());
+        return code;
+    }
+    match name.encode::<32>() {
+        Ok(encoded) => code[..encoded.len()].copy_from_slice(&encoded),
+        Err(Overflow) => code = name.blake2_256(),
+    }
+    code
+}
+
+/// Sr25519 signing and soft derivation nonces are derived from secret key and
+/// transcript, so they stay secure without extra randomness, like ed25519 does.
+struct NoEntropy;
+
+impl RngCore for NoEntropy {
+    fn next_u32(&mut self) -> u32 {
+        0
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        0
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        dest.fill(0);
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        dest.fill(0);
+        Ok(())
+    }
+}
+
+impl CryptoRng for NoEntropy {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seed of RFC 8032 test 1, vectors are `subkey inspect --network robonomics`.
+    const SEED: &str = "0x9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60";
+
+    fn public(hex: &str) -> AccountId {
+        AccountId(Seed::from_hex(hex).unwrap().0)
+    }
+
+    fn check(scheme: Scheme, path: &str, expected: &str, address: &str) {
+        let uri = [SEED, path].concat();
+        let account = Keypair::from_uri(scheme, &uri).unwrap().public();
+        assert_eq!(account, public(expected), "{scheme:?} {uri}");
+        assert_eq!(account.to_ss58(SS58_PREFIX).as_str(), address);
+        assert_eq!(AccountId::from_ss58(address), Ok(account));
+    }
+
+    #[test]
+    fn sr25519_matches_subkey() {
+        let check = |path, public, address| check(Scheme::Sr25519, path, public, address);
+        check(
+            "",
+            "44a996beb1eef7bdcab976ab6d2ca26104834164ecf28fb375600576fcc6eb0f",
+            "4Dk8uZ2DZxjAgd9iC3e1mRGw5qYdZLwQukWo6FPkC9mFZEgS",
+        );
+        check(
+            "//robonomics",
+            "3c312ce9cdcae342e5a67b4c5a06a422d7bc351baa5a7f041eb47e8e39051363",
+            "4DZ2kfDcE2RF4gttefLoRddfvXdW3yzWAjACw3DmVfpGLdpb",
+        );
+        check(
+            "//robonomics//0",
+            "3493d012031dc2dcd677e5764d26d06d54699aa856e1803d34de1079e604ce6c",
+            "4DP3fvUuCod5TD4Qr3PSoMmEootLx8hyvihBj9aeetCq4fur",
+        );
+        check(
+            "/soft",
+            "eed378e4a2bba61d9cce957a5cdaab8043ea86731ce9efd5e0360edb3ce0b615",
+            "4HbFVL6nkR8he1qFAS56CSDdjvjc2sMYx8kAWx4uc2gug9Y3",
+        );
+        check(
+            "//robonomics/soft",
+            "4443b5069b49ba196af278a31963372141048f43fff9ecfc1530c8114fcc0e04",
+            "4DjceAvF5AYeDThVUoqrSkfoNpnYX7hbPamBJ9nvrqdRRCwA",
+        );
+    }
+
+    #[test]
+    fn ed25519_matches_subkey() {
+        let check = |path, public, address| check(Scheme::Ed25519, path, public, address);
+        check(
+            "",
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a",
+            "4H4UUmHsKP4Meg7o5gRFBqG57ExhuKJVLEFYuATBHGbFzzi4",
+        );
+        check(
+            "//robonomics",
+            "e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff",
+            "4HUALi2CgDMdhHA2GfkPWD1pwN3VjNsEvECKHAg3ETfYcVXL",
+        );
+        check(
+            "//robonomics//0",
+            "3844dacc5c0e70dc67e261d9a1fffe8e0af3e0b7af9b87f342f9ad65edbe2c74",
+            "4DTtQcg2WcbJ8HKhBRo1P57zWTWGVt4oo5Nh8ERWajRKG9sX",
+        );
+        assert_eq!(
+            Keypair::from_uri(Scheme::Ed25519, &[SEED, "/soft"].concat()).unwrap_err(),
+            IdentityError::SoftDerivation
+        );
+    }
+
+    #[test]
+    fn sign_verify() {
+        let message = b"robonomics datalog";
+        for scheme in [Scheme::Sr25519, Scheme::Ed25519] {
+            let pair = Keypair::from_uri(scheme, &[SEED, "//robonomics"].concat()).unwrap();
+            let signature = pair.sign(message);
+            assert!(signature.verify(message, &pair.public()), "{scheme:?}");
+            assert!(!signature.verify(b"robonomics datalog!", &pair.public()));
+
+            let other = Keypair::from_uri(scheme, SEED).unwrap().public();
+            assert!(!signature.verify(message, &other));
+
+            let mut bytes = *signature.as_bytes();
+            bytes[0] ^= 1;
+            let tampered = match signature {
+                Signature::Sr25519(_) => Signature::Sr25519(bytes),
+                Signature::Ed25519(_) => Signature::Ed25519(bytes),
+            };
+            assert!(!tampered.verify(message, &pair.public()));
+        }
+    }
+
+    #[test]
+    fn ed25519_signature_matches_rfc8032() {
+        let pair = Keypair::from_uri(Scheme::Ed25519, SEED).unwrap();
+        let expected = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e06522490155\
+                        5fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&Seed::from_hex(&expected[..64]).unwrap().0);
+        bytes[32..].copy_from_slice(&Seed::from_hex(&expected[64..]).unwrap().0);
+        assert_eq!(pair.sign(b"").as_bytes(), &bytes);
+    }
+
+    #[test]
+    fn ss58_two_byte_prefix() {
+        let account = Keypair::from_uri(Scheme::Sr25519, SEED).unwrap().public();
+        for (prefix, address) in [
+            (64, "cEX8EPtGQH8ZQLuPNak9h3sN2ZhdMprJU2rTZNVxMzpRvHxsy"),
+            (2254, "st7pDZy9i7XvezqAw7Y5jkcRFHJN33sispRXqXdo3cd3ECp5Y"),
+            (16383, "yNWt6PjsFVvmyP96DJTGXh94DcegMBgymjqJojgzp7BTCG2Cc"),
+        ] {
+            assert_eq!(account.to_ss58(prefix).as_str(), address);
+            assert_eq!(ss58::decode(address), Ok((prefix, account.0)));
+        }
+    }
+
+    #[test]
+    fn ss58_errors() {
+        let address = "4Dk8uZ2DZxjAgd9iC3e1mRGw5qYdZLwQukWo6FPkC9mFZEgS";
+        assert_eq!(ss58::decode("4Dk8uZ0"), Err(AddressError::Base58));
+        assert_eq!(ss58::decode(&address[..20]), Err(AddressError::Length));
+        let mut corrupted: heapless::String<50> = address.try_into().unwrap();
+        corrupted.pop();
+        _ = corrupted.push('T');
+        assert_eq!(ss58::decode(&corrupted), Err(AddressError::Checksum));
+    }
+
+    #[test]
+    fn malformed_uris() {
+        for uri in [
+            "0x1234",
+            "not a seed",
+            &[SEED, "//"].concat(),
+            &[SEED, "///a"].concat(),
+        ] {
+            assert!(Keypair::from_uri(Scheme::Sr25519, uri).is_err(), "{uri}");
+        }
+    }
+}
@@ -0,0 +1,117 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! SS58 address format of Substrate accounts.
+//!
+//! Address is base58 of network prefix, public key and two bytes of
+//! `blake2b-512("SS58PRE" || prefix || public)` checksum.
+
+use blake2::{Blake2b512, Digest};
+use heapless::{String, Vec};
+
+/// Robonomics network address prefix.
+pub const SS58_PREFIX: u16 = 32;
+
+/// Maximal length of encoded address.
+pub const ADDRESS_MAX_LEN: usize = 50;
+
+const CHECKSUM_PREFIX: &[u8] = b"SS58PRE";
+const CHECKSUM_LEN: usize = 2;
+
+/// Address decoding errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    /// Not a base58 string.
+    Base58,
+    /// Address does not contain 32 bytes public key.
+    Length,
+    /// Prefix is out of range.
+    Prefix,
+    /// Checksum mismatch.
+    Checksum,
+}
+
+/// Encode network prefix, one byte for `0..64` and two bytes for `64..16384`.
+fn encode_prefix(prefix: u16) -> Vec<u8, 2> {
+    let mut bytes = Vec::new();
+    if prefix < 64 {
+        _ = bytes.push(prefix as u8);
+    } else {
+        let prefix = prefix & 0x3fff;
+        _ = bytes.push(((prefix & 0b1111_1100) >> 2) as u8 | 0b0100_0000);
+        _ = bytes.push((prefix >> 8) as u8 | ((prefix & 0b11) << 6) as u8);
+    }
+    bytes
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = Blake2b512::new()
+        .chain_update(CHECKSUM_PREFIX)
+        .chain_update(data)
+        .finalize();
+    [hash[0], hash[1]]
+}
+
+/// Encode public key as SS58 address with given network prefix.
+pub fn encode(prefix: u16, public: &[u8; 32]) -> String<ADDRESS_MAX_LEN> {
+    let mut data: Vec<u8, 36> = Vec::new();
+    _ = data.extend_from_slice(&encode_prefix(prefix));
+    _ = data.extend_from_slice(public);
+    let checksum = checksum(&data);
+    _ = data.extend_from_slice(&checksum);
+
+    let mut buf = [0u8; ADDRESS_MAX_LEN];
+    // 36 bytes never take more than 50 base58 digits.
+    let len = bs58::encode(&data).onto(&mut buf[..]).unwrap_or(0);
+    let mut address = String::new();
+    for &c in &buf[..len] {
+        _ = address.push(c as char);
+    }
+    address
+}
+
+/// Decode SS58 address into network prefix and public key.
+pub fn decode(address: &str) -> Result<(u16, [u8; 32]), AddressError> {
+    let mut buf = [0u8; 40];
+    let len = bs58::decode(address)
+        .onto(&mut buf[..])
+        .map_err(|_| AddressError::Base58)?;
+    let data = &buf[..len];
+
+    let (prefix, prefix_len) = match data.first() {
+        Some(&b) if b < 64 => (b as u16, 1),
+        Some(&b) if b < 128 => {
+            let &second = data.get(1).ok_or(AddressError::Length)?;
+            let lower = ((b << 2) | (second >> 6)) as u16;
+            let upper = (second & 0b0011_1111) as u16;
+            (lower | (upper << 8), 2)
+        }
+        Some(_) => return Err(AddressError::Prefix),
+        None => return Err(AddressError::Length),
+    };
+    if len != prefix_len + 32 + CHECKSUM_LEN {
+        return Err(AddressError::Length);
+    }
+
+    let (body, sum) = data.split_at(prefix_len + 32);
+    if checksum(body) != sum {
+        return Err(AddressError::Checksum);
+    }
+    let mut public = [0u8; 32];
+    public.copy_from_slice(&body[prefix_len..]);
+    Ok((prefix, public))
+}
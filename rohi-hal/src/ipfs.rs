@@ -0,0 +1,401 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! IPFS content identifiers.
+//!
+//! [`Cid::of`] computes identifier of payload the same way as `ipfs add` with
+//! default settings does: content is split into 256 KiB chunks, every chunk is
+//! UnixFS file node in DAG-PB encoding and chunks are linked from root node.
+//! CIDv1 uses raw leaves, as `ipfs add --cid-version=1`. Blocks are hashed
+//! in place with SHA-256 and never stored.
+//!
+//! ```ignore
+//! let cid = Cid::of(Version::V0, b"hello\n")?;
+//! assert_eq!(cid.encode(), "QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN");
+//! ```
+
+use core::fmt;
+use heapless::{String, Vec};
+use sha2::{Digest, Sha256};
+
+/// Size of file chunk, `size-262144` chunker of `ipfs add`.
+pub const CHUNK_SIZE: usize = 262_144;
+
+/// Most links in one DAG-PB node, larger payloads need several levels of nodes.
+pub const MAX_LINKS: usize = 174;
+
+/// Maximal length of encoded CID, reached by base32 CIDv1.
+pub const CID_MAX_LEN: usize = 59;
+
+/// Multicodec of DAG-PB nodes.
+pub const DAG_PB: u8 = 0x70;
+/// Multicodec of raw blocks.
+pub const RAW: u8 = 0x55;
+
+/// Multihash code of SHA-256.
+const SHA2_256: u8 = 0x12;
+/// `File` type of UnixFS node.
+const UNIXFS_FILE: u64 = 2;
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// CID errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CidError {
+    /// Payload needs more than [`MAX_LINKS`] chunks.
+    TooLarge,
+    /// Not a base58 CIDv0 or base32 CIDv1 string.
+    Encoding,
+    /// Codec or hash function is not supported.
+    Unsupported,
+}
+
+/// CID version.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Version {
+    /// Base58 `Qm...` identifiers, default of `ipfs add`.
+    #[default]
+    V0,
+    /// Base32 `b...` identifiers with raw leaves.
+    V1,
+}
+
+/// Content identifier with SHA-256 multihash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cid {
+    version: Version,
+    codec: u8,
+    digest: [u8; 32],
+}
+
+impl Cid {
+    /// CID of block with given codec and SHA-256 digest.
+    ///
+    /// CIDv0 is always DAG-PB, `codec` is ignored for it.
+    pub fn new(version: Version, codec: u8, digest: [u8; 32]) -> Self {
+        let codec = match version {
+            Version::V0 => DAG_PB,
+            Version::V1 => codec,
+        };
+        Self {
+            version,
+            codec,
+            digest,
+        }
+    }
+
+    /// CID of payload added to IPFS as file.
+    pub fn of(version: Version, payload: &[u8]) -> Result<Self, CidError> {
+        if payload.len() <= CHUNK_SIZE {
+            return Ok(match version {
+                Version::V0 => Self::new(version, DAG_PB, leaf(payload).0),
+                Version::V1 => Self::new(version, RAW, Sha256::digest(payload).into()),
+            });
+        }
+
+        // Digest, tree size and content size of every chunk.
+        let mut links: Vec<([u8; 32], u64, u64), MAX_LINKS> = Vec::new();
+        for chunk in payload.chunks(CHUNK_SIZE) {
+            let len = chunk.len() as u64;
+            let link = match version {
+                Version::V0 => {
+                    let (digest, block_len) = leaf(chunk);
+                    (digest, block_len, len)
+                }
+                Version::V1 => (Sha256::digest(chunk).into(), len, len),
+            };
+            links.push(link).map_err(|_| CidError::TooLarge)?;
+        }
+
+        let leaf_codec = match version {
+            Version::V0 => DAG_PB,
+            Version::V1 => RAW,
+        };
+        let unixfs_len = 2
+            + field_len(payload.len() as u64)
+            + links
+                .iter()
+                .map(|&(_, _, len)| field_len(len))
+                .sum::<usize>();
+        let mut hasher = Sha256::new();
+        for &(digest, tree_size, _) in &links {
+            let cid = Self::new(version, leaf_codec, digest).to_bytes();
+            let link_len = 2 + cid.len() + 2 + field_len(tree_size);
+            // PBNode.Links: Hash, empty Name and Tsize.
+            hasher.update([0x12]);
+            write_varint(&mut hasher, link_len as u64);
+            hasher.update([0x0a, cid.len() as u8]);
+            hasher.update(&cid);
+            hasher.update([0x12, 0x00, 0x18]);
+            write_varint(&mut hasher, tree_size);
+        }
+        // PBNode.Data: UnixFS with file size and sizes of chunks.
+        hasher.update([0x0a]);
+        write_varint(&mut hasher, unixfs_len as u64);
+        hasher.update([0x08, UNIXFS_FILE as u8, 0x18]);
+        write_varint(&mut hasher, payload.len() as u64);
+        for &(_, _, len) in &links {
+            hasher.update([0x20]);
+            write_varint(&mut hasher, len);
+        }
+        Ok(Self::new(version, DAG_PB, hasher.finalize().into()))
+    }
+
+    /// Parse base58 CIDv0 or base32 CIDv1.
+    pub fn parse(cid: &str) -> Result<Self, CidError> {
+        if cid.len() == 46 && cid.starts_with("Qm") {
+            let mut buf = [0u8; 34];
+            let len = bs58::decode(cid)
+                .onto(&mut buf[..])
+                .map_err(|_| CidError::Encoding)?;
+            return match buf[..len] {
+                [SHA2_256, 32, ref digest @ ..] if digest.len() == 32 => Ok(Self::new(
+                    Version::V0,
+                    DAG_PB,
+                    digest.try_into().unwrap_or_default(),
+                )),
+                _ => Err(CidError::Unsupported),
+            };
+        }
+
+        let encoded = cid.strip_prefix('b').ok_or(CidError::Encoding)?;
+        let mut bytes: Vec<u8, 36> = Vec::new();
+        let (mut acc, mut bits) = (0u32, 0);
+        for c in encoded.bytes() {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or(CidError::Encoding)?;
+            acc = (acc << 5) | value as u32;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                bytes
+                    .push((acc >> bits) as u8)
+                    .map_err(|_| CidError::Unsupported)?;
+            }
+        }
+        match bytes[..] {
+            [1, codec @ (DAG_PB | RAW), SHA2_256, 32, ref digest @ ..] if digest.len() == 32 => Ok(
+                Self::new(Version::V1, codec, digest.try_into().unwrap_or_default()),
+            ),
+            _ => Err(CidError::Unsupported),
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Multicodec of content, [`DAG_PB`] or [`RAW`].
+    pub fn codec(&self) -> u8 {
+        self.codec
+    }
+
+    /// SHA-256 digest of content block.
+    pub fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+
+    /// Binary form: multihash for CIDv0, version, codec and multihash for CIDv1.
+    pub fn to_bytes(&self) -> Vec<u8, 36> {
+        let mut bytes = Vec::new();
+        if self.version == Version::V1 {
+            _ = bytes.extend_from_slice(&[1, self.codec]);
+        }
+        _ = bytes.extend_from_slice(&[SHA2_256, 32]);
+        _ = bytes.extend_from_slice(&self.digest);
+        bytes
+    }
+
+    /// Text form: base58 for CIDv0, `b` prefixed base32 for CIDv1.
+    pub fn encode(&self) -> String<CID_MAX_LEN> {
+        let bytes = self.to_bytes();
+        let mut cid = String::new();
+        match self.version {
+            Version::V0 => {
+                let mut buf = [0u8; CID_MAX_LEN];
+                // 34 bytes never take more than 46 base58 digits.
+                let len = bs58::encode(&bytes).onto(&mut buf[..]).unwrap_or(0);
+                for &c in &buf[..len] {
+                    _ = cid.push(c as char);
+                }
+            }
+            Version::V1 => {
+                _ = cid.push('b');
+                let (mut acc, mut bits) = (0u32, 0);
+                for &byte in &bytes {
+                    acc = (acc << 8) | byte as u32;
+                    bits += 8;
+                    while bits >= 5 {
+                        bits -= 5;
+                        _ = cid.push(BASE32_ALPHABET[(acc >> bits) as usize & 31] as char);
+                    }
+                }
+                if bits > 0 {
+                    _ = cid.push(BASE32_ALPHABET[(acc << (5 - bits)) as usize & 31] as char);
+                }
+            }
+        }
+        cid
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+/// Digest and size of UnixFS file node holding `chunk`.
+fn leaf(chunk: &[u8]) -> ([u8; 32], u64) {
+    let len = chunk.len() as u64;
+    let data_len = if chunk.is_empty() {
+        0
+    } else {
+        field_len(len) + chunk.len()
+    };
+    let unixfs_len = 2 + data_len + field_len(len);
+
+    let mut hasher = Sha256::new();
+    hasher.update([0x0a]);
+    write_varint(&mut hasher, unixfs_len as u64);
+    hasher.update([0x08, UNIXFS_FILE as u8]);
+    if !chunk.is_empty() {
+        hasher.update([0x12]);
+        write_varint(&mut hasher, len);
+        hasher.update(chunk);
+    }
+    hasher.update([0x18]);
+    write_varint(&mut hasher, len);
+
+    let block_len = 1 + varint_len(unixfs_len as u64) + unixfs_len;
+    (hasher.finalize().into(), block_len as u64)
+}
+
+/// Length of protobuf varint field with one byte tag.
+fn field_len(value: u64) -> usize {
+    1 + varint_len(value)
+}
+
+fn varint_len(value: u64) -> usize {
+    (64 - (value | 1).leading_zeros() as usize).div_ceil(7)
+}
+
+fn write_varint(hasher: &mut Sha256, mut value: u64) {
+    while value >= 0x80 {
+        hasher.update([value as u8 | 0x80]);
+        value >>= 7;
+    }
+    hasher.update([value as u8]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two chunks payload, the second one is a single byte.
+    static PAYLOAD: [u8; CHUNK_SIZE + 1] = pattern();
+
+    const fn pattern() -> [u8; CHUNK_SIZE + 1] {
+        let mut payload = [0u8; CHUNK_SIZE + 1];
+        let mut i = 0;
+        while i < payload.len() {
+            payload[i] = (i % 251) as u8;
+            i += 1;
+        }
+        payload
+    }
+
+    // CIDv0 are `ipfs add`, CIDv1 are `ipfs add --cid-version=1`.
+    const VECTORS: [(&[u8], &str, &str); 4] = [
+        (
+            b"",
+            "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH",
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku",
+        ),
+        (
+            b"hello\n",
+            "QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN",
+            "bafkreicysg23kiwv34eg2d7qweipxwosdo2py4ldv42nbauguluen5v6am",
+        ),
+        (
+            PAYLOAD.split_at(CHUNK_SIZE).0,
+            "QmeqfRyS3vkku7n6krqC3DgGMex3x2sCpSeKMDmrG13QQq",
+            "bafkreibruh455iawsviqslif5c7uurdcfdemh22mtnytyzvnzn75kpejxy",
+        ),
+        (
+            &PAYLOAD,
+            "QmUSjGawaz4ptvREcMKSMJneWCa5j8dAz2wSAAvHtW2rnB",
+            "bafybeiexg2oqkfnj56l7fcmawswqbijt5shq4b5rg6a546uwpkqqzwjioi",
+        ),
+    ];
+
+    #[test]
+    fn cid_of_payload() {
+        for (payload, v0, v1) in VECTORS {
+            let len = payload.len();
+            assert_eq!(Cid::of(Version::V0, payload).unwrap().encode(), v0, "{len}");
+            assert_eq!(Cid::of(Version::V1, payload).unwrap().encode(), v1, "{len}");
+        }
+    }
+
+    #[test]
+    fn single_chunk_codecs() {
+        let v0 = Cid::of(Version::V0, b"hello\n").unwrap();
+        assert_eq!(v0.codec(), DAG_PB);
+        let v1 = Cid::of(Version::V1, b"hello\n").unwrap();
+        assert_eq!(v1.codec(), RAW);
+        let root = Cid::of(Version::V1, &PAYLOAD).unwrap();
+        assert_eq!(root.codec(), DAG_PB);
+    }
+
+    #[test]
+    fn parse_encode_round_trip() {
+        for (_, v0, v1) in VECTORS {
+            for encoded in [v0, v1] {
+                let cid = Cid::parse(encoded).unwrap();
+                assert_eq!(cid.encode(), encoded);
+                assert_eq!(Cid::parse(&cid.encode()), Ok(cid));
+            }
+        }
+        let cid = Cid::parse("QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN").unwrap();
+        assert_eq!(cid.version(), Version::V0);
+        assert_eq!(cid.to_bytes()[..2], [SHA2_256, 32]);
+        let cid =
+            Cid::parse("bafybeiexg2oqkfnj56l7fcmawswqbijt5shq4b5rg6a546uwpkqqzwjioi").unwrap();
+        assert_eq!(cid.version(), Version::V1);
+        assert_eq!(cid.to_bytes()[..4], [1, DAG_PB, SHA2_256, 32]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(Cid::parse(""), Err(CidError::Encoding));
+        assert_eq!(
+            Cid::parse("QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXE0"),
+            Err(CidError::Encoding)
+        );
+        assert_eq!(
+            Cid::parse("bafkreicysg23kiwv34eg2d7qweipxwosdo2py4ldv42nbauguluen5v6aM"),
+            Err(CidError::Encoding)
+        );
+        // Base32 with unsupported `dag-cbor` codec.
+        let mut cbor = Cid::new(Version::V1, 0x71, [7; 32]).encode();
+        assert_eq!(Cid::parse(&cbor), Err(CidError::Unsupported));
+        cbor.truncate(30);
+        assert_eq!(Cid::parse(&cbor), Err(CidError::Unsupported));
+    }
+}
@@ -27,6 +27,37 @@
 /// For example, Altruist is devkit for Air Quality sensing applications.
 pub mod board;
 
+/// Typed device configuration with versioned schema.
+pub mod config;
+
+/// Payload encryption for device owner: X25519 and NaCl `crypto_box`.
+pub mod encryption;
+
+/// Signed extrinsics of Robonomics parachain.
+pub mod extrinsic;
+
+/// On-chip flash memory access.
+pub mod flash;
+
+/// Robonomics account keys: sr25519/ed25519 signing and SS58 addresses.
+pub mod identity;
+
+/// IPFS content identifiers of UnixFS files.
+pub mod ipfs;
+
+/// Over-the-air firmware update.
+#[cfg(any(feature = "ota", test))]
+pub mod ota;
+
+/// SCALE codec subset for Substrate extrinsics.
+pub mod scale;
+
+/// Persistent key-value store in flash.
+pub mod storage;
+
+/// Factory reset triggers: BOOT button, reset counter and serial console.
+pub mod reset;
+
 /// A sensor is often defined as a device that receives and responds to a signal or stimulus.
 /// For example, temperature and humidity sensors is very usual for IoT.
 pub mod sensor;
@@ -0,0 +1,838 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Over-the-air firmware update using ESP-IDF OTA partition layout.
+//!
+//! Flash should contain `otadata` partition and two application slots
+//! `ota_0` / `ota_1`. New image is streamed into inactive slot by [`OtaWriter`],
+//! which doesn't hold flash between chunks, so other tasks keep using it, image is
+//! verified against detached [`ImageSignature`] and selected for the next boot
+//! in [`OtaImageState::New`] state.
+//!
+//! Image is accepted only when it is signed by ed25519 key trusted by running
+//! firmware, see [`UpdatePolicy`], and its security version is not lower than
+//! running one. Signatures are produced by `rohi-sign` tool at build time.
+//!
+//! Rollback is done by firmware itself: [`Ota::boot_check`] should be called
+//! early after start. It moves fresh image into [`OtaImageState::PendingVerify`]
+//! state and application should confirm it using [`Ota::mark_healthy`]. When
+//! device restarts before confirmation, previous image is selected back.
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use embedded_storage::Storage;
+use embedded_storage::nor_flash::{ErrorType, NorFlash};
+use esp_bootloader_esp_idf::ota::Ota as OtaData;
+use esp_bootloader_esp_idf::partitions::{
+    self, AppPartitionSubType, DataPartitionSubType, PARTITION_TABLE_MAX_LEN, PartitionType,
+};
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+
+pub use esp_bootloader_esp_idf::ota::OtaImageState;
+
+use crate::flash::{FlashError, SECTOR_SIZE};
+
+/// First byte of ESP application image.
+pub const ESP_IMAGE_MAGIC: u8 = 0xE9;
+
+/// Prefix of signed message, see [`ImageSignature::message`].
+pub const SIGNATURE_MAGIC: &[u8; 8] = b"ROHI-OTA";
+
+/// Length of encoded [`ImageSignature`].
+pub const SIGNATURE_LEN: usize = 68;
+
+/// Firmware update errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OtaError {
+    /// Partition table or OTA data is missing or broken.
+    Partition(partitions::Error),
+    /// Flash access failure.
+    Flash(FlashError),
+    /// Image is larger than application slot.
+    TooLarge,
+    /// Image size differs from announced one.
+    SizeMismatch,
+    /// Image is not an ESP application image.
+    InvalidImage,
+    /// Image is not signed by trusted key.
+    BadSignature,
+    /// Image security version is lower than running one.
+    Downgrade,
+}
+
+impl From<partitions::Error> for OtaError {
+    fn from(e: partitions::Error) -> Self {
+        Self::Partition(e)
+    }
+}
+
+impl From<FlashError> for OtaError {
+    fn from(e: FlashError) -> Self {
+        Self::Flash(e)
+    }
+}
+
+/// Detached signature of firmware image.
+///
+/// Encoded as little endian security version followed by ed25519 signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageSignature {
+    /// Anti-rollback counter, device refuses images with version lower than running one.
+    pub security_version: u32,
+    /// Ed25519 signature of [`ImageSignature::message`].
+    pub signature: [u8; 64],
+}
+
+impl ImageSignature {
+    /// Decode signature from bytes.
+    pub fn from_bytes(bytes: &[u8; SIGNATURE_LEN]) -> Self {
+        let (version, signature) = bytes.split_at(4);
+        Self {
+            security_version: u32::from_le_bytes(version.try_into().unwrap()),
+            signature: signature.try_into().unwrap(),
+        }
+    }
+
+    /// Encode signature into bytes.
+    pub fn to_bytes(&self) -> [u8; SIGNATURE_LEN] {
+        let mut bytes = [0u8; SIGNATURE_LEN];
+        bytes[..4].copy_from_slice(&self.security_version.to_le_bytes());
+        bytes[4..].copy_from_slice(&self.signature);
+        bytes
+    }
+
+    /// Signed message: magic, security version, image size and SHA-256 digest of image.
+    pub fn message(security_version: u32, size: u32, digest: &[u8; 32]) -> [u8; 48] {
+        let mut message = [0u8; 48];
+        message[..8].copy_from_slice(SIGNATURE_MAGIC);
+        message[8..12].copy_from_slice(&security_version.to_le_bytes());
+        message[12..16].copy_from_slice(&size.to_le_bytes());
+        message[16..].copy_from_slice(digest);
+        message
+    }
+}
+
+/// Images accepted by running firmware.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpdatePolicy {
+    /// Ed25519 public key images should be signed with.
+    pub public_key: [u8; 32],
+    /// Security version of running firmware.
+    pub security_version: u32,
+}
+
+impl UpdatePolicy {
+    /// Check signature of image with given size and SHA-256 digest.
+    pub fn verify(
+        &self,
+        size: u32,
+        digest: &[u8; 32],
+        signature: &ImageSignature,
+    ) -> Result<(), OtaError> {
+        if signature.security_version < self.security_version {
+            return Err(OtaError::Downgrade);
+        }
+        let key = VerifyingKey::from_bytes(&self.public_key).map_err(|_| OtaError::BadSignature)?;
+        let message = ImageSignature::message(signature.security_version, size, digest);
+        key.verify_strict(&message, &Signature::from_bytes(&signature.signature))
+            .map_err(|_| OtaError::BadSignature)
+    }
+}
+
+/// Action to take on boot depending on state of running image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BootAction {
+    /// Image is known to work, nothing to do.
+    None,
+    /// First boot of fresh image, it should be confirmed by application.
+    Verify,
+    /// Image was not confirmed during previous boot, previous one should be restored.
+    Rollback,
+}
+
+impl BootAction {
+    /// Rollback state machine transition for given state of running image.
+    pub fn for_state(state: OtaImageState) -> Self {
+        match state {
+            OtaImageState::New => Self::Verify,
+            OtaImageState::PendingVerify => Self::Rollback,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Result of [`Ota::boot_check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BootStatus {
+    /// Running image is confirmed or device was flashed without OTA.
+    Valid,
+    /// Running image waits for [`Ota::mark_healthy`].
+    PendingVerify,
+    /// Previous image is selected, device should be restarted.
+    RolledBack,
+}
+
+/// Application slot updates are written into: the one after selected, but never
+/// the running one. Factory image is followed by the first OTA slot.
+fn next_slot(
+    selected: AppPartitionSubType,
+    booted: Option<AppPartitionSubType>,
+    count: usize,
+) -> Result<AppPartitionSubType, partitions::Error> {
+    let after = |slot: AppPartitionSubType| {
+        let number = match slot {
+            AppPartitionSubType::Factory => 0,
+            slot => (slot as u8 - AppPartitionSubType::Ota0 as u8 + 1) % count as u8,
+        };
+        AppPartitionSubType::try_from(AppPartitionSubType::Ota0 as u8 + number)
+    };
+    let next = after(selected)?;
+    if Some(next) == booted {
+        after(next)
+    } else {
+        Ok(next)
+    }
+}
+
+/// Firmware update manager on top of flash memory `F`, usually
+/// [`Flash`](crate::flash::Flash).
+pub struct Ota<'a, F> {
+    flash: &'a mut F,
+    table: [u8; PARTITION_TABLE_MAX_LEN],
+}
+
+impl<'a, F> Ota<'a, F>
+where
+    F: Storage + NorFlash,
+    FlashError: From<<F as ErrorType>::Error>,
+{
+    /// New update manager on top of flash memory.
+    pub fn new(flash: &'a mut F) -> Self {
+        Self {
+            flash,
+            table: [0; PARTITION_TABLE_MAX_LEN],
+        }
+    }
+
+    /// Run `f` on OTA data partition with the slot updates should be written into.
+    fn with_otadata<R>(
+        &mut self,
+        f: impl FnOnce(&mut OtaData<'_, F>, AppPartitionSubType) -> Result<R, partitions::Error>,
+    ) -> Result<R, OtaError> {
+        let table = partitions::read_partition_table(&mut *self.flash, &mut self.table)?;
+        let count = table
+            .iter()
+            .filter(|entry| {
+                matches!(
+                    entry.partition_type(),
+                    PartitionType::App(slot)
+                        if slot != AppPartitionSubType::Factory && slot != AppPartitionSubType::Test
+                )
+            })
+            .count();
+        if count < 2 {
+            return Err(partitions::Error::Invalid.into());
+        }
+        // Unknown on the host, where firmware isn't mapped from flash.
+        let booted = match table.booted_partition().ok().flatten() {
+            Some(entry) => match entry.partition_type() {
+                PartitionType::App(slot) => Some(slot),
+                _ => None,
+            },
+            None => None,
+        };
+        let entry = table
+            .find_partition(PartitionType::Data(DataPartitionSubType::Ota))?
+            .ok_or(partitions::Error::Invalid)?;
+        let mut region = entry.as_embedded_storage(&mut *self.flash);
+        let mut otadata = OtaData::new(&mut region, count)?;
+        let next = next_slot(otadata.current_app_partition()?, booted, count)?;
+        Ok(f(&mut otadata, next)?)
+    }
+
+    /// Select the next slot for the boot in given state.
+    fn activate_next(&mut self, state: OtaImageState) -> Result<(), OtaError> {
+        self.with_otadata(|otadata, next| {
+            otadata.set_current_app_partition(next)?;
+            otadata.set_current_ota_state(state)
+        })
+    }
+
+    /// State of currently selected image, `None` when device was flashed without OTA.
+    pub fn state(&mut self) -> Result<Option<OtaImageState>, OtaError> {
+        self.with_otadata(|otadata, _| match otadata.current_ota_state() {
+            Ok(state) => Ok(Some(state)),
+            Err(partitions::Error::InvalidState) => Ok(None),
+            Err(e) => Err(e),
+        })
+    }
+
+    /// Check state of running image and roll back not confirmed update.
+    pub fn boot_check(&mut self) -> Result<BootStatus, OtaError> {
+        let Some(state) = self.state()? else {
+            return Ok(BootStatus::Valid);
+        };
+        match BootAction::for_state(state) {
+            BootAction::None => Ok(BootStatus::Valid),
+            BootAction::Verify => {
+                info!("[OTA] First boot of new image, waiting for confirmation");
+                self.with_otadata(|otadata, _| {
+                    otadata.set_current_ota_state(OtaImageState::PendingVerify)
+                })?;
+                Ok(BootStatus::PendingVerify)
+            }
+            BootAction::Rollback => {
+                warn!("[OTA] Image was not confirmed, rolling back");
+                self.with_otadata(|otadata, _| {
+                    otadata.set_current_ota_state(OtaImageState::Aborted)
+                })?;
+                self.activate_next(OtaImageState::Valid)?;
+                Ok(BootStatus::RolledBack)
+            }
+        }
+    }
+
+    /// Confirm that running image works well, so it will not be rolled back.
+    pub fn mark_healthy(&mut self) -> Result<(), OtaError> {
+        if self.state()? == Some(OtaImageState::PendingVerify) {
+            self.with_otadata(|otadata, _| otadata.set_current_ota_state(OtaImageState::Valid))?;
+            info!("[OTA] Image confirmed");
+        }
+        Ok(())
+    }
+
+    /// Start writing image of given size into inactive application slot.
+    pub fn begin(&mut self, size: usize) -> Result<OtaWriter, OtaError> {
+        let slot = self.with_otadata(|_, next| Ok(next))?;
+        let (offset, capacity) = {
+            let table = partitions::read_partition_table(&mut *self.flash, &mut self.table)?;
+            let entry = table
+                .find_partition(PartitionType::App(slot))?
+                .ok_or(partitions::Error::Invalid)?;
+            (entry.offset(), entry.len() as usize)
+        };
+        if size > capacity {
+            return Err(OtaError::TooLarge);
+        }
+        info!("[OTA] Writing {} bytes image into {:?}", size, slot);
+        Ok(OtaWriter {
+            offset,
+            size,
+            received: 0,
+            programmed: 0,
+            erased: 0,
+            tail: [0xFF; 4],
+            tail_len: 0,
+            hasher: Sha256::new(),
+        })
+    }
+}
+
+/// Streaming writer of image into inactive application slot, flash is passed
+/// to every call.
+pub struct OtaWriter {
+    offset: u32,
+    size: usize,
+    received: usize,
+    programmed: usize,
+    erased: usize,
+    tail: [u8; 4],
+    tail_len: usize,
+    hasher: Sha256,
+}
+
+impl OtaWriter {
+    /// Count of image bytes received.
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Append next chunk of image.
+    pub fn write<F>(&mut self, flash: &mut F, mut data: &[u8]) -> Result<(), OtaError>
+    where
+        F: NorFlash,
+        FlashError: From<F::Error>,
+    {
+        if self.received + data.len() > self.size {
+            return Err(OtaError::SizeMismatch);
+        }
+        if self.received == 0 && data.first().is_some_and(|b| *b != ESP_IMAGE_MAGIC) {
+            return Err(OtaError::InvalidImage);
+        }
+        self.hasher.update(data);
+        self.received += data.len();
+
+        // Flash is written by words, unaligned rest is kept till next chunk.
+        if self.tail_len > 0 {
+            let len = (4 - self.tail_len).min(data.len());
+            self.tail[self.tail_len..self.tail_len + len].copy_from_slice(&data[..len]);
+            self.tail_len += len;
+            data = &data[len..];
+            if self.tail_len < 4 {
+                return Ok(());
+            }
+            let tail = self.tail;
+            self.program(flash, &tail)?;
+            self.tail_len = 0;
+        }
+        let aligned = data.len() & !3;
+        self.program(flash, &data[..aligned])?;
+        let rest = &data[aligned..];
+        self.tail[..rest.len()].copy_from_slice(rest);
+        self.tail_len = rest.len();
+        Ok(())
+    }
+
+    /// Write word aligned data right after already programmed part.
+    fn program<F>(&mut self, flash: &mut F, data: &[u8]) -> Result<(), OtaError>
+    where
+        F: NorFlash,
+        FlashError: From<F::Error>,
+    {
+        let end = self.programmed + data.len();
+        while self.erased < end {
+            let sector = self.offset + self.erased as u32;
+            flash
+                .erase(sector, sector + SECTOR_SIZE as u32)
+                .map_err(FlashError::from)?;
+            self.erased += SECTOR_SIZE;
+        }
+        flash
+            .write(self.offset + self.programmed as u32, data)
+            .map_err(FlashError::from)?;
+        self.programmed = end;
+        Ok(())
+    }
+
+    /// Verify received image signature and select it for the next boot.
+    pub fn finish<F>(
+        mut self,
+        flash: &mut F,
+        signature: &ImageSignature,
+        policy: &UpdatePolicy,
+    ) -> Result<(), OtaError>
+    where
+        F: Storage + NorFlash,
+        FlashError: From<<F as ErrorType>::Error>,
+    {
+        if self.received != self.size {
+            return Err(OtaError::SizeMismatch);
+        }
+        if self.tail_len > 0 {
+            let mut tail = self.tail;
+            tail[self.tail_len..].fill(0xFF);
+            self.program(flash, &tail)?;
+        }
+        let digest: [u8; 32] = self.hasher.finalize().into();
+        policy.verify(self.size as u32, &digest, signature)?;
+        info!(
+            "[OTA] Image signature verified, security version {}",
+            signature.security_version
+        );
+
+        Ota::new(flash).activate_next(OtaImageState::New)?;
+        info!("[OTA] Update ready, restart to apply");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::flash::RamFlash;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const PARTITION_TABLE: usize = 0x8000;
+    const OTADATA: usize = 0xd000;
+    const OTA_0: usize = 0x10000;
+    const OTA_1: usize = 0x18000;
+    const SLOT_SIZE: usize = 0x8000;
+
+    /// Odd sized image spanning two sectors.
+    const IMAGE_LEN: usize = 5001;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    type TestFlash = RamFlash<0x20000>;
+
+    fn partition(kind: u8, subtype: u8, offset: usize, len: usize, label: &str) -> [u8; 32] {
+        let mut entry = [0u8; 32];
+        entry[..4].copy_from_slice(&[0xAA, 0x50, kind, subtype]);
+        entry[4..8].copy_from_slice(&(offset as u32).to_le_bytes());
+        entry[8..12].copy_from_slice(&(len as u32).to_le_bytes());
+        entry[12..12 + label.len()].copy_from_slice(label.as_bytes());
+        entry
+    }
+
+    /// Flash with `nvs`, `otadata`, `ota_0` and `ota_1` partitions, nothing selected.
+    fn flash() -> TestFlash {
+        let mut table = [0xFFu8; 5 * 32];
+        table[..32].copy_from_slice(&partition(0x01, 0x02, 0x9000, 0x4000, "nvs"));
+        table[32..64].copy_from_slice(&partition(0x01, 0x00, OTADATA, 0x2000, "otadata"));
+        table[64..96].copy_from_slice(&partition(0x00, 0x10, OTA_0, SLOT_SIZE, "ota_0"));
+        table[96..128].copy_from_slice(&partition(0x00, 0x11, OTA_1, SLOT_SIZE, "ota_1"));
+        // MD5 digest of entries above, as written by `gen_esp32part.py`.
+        table[128..130].copy_from_slice(&[0xEB, 0xEB]);
+        table[144..160].copy_from_slice(&[
+            0x29, 0xaa, 0x11, 0x1a, 0x58, 0x7a, 0x5a, 0x05, 0x84, 0xc0, 0x73, 0x60, 0x93, 0x46,
+            0x40, 0x9e,
+        ]);
+        let mut flash = TestFlash::new();
+        Storage::write(&mut flash, PARTITION_TABLE as u32, &table).unwrap();
+        flash
+    }
+
+    /// OTA data entry: sequence, empty label, state and CRC-32 of sequence.
+    fn otadata_entry(seq: u32, state: OtaImageState, crc: u32) -> [u8; 32] {
+        let mut entry = [0xFFu8; 32];
+        entry[..4].copy_from_slice(&seq.to_le_bytes());
+        entry[24..28].copy_from_slice(&(state as u32).to_le_bytes());
+        entry[28..].copy_from_slice(&crc.to_le_bytes());
+        entry
+    }
+
+    /// OTA data entries of both copies.
+    fn otadata(flash: &TestFlash) -> [[u8; 32]; 2] {
+        let memory = flash.memory();
+        [OTADATA, OTADATA + 0x1000].map(|offset| memory[offset..offset + 32].try_into().unwrap())
+    }
+
+    /// Flash of device running confirmed image from `ota_0`.
+    fn running_ota_0() -> TestFlash {
+        let mut flash = flash();
+        let entry = otadata_entry(1, OtaImageState::Valid, 0x4743989a);
+        Storage::write(&mut flash, OTADATA as u32, &entry).unwrap();
+        flash
+    }
+
+    fn image() -> [u8; IMAGE_LEN] {
+        let mut image = [0u8; IMAGE_LEN];
+        for (i, byte) in image.iter_mut().enumerate() {
+            *byte = (i * 7 + i / 256) as u8;
+        }
+        image[0] = ESP_IMAGE_MAGIC;
+        image
+    }
+
+    fn policy() -> UpdatePolicy {
+        UpdatePolicy {
+            public_key: SigningKey::from_bytes(&KEY).verifying_key().to_bytes(),
+            security_version: 1,
+        }
+    }
+
+    fn sign(image: &[u8], security_version: u32) -> ImageSignature {
+        let digest: [u8; 32] = Sha256::digest(image).into();
+        let message = ImageSignature::message(security_version, image.len() as u32, &digest);
+        ImageSignature {
+            security_version,
+            signature: SigningKey::from_bytes(&KEY).sign(&message).to_bytes(),
+        }
+    }
+
+    /// Write image by chunks of given sizes, the last one repeated till the end.
+    fn update(flash: &mut TestFlash, image: &[u8], chunks: &[usize]) -> Result<(), OtaError> {
+        let mut writer = Ota::new(flash).begin(image.len())?;
+        let mut rest = image;
+        let mut chunks = chunks.iter();
+        let mut len = 0;
+        while !rest.is_empty() {
+            len = *chunks.next().unwrap_or(&len);
+            let (chunk, next) = rest.split_at(len.min(rest.len()));
+            writer.write(flash, chunk)?;
+            rest = next;
+        }
+        assert_eq!(writer.received(), image.len());
+        writer.finish(flash, &sign(image, 1), &policy())
+    }
+
+    #[test]
+    fn signature_layout() {
+        let signature = ImageSignature {
+            security_version: 0x0403_0201,
+            signature: core::array::from_fn(|i| i as u8),
+        };
+        let bytes = signature.to_bytes();
+        assert_eq!(bytes[..4], [0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(bytes[4..], signature.signature);
+        assert_eq!(ImageSignature::from_bytes(&bytes), signature);
+
+        let digest = [0xAB; 32];
+        let message = ImageSignature::message(2, 0x0001_0203, &digest);
+        assert_eq!(&message[..8], b"ROHI-OTA");
+        assert_eq!(message[8..12], [0x02, 0x00, 0x00, 0x00]);
+        assert_eq!(message[12..16], [0x03, 0x02, 0x01, 0x00]);
+        assert_eq!(message[16..], digest);
+    }
+
+    #[test]
+    fn verify_signature() {
+        let image = image();
+        let digest: [u8; 32] = Sha256::digest(image).into();
+        let size = IMAGE_LEN as u32;
+        let signature = sign(&image, 1);
+        assert_eq!(policy().verify(size, &digest, &signature), Ok(()));
+        // Newer image is fine.
+        assert_eq!(policy().verify(size, &digest, &sign(&image, 2)), Ok(()));
+
+        let mut flipped = image;
+        flipped[IMAGE_LEN / 2] ^= 0x10;
+        let flipped: [u8; 32] = Sha256::digest(flipped).into();
+        assert_eq!(
+            policy().verify(size, &flipped, &signature),
+            Err(OtaError::BadSignature)
+        );
+        assert_eq!(
+            policy().verify(size - 1, &digest, &signature),
+            Err(OtaError::BadSignature)
+        );
+
+        let wrong_key = UpdatePolicy {
+            public_key: SigningKey::from_bytes(&[8; 32]).verifying_key().to_bytes(),
+            ..policy()
+        };
+        assert_eq!(
+            wrong_key.verify(size, &digest, &signature),
+            Err(OtaError::BadSignature)
+        );
+
+        // Signature cut short and padded by zeroes.
+        let mut truncated = [0u8; SIGNATURE_LEN];
+        truncated[..SIGNATURE_LEN - 8].copy_from_slice(&signature.to_bytes()[..SIGNATURE_LEN - 8]);
+        assert_eq!(
+            policy().verify(size, &digest, &ImageSignature::from_bytes(&truncated)),
+            Err(OtaError::BadSignature)
+        );
+
+        // Security version is signed too.
+        let relabeled = ImageSignature {
+            security_version: 2,
+            ..signature
+        };
+        assert_eq!(
+            policy().verify(size, &digest, &relabeled),
+            Err(OtaError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn refuse_downgrade() {
+        let image = image();
+        let digest: [u8; 32] = Sha256::digest(image).into();
+        let policy = UpdatePolicy {
+            security_version: 3,
+            ..policy()
+        };
+        assert_eq!(
+            policy.verify(IMAGE_LEN as u32, &digest, &sign(&image, 2)),
+            Err(OtaError::Downgrade)
+        );
+        assert_eq!(
+            policy.verify(IMAGE_LEN as u32, &digest, &sign(&image, 3)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn next_slot_skips_running_one() {
+        use AppPartitionSubType::*;
+        assert_eq!(next_slot(Factory, None, 2), Ok(Ota0));
+        assert_eq!(next_slot(Factory, Some(Ota0), 2), Ok(Ota1));
+        assert_eq!(next_slot(Ota0, None, 2), Ok(Ota1));
+        assert_eq!(next_slot(Ota1, None, 2), Ok(Ota0));
+        // Selected update is not booted yet.
+        assert_eq!(next_slot(Ota1, Some(Ota0), 2), Ok(Ota1));
+        assert_eq!(next_slot(Ota2, None, 3), Ok(Ota0));
+    }
+
+    #[test]
+    fn update_into_inactive_slot() {
+        let mut flash = running_ota_0();
+        // Leftovers of previous image.
+        blocking_write(&mut flash, OTA_1, &[0u8; SLOT_SIZE]);
+        let erases = flash.erases();
+        let image = image();
+        update(&mut flash, &image, &[1024]).unwrap();
+
+        let memory = flash.memory();
+        assert_eq!(&memory[OTA_1..OTA_1 + IMAGE_LEN], &image);
+        // Only sectors of image are erased, unaligned tail is padded. OTA data
+        // sector is rewritten for sequence and for state.
+        assert_eq!(flash.erases() - erases, 2 + 2);
+        assert!(
+            memory[OTA_1 + IMAGE_LEN..OTA_1 + 2 * SECTOR_SIZE]
+                .iter()
+                .all(|b| *b == 0xFF)
+        );
+        assert!(
+            memory[OTA_1 + 2 * SECTOR_SIZE..OTA_0 + 2 * SLOT_SIZE]
+                .iter()
+                .all(|b| *b == 0)
+        );
+        assert!(memory[OTA_0..OTA_1].iter().all(|b| *b == 0xFF));
+
+        // Sequence is incremented in another copy, so `ota_1` is selected.
+        assert_eq!(
+            otadata(&flash),
+            [
+                otadata_entry(1, OtaImageState::Valid, 0x4743989a),
+                otadata_entry(2, OtaImageState::New, 0x55f63774),
+            ]
+        );
+        assert_eq!(Ota::new(&mut flash).state(), Ok(Some(OtaImageState::New)));
+    }
+
+    #[test]
+    fn unaligned_chunks() {
+        let image = image();
+        let mut flash = running_ota_0();
+        update(&mut flash, &image, &[1, 2, 1, 3, 5, 4, 6, 2, 999, 7]).unwrap();
+        assert_eq!(&flash.memory()[OTA_1..OTA_1 + IMAGE_LEN], &image);
+        assert_eq!(
+            &flash.memory()[OTA_1 + IMAGE_LEN..OTA_1 + IMAGE_LEN + 3],
+            &[0xFF; 3]
+        );
+    }
+
+    #[test]
+    fn flashed_without_ota() {
+        let mut flash = flash();
+        assert_eq!(Ota::new(&mut flash).state(), Ok(None));
+        assert_eq!(Ota::new(&mut flash).boot_check(), Ok(BootStatus::Valid));
+        assert_eq!(Ota::new(&mut flash).mark_healthy(), Ok(()));
+
+        let image = image();
+        update(&mut flash, &image, &[512]).unwrap();
+        assert_eq!(&flash.memory()[OTA_0..OTA_0 + IMAGE_LEN], &image);
+        assert_eq!(
+            otadata(&flash)[0],
+            otadata_entry(1, OtaImageState::New, 0x4743989a)
+        );
+    }
+
+    #[test]
+    fn confirm_new_image() {
+        let mut flash = running_ota_0();
+        update(&mut flash, &image(), &[1024]).unwrap();
+
+        assert_eq!(
+            Ota::new(&mut flash).boot_check(),
+            Ok(BootStatus::PendingVerify)
+        );
+        assert_eq!(
+            Ota::new(&mut flash).state(),
+            Ok(Some(OtaImageState::PendingVerify))
+        );
+        Ota::new(&mut flash).mark_healthy().unwrap();
+        assert_eq!(
+            otadata(&flash)[1],
+            otadata_entry(2, OtaImageState::Valid, 0x55f63774)
+        );
+        // Confirmed image stays.
+        assert_eq!(Ota::new(&mut flash).boot_check(), Ok(BootStatus::Valid));
+        assert_eq!(Ota::new(&mut flash).state(), Ok(Some(OtaImageState::Valid)));
+    }
+
+    #[test]
+    fn rollback_unconfirmed_image() {
+        let mut flash = running_ota_0();
+        update(&mut flash, &image(), &[1024]).unwrap();
+        assert_eq!(
+            Ota::new(&mut flash).boot_check(),
+            Ok(BootStatus::PendingVerify)
+        );
+
+        // Restarted without confirmation.
+        assert_eq!(
+            Ota::new(&mut flash).boot_check(),
+            Ok(BootStatus::RolledBack)
+        );
+        assert_eq!(
+            otadata(&flash),
+            [
+                otadata_entry(3, OtaImageState::Valid, 0xed4a5011),
+                otadata_entry(2, OtaImageState::Aborted, 0x55f63774),
+            ]
+        );
+        assert_eq!(Ota::new(&mut flash).boot_check(), Ok(BootStatus::Valid));
+
+        // Next update goes into the slot of rolled back image.
+        update(&mut flash, &image(), &[1024]).unwrap();
+        assert_eq!(
+            otadata(&flash)[1],
+            otadata_entry(4, OtaImageState::New, 0x709d68a8)
+        );
+    }
+
+    #[test]
+    fn rejected_images() {
+        let image = image();
+        let mut flash = running_ota_0();
+        let selected = otadata(&flash);
+        let mut ota = Ota::new(&mut flash);
+        assert_eq!(ota.begin(SLOT_SIZE + 1).err(), Some(OtaError::TooLarge));
+
+        let mut writer = ota.begin(IMAGE_LEN).unwrap();
+        assert_eq!(
+            writer.write(&mut flash, &[0x7F]),
+            Err(OtaError::InvalidImage)
+        );
+        writer.write(&mut flash, &image[..100]).unwrap();
+        assert_eq!(
+            writer.write(&mut flash, &image[..IMAGE_LEN]),
+            Err(OtaError::SizeMismatch)
+        );
+        assert_eq!(
+            writer.finish(&mut flash, &sign(&image, 1), &policy()),
+            Err(OtaError::SizeMismatch)
+        );
+
+        let mut writer = Ota::new(&mut flash).begin(IMAGE_LEN).unwrap();
+        let mut corrupted = image;
+        corrupted[100] ^= 1;
+        writer.write(&mut flash, &corrupted).unwrap();
+        assert_eq!(
+            writer.finish(&mut flash, &sign(&image, 1), &policy()),
+            Err(OtaError::BadSignature)
+        );
+        assert_eq!(otadata(&flash), selected);
+    }
+
+    fn blocking_write(flash: &mut TestFlash, offset: usize, data: &[u8]) {
+        NorFlash::write(flash, offset as u32, data).unwrap();
+    }
+
+    #[test]
+    fn boot_action_for_state() {
+        assert_eq!(
+            BootAction::for_state(OtaImageState::New),
+            BootAction::Verify
+        );
+        assert_eq!(
+            BootAction::for_state(OtaImageState::PendingVerify),
+            BootAction::Rollback
+        );
+        for state in [
+            OtaImageState::Valid,
+            OtaImageState::Invalid,
+            OtaImageState::Aborted,
+            OtaImageState::Undefined,
+        ] {
+            assert_eq!(BootAction::for_state(state), BootAction::None);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Factory reset triggers for devices which became unreachable.
+//!
+//! - [`BootButton`]: long press of BOOT button (GPIO9 on ESP32-C3);
+//! - [`ResetCounter`]: several resets in a row, each one shortly after boot;
+//! - [`Console`]: `factory-reset` command on USB serial console.
+//!
+//! Firmware wipes config store when any of them fires and restarts with factory
+//! defaults, i.e. in provisioning mode.
+
+use embassy_time::Duration;
+use embedded_storage_async::nor_flash::MultiwriteNorFlash;
+use log::warn;
+
+use crate::storage::{Key, KvStore, StoreError};
+#[cfg(feature = "esp32c3")]
+use {
+    embassy_futures::select::{Either, select},
+    embassy_time::Timer,
+    embedded_io_async::Read,
+    esp_hal::Async,
+    esp_hal::gpio::{Input, InputConfig, Pull},
+    esp_hal::peripherals::{GPIO9, USB_DEVICE},
+    esp_hal::rtc_cntl::SocResetReason,
+    esp_hal::usb_serial_jtag::{UsbSerialJtag, UsbSerialJtagRx},
+    heapless::String,
+    log::info,
+};
+
+/// BOOT button hold time which triggers factory reset.
+pub const LONG_PRESS: Duration = Duration::from_secs(5);
+
+/// Count of resets in a row which triggers factory reset.
+pub const RESET_THRESHOLD: u32 = 3;
+
+/// Reset is counted when it happens within this time after boot.
+pub const RESET_WINDOW: Duration = Duration::from_secs(10);
+
+/// Key of reset counter in store.
+pub const RESET_COUNTER_KEY: Key<u32> = Key::new(3);
+
+/// BOOT button, pressed when low.
+#[cfg(feature = "esp32c3")]
+pub struct BootButton {
+    input: Input<'static>,
+}
+
+#[cfg(feature = "esp32c3")]
+impl BootButton {
+    /// Take button pin.
+    pub fn new(pin: GPIO9<'static>) -> Self {
+        Self {
+            input: Input::new(pin, InputConfig::default().with_pull(Pull::Up)),
+        }
+    }
+
+    /// Wait until button is held at least for given time.
+    pub async fn wait_long_press(&mut self, duration: Duration) {
+        loop {
+            self.input.wait_for_low().await;
+            if let Either::Second(_) =
+                select(self.input.wait_for_high(), Timer::after(duration)).await
+            {
+                info!("[Reset] BOOT button long press");
+                return;
+            }
+        }
+    }
+}
+
+/// Counter of resets in a row kept in key-value store.
+///
+/// Counter is written at boot and removed once device is up for [`RESET_WINDOW`],
+/// so it survives any kind of reset including power loss.
+pub struct ResetCounter;
+
+impl ResetCounter {
+    /// Count current boot, returns `true` when device was reset `threshold` times in a row.
+    ///
+    /// Software resets (reboot command, firmware update) break the sequence.
+    /// [`ResetCounter::clear`] should be called after [`RESET_WINDOW`].
+    #[cfg(feature = "esp32c3")]
+    pub async fn register_boot<S: MultiwriteNorFlash>(
+        store: &mut KvStore<S>,
+        threshold: u32,
+    ) -> Result<bool, StoreError<S::Error>> {
+        let software = matches!(
+            esp_hal::system::reset_reason(),
+            Some(SocResetReason::CoreSw | SocResetReason::Cpu0Sw)
+        );
+        Self::count(store, threshold, software).await
+    }
+
+    /// Count boot, the sequence is broken by `software` reset.
+    pub async fn count<S: MultiwriteNorFlash>(
+        store: &mut KvStore<S>,
+        threshold: u32,
+        software: bool,
+    ) -> Result<bool, StoreError<S::Error>> {
+        if software {
+            Self::clear(store).await?;
+            return Ok(false);
+        }
+        let count = store.get(&RESET_COUNTER_KEY).await?.unwrap_or(0) + 1;
+        if count >= threshold {
+            warn!("[Reset] Device is reset {} times in a row", count);
+            Self::clear(store).await?;
+            Ok(true)
+        } else {
+            store.set(&RESET_COUNTER_KEY, &count).await?;
+            Ok(false)
+        }
+    }
+
+    /// Break the sequence, device is up long enough.
+    pub async fn clear<S: MultiwriteNorFlash>(
+        store: &mut KvStore<S>,
+    ) -> Result<(), StoreError<S::Error>> {
+        if store.get(&RESET_COUNTER_KEY).await?.is_some() {
+            store.remove(&RESET_COUNTER_KEY).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Command received from serial console.
+#[cfg(feature = "esp32c3")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Reboot,
+    FactoryReset,
+}
+
+/// Line based command reader on USB serial console.
+#[cfg(feature = "esp32c3")]
+pub struct Console {
+    rx: UsbSerialJtagRx<'static, Async>,
+    line: String<32>,
+}
+
+#[cfg(feature = "esp32c3")]
+impl Console {
+    /// Take USB serial, output still goes through logger.
+    pub fn new(usb: USB_DEVICE<'static>) -> Self {
+        let (rx, _tx) = UsbSerialJtag::new(usb).into_async().split();
+        Self {
+            rx,
+            line: String::new(),
+        }
+    }
+
+    /// Wait for the next known command, unknown lines are ignored.
+    pub async fn command(&mut self) -> Command {
+        let mut buf = [0u8; 16];
+        loop {
+            let Ok(len) = self.rx.read(&mut buf).await;
+            for &byte in &buf[..len] {
+                if byte != b'\r' && byte != b'\n' {
+                    // Too long lines are truncated, they are not commands anyway.
+                    _ = self.line.push(byte as char);
+                    continue;
+                }
+                let command = match self.line.trim() {
+                    "reboot" => Some(Command::Reboot),
+                    "factory-reset" => Some(Command::FactoryReset),
+                    "" => None,
+                    line => {
+                        warn!("[Console] Unknown command: {}", line);
+                        None
+                    }
+                };
+                self.line.clear();
+                if let Some(command) = command {
+                    info!("[Console] {:?}", command);
+                    return command;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_futures::block_on;
+
+    use super::*;
+    use crate::flash::{RamFlash, SECTOR_SIZE};
+
+    type Flash = RamFlash<{ 2 * SECTOR_SIZE }>;
+
+    /// Boot counted on store reopened on the same memory, as after power loss.
+    async fn boot(flash: Flash, software: bool) -> (bool, Flash) {
+        let mut store = KvStore::new(flash);
+        let wipe = ResetCounter::count(&mut store, RESET_THRESHOLD, software)
+            .await
+            .unwrap();
+        (wipe, store.into_inner())
+    }
+
+    #[test]
+    fn resets_in_a_row_trigger_wipe() {
+        block_on(async {
+            let (wipe, flash) = boot(Flash::new(), false).await;
+            assert!(!wipe);
+            let (wipe, flash) = boot(flash, false).await;
+            assert!(!wipe);
+            let (wipe, flash) = boot(flash, false).await;
+            assert!(wipe);
+            // Sequence starts over after wipe.
+            let (wipe, _) = boot(flash, false).await;
+            assert!(!wipe);
+        })
+    }
+
+    #[test]
+    fn sequence_is_broken() {
+        block_on(async {
+            let (_, flash) = boot(Flash::new(), false).await;
+            let (_, flash) = boot(flash, false).await;
+            // Device stayed up for reset window.
+            let mut store = KvStore::new(flash);
+            ResetCounter::clear(&mut store).await.unwrap();
+            assert_eq!(store.get(&RESET_COUNTER_KEY).await, Ok(None));
+            let (wipe, flash) = boot(store.into_inner(), false).await;
+            assert!(!wipe);
+
+            let (_, flash) = boot(flash, false).await;
+            // Reboot command or firmware update.
+            let (wipe, flash) = boot(flash, true).await;
+            assert!(!wipe);
+            let (wipe, _) = boot(flash, false).await;
+            assert!(!wipe);
+        })
+    }
+}
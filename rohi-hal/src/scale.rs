@@ -0,0 +1,234 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Subset of [SCALE codec](https://docs.polkadot.com/polkadot-protocol/parachain-basics/data-encoding/)
+//! used by Substrate extrinsics.
+//!
+//! Values are written into any [`Output`]: fixed size buffer or hasher, so
+//! payloads are never allocated on heap. Decoding is done field by field with
+//! [`Input`] cursor, values borrow from source bytes.
+
+use blake2::Blake2b;
+use blake2::digest::consts::U32;
+use blake2::digest::{FixedOutput, Update};
+use heapless::Vec;
+
+/// Output buffer is too small for encoded value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Overflow;
+
+/// Destination of encoded bytes.
+pub trait Output {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Overflow>;
+}
+
+impl<const N: usize> Output for Vec<u8, N> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Overflow> {
+        self.extend_from_slice(bytes).map_err(|_| Overflow)
+    }
+}
+
+/// Hash encoded value without buffering it.
+impl Output for Blake2b<U32> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Overflow> {
+        self.update(bytes);
+        Ok(())
+    }
+}
+
+/// SCALE encodable value.
+pub trait Encode {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow>;
+
+    /// Encode into new buffer.
+    fn encode<const N: usize>(&self) -> Result<Vec<u8, N>, Overflow> {
+        let mut buf = Vec::new();
+        self.encode_to(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// BLAKE2b-256 hash of encoded value.
+    fn blake2_256(&self) -> [u8; 32] {
+        let mut hasher = Blake2b::<U32>::default();
+        // Hasher never overflows.
+        _ = self.encode_to(&mut hasher);
+        hasher.finalize_fixed().into()
+    }
+}
+
+macro_rules! impl_fixed {
+    ($($t:ty),*) => {$(
+        impl Encode for $t {
+            fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+                out.write(&self.to_le_bytes())
+            }
+        }
+    )*};
+}
+
+impl_fixed!(u8, u16, u32, u64, u128);
+
+impl Encode for bool {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        out.write(&[*self as u8])
+    }
+}
+
+/// Fixed size arrays like hashes and public keys are written as is.
+impl<const N: usize> Encode for [u8; N] {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        out.write(self)
+    }
+}
+
+/// Dynamic length byte string: compact length followed by bytes.
+impl Encode for [u8] {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        Compact(self.len() as u128).encode_to(out)?;
+        out.write(self)
+    }
+}
+
+impl Encode for str {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        self.as_bytes().encode_to(out)
+    }
+}
+
+impl<T: Encode> Encode for Option<T> {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        match self {
+            None => out.write(&[0]),
+            Some(value) => {
+                out.write(&[1])?;
+                value.encode_to(out)
+            }
+        }
+    }
+}
+
+impl<T: Encode + ?Sized> Encode for &T {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        (**self).encode_to(out)
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($name:ident),*) => {
+        /// Tuples are encoded as sequence of fields.
+        impl<$($name: Encode),*> Encode for ($($name,)*) {
+            #[allow(non_snake_case)]
+            fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+                let ($($name,)*) = self;
+                $($name.encode_to(out)?;)*
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_tuple!(A, B);
+impl_tuple!(A, B, C);
+impl_tuple!(A, B, C, D);
+
+/// Compact encoded unsigned integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compact(pub u128);
+
+impl Encode for Compact {
+    fn encode_to<O: Output>(&self, out: &mut O) -> Result<(), Overflow> {
+        let n = self.0;
+        match n {
+            0..0x40 => out.write(&[(n as u8) << 2]),
+            0x40..0x4000 => out.write(&(((n as u16) << 2) | 0b01).to_le_bytes()),
+            0x4000..0x4000_0000 => out.write(&(((n as u32) << 2) | 0b10).to_le_bytes()),
+            _ => {
+                let bytes = n.to_le_bytes();
+                let len = bytes.len() - (n.leading_zeros() / 8) as usize;
+                out.write(&[(((len - 4) as u8) << 2) | 0b11])?;
+                out.write(&bytes[..len])
+            }
+        }
+    }
+}
+
+/// Cursor over encoded bytes, every read returns `None` when input is too short.
+#[derive(Clone, Copy, Debug)]
+pub struct Input<'a>(&'a [u8]);
+
+impl<'a> Input<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self(bytes)
+    }
+
+    /// Bytes left unread.
+    pub fn remaining(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Read `len` raw bytes.
+    pub fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.0.len() {
+            return None;
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    /// Read fixed size array.
+    pub fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.bytes(N)?.try_into().ok()
+    }
+
+    pub fn u8(&mut self) -> Option<u8> {
+        self.array::<1>().map(|[b]| b)
+    }
+
+    /// Read compact encoded integer.
+    pub fn compact(&mut self) -> Option<u128> {
+        let first = self.u8()?;
+        match first & 0b11 {
+            0b00 => Some((first >> 2).into()),
+            0b01 => Some((u16::from_le_bytes([first, self.u8()?]) >> 2).into()),
+            0b10 => {
+                let [b1, b2, b3] = self.array()?;
+                Some((u32::from_le_bytes([first, b1, b2, b3]) >> 2).into())
+            }
+            _ => {
+                let len = (first >> 2) as usize + 4;
+                let mut bytes = [0u8; 16];
+                bytes.get_mut(..len)?.copy_from_slice(self.bytes(len)?);
+                Some(u128::from_le_bytes(bytes))
+            }
+        }
+    }
+
+    /// Read compact length prefixed byte string.
+    pub fn byte_string(&mut self) -> Option<&'a [u8]> {
+        let len = self.compact()?;
+        self.bytes(usize::try_from(len).ok()?)
+    }
+}
+
+/// BLAKE2b-256 hash of raw bytes.
+pub fn blake2_256(data: &[u8]) -> [u8; 32] {
+    Blake2b::<U32>::default()
+        .chain(data)
+        .finalize_fixed()
+        .into()
+}
@@ -19,12 +19,63 @@
 //! async interface. For example, access sensor data for board instance will
 //! looks like:
 //!
-//! ```rust
+//! ```ignore
 //! let board_sensors = ...
 //! let temp = board_sensors.temperature().await;
 //! println!("{}", temp);
 //! ```
 
+/// Plantower PMS5003 / PMS7003 particulate matter sensors.
+pub mod pmsx003;
+pub use pmsx003::Pmsx003;
+
+/// Common I2C protocol of Sensirion sensors.
+pub mod sensirion;
+
+/// Sensirion SPS30 particulate matter sensor.
+pub mod sps30;
+pub use sps30::Sps30;
+
+/// Sensirion SEN5x environmental sensor nodes.
+pub mod sen5x;
+pub use sen5x::Sen5x;
+
+/// Sensirion SCD40 / SCD41 CO2 sensors.
+pub mod scd4x;
+pub use scd4x::Scd4x;
+
+/// Winsen MH-Z19B / MH-Z19C CO2 sensors.
+pub mod mhz19;
+pub use mhz19::MhZ19;
+
+/// Sensirion gas index algorithm.
+pub mod gas_index;
+
+/// Sensirion SGP40 / SGP41 gas sensors.
+pub mod sgp4x;
+pub use sgp4x::Sgp4x;
+
+/// Bosch BME280 humidity, pressure and temperature sensor.
+pub mod bme280;
+pub use bme280::Bme280;
+
+/// TDK InvenSense ICS-43434 microphone sound level.
+pub mod ics43434;
+pub use ics43434::SoundMeter;
+
+/// Sensirion SHT3x humidity and temperature sensors.
+pub mod sht3x;
+pub use sht3x::Sht3x;
+
+/// Sensirion SHT4x humidity and temperature sensors.
+pub mod sht4x;
+pub use sht4x::Sht4x;
+
+#[cfg(test)]
+mod fake;
+
+use serde::{Deserialize, Serialize};
+
 /// A Particulate Matter (PM) sensor measures the floating particles in the air.
 #[allow(async_fn_in_trait)]
 pub trait ParticulateMatter {
@@ -49,9 +100,173 @@ pub trait Temperature {
     async fn temperature(&mut self) -> Option<i16>;
 }
 
+/// Borrowed sensor, e.g. compensation source shared with other consumers.
+impl<T: Humidity + ?Sized> Humidity for &mut T {
+    async fn humidity(&mut self) -> Option<u16> {
+        (**self).humidity().await
+    }
+}
+
+/// Borrowed sensor, e.g. compensation source shared with other consumers.
+impl<T: Temperature + ?Sized> Temperature for &mut T {
+    async fn temperature(&mut self) -> Option<i16> {
+        (**self).temperature().await
+    }
+}
+
 /// A Pressure sensor measures air / liquid pressure in environment.
 #[allow(async_fn_in_trait)]
 pub trait Pressure {
     /// The measured pressure in **Pascals**.
     async fn pressure(&mut self) -> Option<u32>;
 }
+
+/// A Noise sensor measures ambient sound level.
+#[allow(async_fn_in_trait)]
+pub trait Noise {
+    /// The measured A-weighted sound level in tenths of **dBA**.
+    async fn noise(&mut self) -> Option<u16>;
+}
+
+/// A VOC sensor measures volatile organic compounds in the air.
+#[allow(async_fn_in_trait)]
+pub trait VolatileOrganicCompounds {
+    /// The VOC index in `1..=500`, 100 is average air of the last 24 hours.
+    async fn voc_index(&mut self) -> Option<u16>;
+}
+
+/// A CO2 sensor measures carbon dioxide concentration in the air.
+#[allow(async_fn_in_trait)]
+pub trait Co2 {
+    /// The measured CO2 concentration in **ppm**.
+    async fn co2(&mut self) -> Option<u16>;
+}
+
+/// A snapshot of board sensors taken at the same moment.
+///
+/// Values keep units of sensor traits: particulate matter in tenths of µg/m³,
+/// temperature, humidity and noise in tenths, pressure in Pascals. Sensors which are
+/// absent or failed to respond are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Measurement {
+    /// Time of measurement in milliseconds since boot.
+    pub timestamp: u64,
+    /// PM10 fine dust pollution.
+    pub pm10: Option<u16>,
+    /// PM2.5 fine dust pollution.
+    pub pm25: Option<u16>,
+    /// Temperature in tenths of degrees **Celsius**.
+    pub temperature: Option<i16>,
+    /// Relative humidity in tenths of a percent.
+    pub humidity: Option<u16>,
+    /// Pressure in **Pascals**.
+    pub pressure: Option<u32>,
+    /// Noise level in tenths of **dBA**.
+    pub noise: Option<u16>,
+}
+
+impl Measurement {
+    /// Empty measurement taken at given time.
+    pub const fn new(timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            pm10: None,
+            pm25: None,
+            temperature: None,
+            humidity: None,
+            pressure: None,
+            noise: None,
+        }
+    }
+}
+
+/// Running mean of optional sensor value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Mean {
+    sum: i64,
+    count: u32,
+}
+
+impl Mean {
+    fn add(&mut self, value: Option<impl Into<i64>>) {
+        if let Some(value) = value {
+            self.sum += value.into();
+            self.count += 1;
+        }
+    }
+
+    /// Mean rounded half away from zero.
+    fn get<T: TryFrom<i64>>(&self) -> Option<T> {
+        if self.count == 0 {
+            return None;
+        }
+        let count = self.count as i64;
+        let half = if self.sum < 0 { -count / 2 } else { count / 2 };
+        T::try_from((self.sum + half) / count).ok()
+    }
+}
+
+/// Average of measurements taken over a period, e.g. between uploads.
+///
+/// Every value is averaged over measurements where it is present, timestamp
+/// of the last measurement is kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Aggregate {
+    timestamp: u64,
+    pm10: Mean,
+    pm25: Mean,
+    temperature: Mean,
+    humidity: Mean,
+    pressure: Mean,
+    noise: Mean,
+}
+
+impl Aggregate {
+    /// Empty aggregate.
+    pub const fn new() -> Self {
+        const EMPTY: Mean = Mean { sum: 0, count: 0 };
+        Self {
+            timestamp: 0,
+            pm10: EMPTY,
+            pm25: EMPTY,
+            temperature: EMPTY,
+            humidity: EMPTY,
+            pressure: EMPTY,
+            noise: EMPTY,
+        }
+    }
+
+    /// Account measurement.
+    pub fn add(&mut self, m: &Measurement) {
+        self.timestamp = m.timestamp;
+        self.pm10.add(m.pm10);
+        self.pm25.add(m.pm25);
+        self.temperature.add(m.temperature);
+        self.humidity.add(m.humidity);
+        self.pressure.add(m.pressure);
+        self.noise.add(m.noise);
+    }
+
+    /// Averaged measurement, `None` when nothing was added.
+    pub fn average(&self) -> Option<Measurement> {
+        if *self == Self::new() {
+            return None;
+        }
+        Some(Measurement {
+            timestamp: self.timestamp,
+            pm10: self.pm10.get(),
+            pm25: self.pm25.get(),
+            temperature: self.temperature.get(),
+            humidity: self.humidity.get(),
+            pressure: self.pressure.get(),
+            noise: self.noise.get(),
+        })
+    }
+
+    /// Averaged measurement, aggregate is reset for the next period.
+    pub fn take(&mut self) -> Option<Measurement> {
+        let average = self.average();
+        *self = Self::new();
+        average
+    }
+}
@@ -0,0 +1,436 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Bosch BME280 humidity, pressure and temperature sensor over I2C, address
+//! `0x76` or `0x77` when SDO pin is high.
+//!
+//! Driver uses forced mode with single oversampling of every value: sensor
+//! sleeps between measurements and doesn't warm itself up. Raw values are
+//! compensated by calibration words read at init with integer formulas of
+//! datasheet, temperature is needed for pressure and humidity compensation
+//! so all three values are read at once.
+//!
+//! ```ignore
+//! let mut bme = Bme280::new(i2c);
+//! bme.init().await?;
+//! let reading = bme.measure().await?;
+//! info!("{} °C/10, {} %/10, {} Pa", reading.temperature, reading.humidity, reading.pressure);
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::{Humidity, Pressure, Temperature};
+
+/// I2C address of sensor with SDO pin low.
+pub const ADDRESS: u8 = 0x76;
+/// I2C address of sensor with SDO pin high.
+pub const ADDRESS_ALT: u8 = 0x77;
+
+/// Content of chip ID register.
+pub const CHIP_ID: u8 = 0x60;
+
+/// Reading is reused by trait methods within this period.
+const FRESH: Duration = Duration::from_secs(1);
+
+const REG_CALIB_TP: u8 = 0x88;
+const REG_CHIP_ID: u8 = 0xd0;
+const REG_RESET: u8 = 0xe0;
+const REG_CALIB_H: u8 = 0xe1;
+const REG_CTRL_HUM: u8 = 0xf2;
+const REG_STATUS: u8 = 0xf3;
+const REG_CTRL_MEAS: u8 = 0xf4;
+const REG_DATA: u8 = 0xf7;
+
+const RESET: u8 = 0xb6;
+/// Humidity oversampling x1.
+const CTRL_HUM: u8 = 0x01;
+/// Temperature and pressure oversampling x1 (`001`, `001`), forced mode (`01`).
+const CTRL_MEAS_FORCED: u8 = 0b0010_0101;
+/// Conversion is running.
+const STATUS_MEASURING: u8 = 1 << 3;
+/// Calibration words are copied from NVM.
+const STATUS_UPDATING: u8 = 1 << 0;
+
+/// BME280 errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bme280Error<E> {
+    /// Bus failure or sensor doesn't acknowledge.
+    I2c(E),
+    /// Other chip at the address, e.g. BMP280 without humidity sensor.
+    ChipId(u8),
+    /// Calibration isn't read yet or measurement doesn't complete.
+    NotReady,
+}
+
+impl<E> From<E> for Bme280Error<E> {
+    fn from(e: E) -> Self {
+        Self::I2c(e)
+    }
+}
+
+/// Measured values in units of sensor traits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// Temperature in tenths of degrees Celsius.
+    pub temperature: i16,
+    /// Relative humidity in tenths of a percent.
+    pub humidity: u16,
+    /// Pressure in Pascals.
+    pub pressure: u32,
+}
+
+/// Factory trimming words of sensor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Calibration {
+    t1: u16,
+    t2: i16,
+    t3: i16,
+    p: [i64; 9],
+    h1: u8,
+    h2: i16,
+    h3: u8,
+    h4: i16,
+    h5: i16,
+    h6: i8,
+}
+
+impl Calibration {
+    /// Words of `0x88..=0xa1` and `0xe1..=0xe7` registers.
+    fn parse(tp: &[u8; 26], h: &[u8; 7]) -> Self {
+        let word = |i: usize| u16::from_le_bytes([tp[i], tp[i + 1]]);
+        let mut p = [0; 9];
+        p[0] = word(6).into();
+        for (i, p) in p.iter_mut().enumerate().skip(1) {
+            *p = (word(6 + 2 * i) as i16).into();
+        }
+        Self {
+            t1: word(0),
+            t2: word(2) as i16,
+            t3: word(4) as i16,
+            p,
+            h1: tp[25],
+            h2: i16::from_le_bytes([h[0], h[1]]),
+            h3: h[2],
+            // 12-bit values sharing the middle byte, upper bytes are signed.
+            h4: i16::from(h[3] as i8) << 4 | i16::from(h[4] & 0x0f),
+            h5: i16::from(h[5] as i8) << 4 | i16::from(h[4] >> 4),
+            h6: h[6] as i8,
+        }
+    }
+
+    /// Fine temperature of raw one, shared by other compensations.
+    fn t_fine(&self, adc: i32) -> i32 {
+        let t1 = i32::from(self.t1);
+        let var1 = (((adc >> 3) - (t1 << 1)) * i32::from(self.t2)) >> 11;
+        let var2 = (((((adc >> 4) - t1) * ((adc >> 4) - t1)) >> 12) * i32::from(self.t3)) >> 14;
+        var1 + var2
+    }
+
+    /// Temperature in hundredths of degrees Celsius.
+    fn temperature(t_fine: i32) -> i32 {
+        (t_fine * 5 + 128) >> 8
+    }
+
+    /// Pressure in Pascals as Q24.8.
+    fn pressure(&self, t_fine: i32, adc: i32) -> u32 {
+        let p = &self.p;
+        let mut var1 = i64::from(t_fine) - 128000;
+        let mut var2 = var1 * var1 * p[5];
+        var2 += (var1 * p[4]) << 17;
+        var2 += p[3] << 35;
+        var1 = ((var1 * var1 * p[2]) >> 8) + ((var1 * p[1]) << 12);
+        var1 = (((1 << 47) + var1) * p[0]) >> 33;
+        if var1 == 0 {
+            return 0;
+        }
+        let mut pressure = 1048576 - i64::from(adc);
+        pressure = (((pressure << 31) - var2) * 3125) / var1;
+        var1 = (p[8] * (pressure >> 13) * (pressure >> 13)) >> 25;
+        var2 = (p[7] * pressure) >> 19;
+        (((pressure + var1 + var2) >> 8) + (p[6] << 4)) as u32
+    }
+
+    /// Relative humidity in percents as Q22.10.
+    fn humidity(&self, t_fine: i32, adc: i32) -> u32 {
+        let x = t_fine - 76800;
+        let mut x =
+            ((((adc << 14) - (i32::from(self.h4) << 20) - (i32::from(self.h5) * x)) + 16384) >> 15)
+                * (((((((x * i32::from(self.h6)) >> 10)
+                    * (((x * i32::from(self.h3)) >> 11) + 32768))
+                    >> 10)
+                    + 2097152)
+                    * i32::from(self.h2)
+                    + 8192)
+                    >> 14);
+        x -= ((((x >> 15) * (x >> 15)) >> 7) * i32::from(self.h1)) >> 4;
+        (x.clamp(0, 419430400) >> 12) as u32
+    }
+
+    /// Compensated reading of raw data registers.
+    fn reading(&self, data: &[u8; 8]) -> Reading {
+        let adc20 =
+            |b: &[u8]| (i32::from(b[0]) << 12) | (i32::from(b[1]) << 4) | (i32::from(b[2]) >> 4);
+        let t_fine = self.t_fine(adc20(&data[3..6]));
+        let humidity = self.humidity(t_fine, i32::from(u16::from_be_bytes([data[6], data[7]])));
+        Reading {
+            temperature: (Self::temperature(t_fine) + 5).div_euclid(10) as i16,
+            humidity: ((humidity * 10 + 512) >> 10) as u16,
+            pressure: (self.pressure(t_fine, adc20(&data[..3])) + 128) >> 8,
+        }
+    }
+}
+
+/// BME280 sensor.
+pub struct Bme280<I> {
+    i2c: I,
+    address: u8,
+    calibration: Option<Calibration>,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Bme280<I> {
+    /// Sensor at default address, [`init`](Self::init) it before measurements.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            address: ADDRESS,
+            calibration: None,
+            last: None,
+        }
+    }
+
+    /// Other address, e.g. [`ADDRESS_ALT`].
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Release I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Check chip ID, restart sensor and read its calibration.
+    pub async fn init(&mut self) -> Result<(), Bme280Error<I::Error>> {
+        let mut id = [0];
+        self.read(REG_CHIP_ID, &mut id).await?;
+        if id[0] != CHIP_ID {
+            return Err(Bme280Error::ChipId(id[0]));
+        }
+        self.calibration = None;
+        self.last = None;
+        self.write(REG_RESET, RESET).await?;
+        Timer::after_millis(2).await;
+        let mut status = [STATUS_UPDATING];
+        for _ in 0..10 {
+            self.read(REG_STATUS, &mut status).await?;
+            if status[0] & STATUS_UPDATING == 0 {
+                break;
+            }
+            Timer::after_millis(1).await;
+        }
+        if status[0] & STATUS_UPDATING != 0 {
+            return Err(Bme280Error::NotReady);
+        }
+        let mut tp = [0; 26];
+        let mut h = [0; 7];
+        self.read(REG_CALIB_TP, &mut tp).await?;
+        self.read(REG_CALIB_H, &mut h).await?;
+        self.calibration = Some(Calibration::parse(&tp, &h));
+        // Humidity control is applied by the next write of measurement control.
+        self.write(REG_CTRL_HUM, CTRL_HUM).await
+    }
+
+    /// Measure temperature, humidity and pressure once.
+    pub async fn measure(&mut self) -> Result<Reading, Bme280Error<I::Error>> {
+        let calibration = self.calibration.ok_or(Bme280Error::NotReady)?;
+        self.write(REG_CTRL_MEAS, CTRL_MEAS_FORCED).await?;
+        // The longest conversion with single oversampling takes 9.3 ms.
+        Timer::after_millis(10).await;
+        let mut status = [0];
+        self.read(REG_STATUS, &mut status).await?;
+        if status[0] & STATUS_MEASURING != 0 {
+            return Err(Bme280Error::NotReady);
+        }
+        let mut data = [0; 8];
+        self.read(REG_DATA, &mut data).await?;
+        let reading = calibration.reading(&data);
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Last reading if it is fresh, new one otherwise.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => self.measure().await.ok(),
+        }
+    }
+
+    async fn write(&mut self, register: u8, value: u8) -> Result<(), Bme280Error<I::Error>> {
+        Ok(self.i2c.write(self.address, &[register, value]).await?)
+    }
+
+    async fn read(&mut self, register: u8, buf: &mut [u8]) -> Result<(), Bme280Error<I::Error>> {
+        Ok(self.i2c.write_read(self.address, &[register], buf).await?)
+    }
+}
+
+impl<I: I2c> Temperature for Bme280<I> {
+    async fn temperature(&mut self) -> Option<i16> {
+        self.latest().await.map(|r| r.temperature)
+    }
+}
+
+impl<I: I2c> Humidity for Bme280<I> {
+    async fn humidity(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.humidity)
+    }
+}
+
+impl<I: I2c> Pressure for Bme280<I> {
+    async fn pressure(&mut self) -> Option<u32> {
+        self.latest().await.map(|r| r.pressure)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, recorded};
+    use embassy_futures::block_on;
+    use embedded_hal_async::i2c::{ErrorKind, NoAcknowledgeSource};
+
+    /// Trimming of temperature and pressure example of datasheet, humidity
+    /// words of a real sensor.
+    const CALIB_TP: [u8; 26] = [
+        0x70, 0x6b, 0x43, 0x67, 0x18, 0xfc, // T1..T3
+        0x7d, 0x8e, 0x43, 0xd6, 0xd0, 0x0b, 0x27, 0x0b, 0x8c, 0x00, // P1..P5
+        0xf9, 0xff, 0x8c, 0x3c, 0xf8, 0xc6, 0x70, 0x17, // P6..P9
+        0x00, 0x4b, // H1
+    ];
+    const CALIB_H: [u8; 7] = [0x6a, 0x01, 0x00, 0x13, 0x29, 0x03, 0x1e];
+    /// Raw pressure 415148, temperature 519888 and humidity 30000.
+    const DATA: [u8; 8] = [0x65, 0x5a, 0xc0, 0x7e, 0xed, 0x00, 0x75, 0x30];
+    /// 25.08 °C, 100653.27 Pa of datasheet example and 55 %.
+    const READING: Reading = Reading {
+        temperature: 251,
+        humidity: 550,
+        pressure: 100653,
+    };
+
+    fn calibration() -> Calibration {
+        Calibration::parse(&CALIB_TP, &CALIB_H)
+    }
+
+    #[test]
+    fn calibration_words() {
+        let c = calibration();
+        assert_eq!((c.t1, c.t2, c.t3), (27504, 26435, -1000));
+        assert_eq!(
+            c.p,
+            [36477, -10685, 3024, 2855, 140, -7, 15500, -14600, 6000]
+        );
+        assert_eq!((c.h1, c.h2, c.h3), (75, 362, 0));
+        assert_eq!((c.h4, c.h5, c.h6), (313, 50, 30));
+
+        // Negative 12-bit words.
+        let c = Calibration::parse(&CALIB_TP, &[0, 0, 0, 0xff, 0xef, 0xfe, 0xe2]);
+        assert_eq!((c.h4, c.h5, c.h6), (-1, -18, -30));
+    }
+
+    #[test]
+    fn compensation() {
+        let c = calibration();
+        let t_fine = c.t_fine(519888);
+        assert_eq!(Calibration::temperature(t_fine), 2508);
+        assert_eq!(c.pressure(t_fine, 415148), 25767233);
+        assert_eq!(c.humidity(t_fine, 30000), 56317);
+        assert_eq!(c.reading(&DATA), READING);
+
+        // Humidity is clamped to 0..100 %.
+        assert_eq!(c.humidity(t_fine, 0), 0);
+        assert_eq!(c.humidity(t_fine, 0xffff), 100 << 10);
+
+        // Temperature below zero is rounded too.
+        assert_eq!(Calibration::temperature(c.t_fine(390000)), -1581);
+        let cold = [0x65, 0x5a, 0xc0, 0x5f, 0x37, 0x00, 0x75, 0x30];
+        assert_eq!(c.reading(&cold).temperature, -158);
+    }
+
+    #[test]
+    fn init_and_measure() {
+        let rx: heapless::Vec<u8, 64> =
+            recorded(&[&[CHIP_ID], &[0x00], &CALIB_TP, &CALIB_H, &[0x00], &DATA]);
+        let mut bme = Bme280::new(I2cBus::new(&rx)).with_address(ADDRESS_ALT);
+        block_on(async {
+            assert_eq!(bme.measure().await, Err(Bme280Error::NotReady));
+            bme.init().await.unwrap();
+            assert_eq!(bme.measure().await, Ok(READING));
+            // Reading is reused by sensor traits.
+            assert_eq!(bme.temperature().await, Some(251));
+            assert_eq!(bme.humidity().await, Some(550));
+            assert_eq!(bme.pressure().await, Some(100653));
+        });
+        let bus = bme.release();
+        let writes: heapless::Vec<&[u8], 16> =
+            bus.writes.iter().map(|(_, bytes)| &bytes[..]).collect();
+        assert_eq!(
+            writes[..],
+            [
+                &[0xd0][..],
+                &[0xe0, 0xb6],
+                &[0xf3],
+                &[0x88],
+                &[0xe1],
+                &[0xf2, 0x01],
+                &[0xf4, 0x25],
+                &[0xf3],
+                &[0xf7],
+            ]
+        );
+        assert!(
+            bus.writes
+                .iter()
+                .all(|(address, _)| *address == ADDRESS_ALT)
+        );
+    }
+
+    #[test]
+    fn failures() {
+        // BMP280 has no humidity sensor.
+        let mut bme = Bme280::new(I2cBus::new(&[0x58]));
+        block_on(async {
+            assert_eq!(bme.init().await, Err(Bme280Error::ChipId(0x58)));
+            assert_eq!(
+                bme.init().await,
+                Err(Bme280Error::I2c(ErrorKind::NoAcknowledge(
+                    NoAcknowledgeSource::Data
+                )))
+            );
+        });
+
+        // Conversion isn't complete in time.
+        let mut bme = Bme280::new(I2cBus::new(&[STATUS_MEASURING]));
+        bme.calibration = Some(calibration());
+        block_on(async {
+            assert_eq!(bme.measure().await, Err(Bme280Error::NotReady));
+            assert_eq!(bme.temperature().await, None);
+        });
+    }
+}
@@ -0,0 +1,141 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Fake buses of sensor driver tests.
+
+use core::convert::Infallible;
+use embedded_hal_async::i2c::{self, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+use embedded_io_async::{ErrorType, Read, Write};
+use heapless::Vec;
+
+/// UART replaying recorded bytes and keeping written ones.
+pub struct Serial<'a> {
+    /// Bytes sent by sensor, stream ends after them.
+    pub rx: &'a [u8],
+    /// Bytes written by driver.
+    pub tx: Vec<u8, 64>,
+}
+
+impl<'a> Serial<'a> {
+    pub fn new(rx: &'a [u8]) -> Self {
+        Self { rx, tx: Vec::new() }
+    }
+}
+
+impl ErrorType for Serial<'_> {
+    type Error = Infallible;
+}
+
+impl Read for Serial<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        let len = buf.len().min(self.rx.len());
+        buf[..len].copy_from_slice(&self.rx[..len]);
+        self.rx = &self.rx[len..];
+        Ok(len)
+    }
+}
+
+impl Write for Serial<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        self.tx
+            .extend_from_slice(buf)
+            .expect("too many written bytes");
+        Ok(buf.len())
+    }
+}
+
+/// I2C bus replaying recorded response bytes, CRC included, and keeping writes.
+pub struct I2cBus<'a> {
+    /// Bytes read from sensors, reads beyond them aren't acknowledged.
+    pub rx: &'a [u8],
+    /// Addresses and bytes of acknowledged writes.
+    pub writes: Vec<(u8, Vec<u8, 16>), 32>,
+    /// Count of the next writes which aren't acknowledged, e.g. by sleeping sensor.
+    pub nack: usize,
+}
+
+impl<'a> I2cBus<'a> {
+    pub fn new(rx: &'a [u8]) -> Self {
+        Self {
+            rx,
+            writes: Vec::new(),
+            nack: 0,
+        }
+    }
+
+    /// Commands of writes, the first two bytes.
+    pub fn commands(&self) -> Vec<u16, 32> {
+        self.writes
+            .iter()
+            .map(|(_, bytes)| u16::from_be_bytes([bytes[0], bytes[1]]))
+            .collect()
+    }
+}
+
+impl i2c::ErrorType for I2cBus<'_> {
+    type Error = ErrorKind;
+}
+
+impl I2c for I2cBus<'_> {
+    async fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), ErrorKind> {
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    if self.nack > 0 {
+                        self.nack -= 1;
+                        return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address));
+                    }
+                    let bytes = Vec::from_slice(bytes).expect("too long write");
+                    self.writes.push((address, bytes)).expect("too many writes");
+                }
+                Operation::Read(buf) => {
+                    if buf.len() > self.rx.len() {
+                        return Err(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data));
+                    }
+                    let (bytes, rest) = self.rx.split_at(buf.len());
+                    buf.copy_from_slice(bytes);
+                    self.rx = rest;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stream of recorded parts, e.g. frames or responses.
+pub fn recorded<const N: usize>(parts: &[&[u8]]) -> Vec<u8, N> {
+    let mut stream = Vec::new();
+    for part in parts {
+        stream.extend_from_slice(part).expect("too long stream");
+    }
+    stream
+}
+
+/// Zero words with CRC, e.g. padding of strings.
+pub const fn null_words<const N: usize>() -> [u8; N] {
+    let mut bytes = [0; N];
+    let mut i = 2;
+    while i < N {
+        bytes[i] = 0x81;
+        i += 3;
+    }
+    bytes
+}
@@ -0,0 +1,683 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion gas index algorithm, port of floating point reference
+//! implementation 3.2.
+//!
+//! Algorithm turns raw ticks of SGP40 / SGP41 / SEN5x MOX pixels into VOC
+//! index or NOx index in `1..=500`. VOC index compares air with average of
+//! the last 24 hours mapped to 100: values above mean more VOCs, below mean
+//! less. NOx index maps average to 1 and reports NOx events above it.
+//!
+//! Raw signal is sampled every second, or every 10 s in low power VOC mode.
+//! Index is 0 during 45 s of initial blackout and then adapts to environment
+//! within hours, its state could be saved and restored over power cycles by
+//! [`GasIndexAlgorithm::states`] and [`GasIndexAlgorithm::set_states`].
+//!
+//! State is kept in `f32`, expressions with double literals in reference are
+//! evaluated in `f64` as C does, so indices are the same.
+//!
+//! ```ignore
+//! let mut voc = GasIndexAlgorithm::new(AlgorithmType::Voc);
+//! loop {
+//!     let sraw = sgp.measure_raw(humidity, temperature).await?;
+//!     info!("VOC index {}", voc.process(sraw.voc.into()));
+//!     Timer::after_secs(1).await;
+//! }
+//! ```
+
+use libm::{expf, fabsf, sqrtf};
+
+/// Default sampling interval in seconds.
+pub const DEFAULT_SAMPLING_INTERVAL: f32 = 1.0;
+
+const INITIAL_BLACKOUT: f32 = 45.0;
+const INDEX_GAIN: f32 = 230.0;
+const SRAW_STD_INITIAL: f32 = 50.0;
+const SRAW_STD_BONUS_VOC: f32 = 220.0;
+const SRAW_STD_NOX: f32 = 2000.0;
+const TAU_MEAN_HOURS: f32 = 12.0;
+const TAU_VARIANCE_HOURS: f32 = 12.0;
+const TAU_INITIAL_MEAN_VOC: f32 = 20.0;
+const TAU_INITIAL_MEAN_NOX: f32 = 1200.0;
+const INIT_DURATION_MEAN_VOC: f32 = 3600.0 * 0.75;
+const INIT_DURATION_MEAN_NOX: f32 = 3600.0 * 4.75;
+const INIT_TRANSITION_MEAN: f32 = 0.01;
+const TAU_INITIAL_VARIANCE: f32 = 2500.0;
+const INIT_DURATION_VARIANCE_VOC: f32 = 3600.0 * 1.45;
+const INIT_DURATION_VARIANCE_NOX: f32 = 3600.0 * 5.70;
+const INIT_TRANSITION_VARIANCE: f32 = 0.01;
+const GATING_THRESHOLD_VOC: f32 = 340.0;
+const GATING_THRESHOLD_NOX: f32 = 30.0;
+const GATING_THRESHOLD_INITIAL: f32 = 510.0;
+const GATING_THRESHOLD_TRANSITION: f32 = 0.09;
+const GATING_VOC_MAX_DURATION_MINUTES: f32 = 60.0 * 3.0;
+const GATING_NOX_MAX_DURATION_MINUTES: f32 = 60.0 * 12.0;
+const GATING_MAX_RATIO: f32 = 0.3;
+const SIGMOID_L: f32 = 500.0;
+const SIGMOID_K_VOC: f32 = -0.0065;
+const SIGMOID_X0_VOC: f32 = 213.0;
+const SIGMOID_K_NOX: f32 = -0.0101;
+const SIGMOID_X0_NOX: f32 = 614.0;
+const VOC_INDEX_OFFSET_DEFAULT: f32 = 100.0;
+const NOX_INDEX_OFFSET_DEFAULT: f32 = 1.0;
+const LP_TAU_FAST: f32 = 20.0;
+const LP_TAU_SLOW: f32 = 500.0;
+const LP_ALPHA: f32 = -0.2;
+const VOC_SRAW_MINIMUM: i32 = 20000;
+const NOX_SRAW_MINIMUM: i32 = 10000;
+const PERSISTENCE_UPTIME_GAMMA: f32 = 3.0 * 3600.0;
+const GAMMA_SCALING: f32 = 64.0;
+const ADDITIONAL_GAMMA_MEAN_SCALING: f32 = 8.0;
+const FIX16_MAX: f32 = 32767.0;
+
+/// Gas measured by pixel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlgorithmType {
+    /// Volatile organic compounds.
+    Voc,
+    /// Nitrogen oxides.
+    Nox,
+}
+
+/// Tuning of algorithm, defaults are given by [`GasIndexAlgorithm::tuning`]
+/// of new instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TuningParameters {
+    /// Index of average conditions, `1..=250`, 100 for VOC and 1 for NOx.
+    pub index_offset: i32,
+    /// Time constant of offset estimation in hours, `1..=1000`, 12 by default.
+    pub learning_time_offset_hours: i32,
+    /// Time constant of gain estimation in hours, `1..=1000`, 12 by
+    /// default, ignored for NOx.
+    pub learning_time_gain_hours: i32,
+    /// Longest period when estimation is frozen by high index in minutes,
+    /// `0..=3000`, 180 for VOC and 720 for NOx, zero disables it.
+    pub gating_max_duration_minutes: i32,
+    /// Initial estimation of standard deviation, `10..=5000`, 50 by
+    /// default, ignored for NOx.
+    pub std_initial: i32,
+    /// Gain factor of index, `1..=1000`, 230 by default.
+    pub gain_factor: i32,
+}
+
+/// Gas index algorithm state of one pixel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GasIndexAlgorithm {
+    algorithm_type: AlgorithmType,
+    sampling_interval: f32,
+    index_offset: f32,
+    sraw_minimum: i32,
+    gating_max_duration_minutes: f32,
+    init_duration_mean: f32,
+    init_duration_variance: f32,
+    gating_threshold: f32,
+    index_gain: f32,
+    tau_mean_hours: f32,
+    tau_variance_hours: f32,
+    sraw_std_initial: f32,
+    uptime: f32,
+    sraw: f32,
+    gas_index: f32,
+    estimator: MeanVarianceEstimator,
+    mox_model: MoxModel,
+    sigmoid_scaled: SigmoidScaled,
+    lowpass: AdaptiveLowpass,
+}
+
+/// Estimator of raw signal mean and deviation, gated by high index.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct MeanVarianceEstimator {
+    initialized: bool,
+    mean: f32,
+    sraw_offset: f32,
+    std: f32,
+    gamma_mean: f32,
+    gamma_variance: f32,
+    gamma_initial_mean: f32,
+    gamma_initial_variance: f32,
+    current_gamma_mean: f32,
+    current_gamma_variance: f32,
+    uptime_gamma: f32,
+    uptime_gating: f32,
+    gating_duration_minutes: f32,
+    sigmoid: Sigmoid,
+}
+
+/// Logistic function of estimator.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Sigmoid {
+    k: f32,
+    x0: f32,
+}
+
+impl Sigmoid {
+    fn new(x0: f32, k: f32) -> Self {
+        Self { k, x0 }
+    }
+
+    fn process(&self, sample: f32) -> f32 {
+        let x = self.k * (sample - self.x0);
+        if x < -50.0 {
+            1.0
+        } else if x > 50.0 {
+            0.0
+        } else {
+            (1.0 / (1.0 + f64::from(expf(x)))) as f32
+        }
+    }
+}
+
+/// Normalization of raw signal by estimated mean and deviation.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct MoxModel {
+    sraw_std: f32,
+    sraw_mean: f32,
+}
+
+/// Mapping of normalized signal to index range.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct SigmoidScaled {
+    k: f32,
+    x0: f32,
+    offset_default: f32,
+}
+
+/// Low pass filter with time constant adapted to signal changes.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct AdaptiveLowpass {
+    a1: f32,
+    a2: f32,
+    initialized: bool,
+    x1: f32,
+    x2: f32,
+    x3: f32,
+}
+
+impl GasIndexAlgorithm {
+    /// Algorithm with default sampling interval of 1 s.
+    pub fn new(algorithm_type: AlgorithmType) -> Self {
+        Self::with_sampling_interval(algorithm_type, DEFAULT_SAMPLING_INTERVAL)
+    }
+
+    /// Algorithm with sampling interval in seconds, e.g. 10 s of low power VOC mode.
+    pub fn with_sampling_interval(algorithm_type: AlgorithmType, sampling_interval: f32) -> Self {
+        let (index_offset, sraw_minimum, gating_max, init_mean, init_variance, gating) =
+            match algorithm_type {
+                AlgorithmType::Nox => (
+                    NOX_INDEX_OFFSET_DEFAULT,
+                    NOX_SRAW_MINIMUM,
+                    GATING_NOX_MAX_DURATION_MINUTES,
+                    INIT_DURATION_MEAN_NOX,
+                    INIT_DURATION_VARIANCE_NOX,
+                    GATING_THRESHOLD_NOX,
+                ),
+                AlgorithmType::Voc => (
+                    VOC_INDEX_OFFSET_DEFAULT,
+                    VOC_SRAW_MINIMUM,
+                    GATING_VOC_MAX_DURATION_MINUTES,
+                    INIT_DURATION_MEAN_VOC,
+                    INIT_DURATION_VARIANCE_VOC,
+                    GATING_THRESHOLD_VOC,
+                ),
+            };
+        let mut algorithm = Self {
+            algorithm_type,
+            sampling_interval,
+            index_offset,
+            sraw_minimum,
+            gating_max_duration_minutes: gating_max,
+            init_duration_mean: init_mean,
+            init_duration_variance: init_variance,
+            gating_threshold: gating,
+            index_gain: INDEX_GAIN,
+            tau_mean_hours: TAU_MEAN_HOURS,
+            tau_variance_hours: TAU_VARIANCE_HOURS,
+            sraw_std_initial: SRAW_STD_INITIAL,
+            uptime: 0.0,
+            sraw: 0.0,
+            gas_index: 0.0,
+            estimator: MeanVarianceEstimator::default(),
+            mox_model: MoxModel::default(),
+            sigmoid_scaled: SigmoidScaled::default(),
+            lowpass: AdaptiveLowpass::default(),
+        };
+        algorithm.reset();
+        algorithm
+    }
+
+    /// Kind of gas.
+    pub fn algorithm_type(&self) -> AlgorithmType {
+        self.algorithm_type
+    }
+
+    /// Sampling interval in seconds.
+    pub fn sampling_interval(&self) -> f32 {
+        self.sampling_interval
+    }
+
+    /// Forget learned state, tuning is kept.
+    pub fn reset(&mut self) {
+        self.uptime = 0.0;
+        self.sraw = 0.0;
+        self.gas_index = 0.0;
+        self.init_instances();
+    }
+
+    /// Current tuning.
+    pub fn tuning(&self) -> TuningParameters {
+        TuningParameters {
+            index_offset: self.index_offset as i32,
+            learning_time_offset_hours: self.tau_mean_hours as i32,
+            learning_time_gain_hours: self.tau_variance_hours as i32,
+            gating_max_duration_minutes: self.gating_max_duration_minutes as i32,
+            std_initial: self.sraw_std_initial as i32,
+            gain_factor: self.index_gain as i32,
+        }
+    }
+
+    /// Change tuning, learned state is reset.
+    pub fn set_tuning(&mut self, tuning: TuningParameters) {
+        self.index_offset = tuning.index_offset as f32;
+        self.tau_mean_hours = tuning.learning_time_offset_hours as f32;
+        self.tau_variance_hours = tuning.learning_time_gain_hours as f32;
+        self.gating_max_duration_minutes = tuning.gating_max_duration_minutes as f32;
+        self.sraw_std_initial = tuning.std_initial as f32;
+        self.index_gain = tuning.gain_factor as f32;
+        self.init_instances();
+    }
+
+    /// Learned mean and deviation of raw signal, to be restored after
+    /// power cycle of less than 10 minutes. NOx algorithm has no such state.
+    pub fn states(&self) -> (f32, f32) {
+        (self.estimator.mean(), self.estimator.std)
+    }
+
+    /// Restore learned state, initial blackout should still be passed.
+    pub fn set_states(&mut self, mean: f32, std: f32) {
+        self.estimator
+            .set_states(mean, std, PERSISTENCE_UPTIME_GAMMA);
+        self.update_mox_model();
+        self.sraw = mean;
+    }
+
+    /// Process raw ticks of pixel sampled at sampling interval, returns index.
+    ///
+    /// Index is 0 during initial blackout, raw values outside of `1..65000`
+    /// are ignored.
+    pub fn process(&mut self, sraw: i32) -> i32 {
+        if self.uptime <= INITIAL_BLACKOUT {
+            self.uptime += self.sampling_interval;
+        } else {
+            if sraw > 0 && sraw < 65000 {
+                let sraw = sraw.clamp(self.sraw_minimum + 1, self.sraw_minimum + 32767);
+                self.sraw = (sraw - self.sraw_minimum) as f32;
+            }
+            self.gas_index =
+                if self.algorithm_type == AlgorithmType::Voc || self.estimator.initialized {
+                    let index = self.mox_model_process(self.sraw);
+                    self.sigmoid_scaled_process(index)
+                } else {
+                    self.index_offset
+                };
+            self.gas_index = self.lowpass.process(self.gas_index, self.sampling_interval);
+            if self.gas_index < 0.5 {
+                self.gas_index = 0.5;
+            }
+            if self.sraw > 0.0 {
+                self.estimator_process(self.sraw);
+                self.update_mox_model();
+            }
+        }
+        (f64::from(self.gas_index) + 0.5) as i32
+    }
+
+    fn init_instances(&mut self) {
+        self.estimator_set_parameters();
+        self.update_mox_model();
+        self.sigmoid_scaled = match self.algorithm_type {
+            AlgorithmType::Nox => SigmoidScaled {
+                k: SIGMOID_K_NOX,
+                x0: SIGMOID_X0_NOX,
+                offset_default: NOX_INDEX_OFFSET_DEFAULT,
+            },
+            AlgorithmType::Voc => SigmoidScaled {
+                k: SIGMOID_K_VOC,
+                x0: SIGMOID_X0_VOC,
+                offset_default: VOC_INDEX_OFFSET_DEFAULT,
+            },
+        };
+        self.lowpass.set_parameters(self.sampling_interval);
+    }
+
+    fn update_mox_model(&mut self) {
+        self.mox_model = MoxModel {
+            sraw_std: self.estimator.std,
+            sraw_mean: self.estimator.mean(),
+        };
+    }
+
+    fn estimator_set_parameters(&mut self) {
+        let interval = self.sampling_interval;
+        let interval_hours = interval / 3600.0;
+        let tau_initial_mean = match self.algorithm_type {
+            AlgorithmType::Nox => TAU_INITIAL_MEAN_NOX,
+            AlgorithmType::Voc => TAU_INITIAL_MEAN_VOC,
+        };
+        self.estimator = MeanVarianceEstimator {
+            initialized: false,
+            mean: 0.0,
+            sraw_offset: 0.0,
+            std: self.sraw_std_initial,
+            gamma_mean: ((ADDITIONAL_GAMMA_MEAN_SCALING * GAMMA_SCALING) * interval_hours)
+                / (self.tau_mean_hours + interval_hours),
+            gamma_variance: (GAMMA_SCALING * interval_hours)
+                / (self.tau_variance_hours + interval_hours),
+            gamma_initial_mean: ((ADDITIONAL_GAMMA_MEAN_SCALING * GAMMA_SCALING) * interval)
+                / (tau_initial_mean + interval),
+            gamma_initial_variance: (GAMMA_SCALING * interval) / (TAU_INITIAL_VARIANCE + interval),
+            current_gamma_mean: 0.0,
+            current_gamma_variance: 0.0,
+            uptime_gamma: 0.0,
+            uptime_gating: 0.0,
+            gating_duration_minutes: 0.0,
+            sigmoid: Sigmoid::default(),
+        };
+    }
+
+    fn estimator_calculate_gamma(&mut self) {
+        let interval = self.sampling_interval;
+        let gas_index = self.gas_index;
+        let e = &mut self.estimator;
+
+        let uptime_limit = FIX16_MAX - interval;
+        if e.uptime_gamma < uptime_limit {
+            e.uptime_gamma += interval;
+        }
+        if e.uptime_gating < uptime_limit {
+            e.uptime_gating += interval;
+        }
+
+        e.sigmoid = Sigmoid::new(self.init_duration_mean, INIT_TRANSITION_MEAN);
+        let sigmoid_gamma_mean = e.sigmoid.process(e.uptime_gamma);
+        let gamma_mean = e.gamma_mean + (e.gamma_initial_mean - e.gamma_mean) * sigmoid_gamma_mean;
+        let gating_threshold_mean = self.gating_threshold
+            + (GATING_THRESHOLD_INITIAL - self.gating_threshold)
+                * e.sigmoid.process(e.uptime_gating);
+        e.sigmoid = Sigmoid::new(gating_threshold_mean, GATING_THRESHOLD_TRANSITION);
+        let sigmoid_gating_mean = e.sigmoid.process(gas_index);
+        e.current_gamma_mean = sigmoid_gating_mean * gamma_mean;
+
+        e.sigmoid = Sigmoid::new(self.init_duration_variance, INIT_TRANSITION_VARIANCE);
+        let sigmoid_gamma_variance = e.sigmoid.process(e.uptime_gamma);
+        let gamma_variance = e.gamma_variance
+            + (e.gamma_initial_variance - e.gamma_variance)
+                * (sigmoid_gamma_variance - sigmoid_gamma_mean);
+        let gating_threshold_variance = self.gating_threshold
+            + (GATING_THRESHOLD_INITIAL - self.gating_threshold)
+                * e.sigmoid.process(e.uptime_gating);
+        e.sigmoid = Sigmoid::new(gating_threshold_variance, GATING_THRESHOLD_TRANSITION);
+        let sigmoid_gating_variance = e.sigmoid.process(gas_index);
+        e.current_gamma_variance = sigmoid_gating_variance * gamma_variance;
+
+        e.gating_duration_minutes = (f64::from(e.gating_duration_minutes)
+            + (f64::from(interval) / 60.0)
+                * (((1.0 - f64::from(sigmoid_gating_mean)) * (1.0 + f64::from(GATING_MAX_RATIO)))
+                    - f64::from(GATING_MAX_RATIO))) as f32;
+        if e.gating_duration_minutes < 0.0 {
+            e.gating_duration_minutes = 0.0;
+        }
+        if e.gating_duration_minutes > self.gating_max_duration_minutes {
+            e.uptime_gating = 0.0;
+        }
+    }
+
+    fn estimator_process(&mut self, sraw: f32) {
+        if !self.estimator.initialized {
+            let e = &mut self.estimator;
+            e.initialized = true;
+            e.sraw_offset = sraw;
+            e.mean = 0.0;
+            return;
+        }
+        {
+            let e = &mut self.estimator;
+            if e.mean >= 100.0 || e.mean <= -100.0 {
+                e.sraw_offset += e.mean;
+                e.mean = 0.0;
+            }
+        }
+        let sraw = sraw - self.estimator.sraw_offset;
+        self.estimator_calculate_gamma();
+        let e = &mut self.estimator;
+        let delta_sgp = (sraw - e.mean) / GAMMA_SCALING;
+        let c = if delta_sgp < 0.0 {
+            e.std - delta_sgp
+        } else {
+            e.std + delta_sgp
+        };
+        let additional_scaling = if c > 1440.0 {
+            let ratio = f64::from(c) / 1440.0;
+            (ratio * ratio) as f32
+        } else {
+            1.0
+        };
+        e.std = sqrtf(additional_scaling * (GAMMA_SCALING - e.current_gamma_variance))
+            * sqrtf(
+                (e.std * (e.std / (GAMMA_SCALING * additional_scaling)))
+                    + (((e.current_gamma_variance * delta_sgp) / additional_scaling) * delta_sgp),
+            );
+        e.mean += (e.current_gamma_mean * delta_sgp) / ADDITIONAL_GAMMA_MEAN_SCALING;
+    }
+
+    fn mox_model_process(&self, sraw: f32) -> f32 {
+        let m = &self.mox_model;
+        match self.algorithm_type {
+            AlgorithmType::Nox => ((sraw - m.sraw_mean) / SRAW_STD_NOX) * self.index_gain,
+            AlgorithmType::Voc => {
+                ((sraw - m.sraw_mean) / -(m.sraw_std + SRAW_STD_BONUS_VOC)) * self.index_gain
+            }
+        }
+    }
+
+    fn sigmoid_scaled_process(&self, sample: f32) -> f32 {
+        let s = &self.sigmoid_scaled;
+        let x = s.k * (sample - s.x0);
+        if x < -50.0 {
+            SIGMOID_L
+        } else if x > 50.0 {
+            0.0
+        } else if sample >= 0.0 {
+            let index_offset = f64::from(self.index_offset);
+            let shift = if s.offset_default == 1.0 {
+                ((500.0 / 499.0) * (1.0 - index_offset)) as f32
+            } else {
+                ((f64::from(SIGMOID_L) - (5.0 * index_offset)) / 4.0) as f32
+            };
+            ((f64::from(SIGMOID_L + shift) / (1.0 + f64::from(expf(x)))) - f64::from(shift)) as f32
+        } else {
+            let scale = self.index_offset / s.offset_default;
+            (f64::from(scale) * (f64::from(SIGMOID_L) / (1.0 + f64::from(expf(x))))) as f32
+        }
+    }
+}
+
+impl MeanVarianceEstimator {
+    fn mean(&self) -> f32 {
+        self.mean + self.sraw_offset
+    }
+
+    fn set_states(&mut self, mean: f32, std: f32, uptime_gamma: f32) {
+        self.mean = mean;
+        self.sraw_offset = 0.0;
+        self.std = std;
+        self.uptime_gamma = uptime_gamma;
+        self.initialized = true;
+    }
+}
+
+impl AdaptiveLowpass {
+    fn set_parameters(&mut self, interval: f32) {
+        self.a1 = interval / (LP_TAU_FAST + interval);
+        self.a2 = interval / (LP_TAU_SLOW + interval);
+        self.initialized = false;
+    }
+
+    fn process(&mut self, sample: f32, interval: f32) -> f32 {
+        if !self.initialized {
+            self.x1 = sample;
+            self.x2 = sample;
+            self.x3 = sample;
+            self.initialized = true;
+        }
+        self.x1 = mix(self.x1, self.a1, sample);
+        self.x2 = mix(self.x2, self.a2, sample);
+        let abs_delta = fabsf(self.x1 - self.x2);
+        let f1 = expf(LP_ALPHA * abs_delta);
+        let tau_a = (LP_TAU_SLOW - LP_TAU_FAST) * f1 + LP_TAU_FAST;
+        let a3 = interval / (interval + tau_a);
+        self.x3 = mix(self.x3, a3, sample);
+        self.x3
+    }
+}
+
+/// Filter step `(1 - a) * x + a * sample`.
+fn mix(x: f32, a: f32, sample: f32) -> f32 {
+    ((1.0 - f64::from(a)) * f64::from(x) + f64::from(a * sample)) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Four hours of raw ticks sampled every second: VOC baseline of 30000
+    /// slowly rising, VOC event of 10 min at 2 h, cleaner air at 3 h, three
+    /// invalid zero samples and NOx event of 5 min at 2.5 h.
+    fn raw_ticks() -> impl Iterator<Item = (i32, i32)> {
+        let mut state = 1u32;
+        (0..14400).map(move |t| {
+            state = (state.wrapping_mul(1103515245).wrapping_add(12345)) & 0x7fff_ffff;
+            let noise = (state >> 16) as i32 % 41 - 20;
+            let mut voc = 30000 + noise + t / 120;
+            match t {
+                7200..7800 => voc -= 1500,
+                10800..11400 => voc += 400,
+                5000..5003 => voc = 0,
+                _ => {}
+            }
+            let mut nox = 16000 + noise.div_euclid(4);
+            if (9000..9300).contains(&t) {
+                nox += 4000;
+            }
+            (voc, nox)
+        })
+    }
+
+    /// Indices of C reference implementation 3.2 for [`raw_ticks`] every 2 minutes.
+    const VOC_INDICES: [i32; 120] = [
+        97, 99, 101, 100, 100, 101, 100, 100, 100, 100, 101, 100, 100, 100, 100, 100, 100, 100,
+        100, 100, 100, 100, 100, 100, 100, 100, 100, 99, 99, 98, 98, 98, 98, 97, 97, 96, 96, 95,
+        95, 94, 94, 93, 93, 92, 92, 92, 91, 91, 91, 90, 90, 89, 89, 88, 88, 88, 87, 87, 87, 86,
+        498, 499, 499, 499, 499, 85, 83, 83, 82, 82, 82, 83, 79, 80, 81, 79, 80, 78, 80, 79, 79,
+        78, 78, 78, 77, 77, 77, 77, 76, 76, 10, 10, 11, 11, 11, 79, 78, 77, 77, 76, 76, 76, 76, 76,
+        75, 75, 75, 75, 75, 75, 74, 74, 74, 74, 73, 73, 73, 73, 72, 72,
+    ];
+    const NOX_INDICES: [i32; 120] = [
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 64, 45, 12, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+        1,
+    ];
+    /// NOx indices of C reference every 20 s since the start of event.
+    const NOX_EVENT: [i32; 20] = [
+        1, 47, 67, 71, 70, 67, 63, 60, 56, 53, 50, 47, 45, 43, 41, 39, 34, 22, 12, 6,
+    ];
+
+    #[test]
+    fn reference_indices() {
+        let mut voc = GasIndexAlgorithm::new(AlgorithmType::Voc);
+        let mut nox = GasIndexAlgorithm::new(AlgorithmType::Nox);
+        let (mut voc_indices, mut nox_indices, mut nox_event) = (
+            heapless::Vec::<i32, 120>::new(),
+            heapless::Vec::<i32, 120>::new(),
+            heapless::Vec::<i32, 20>::new(),
+        );
+        for (t, (voc_raw, nox_raw)) in raw_ticks().enumerate() {
+            let (voc_index, nox_index) = (voc.process(voc_raw), nox.process(nox_raw));
+            if t <= 45 {
+                // Initial blackout.
+                assert_eq!((voc_index, nox_index), (0, 0), "{t}");
+            }
+            if t % 120 == 119 {
+                _ = voc_indices.push(voc_index);
+                _ = nox_indices.push(nox_index);
+            }
+            if (9000..9400).contains(&t) && t % 20 == 0 {
+                _ = nox_event.push(nox_index);
+            }
+        }
+        assert_eq!(voc_indices, VOC_INDICES);
+        assert_eq!(nox_indices, NOX_INDICES);
+        assert_eq!(nox_event, NOX_EVENT);
+    }
+
+    #[test]
+    fn restored_states() {
+        let mut voc = GasIndexAlgorithm::new(AlgorithmType::Voc);
+        for (voc_raw, _) in raw_ticks().take(7200) {
+            voc.process(voc_raw);
+        }
+        let (mean, std) = voc.states();
+        let mut restored = GasIndexAlgorithm::new(AlgorithmType::Voc);
+        restored.set_states(mean, std);
+        assert_eq!(restored.states(), (mean, std));
+        // Learned average is reported right after blackout.
+        let index = (0..60)
+            .map(|_| restored.process(mean as i32 + 20000))
+            .last();
+        assert_eq!(index, Some(100));
+    }
+
+    #[test]
+    fn tuning() {
+        let mut nox = GasIndexAlgorithm::new(AlgorithmType::Nox);
+        let default = nox.tuning();
+        assert_eq!(
+            default,
+            TuningParameters {
+                index_offset: 1,
+                learning_time_offset_hours: 12,
+                learning_time_gain_hours: 12,
+                gating_max_duration_minutes: 720,
+                std_initial: 50,
+                gain_factor: 230,
+            }
+        );
+        nox.set_tuning(TuningParameters {
+            gain_factor: 100,
+            ..default
+        });
+        assert_eq!(nox.tuning().gain_factor, 100);
+        assert_eq!(
+            GasIndexAlgorithm::new(AlgorithmType::Voc)
+                .tuning()
+                .index_offset,
+            100
+        );
+    }
+}
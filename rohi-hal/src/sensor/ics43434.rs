@@ -0,0 +1,173 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! TDK InvenSense ICS-43434 digital MEMS microphone sound level.
+//!
+//! Microphone sends 24-bit two's complement samples, left justified in 32-bit
+//! I2S slots. Board reads them at [`SAMPLE_RATE`] and feeds into [`SoundMeter`],
+//! which weights them by A curve of IEC 61672 and averages their energy into
+//! equivalent continuous level. Sine of `-26 dBFS` RMS is `94 dB SPL` by
+//! sensitivity of microphone, acoustic overload point is `120 dB SPL`.
+//!
+//! ```ignore
+//! let mut meter = SoundMeter::new();
+//! loop {
+//!     i2s.read(&mut samples).await?;
+//!     meter.push(&samples);
+//! }
+//! info!("{} dBA/10", meter.level());
+//! ```
+
+/// Sample rate weighting filter is designed for.
+pub const SAMPLE_RATE: u32 = 48_000;
+
+/// Sound pressure level of digital full scale sine in dB.
+const FULL_SCALE_DB: f32 = 94.0 + 26.0;
+
+/// Samples dropped after start while microphone and filter settle, 100 ms.
+const SETTLE: u32 = SAMPLE_RATE / 10;
+
+/// A-weighting at 48 kHz as gain and second order sections of `[b1, b2, a1, a2]`
+/// with `b0 = 1` and negated poles, i.e. `y = x + b1·w1 + b2·w2`, `w0 = x + a1·w1 + a2·w2`.
+const A_GAIN: f32 = 0.169_994_95;
+const A_SECTIONS: [[f32; 4]; 3] = [
+    [-2.000_27, 1.000_27, -1.060_868_4, -0.163_987_45],
+    [4.359_124, 3.091_202_6, 1.208_419_9, -0.273_167],
+    [-0.709_303, -0.290_718_7, 1.982_242_2, -0.982_298_6],
+];
+
+/// Equivalent continuous A-weighted sound level of samples.
+#[derive(Clone, Debug, Default)]
+pub struct SoundMeter {
+    /// Delay lines of weighting sections.
+    state: [[f32; 2]; 3],
+    /// Sum of squared weighted samples, full scale is 1.
+    energy: f64,
+    count: u32,
+    dropped: u32,
+}
+
+impl SoundMeter {
+    /// New meter, the first samples are dropped while microphone starts.
+    pub const fn new() -> Self {
+        Self {
+            state: [[0.0; 2]; 3],
+            energy: 0.0,
+            count: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Add raw 32-bit slots of microphone.
+    pub fn push(&mut self, samples: &[i32]) {
+        for &sample in samples {
+            let weighted = self.weight(sample as f32 / 2_147_483_648.0);
+            if self.dropped < SETTLE {
+                self.dropped += 1;
+                continue;
+            }
+            self.energy += f64::from(weighted * weighted);
+            self.count += 1;
+        }
+    }
+
+    /// Count of averaged samples.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Level in tenths of dBA since the last call, `None` without samples.
+    pub fn level(&mut self) -> Option<u16> {
+        if self.count == 0 {
+            return None;
+        }
+        let mean = self.energy / f64::from(self.count);
+        self.energy = 0.0;
+        self.count = 0;
+        let db = FULL_SCALE_DB + 10.0 * libm::log10(mean) as f32;
+        // Digital silence is below any meaningful level.
+        Some(libm::roundf(db.max(0.0) * 10.0) as u16)
+    }
+
+    fn weight(&mut self, mut x: f32) -> f32 {
+        for ([b1, b2, a1, a2], w) in A_SECTIONS.iter().zip(self.state.iter_mut()) {
+            let w0 = x + a1 * w[0] + a2 * w[1];
+            x = w0 + b1 * w[0] + b2 * w[1];
+            *w = [w0, w[0]];
+        }
+        x * A_GAIN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One second of sine with RMS `rms_db` dBFS.
+    fn sine(meter: &mut SoundMeter, frequency: f32, rms_db: f32) {
+        let amplitude = libm::powf(10.0, rms_db / 20.0) * core::f32::consts::SQRT_2;
+        let mut block = [0i32; 480];
+        for n in 0..100 {
+            for (i, sample) in block.iter_mut().enumerate() {
+                let t = (n * 480 + i) as f32 / SAMPLE_RATE as f32;
+                let x = amplitude * libm::sinf(2.0 * core::f32::consts::PI * frequency * t);
+                // 24-bit sample in upper bits of slot.
+                *sample = ((x * 8_388_607.0) as i32) << 8;
+            }
+            meter.push(&block);
+        }
+    }
+
+    #[test]
+    fn reference_tone() {
+        let mut meter = SoundMeter::new();
+        assert_eq!(meter.level(), None);
+        sine(&mut meter, 1000.0, -26.0);
+        assert_eq!(meter.count(), SAMPLE_RATE - SETTLE);
+        assert!(meter.level().unwrap().abs_diff(940) <= 1);
+        // Level is reset after reading.
+        assert_eq!(meter.level(), None);
+
+        sine(&mut meter, 1000.0, -46.0);
+        assert!(meter.level().unwrap().abs_diff(740) <= 1);
+    }
+
+    #[test]
+    fn a_weighting() {
+        // IEC 61672 weights are -19.1, +1.0 and -2.5 dB.
+        for (frequency, expected) in [(100.0, 749), (4000.0, 950), (10000.0, 915)] {
+            let mut meter = SoundMeter::new();
+            sine(&mut meter, frequency, -26.0);
+            let level = meter.level().unwrap();
+            assert!(level.abs_diff(expected) <= 2, "{frequency} Hz: {level}");
+        }
+    }
+
+    #[test]
+    fn silence() {
+        let mut meter = SoundMeter::new();
+        meter.push(&[0; 4800]);
+        assert_eq!(meter.level(), None);
+        meter.push(&[0; 480]);
+        assert_eq!(meter.level(), Some(0));
+
+        // DC offset of microphone is removed by weighting.
+        let mut meter = SoundMeter::new();
+        meter.push(&[1 << 24; 9600]);
+        assert!(meter.level().unwrap() < 100);
+    }
+}
@@ -0,0 +1,279 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Winsen MH-Z19B / MH-Z19C NDIR CO2 sensors over UART, 9600 8N1.
+//!
+//! Command is `FF 01 cmd` followed by five data bytes and checksum, answer is
+//! `FF cmd` followed by six data bytes and checksum. Checksum is negated sum
+//! of all bytes but the first one. Only read command is answered, answer is
+//! CO2 in ppm as big endian word.
+//!
+//! Readings are meaningless during 3 minutes of preheating after power up.
+//! Automatic baseline correction (ABC), enabled by default, assumes fresh air
+//! of 400 ppm once a day. Zero point calibration should be done after 20
+//! minutes in fresh air, span calibration after zero one in known
+//! concentration.
+//!
+//! Driver works with any [`embedded_io_async`] byte stream, e.g. async UART or
+//! recorded frames in tests.
+//!
+//! ```ignore
+//! let mut mhz = MhZ19::new(uart);
+//! mhz.set_abc(false).await?;
+//! let reading = mhz.read().await?;
+//! info!("CO2 {} ppm", reading.co2);
+//! ```
+
+use embassy_time::{Duration, Instant, with_timeout};
+use embedded_io_async::{Read, ReadExactError, Write};
+
+use super::Co2;
+
+/// First byte of every frame.
+const START: u8 = 0xff;
+/// Sensor number byte of commands.
+const SENSOR: u8 = 0x01;
+/// Longest wait for answer.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(1);
+/// Reading is reused by trait method within this period, sensor updates it
+/// every few seconds.
+const FRESH: Duration = Duration::from_secs(5);
+
+const CMD_READ: u8 = 0x86;
+const CMD_ZERO: u8 = 0x87;
+const CMD_SPAN: u8 = 0x88;
+const CMD_ABC: u8 = 0x79;
+const CMD_RANGE: u8 = 0x99;
+
+/// MH-Z19 driver errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MhzError<E> {
+    /// UART failure.
+    Io(E),
+    /// Stream ended in the middle of frame.
+    Eof,
+    /// Frame checksum doesn't match, frame is dropped.
+    Checksum,
+    /// No answer within timeout, sensor is absent.
+    Timeout,
+}
+
+impl<E> From<ReadExactError<E>> for MhzError<E> {
+    fn from(e: ReadExactError<E>) -> Self {
+        match e {
+            ReadExactError::UnexpectedEof => Self::Eof,
+            ReadExactError::Other(e) => Self::Io(e),
+        }
+    }
+}
+
+/// Decoded answer to read command.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// CO2 concentration in ppm.
+    pub co2: u16,
+    /// Internal temperature in degrees Celsius, undocumented and coarse.
+    pub temperature: i16,
+}
+
+/// Checksum of frame, the first and the last bytes are excluded.
+fn checksum(frame: &[u8; 9]) -> u8 {
+    frame[1..8]
+        .iter()
+        .fold(0u8, |sum, &b| sum.wrapping_add(b))
+        .wrapping_neg()
+}
+
+/// MH-Z19B / MH-Z19C sensor.
+pub struct MhZ19<U> {
+    uart: U,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<U: Read + Write> MhZ19<U> {
+    /// Sensor on given UART.
+    pub fn new(uart: U) -> Self {
+        Self { uart, last: None }
+    }
+
+    /// Release UART.
+    pub fn release(self) -> U {
+        self.uart
+    }
+
+    /// Request and read CO2 concentration.
+    pub async fn read(&mut self) -> Result<Reading, MhzError<U::Error>> {
+        self.command(CMD_READ, [0; 5]).await?;
+        let frame = with_timeout(ANSWER_TIMEOUT, self.read_answer(CMD_READ))
+            .await
+            .map_err(|_| MhzError::Timeout)??;
+        let reading = Reading {
+            co2: u16::from_be_bytes([frame[2], frame[3]]),
+            temperature: i16::from(frame[4]) - 40,
+        };
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Enable or disable automatic baseline correction.
+    pub async fn set_abc(&mut self, enabled: bool) -> Result<(), MhzError<U::Error>> {
+        let data = if enabled { 0xa0 } else { 0x00 };
+        self.command(CMD_ABC, [data, 0, 0, 0, 0]).await
+    }
+
+    /// Calibrate zero point to 400 ppm of fresh air.
+    pub async fn calibrate_zero(&mut self) -> Result<(), MhzError<U::Error>> {
+        self.last = None;
+        self.command(CMD_ZERO, [0; 5]).await
+    }
+
+    /// Calibrate span point to `ppm` of calibration gas, e.g. 2000.
+    pub async fn calibrate_span(&mut self, ppm: u16) -> Result<(), MhzError<U::Error>> {
+        self.last = None;
+        let [high, low] = ppm.to_be_bytes();
+        self.command(CMD_SPAN, [high, low, 0, 0, 0]).await
+    }
+
+    /// Set measurement range in ppm, 2000, 5000 or 10000.
+    pub async fn set_range(&mut self, ppm: u16) -> Result<(), MhzError<U::Error>> {
+        let [high, low] = ppm.to_be_bytes();
+        self.command(CMD_RANGE, [0, 0, 0, high, low]).await
+    }
+
+    /// Last reading if it is fresh, new one otherwise.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => self.read().await.ok(),
+        }
+    }
+
+    /// Read frames until answer to `cmd`, other bytes and answers are skipped.
+    async fn read_answer(&mut self, cmd: u8) -> Result<[u8; 9], MhzError<U::Error>> {
+        let mut frame = [0u8; 9];
+        self.uart.read_exact(&mut frame[..2]).await?;
+        while frame[..2] != [START, cmd] {
+            // Second byte could start the answer, e.g. in `FF FF 86`.
+            frame[0] = frame[1];
+            self.uart.read_exact(&mut frame[1..2]).await?;
+        }
+        self.uart.read_exact(&mut frame[2..]).await?;
+        if checksum(&frame) != frame[8] {
+            return Err(MhzError::Checksum);
+        }
+        Ok(frame)
+    }
+
+    async fn command(&mut self, cmd: u8, data: [u8; 5]) -> Result<(), MhzError<U::Error>> {
+        let mut frame = [
+            START, SENSOR, cmd, data[0], data[1], data[2], data[3], data[4], 0,
+        ];
+        frame[8] = checksum(&frame);
+        self.uart.write_all(&frame).await.map_err(MhzError::Io)?;
+        self.uart.flush().await.map_err(MhzError::Io)
+    }
+}
+
+impl<U: Read + Write> Co2 for MhZ19<U> {
+    async fn co2(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.co2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{Serial, recorded};
+    use embassy_futures::block_on;
+
+    const READ: [u8; 9] = [0xff, 0x01, 0x86, 0x00, 0x00, 0x00, 0x00, 0x00, 0x79];
+    /// 608 ppm at 31 °C.
+    const ANSWER: [u8; 9] = [0xff, 0x86, 0x02, 0x60, 0x47, 0x00, 0x00, 0x00, 0xd1];
+    const READING: Reading = Reading {
+        co2: 608,
+        temperature: 31,
+    };
+
+    #[test]
+    fn read_command() {
+        let mut mhz = MhZ19::new(Serial::new(&ANSWER));
+        assert_eq!(block_on(mhz.read()), Ok(READING));
+        assert_eq!(mhz.release().tx, READ);
+    }
+
+    #[test]
+    fn other_answers_are_skipped() {
+        // Noise, answer to ABC command and start byte of partial frame.
+        let noise = [
+            0x00, 0xff, 0x79, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0xff,
+        ];
+        for skipped in [&noise[..], &[0xff], &[0xff, 0xff], &[0x86, 0xff]] {
+            let rx: heapless::Vec<u8, 24> = recorded(&[skipped, &ANSWER]);
+            let mut mhz = MhZ19::new(Serial::new(&rx));
+            assert_eq!(block_on(mhz.read()), Ok(READING), "{skipped:02x?}");
+        }
+    }
+
+    #[test]
+    fn checksum_failure() {
+        let mut corrupted = ANSWER;
+        corrupted[3] = 0x61;
+        let rx: heapless::Vec<u8, 18> = recorded(&[&corrupted, &ANSWER]);
+        let mut mhz = MhZ19::new(Serial::new(&rx));
+        block_on(async {
+            assert_eq!(mhz.read().await, Err(MhzError::Checksum));
+            assert_eq!(mhz.read().await, Ok(READING));
+            assert_eq!(mhz.read().await, Err(MhzError::Eof));
+        });
+    }
+
+    #[test]
+    fn configuration_commands() {
+        let mut mhz = MhZ19::new(Serial::new(&[]));
+        block_on(async {
+            mhz.set_abc(true).await.unwrap();
+            mhz.set_abc(false).await.unwrap();
+            mhz.calibrate_zero().await.unwrap();
+            mhz.calibrate_span(2000).await.unwrap();
+            mhz.set_range(5000).await.unwrap();
+        });
+        let tx = mhz.release().tx;
+        let frames: heapless::Vec<&[u8], 5> = tx.chunks(9).collect();
+        assert_eq!(
+            frames[..],
+            [
+                &[0xff, 0x01, 0x79, 0xa0, 0x00, 0x00, 0x00, 0x00, 0xe6][..],
+                &[0xff, 0x01, 0x79, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86],
+                &[0xff, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00, 0x78],
+                &[0xff, 0x01, 0x88, 0x07, 0xd0, 0x00, 0x00, 0x00, 0xa0],
+                &[0xff, 0x01, 0x99, 0x00, 0x00, 0x00, 0x13, 0x88, 0xcb],
+            ]
+        );
+    }
+
+    #[test]
+    fn co2_reading_is_reused() {
+        let mut mhz = MhZ19::new(Serial::new(&ANSWER));
+        block_on(async {
+            assert_eq!(mhz.co2().await, Some(608));
+            assert_eq!(mhz.co2().await, Some(608));
+            mhz.calibrate_zero().await.unwrap();
+            assert_eq!(mhz.co2().await, None);
+        });
+        assert_eq!(mhz.release().tx.len(), 3 * 9);
+    }
+}
@@ -0,0 +1,400 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Plantower PMS5003 / PMS7003 particulate matter sensors over UART, 9600 8N1.
+//!
+//! Data frame is `42 4D`, length `00 1C`, thirteen big endian words and sum
+//! of all previous bytes. Words are PM1.0, PM2.5 and PM10 for standard
+//! particle (CF=1), the same for atmospheric environment, six particle counts
+//! in 0.1 L of air and reserved word. Commands are `42 4D cmd data_h data_l`
+//! followed by sum of bytes, sensor answers to them with short frames which
+//! are skipped.
+//!
+//! In active mode, the default one, sensor sends frames every 0.2..2.3 s, in
+//! passive mode it answers to read command only. Sensor sleeps with fan
+//! stopped while SET pin is low or after sleep command, readings are stable
+//! 30 s after wakeup.
+//!
+//! Driver works with any [`embedded_io_async`] byte stream, e.g. async UART or
+//! recorded frames in tests.
+//!
+//! ```ignore
+//! let mut pms = Pmsx003::new(uart).with_set_pin(set);
+//! pms.set_mode(Mode::Passive).await?;
+//! let reading = pms.read().await?;
+//! info!("PM1.0 {} µg/m³, {} particles > 0.3 µm", reading.pm1_0, reading.counts[0]);
+//! ```
+
+use core::convert::Infallible;
+use embassy_time::{Duration, Instant, with_timeout};
+use embedded_hal::digital::{ErrorType, OutputPin};
+use embedded_io_async::{Read, ReadExactError, Write};
+
+use super::ParticulateMatter;
+
+/// Start of every frame.
+const MAGIC: [u8; 2] = [0x42, 0x4d];
+/// Length field of data frame: thirteen words and checksum.
+const DATA_LEN: usize = 28;
+/// Longest wait for frame, active mode period is up to 2.3 s.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(3);
+/// Reading is reused by trait methods within this period.
+const FRESH: Duration = Duration::from_secs(2);
+
+const CMD_READ: u8 = 0xe2;
+const CMD_MODE: u8 = 0xe1;
+const CMD_SLEEP: u8 = 0xe4;
+
+/// PMSx003 driver errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PmsError<E> {
+    /// UART failure.
+    Io(E),
+    /// Stream ended in the middle of frame.
+    Eof,
+    /// Frame sum doesn't match, frame is dropped.
+    Checksum,
+    /// No frame within timeout, sensor is absent or sleeping.
+    Timeout,
+    /// SET pin failure.
+    Pin,
+}
+
+impl<E> From<ReadExactError<E>> for PmsError<E> {
+    fn from(e: ReadExactError<E>) -> Self {
+        match e {
+            ReadExactError::UnexpectedEof => Self::Eof,
+            ReadExactError::Other(e) => Self::Io(e),
+        }
+    }
+}
+
+/// Reporting mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// Sensor sends frames continuously.
+    #[default]
+    Active,
+    /// Sensor sends frame on request.
+    Passive,
+}
+
+/// Decoded data frame, concentrations are in µg/m³.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// PM1.0 in atmospheric environment.
+    pub pm1_0: u16,
+    /// PM2.5 in atmospheric environment.
+    pub pm2_5: u16,
+    /// PM10 in atmospheric environment.
+    pub pm10: u16,
+    /// PM1.0, PM2.5 and PM10 of standard particle, CF=1.
+    pub cf1: [u16; 3],
+    /// Count of particles in 0.1 L of air with diameter beyond 0.3, 0.5, 1.0,
+    /// 2.5, 5.0 and 10 µm. PMS7003 and PMS5003 report all bins.
+    pub counts: [u16; 6],
+}
+
+impl Reading {
+    /// Decode data words, `frame` is data frame without magic and length.
+    fn decode(frame: &[u8; DATA_LEN]) -> Self {
+        let word = |i: usize| u16::from_be_bytes([frame[2 * i], frame[2 * i + 1]]);
+        Self {
+            cf1: [word(0), word(1), word(2)],
+            pm1_0: word(3),
+            pm2_5: word(4),
+            pm10: word(5),
+            counts: [word(6), word(7), word(8), word(9), word(10), word(11)],
+        }
+    }
+}
+
+/// Placeholder of absent SET pin, sleep and wakeup are done by commands.
+pub struct NoSetPin;
+
+impl ErrorType for NoSetPin {
+    type Error = Infallible;
+}
+
+impl OutputPin for NoSetPin {
+    fn set_low(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+/// PMS5003 / PMS7003 sensor.
+pub struct Pmsx003<U, P = NoSetPin> {
+    uart: U,
+    set: Option<P>,
+    mode: Mode,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<U: Read + Write> Pmsx003<U> {
+    /// Sensor on given UART in its default active mode.
+    pub fn new(uart: U) -> Self {
+        Self {
+            uart,
+            set: None,
+            mode: Mode::Active,
+            last: None,
+        }
+    }
+}
+
+impl<U: Read + Write, P: OutputPin> Pmsx003<U, P> {
+    /// Control sleep by SET pin instead of commands, pin is set high.
+    pub fn with_set_pin<Q: OutputPin>(self, mut pin: Q) -> Pmsx003<U, Q> {
+        _ = pin.set_high();
+        Pmsx003 {
+            uart: self.uart,
+            set: Some(pin),
+            mode: self.mode,
+            last: self.last,
+        }
+    }
+
+    /// Release UART and SET pin.
+    pub fn release(self) -> (U, Option<P>) {
+        (self.uart, self.set)
+    }
+
+    /// Current reporting mode.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Switch reporting mode.
+    pub async fn set_mode(&mut self, mode: Mode) -> Result<(), PmsError<U::Error>> {
+        let data = match mode {
+            Mode::Active => 1,
+            Mode::Passive => 0,
+        };
+        self.command(CMD_MODE, data).await?;
+        self.mode = mode;
+        Ok(())
+    }
+
+    /// Stop fan and laser.
+    pub async fn sleep(&mut self) -> Result<(), PmsError<U::Error>> {
+        self.last = None;
+        match &mut self.set {
+            Some(pin) => pin.set_low().map_err(|_| PmsError::Pin),
+            None => self.command(CMD_SLEEP, 0).await,
+        }
+    }
+
+    /// Start fan and laser, readings are stable after 30 s.
+    pub async fn wake(&mut self) -> Result<(), PmsError<U::Error>> {
+        match &mut self.set {
+            Some(pin) => pin.set_high().map_err(|_| PmsError::Pin),
+            None => self.command(CMD_SLEEP, 1).await,
+        }
+    }
+
+    /// Wait for data frame, it is requested first in passive mode.
+    ///
+    /// Frames with wrong sum are dropped and reported, next read gets the next frame.
+    pub async fn read(&mut self) -> Result<Reading, PmsError<U::Error>> {
+        if self.mode == Mode::Passive {
+            self.command(CMD_READ, 0).await?;
+        }
+        let reading = with_timeout(FRAME_TIMEOUT, self.read_frame())
+            .await
+            .map_err(|_| PmsError::Timeout)??;
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Last reading if it is fresh, new one otherwise.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => self.read().await.ok(),
+        }
+    }
+
+    /// Read frames until data frame, answers to commands are skipped.
+    async fn read_frame(&mut self) -> Result<Reading, PmsError<U::Error>> {
+        loop {
+            // Synchronize on magic, bytes of partially received frame are skipped.
+            let mut magic = [0u8; 2];
+            self.uart.read_exact(&mut magic).await?;
+            while magic != MAGIC {
+                // Second byte could start the frame, e.g. in `42 42 4D`.
+                magic[0] = magic[1];
+                self.uart.read_exact(&mut magic[1..]).await?;
+            }
+            let mut len = [0u8; 2];
+            self.uart.read_exact(&mut len).await?;
+            let mut frame = [0u8; DATA_LEN];
+            let frame_len = u16::from_be_bytes(len) as usize;
+            if frame_len != DATA_LEN {
+                // Short answer to command, consumed in chunks.
+                let mut rest = frame_len;
+                while rest > 0 {
+                    let chunk = rest.min(DATA_LEN);
+                    self.uart.read_exact(&mut frame[..chunk]).await?;
+                    rest -= chunk;
+                }
+                continue;
+            }
+            self.uart.read_exact(&mut frame).await?;
+
+            let sum = MAGIC
+                .iter()
+                .chain(&len)
+                .chain(&frame[..DATA_LEN - 2])
+                .fold(0u16, |sum, &b| sum.wrapping_add(b.into()));
+            if sum.to_be_bytes() != frame[DATA_LEN - 2..] {
+                return Err(PmsError::Checksum);
+            }
+            return Ok(Reading::decode(&frame));
+        }
+    }
+
+    async fn command(&mut self, cmd: u8, data: u8) -> Result<(), PmsError<U::Error>> {
+        let mut frame = [MAGIC[0], MAGIC[1], cmd, 0, data, 0, 0];
+        let sum = frame[..5]
+            .iter()
+            .fold(0u16, |sum, &b| sum.wrapping_add(b.into()));
+        frame[5..].copy_from_slice(&sum.to_be_bytes());
+        self.uart.write_all(&frame).await.map_err(PmsError::Io)?;
+        self.uart.flush().await.map_err(PmsError::Io)
+    }
+}
+
+/// Atmospheric environment concentrations in tenths of µg/m³.
+impl<U: Read + Write, P: OutputPin> ParticulateMatter for Pmsx003<U, P> {
+    async fn pm10(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.pm10.saturating_mul(10))
+    }
+
+    async fn pm25(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.pm2_5.saturating_mul(10))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{Serial, recorded};
+    use embassy_futures::block_on;
+
+    /// PMS7003 data frame: CF=1 and atmospheric 5, 7, 8 µg/m³, counts 933,
+    /// 267, 42, 6, 2, 2 and version 0x97 in reserved word.
+    const FRAME: [u8; 32] = [
+        0x42, 0x4d, 0x00, 0x1c, 0x00, 0x05, 0x00, 0x07, 0x00, 0x08, 0x00, 0x05, 0x00, 0x07, 0x00,
+        0x08, 0x03, 0xa5, 0x01, 0x0b, 0x00, 0x2a, 0x00, 0x06, 0x00, 0x02, 0x00, 0x02, 0x97, 0x00,
+        0x02, 0x52,
+    ];
+    /// Answer to passive mode command.
+    const MODE_ANSWER: [u8; 8] = [0x42, 0x4d, 0x00, 0x04, 0xe1, 0x00, 0x01, 0x74];
+
+    const READING: Reading = Reading {
+        pm1_0: 5,
+        pm2_5: 7,
+        pm10: 8,
+        cf1: [5, 7, 8],
+        counts: [933, 267, 42, 6, 2, 2],
+    };
+
+    #[test]
+    fn active_frames() {
+        // Tail of previous frame is skipped.
+        let stream: heapless::Vec<u8, 80> = recorded(&[&FRAME[20..], &FRAME, &FRAME]);
+        let mut pms = Pmsx003::new(Serial::new(&stream));
+        block_on(async {
+            assert_eq!(pms.read().await, Ok(READING));
+            assert_eq!(pms.read().await, Ok(READING));
+            assert_eq!(pms.read().await, Err(PmsError::Eof));
+        });
+        assert!(pms.release().0.tx.is_empty());
+    }
+
+    #[test]
+    fn resync_on_repeated_magic() {
+        for noise in [
+            &[0x42][..],
+            &[0x42, 0x42],
+            &[0x4d, 0x42],
+            &[0x42, 0x00, 0x42],
+        ] {
+            let stream: heapless::Vec<u8, 40> = recorded(&[noise, &FRAME]);
+            let mut pms = Pmsx003::new(Serial::new(&stream));
+            assert_eq!(block_on(pms.read()), Ok(READING), "{noise:02x?}");
+        }
+    }
+
+    #[test]
+    fn checksum_failure() {
+        let mut corrupted = FRAME;
+        corrupted[9] ^= 0x10;
+        let stream: heapless::Vec<u8, 64> = recorded(&[&corrupted, &FRAME]);
+        let mut pms = Pmsx003::new(Serial::new(&stream));
+        block_on(async {
+            assert_eq!(pms.read().await, Err(PmsError::Checksum));
+            // Next frame is read after failed one.
+            assert_eq!(pms.read().await, Ok(READING));
+        });
+    }
+
+    #[test]
+    fn truncated_frame() {
+        let mut pms = Pmsx003::new(Serial::new(&FRAME[..30]));
+        assert_eq!(block_on(pms.read()), Err(PmsError::Eof));
+    }
+
+    #[test]
+    fn passive_mode() {
+        let stream: heapless::Vec<u8, 40> = recorded(&[&MODE_ANSWER, &FRAME]);
+        let mut pms = Pmsx003::new(Serial::new(&stream));
+        block_on(async {
+            pms.set_mode(Mode::Passive).await.unwrap();
+            assert_eq!(pms.mode(), Mode::Passive);
+            // Answer to mode command is skipped.
+            assert_eq!(pms.read().await, Ok(READING));
+            pms.sleep().await.unwrap();
+            pms.wake().await.unwrap();
+        });
+        assert_eq!(
+            pms.release().0.tx[..],
+            [
+                0x42, 0x4d, 0xe1, 0x00, 0x00, 0x01, 0x70, // passive mode
+                0x42, 0x4d, 0xe2, 0x00, 0x00, 0x01, 0x71, // read
+                0x42, 0x4d, 0xe4, 0x00, 0x00, 0x01, 0x73, // sleep
+                0x42, 0x4d, 0xe4, 0x00, 0x01, 0x01, 0x74, // wake
+            ]
+        );
+    }
+
+    #[test]
+    fn particulate_matter() {
+        let mut pms = Pmsx003::new(Serial::new(&FRAME));
+        block_on(async {
+            assert_eq!(pms.pm25().await, Some(70));
+            // Fresh reading is reused, stream has ended.
+            assert_eq!(pms.pm10().await, Some(80));
+            pms.sleep().await.unwrap();
+            assert_eq!(pms.pm10().await, None);
+        });
+    }
+}
@@ -0,0 +1,483 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion SCD40 / SCD41 photoacoustic CO2 sensors over I2C, address `0x62`.
+//!
+//! Commands and words follow [common protocol](super::sensirion). In periodic
+//! mode sensor measures every 5 s, in low power periodic mode every 30 s.
+//! Measurement is CO2 in ppm, temperature and relative humidity scaled to
+//! `0..65535`. While periodic measurement runs, sensor accepts only reading
+//! commands, stop and ambient pressure, other commands fail with
+//! [`SensirionError::Busy`].
+//!
+//! SCD41 measures on demand as well: single shot takes 5 s and sensor may be
+//! powered down between them. Automatic self calibration (ASC) assumes that
+//! sensor sees fresh air of 400 ppm at least once a week, otherwise forced
+//! recalibration against known concentration should be done after 3 minutes
+//! of measurement in that air.
+//!
+//! ```ignore
+//! let mut scd = Scd4x::new(i2c);
+//! scd.set_automatic_self_calibration(false).await?;
+//! scd.start_periodic().await?;
+//! let reading = scd.read().await?;
+//! info!("CO2 {} ppm", reading.co2);
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::sensirion::{self, SensirionError};
+use super::{Co2, Humidity, Temperature};
+
+/// I2C address of sensor.
+pub const ADDRESS: u8 = 0x62;
+
+/// Time to execute most of commands before the next one or response.
+const EXECUTION: Duration = Duration::from_millis(1);
+/// Period of polling data-ready status.
+const READY_POLL: Duration = Duration::from_millis(250);
+
+const CMD_START_PERIODIC: u16 = 0x21b1;
+const CMD_START_LOW_POWER: u16 = 0x21ac;
+const CMD_READ_MEASUREMENT: u16 = 0xec05;
+const CMD_STOP: u16 = 0x3f86;
+const CMD_DATA_READY: u16 = 0xe4b8;
+const CMD_AMBIENT_PRESSURE: u16 = 0xe000;
+const CMD_FORCED_RECALIBRATION: u16 = 0x362f;
+const CMD_SET_ASC: u16 = 0x2416;
+const CMD_GET_ASC: u16 = 0x2313;
+const CMD_PERSIST: u16 = 0x3615;
+const CMD_SERIAL: u16 = 0x3682;
+const CMD_SELF_TEST: u16 = 0x3639;
+const CMD_SINGLE_SHOT: u16 = 0x219d;
+const CMD_POWER_DOWN: u16 = 0x36e0;
+const CMD_WAKE: u16 = 0x36f6;
+
+/// Measurement mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// No periodic measurement, configuration and single shots are allowed.
+    #[default]
+    Idle,
+    /// Measurement every 5 s.
+    Periodic,
+    /// Measurement every 30 s.
+    LowPower,
+}
+
+impl Mode {
+    /// Measurement interval, `None` when idle.
+    pub fn interval(self) -> Option<Duration> {
+        match self {
+            Self::Idle => None,
+            Self::Periodic => Some(Duration::from_secs(5)),
+            Self::LowPower => Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// Measured values in units of sensor traits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// CO2 concentration in ppm.
+    pub co2: u16,
+    /// Temperature in tenths of degrees Celsius.
+    pub temperature: i16,
+    /// Relative humidity in tenths of a percent.
+    pub humidity: u16,
+}
+
+impl Reading {
+    fn decode(words: &[u16; 3]) -> Self {
+        Self {
+            co2: words[0],
+            temperature: sensirion::temperature(words[1]),
+            humidity: sensirion::humidity(words[2]),
+        }
+    }
+}
+
+/// SCD40 / SCD41 sensor.
+pub struct Scd4x<I> {
+    i2c: I,
+    mode: Mode,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Scd4x<I> {
+    /// Sensor on given bus, it is idle after power up.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            mode: Mode::Idle,
+            last: None,
+        }
+    }
+
+    /// Release I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Current measurement mode.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Start measurement every 5 s.
+    pub async fn start_periodic(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.idle_write(CMD_START_PERIODIC, &[], EXECUTION).await?;
+        self.mode = Mode::Periodic;
+        Ok(())
+    }
+
+    /// Start measurement every 30 s.
+    pub async fn start_low_power(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.idle_write(CMD_START_LOW_POWER, &[], EXECUTION).await?;
+        self.mode = Mode::LowPower;
+        Ok(())
+    }
+
+    /// Stop periodic measurement, sensor returns to idle mode.
+    pub async fn stop(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_STOP, &[], Duration::from_millis(500))
+            .await?;
+        self.mode = Mode::Idle;
+        self.last = None;
+        Ok(())
+    }
+
+    /// New values are ready.
+    pub async fn is_ready(&mut self) -> Result<bool, SensirionError<I::Error>> {
+        let mut status = [0];
+        self.read_words(CMD_DATA_READY, &mut status).await?;
+        Ok(status[0] & 0x07ff != 0)
+    }
+
+    /// Wait for new values of periodic measurement and read them.
+    pub async fn read(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        let Some(interval) = self.mode.interval() else {
+            return Err(SensirionError::NotReady);
+        };
+        let deadline = Instant::now() + interval + Duration::from_secs(1);
+        while !self.is_ready().await? {
+            if Instant::now() >= deadline {
+                return Err(SensirionError::NotReady);
+            }
+            Timer::after(READY_POLL).await;
+        }
+        self.read_measurement().await
+    }
+
+    /// Measure once and read values, SCD41 only.
+    pub async fn measure_single_shot(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        self.idle_write(CMD_SINGLE_SHOT, &[], Duration::from_secs(5))
+            .await?;
+        self.read_measurement().await
+    }
+
+    /// Compensate CO2 by ambient pressure in Pa, allowed during measurement.
+    ///
+    /// It overrides altitude compensation until power cycle.
+    pub async fn set_ambient_pressure(
+        &mut self,
+        pressure: u32,
+    ) -> Result<(), SensirionError<I::Error>> {
+        let hpa = (pressure + 50) / 100;
+        self.write(CMD_AMBIENT_PRESSURE, &[hpa as u16], EXECUTION)
+            .await
+    }
+
+    /// Automatic self calibration is enabled, default is enabled.
+    pub async fn automatic_self_calibration(&mut self) -> Result<bool, SensirionError<I::Error>> {
+        self.idle()?;
+        let mut enabled = [0];
+        self.read_words(CMD_GET_ASC, &mut enabled).await?;
+        Ok(enabled[0] != 0)
+    }
+
+    /// Enable or disable automatic self calibration, kept until power cycle
+    /// unless [persisted](Self::persist_settings).
+    pub async fn set_automatic_self_calibration(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), SensirionError<I::Error>> {
+        self.idle_write(CMD_SET_ASC, &[enabled.into()], EXECUTION)
+            .await
+    }
+
+    /// Recalibrate sensor to `target` ppm, returns applied correction in ppm.
+    ///
+    /// Sensor should measure in air of target concentration for 3 minutes
+    /// before, measurement should be stopped.
+    pub async fn forced_recalibration(
+        &mut self,
+        target: u16,
+    ) -> Result<i16, SensirionError<I::Error>> {
+        self.idle_write(
+            CMD_FORCED_RECALIBRATION,
+            &[target],
+            Duration::from_millis(400),
+        )
+        .await?;
+        let mut correction = [0];
+        sensirion::read_response(&mut self.i2c, ADDRESS, &mut correction).await?;
+        if correction[0] == u16::MAX {
+            return Err(SensirionError::Device);
+        }
+        Ok((i32::from(correction[0]) - 0x8000) as i16)
+    }
+
+    /// Store configuration in EEPROM, sensor endures 2000 writes.
+    pub async fn persist_settings(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.idle_write(CMD_PERSIST, &[], Duration::from_millis(800))
+            .await
+    }
+
+    /// 48-bit serial number.
+    pub async fn serial_number(&mut self) -> Result<u64, SensirionError<I::Error>> {
+        self.idle()?;
+        let mut words = [0; 3];
+        self.read_words(CMD_SERIAL, &mut words).await?;
+        Ok(words
+            .iter()
+            .fold(0, |serial, &w| serial << 16 | u64::from(w)))
+    }
+
+    /// Run self test for 10 s, fails with [`SensirionError::Device`] on malfunction.
+    pub async fn self_test(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.idle_write(CMD_SELF_TEST, &[], Duration::from_secs(10))
+            .await?;
+        let mut result = [0];
+        sensirion::read_response(&mut self.i2c, ADDRESS, &mut result).await?;
+        if result[0] != 0 {
+            return Err(SensirionError::Device);
+        }
+        Ok(())
+    }
+
+    /// Power down idle sensor, SCD41 only.
+    pub async fn power_down(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.idle_write(CMD_POWER_DOWN, &[], EXECUTION).await
+    }
+
+    /// Wake up sensor after power down, the first single shot reading should be discarded.
+    pub async fn wake(&mut self) -> Result<(), SensirionError<I::Error>> {
+        // Sensor doesn't acknowledge wake up command.
+        _ = sensirion::write(&mut self.i2c, ADDRESS, CMD_WAKE, &[]).await;
+        Timer::after_millis(30).await;
+        Ok(())
+    }
+
+    /// Last reading within measurement interval, new one otherwise,
+    /// periodic measurement is started on demand.
+    async fn latest(&mut self) -> Option<Reading> {
+        match (self.last, self.mode.interval()) {
+            (Some((at, reading)), Some(interval)) if at.elapsed() < interval => {
+                return Some(reading);
+            }
+            _ => {}
+        }
+        if self.mode == Mode::Idle {
+            self.start_periodic().await.ok()?;
+        }
+        self.read().await.ok()
+    }
+
+    async fn read_measurement(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        let mut words = [0; 3];
+        self.read_words(CMD_READ_MEASUREMENT, &mut words).await?;
+        let reading = Reading::decode(&words);
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    fn idle(&self) -> Result<(), SensirionError<I::Error>> {
+        match self.mode {
+            Mode::Idle => Ok(()),
+            _ => Err(SensirionError::Busy),
+        }
+    }
+
+    async fn idle_write(
+        &mut self,
+        command: u16,
+        args: &[u16],
+        delay: Duration,
+    ) -> Result<(), SensirionError<I::Error>> {
+        self.idle()?;
+        self.write(command, args, delay).await
+    }
+
+    async fn write(
+        &mut self,
+        command: u16,
+        args: &[u16],
+        delay: Duration,
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, ADDRESS, command, args).await?;
+        Timer::after(delay).await;
+        Ok(())
+    }
+
+    async fn read_words(
+        &mut self,
+        command: u16,
+        words: &mut [u16],
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::read(&mut self.i2c, ADDRESS, command, EXECUTION, words).await
+    }
+}
+
+impl<I: I2c> Co2 for Scd4x<I> {
+    async fn co2(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.co2)
+    }
+}
+
+impl<I: I2c> Temperature for Scd4x<I> {
+    async fn temperature(&mut self) -> Option<i16> {
+        self.latest().await.map(|r| r.temperature)
+    }
+}
+
+impl<I: I2c> Humidity for Scd4x<I> {
+    async fn humidity(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.humidity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, recorded};
+    use embassy_futures::block_on;
+
+    const READY: [u8; 3] = [0x80, 0x06, 0x04];
+    const NOT_READY: [u8; 3] = [0x80, 0x00, 0xa2];
+    /// Example of datasheet: 500 ppm, 25 °C and 37 %.
+    const MEASUREMENT: [u8; 9] = [0x01, 0xf4, 0x33, 0x66, 0x67, 0xa2, 0x5e, 0xb9, 0x3c];
+    const READING: Reading = Reading {
+        co2: 500,
+        temperature: 250,
+        humidity: 370,
+    };
+
+    #[test]
+    fn periodic_measurement() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&NOT_READY, &READY, &MEASUREMENT]);
+        let mut scd = Scd4x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(scd.read().await, Err(SensirionError::NotReady));
+            scd.start_periodic().await.unwrap();
+            assert_eq!(scd.mode(), Mode::Periodic);
+            assert_eq!(scd.read().await, Ok(READING));
+            scd.stop().await.unwrap();
+            assert_eq!(scd.mode(), Mode::Idle);
+        });
+        let bus = scd.release();
+        assert_eq!(bus.commands()[..], [0x21b1, 0xe4b8, 0xe4b8, 0xec05, 0x3f86]);
+        assert!(
+            bus.writes
+                .iter()
+                .all(|(address, bytes)| *address == ADDRESS && bytes.len() == 2)
+        );
+    }
+
+    #[test]
+    fn sensor_traits() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&READY, &MEASUREMENT]);
+        let mut scd = Scd4x::new(I2cBus::new(&rx));
+        block_on(async {
+            // Periodic measurement is started on demand, reading is reused within interval.
+            assert_eq!(scd.co2().await, Some(500));
+            assert_eq!(scd.temperature().await, Some(250));
+            assert_eq!(scd.humidity().await, Some(370));
+        });
+        assert_eq!(scd.release().commands()[..], [0x21b1, 0xe4b8, 0xec05]);
+    }
+
+    #[test]
+    fn busy_while_measuring() {
+        let mut scd = Scd4x::new(I2cBus::new(&[]));
+        block_on(async {
+            scd.start_low_power().await.unwrap();
+            assert_eq!(scd.mode(), Mode::LowPower);
+            assert_eq!(scd.start_periodic().await, Err(SensirionError::Busy));
+            assert_eq!(
+                scd.set_automatic_self_calibration(false).await,
+                Err(SensirionError::Busy)
+            );
+            assert_eq!(scd.serial_number().await, Err(SensirionError::Busy));
+            // Pressure is rounded to hPa.
+            scd.set_ambient_pressure(98_660).await.unwrap();
+        });
+        let bus = scd.release();
+        assert_eq!(bus.commands()[..], [0x21ac, 0xe000]);
+        assert_eq!(bus.writes[1].1, [0xe0, 0x00, 0x03, 0xdb, 0x42]);
+    }
+
+    #[test]
+    fn calibration() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[
+            &[0x00, 0x01, 0xb0],
+            &[0x7f, 0xce, 0x7b],
+            &[0xff, 0xff, 0xac],
+        ]);
+        let mut scd = Scd4x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(scd.automatic_self_calibration().await, Ok(true));
+            scd.set_automatic_self_calibration(false).await.unwrap();
+            assert_eq!(scd.forced_recalibration(480).await, Ok(-50));
+            // Failed recalibration.
+            assert_eq!(
+                scd.forced_recalibration(480).await,
+                Err(SensirionError::Device)
+            );
+        });
+        let bus = scd.release();
+        assert_eq!(bus.commands()[..], [0x2313, 0x2416, 0x362f, 0x362f]);
+        assert_eq!(bus.writes[1].1, [0x24, 0x16, 0x00, 0x00, 0x81]);
+        assert_eq!(bus.writes[2].1, [0x36, 0x2f, 0x01, 0xe0, 0xb4]);
+    }
+
+    #[test]
+    fn serial_number() {
+        let mut rx = [0xf8, 0x96, 0x31, 0x9f, 0x07, 0xc2, 0x3b, 0xb7, 0x01];
+        let mut scd = Scd4x::new(I2cBus::new(&rx));
+        assert_eq!(block_on(scd.serial_number()), Ok(0xf896_9f07_3bb7));
+        assert_eq!(scd.release().commands()[..], [0x3682]);
+        rx[5] ^= 0x01;
+        let mut scd = Scd4x::new(I2cBus::new(&rx));
+        assert_eq!(block_on(scd.serial_number()), Err(SensirionError::Crc));
+    }
+
+    #[test]
+    fn power_down_and_wake() {
+        let mut bus = I2cBus::new(&[]);
+        // Sensor doesn't acknowledge wake up command.
+        bus.nack = 1;
+        let mut scd = Scd4x::new(bus);
+        block_on(async {
+            scd.wake().await.unwrap();
+            scd.power_down().await.unwrap();
+            scd.wake().await.unwrap();
+        });
+        assert_eq!(scd.release().commands()[..], [0x36e0, 0x36f6]);
+    }
+}
@@ -0,0 +1,491 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion SEN50 / SEN54 / SEN55 environmental sensor nodes over I2C,
+//! address `0x69`.
+//!
+//! Commands and words follow [common protocol](super::sensirion). Sensor
+//! measures once a second after start command and reports eight scaled
+//! integers: PM1.0, PM2.5, PM4.0 and PM10, relative humidity, temperature,
+//! VOC and NOx indices. Values which are not measured by variant or are not
+//! available yet are reported as `0xFFFF` / `0x7FFF` and decoded as `None`:
+//! SEN50 measures particulate matter only, SEN54 adds humidity, temperature
+//! and VOC index, SEN55 adds NOx index. Gas indices are not available during
+//! the first seconds after start.
+//!
+//! Particulate matter measurement can be switched off to save power and fan,
+//! the other values are still measured then.
+//!
+//! ```ignore
+//! let mut sen = Sen5x::new(i2c);
+//! info!("{:?}", sen.info().await?);
+//! sen.start().await?;
+//! let reading = sen.read().await?;
+//! info!("VOC index {:?}, NOx index {:?}", reading.voc_index, reading.nox_index);
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::sensirion::{self, DeviceInfo, SensirionError};
+use super::{Humidity, ParticulateMatter, Temperature, VolatileOrganicCompounds};
+
+/// I2C address of sensor.
+pub const ADDRESS: u8 = 0x69;
+
+/// Time to execute most of commands before the next one or response.
+const EXECUTION: Duration = Duration::from_millis(20);
+/// Wait for values, sensor measures once a second.
+const READY_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Period of polling data-ready flag.
+const READY_POLL: Duration = Duration::from_millis(100);
+/// Reading is reused by trait methods within this period.
+const FRESH: Duration = Duration::from_secs(2);
+
+const CMD_START: u16 = 0x0021;
+const CMD_START_WITHOUT_PM: u16 = 0x0037;
+const CMD_STOP: u16 = 0x0104;
+const CMD_DATA_READY: u16 = 0x0202;
+const CMD_READ_VALUES: u16 = 0x03c4;
+const CMD_FAN_CLEANING: u16 = 0x5607;
+const CMD_CLEANING_INTERVAL: u16 = 0x8004;
+const CMD_PRODUCT_NAME: u16 = 0xd014;
+const CMD_SERIAL: u16 = 0xd033;
+const CMD_FIRMWARE: u16 = 0xd100;
+const CMD_STATUS: u16 = 0xd206;
+const CMD_RESET: u16 = 0xd304;
+
+/// Bits of device status register.
+pub mod status {
+    /// Fan speed is out of range.
+    pub const SPEED: u32 = 1 << 21;
+    /// Fan cleaning is running.
+    pub const FAN_CLEANING: u32 = 1 << 19;
+    /// Gas sensor failure, SEN54 and SEN55 only.
+    pub const GAS: u32 = 1 << 7;
+    /// Humidity and temperature sensor failure, SEN54 and SEN55 only.
+    pub const RHT: u32 = 1 << 6;
+    /// Laser current is out of range.
+    pub const LASER: u32 = 1 << 5;
+    /// Fan is blocked or broken.
+    pub const FAN: u32 = 1 << 4;
+}
+
+/// Measured values in units of sensor traits, `None` when not available.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// PM1.0 in tenths of µg/m³.
+    pub pm1_0: Option<u16>,
+    /// PM2.5 in tenths of µg/m³.
+    pub pm2_5: Option<u16>,
+    /// PM4.0 in tenths of µg/m³.
+    pub pm4_0: Option<u16>,
+    /// PM10 in tenths of µg/m³.
+    pub pm10: Option<u16>,
+    /// Relative humidity in tenths of a percent.
+    pub humidity: Option<u16>,
+    /// Temperature in tenths of degrees Celsius.
+    pub temperature: Option<i16>,
+    /// VOC index, 1..500 where 100 is average of the last 24 hours.
+    pub voc_index: Option<u16>,
+    /// NOx index, 1..500 where 1 is average of the last 24 hours.
+    pub nox_index: Option<u16>,
+}
+
+impl Reading {
+    fn decode(words: &[u16; 8]) -> Self {
+        // Particulate matter is unsigned with scale 10, the rest are signed.
+        let pm = |i: usize| Some(words[i]).filter(|&v| v != u16::MAX);
+        let signed = |i: usize, scale: i32| {
+            let raw = words[i] as i16;
+            (raw != i16::MAX).then(|| div_round(raw.into(), scale))
+        };
+        Self {
+            pm1_0: pm(0),
+            pm2_5: pm(1),
+            pm4_0: pm(2),
+            pm10: pm(3),
+            humidity: signed(4, 10).map(|v| v.clamp(0, 1000) as u16),
+            temperature: signed(5, 20).map(|v| v as i16),
+            voc_index: signed(6, 10).map(|v| v.max(0) as u16),
+            nox_index: signed(7, 10).map(|v| v.max(0) as u16),
+        }
+    }
+}
+
+/// Quotient rounded half away from zero.
+fn div_round(value: i32, divisor: i32) -> i32 {
+    let half = if value < 0 { -divisor / 2 } else { divisor / 2 };
+    (value + half) / divisor
+}
+
+/// SEN5x sensor.
+pub struct Sen5x<I> {
+    i2c: I,
+    measuring: bool,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Sen5x<I> {
+    /// Sensor on given bus, it is idle after power up.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            measuring: false,
+            last: None,
+        }
+    }
+
+    /// Release I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Measurement is started.
+    pub fn is_measuring(&self) -> bool {
+        self.measuring
+    }
+
+    /// Start measurement of all values.
+    pub async fn start(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_START, Duration::from_millis(50)).await?;
+        self.measuring = true;
+        Ok(())
+    }
+
+    /// Start measurement with fan and laser off, particulate matter is not available.
+    pub async fn start_without_pm(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_START_WITHOUT_PM, Duration::from_millis(50))
+            .await?;
+        self.measuring = true;
+        Ok(())
+    }
+
+    /// Stop measurement, sensor returns to idle mode.
+    pub async fn stop(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_STOP, Duration::from_millis(200)).await?;
+        self.measuring = false;
+        self.last = None;
+        Ok(())
+    }
+
+    /// New values are ready.
+    pub async fn is_ready(&mut self) -> Result<bool, SensirionError<I::Error>> {
+        let mut flag = [0];
+        self.read_words(CMD_DATA_READY, &mut flag).await?;
+        Ok(flag[0] & 0xff == 1)
+    }
+
+    /// Wait for new values and read them, measurement should be started.
+    pub async fn read(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        if !self.measuring {
+            return Err(SensirionError::NotReady);
+        }
+        let deadline = Instant::now() + READY_TIMEOUT;
+        while !self.is_ready().await? {
+            if Instant::now() >= deadline {
+                return Err(SensirionError::NotReady);
+            }
+            Timer::after(READY_POLL).await;
+        }
+        let mut words = [0; 8];
+        self.read_words(CMD_READ_VALUES, &mut words).await?;
+        let reading = Reading::decode(&words);
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Run fan at maximal speed for 10 s, measurement of all values should be started.
+    pub async fn start_fan_cleaning(&mut self) -> Result<(), SensirionError<I::Error>> {
+        if !self.measuring {
+            return Err(SensirionError::NotReady);
+        }
+        self.write(CMD_FAN_CLEANING, EXECUTION).await
+    }
+
+    /// Period of automatic fan cleaning in seconds, zero when disabled.
+    pub async fn cleaning_interval(&mut self) -> Result<u32, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(CMD_CLEANING_INTERVAL, &mut words).await?;
+        Ok(u32::from(words[0]) << 16 | u32::from(words[1]))
+    }
+
+    /// Set period of automatic fan cleaning in seconds, zero disables it.
+    ///
+    /// Default period is a week, it is kept over power cycles.
+    pub async fn set_cleaning_interval(
+        &mut self,
+        seconds: u32,
+    ) -> Result<(), SensirionError<I::Error>> {
+        let words = [(seconds >> 16) as u16, seconds as u16];
+        sensirion::write(&mut self.i2c, ADDRESS, CMD_CLEANING_INTERVAL, &words).await?;
+        Timer::after(EXECUTION).await;
+        Ok(())
+    }
+
+    /// Product name, serial number and firmware version.
+    pub async fn info(&mut self) -> Result<DeviceInfo, SensirionError<I::Error>> {
+        let mut words = [0; 16];
+        self.read_words(CMD_PRODUCT_NAME, &mut words).await?;
+        let product = sensirion::string(&words);
+        self.read_words(CMD_SERIAL, &mut words).await?;
+        let serial = sensirion::string(&words);
+        self.read_words(CMD_FIRMWARE, &mut words[..1]).await?;
+        Ok(DeviceInfo {
+            product,
+            serial,
+            firmware: (words[0].to_be_bytes()[0], 0),
+        })
+    }
+
+    /// Device status register, see [`status`] bits.
+    pub async fn status(&mut self) -> Result<u32, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(CMD_STATUS, &mut words).await?;
+        Ok(u32::from(words[0]) << 16 | u32::from(words[1]))
+    }
+
+    /// Restart sensor, it is idle after reset.
+    pub async fn reset(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_RESET, Duration::from_millis(100)).await?;
+        self.measuring = false;
+        self.last = None;
+        Ok(())
+    }
+
+    /// NOx index, available on SEN55 only.
+    pub async fn nox_index(&mut self) -> Option<u16> {
+        self.latest().await.and_then(|r| r.nox_index)
+    }
+
+    /// Last reading if it is fresh, new one otherwise, measurement is started on demand.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => {
+                if !self.measuring {
+                    self.start().await.ok()?;
+                }
+                self.read().await.ok()
+            }
+        }
+    }
+
+    async fn write(
+        &mut self,
+        command: u16,
+        delay: Duration,
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, ADDRESS, command, &[]).await?;
+        Timer::after(delay).await;
+        Ok(())
+    }
+
+    async fn read_words(
+        &mut self,
+        command: u16,
+        words: &mut [u16],
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::read(&mut self.i2c, ADDRESS, command, EXECUTION, words).await
+    }
+}
+
+/// Mass concentrations in tenths of µg/m³.
+impl<I: I2c> ParticulateMatter for Sen5x<I> {
+    async fn pm10(&mut self) -> Option<u16> {
+        self.latest().await.and_then(|r| r.pm10)
+    }
+
+    async fn pm25(&mut self) -> Option<u16> {
+        self.latest().await.and_then(|r| r.pm2_5)
+    }
+}
+
+/// Not available on SEN50.
+impl<I: I2c> Humidity for Sen5x<I> {
+    async fn humidity(&mut self) -> Option<u16> {
+        self.latest().await.and_then(|r| r.humidity)
+    }
+}
+
+/// Not available on SEN50.
+impl<I: I2c> Temperature for Sen5x<I> {
+    async fn temperature(&mut self) -> Option<i16> {
+        self.latest().await.and_then(|r| r.temperature)
+    }
+}
+
+/// Not available on SEN50 and during the first seconds of measurement.
+impl<I: I2c> VolatileOrganicCompounds for Sen5x<I> {
+    async fn voc_index(&mut self) -> Option<u16> {
+        self.latest().await.and_then(|r| r.voc_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, null_words, recorded};
+    use embassy_futures::block_on;
+
+    const READY: [u8; 3] = [0x00, 0x01, 0xb0];
+    /// SEN55 values: PM 1.2, 3.5, 4.0, 4.1 µg/m³, humidity 45.23 %,
+    /// temperature 23.25 °C, VOC index 100 and NOx index 1.
+    const VALUES: [u8; 24] = [
+        0x00, 0x0c, 0xfc, 0x00, 0x23, 0x54, 0x00, 0x28, 0xbe, 0x00, 0x29, 0x8f, 0x11, 0xab, 0x0d,
+        0x12, 0x2a, 0x6b, 0x03, 0xe8, 0xd4, 0x00, 0x0a, 0x5a,
+    ];
+    const READING: Reading = Reading {
+        pm1_0: Some(12),
+        pm2_5: Some(35),
+        pm4_0: Some(40),
+        pm10: Some(41),
+        humidity: Some(452),
+        temperature: Some(233),
+        voc_index: Some(100),
+        nox_index: Some(1),
+    };
+
+    #[test]
+    fn measurement() {
+        let rx: heapless::Vec<u8, 32> = recorded(&[&READY, &VALUES]);
+        let mut sen = Sen5x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sen.read().await, Err(SensirionError::NotReady));
+            sen.start().await.unwrap();
+            assert_eq!(sen.read().await, Ok(READING));
+            sen.stop().await.unwrap();
+        });
+        let bus = sen.release();
+        assert_eq!(bus.commands()[..], [0x0021, 0x0202, 0x03c4, 0x0104]);
+        assert!(
+            bus.writes
+                .iter()
+                .all(|(address, bytes)| *address == ADDRESS && bytes.len() == 2)
+        );
+    }
+
+    #[test]
+    fn sensor_traits() {
+        let rx: heapless::Vec<u8, 32> = recorded(&[&READY, &VALUES]);
+        let mut sen = Sen5x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sen.pm25().await, Some(35));
+            assert_eq!(sen.pm10().await, Some(41));
+            assert_eq!(sen.humidity().await, Some(452));
+            assert_eq!(sen.temperature().await, Some(233));
+            assert_eq!(sen.voc_index().await, Some(100));
+            assert_eq!(sen.nox_index().await, Some(1));
+        });
+        assert_eq!(sen.release().commands()[..], [0x0021, 0x0202, 0x03c4]);
+    }
+
+    #[test]
+    fn unavailable_values() {
+        // SEN50 doesn't measure gas, humidity and temperature.
+        let sen50 = Reading::decode(&[12, 35, 40, 41, 0x7fff, 0x7fff, 0x7fff, 0x7fff]);
+        assert_eq!(
+            sen50,
+            Reading {
+                pm1_0: Some(12),
+                pm2_5: Some(35),
+                pm4_0: Some(40),
+                pm10: Some(41),
+                ..Reading::default()
+            }
+        );
+        // Without particulate matter, negative values are rounded away from zero.
+        let signed = |v: i16| v as u16;
+        let reading = Reading::decode(&[
+            u16::MAX,
+            u16::MAX,
+            u16::MAX,
+            u16::MAX,
+            signed(-25),
+            signed(-105),
+            signed(-3),
+            0,
+        ]);
+        assert_eq!(
+            reading,
+            Reading {
+                humidity: Some(0),
+                temperature: Some(-5),
+                voc_index: Some(0),
+                nox_index: Some(0),
+                ..Reading::default()
+            }
+        );
+        let reading = Reading::decode(&[0, 0, 0, 0, 10010, signed(-110), 5000, 5000]);
+        assert_eq!(reading.humidity, Some(1000));
+        assert_eq!(reading.temperature, Some(-6));
+        assert_eq!(reading.voc_index, Some(500));
+    }
+
+    #[test]
+    fn corrupted_values() {
+        let mut rx: heapless::Vec<u8, 32> = recorded(&[&READY, &VALUES]);
+        rx[READY.len() + VALUES.len() - 1] ^= 0x80;
+        let mut sen = Sen5x::new(I2cBus::new(&rx));
+        block_on(async {
+            sen.start_without_pm().await.unwrap();
+            assert_eq!(sen.read().await, Err(SensirionError::Crc));
+        });
+        assert_eq!(sen.release().commands()[..], [0x0037, 0x0202, 0x03c4]);
+    }
+
+    #[test]
+    fn device_info() {
+        let rx: heapless::Vec<u8, 100> = recorded(&[
+            &[0x53, 0x45, 0x83, 0x4e, 0x35, 0x55, 0x35, 0x00, 0x44],
+            &null_words::<39>(),
+            &[
+                0x39, 0x45, 0x38, 0x37, 0x42, 0xc2, 0x33, 0x41, 0x12, 0x31, 0x43, 0xa9, 0x30, 0x34,
+                0x32, 0x42, 0x31, 0x25, 0x44, 0x36, 0xe8, 0x41, 0x32, 0x5b,
+            ],
+            &null_words::<24>(),
+            &[0x02, 0x01, 0x69],
+        ]);
+        let mut sen = Sen5x::new(I2cBus::new(&rx));
+        let info = block_on(sen.info()).unwrap();
+        assert_eq!(info.product, "SEN55");
+        assert_eq!(info.serial, "9E7B3A1C04B1D6A2");
+        // Minor version is not reported.
+        assert_eq!(info.firmware, (2, 0));
+        assert_eq!(sen.release().commands()[..], [0xd014, 0xd033, 0xd100]);
+    }
+
+    #[test]
+    fn cleaning_interval() {
+        let mut sen = Sen5x::new(I2cBus::new(&[]));
+        block_on(async {
+            assert_eq!(
+                sen.start_fan_cleaning().await,
+                Err(SensirionError::NotReady)
+            );
+            sen.start().await.unwrap();
+            sen.start_fan_cleaning().await.unwrap();
+            sen.set_cleaning_interval(604_800).await.unwrap();
+        });
+        let bus = sen.release();
+        assert_eq!(bus.commands()[..], [0x0021, 0x5607, 0x8004]);
+        assert_eq!(
+            bus.writes[2].1,
+            [0x80, 0x04, 0x00, 0x09, 0x09, 0x3a, 0x80, 0xa7]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Common I2C protocol of Sensirion sensors.
+//!
+//! Commands are 16-bit big endian codes, optionally followed by arguments.
+//! Arguments and responses are 16-bit big endian words, every word is
+//! followed by its CRC-8 with polynomial `0x31` and initial value `0xFF`.
+//! Response is read after command execution time.
+
+use embassy_time::{Duration, Timer};
+use embedded_hal_async::i2c::I2c;
+use heapless::String;
+
+/// Most words in one response.
+pub const MAX_WORDS: usize = 32;
+
+/// Most arguments of one command.
+pub const MAX_ARGS: usize = 4;
+
+/// Sensirion sensor errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SensirionError<E> {
+    /// Bus failure or sensor doesn't acknowledge.
+    I2c(E),
+    /// Received word is corrupted.
+    Crc,
+    /// Measurement is not started or not ready yet.
+    NotReady,
+    /// Command isn't accepted while measurement is running.
+    Busy,
+    /// Sensor reports failure, e.g. self test or calibration error.
+    Device,
+    /// More than [`MAX_ARGS`] arguments or [`MAX_WORDS`] response words.
+    Length,
+}
+
+/// CRC-8 of data word.
+pub const fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0xff;
+    let mut i = 0;
+    while i < data.len() {
+        crc ^= data[i];
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x31
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// Send command with up to [`MAX_ARGS`] arguments.
+pub async fn write<I: I2c>(
+    i2c: &mut I,
+    address: u8,
+    command: u16,
+    args: &[u16],
+) -> Result<(), SensirionError<I::Error>> {
+    if args.len() > MAX_ARGS {
+        return Err(SensirionError::Length);
+    }
+    let mut buf = [0u8; 2 + 3 * MAX_ARGS];
+    buf[..2].copy_from_slice(&command.to_be_bytes());
+    let mut len = 2;
+    for arg in args {
+        let word = arg.to_be_bytes();
+        buf[len..len + 2].copy_from_slice(&word);
+        buf[len + 2] = crc8(&word);
+        len += 3;
+    }
+    i2c.write(address, &buf[..len])
+        .await
+        .map_err(SensirionError::I2c)
+}
+
+/// Send command and read `words.len()` words after `delay`.
+pub async fn read<I: I2c>(
+    i2c: &mut I,
+    address: u8,
+    command: u16,
+    delay: Duration,
+    words: &mut [u16],
+) -> Result<(), SensirionError<I::Error>> {
+    write(i2c, address, command, &[]).await?;
+    Timer::after(delay).await;
+    read_response(i2c, address, words).await
+}
+
+/// Read up to [`MAX_WORDS`] words of response to previously sent command.
+pub async fn read_response<I: I2c>(
+    i2c: &mut I,
+    address: u8,
+    words: &mut [u16],
+) -> Result<(), SensirionError<I::Error>> {
+    if words.len() > MAX_WORDS {
+        return Err(SensirionError::Length);
+    }
+    let mut buf = [0u8; 3 * MAX_WORDS];
+    let buf = &mut buf[..3 * words.len()];
+    i2c.read(address, buf).await.map_err(SensirionError::I2c)?;
+    for (word, chunk) in words.iter_mut().zip(buf.chunks_exact(3)) {
+        if crc8(&chunk[..2]) != chunk[2] {
+            return Err(SensirionError::Crc);
+        }
+        *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+    }
+    Ok(())
+}
+
+/// Null terminated ASCII string packed into words.
+pub fn string<const N: usize>(words: &[u16]) -> String<N> {
+    let mut text = String::new();
+    let bytes = words.iter().flat_map(|word| word.to_be_bytes());
+    for byte in bytes.take_while(|&b| b != 0) {
+        _ = text.push(byte as char);
+    }
+    text
+}
+
+/// Big endian float of two words.
+pub fn float(words: &[u16]) -> f32 {
+    f32::from_bits(u32::from(words[0]) << 16 | u32::from(words[1]))
+}
+
+/// Identity of sensor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Product type or name, e.g. `00080000` of SPS30 or `SEN55`.
+    pub product: String<32>,
+    /// Serial number.
+    pub serial: String<32>,
+    /// Firmware major and minor version, sensors reporting major only have zero minor.
+    pub firmware: (u8, u8),
+}
+
+/// Measurement repeatability of humidity and temperature sensors, higher one
+/// takes longer and consumes more power.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Repeatability {
+    /// The fastest measurement with the highest noise.
+    Low,
+    /// Balanced measurement.
+    Medium,
+    /// The most precise measurement.
+    #[default]
+    High,
+}
+
+/// Temperature in tenths of degrees Celsius of `-45 + 175 * raw / 65535`.
+pub fn temperature(raw: u16) -> i16 {
+    ((1750 * u32::from(raw) + 32767) / 65535) as i16 - 450
+}
+
+/// Relative humidity in tenths of a percent of `100 * raw / 65535`.
+pub fn humidity(raw: u16) -> u16 {
+    ((1000 * u32::from(raw) + 32767) / 65535) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::I2cBus;
+    use embassy_futures::block_on;
+
+    #[test]
+    fn crc() {
+        // Example of datasheets.
+        assert_eq!(crc8(&[0xbe, 0xef]), 0x92);
+        assert_eq!(crc8(&[0x00, 0x00]), 0x81);
+        assert_eq!(crc8(&[0x03, 0x00]), 0xac);
+        assert_eq!(crc8(&[]), 0xff);
+    }
+
+    #[test]
+    fn command_bytes() {
+        let mut bus = I2cBus::new(&[]);
+        block_on(async {
+            write(&mut bus, 0x69, 0x0010, &[0x0300]).await.unwrap();
+            write(&mut bus, 0x62, 0x21b1, &[]).await.unwrap();
+            write(&mut bus, 0x69, 0x8004, &[0x0009, 0x3a80])
+                .await
+                .unwrap();
+        });
+        assert_eq!(
+            bus.writes[0],
+            (0x69, [0x00, 0x10, 0x03, 0x00, 0xac][..].try_into().unwrap())
+        );
+        assert_eq!(bus.writes[1], (0x62, [0x21, 0xb1][..].try_into().unwrap()));
+        assert_eq!(
+            bus.writes[2].1,
+            [0x80, 0x04, 0x00, 0x09, 0x09, 0x3a, 0x80, 0xa7]
+        );
+    }
+
+    #[test]
+    fn too_many_words() {
+        let mut bus = I2cBus::new(&[0; 3 * (MAX_WORDS + 1)]);
+        block_on(async {
+            let args = [0xbeef; MAX_ARGS + 1];
+            assert_eq!(
+                write(&mut bus, 0x69, 0x0010, &args).await,
+                Err(SensirionError::Length)
+            );
+            let mut words = [0; MAX_WORDS + 1];
+            assert_eq!(
+                read_response(&mut bus, 0x69, &mut words).await,
+                Err(SensirionError::Length)
+            );
+            write(&mut bus, 0x69, 0x0010, &args[..MAX_ARGS])
+                .await
+                .unwrap();
+        });
+        assert_eq!(bus.writes.len(), 1);
+        assert_eq!(bus.writes[0].1.len(), 2 + 3 * MAX_ARGS);
+        assert_eq!(bus.rx.len(), 3 * (MAX_WORDS + 1));
+    }
+
+    #[test]
+    fn response_words() {
+        let mut bus = I2cBus::new(&[0xbe, 0xef, 0x92, 0x00, 0x00, 0x81, 0xbe, 0xef, 0x93]);
+        let mut words = [0; 2];
+        block_on(async {
+            read(&mut bus, 0x69, 0xd100, Duration::from_millis(1), &mut words)
+                .await
+                .unwrap();
+            assert_eq!(words, [0xbeef, 0x0000]);
+            assert_eq!(
+                read_response(&mut bus, 0x69, &mut words[..1]).await,
+                Err(SensirionError::Crc)
+            );
+            assert!(matches!(
+                read_response(&mut bus, 0x69, &mut words[..1]).await,
+                Err(SensirionError::I2c(_))
+            ));
+        });
+        assert_eq!(bus.commands()[..], [0xd100]);
+    }
+
+    #[test]
+    fn words_decoding() {
+        assert_eq!(string::<8>(&[0x5345, 0x4e35, 0x3500, 0x4141]), "SEN55");
+        assert_eq!(string::<4>(&[0x3030, 0x3038, 0x3030]), "0008");
+        assert_eq!(float(&[0x40a8, 0x0000]), 5.25);
+        assert_eq!(temperature(0), -450);
+        assert_eq!(temperature(0x6666), 250);
+        assert_eq!(temperature(u16::MAX), 1300);
+        assert_eq!(humidity(0), 0);
+        assert_eq!(humidity(0x8000), 500);
+        assert_eq!(humidity(u16::MAX), 1000);
+    }
+}
@@ -0,0 +1,448 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion SGP40 / SGP41 MOX gas sensors over I2C, address `0x59`.
+//!
+//! Commands and words follow [common protocol](super::sensirion). Sensor
+//! reports raw ticks of VOC pixel, SGP41 of NOx pixel too, which are turned
+//! into indices by [gas index algorithm](super::gas_index). Measurement is
+//! compensated by relative humidity and temperature passed as arguments,
+//! they are taken from [`Humidity`] and [`Temperature`] of compensation
+//! sensor when it is given, 50 % and 25 °C otherwise.
+//!
+//! Algorithm expects sample every second, so [`Sgp4x::process`] or trait
+//! method should be called every second, e.g. from dedicated task. SGP41 NOx
+//! pixel is conditioned during the first 10 samples after power up or heater
+//! off, NOx index isn't reported meanwhile. Both indices are 0, reported as
+//! `None`, during 45 s of initial blackout.
+//!
+//! Bosch BME680 / BME688 boards are out of scope: their gas resistance isn't
+//! SGP ticks the algorithm is tuned for, and Bosch IAQ index needs proprietary
+//! BSEC library, so they provide no [`VolatileOrganicCompounds`] readings.
+//!
+//! ```ignore
+//! let mut sgp = Sgp4x::new(i2c, Variant::Sgp41).with_compensation(&mut bme280);
+//! let mut ticker = Ticker::every(Duration::from_secs(1));
+//! loop {
+//!     let reading = sgp.process().await?;
+//!     info!("VOC index {:?}, NOx index {:?}", reading.voc_index, reading.nox_index);
+//!     ticker.next().await;
+//! }
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::gas_index::{AlgorithmType, GasIndexAlgorithm};
+use super::sensirion::{self, SensirionError};
+use super::{Humidity, Temperature, VolatileOrganicCompounds};
+
+/// I2C address of sensor.
+pub const ADDRESS: u8 = 0x59;
+
+/// Samples of SGP41 NOx pixel conditioning, one per second.
+const CONDITIONING_SAMPLES: u8 = 10;
+/// Reading is reused by trait method within sampling interval.
+const FRESH: Duration = Duration::from_secs(1);
+/// Compensation arguments of 50 % and 25 °C.
+const DEFAULT_HUMIDITY_TICKS: u16 = 0x8000;
+const DEFAULT_TEMPERATURE_TICKS: u16 = 0x6666;
+/// Answer to self test when all tests passed.
+const SELF_TEST_OK: u16 = 0xd400;
+
+const CMD_MEASURE_SGP40: u16 = 0x260f;
+const CMD_MEASURE_SGP41: u16 = 0x2619;
+const CMD_CONDITIONING: u16 = 0x2612;
+const CMD_SELF_TEST: u16 = 0x280e;
+const CMD_HEATER_OFF: u16 = 0x3615;
+const CMD_SERIAL: u16 = 0x3682;
+
+/// Sensor model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// VOC pixel only.
+    Sgp40,
+    /// VOC and NOx pixels.
+    Sgp41,
+}
+
+/// Raw ticks of pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RawSignals {
+    /// VOC pixel ticks.
+    pub voc: u16,
+    /// NOx pixel ticks, SGP41 only and not during conditioning.
+    pub nox: Option<u16>,
+}
+
+/// Processed measurement.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// VOC index, `None` during initial blackout.
+    pub voc_index: Option<u16>,
+    /// NOx index, `None` on SGP40, during conditioning and initial blackout.
+    pub nox_index: Option<u16>,
+    /// Raw ticks of pixels.
+    pub raw: RawSignals,
+}
+
+/// Placeholder of absent compensation sensor, default conditions are used.
+pub struct NoCompensation;
+
+impl Humidity for NoCompensation {
+    async fn humidity(&mut self) -> Option<u16> {
+        None
+    }
+}
+
+impl Temperature for NoCompensation {
+    async fn temperature(&mut self) -> Option<i16> {
+        None
+    }
+}
+
+/// Compensation argument of relative humidity in tenths of a percent.
+fn humidity_ticks(humidity: Option<u16>) -> u16 {
+    humidity.map_or(DEFAULT_HUMIDITY_TICKS, |h| {
+        (u32::from(h.min(1000)) * 65535 / 1000) as u16
+    })
+}
+
+/// Compensation argument of temperature in tenths of degrees Celsius.
+fn temperature_ticks(temperature: Option<i16>) -> u16 {
+    temperature.map_or(DEFAULT_TEMPERATURE_TICKS, |t| {
+        ((i32::from(t.clamp(-450, 1300)) + 450) * 65535 / 1750) as u16
+    })
+}
+
+/// SGP40 / SGP41 sensor with gas index algorithms.
+pub struct Sgp4x<I, C = NoCompensation> {
+    i2c: I,
+    variant: Variant,
+    compensation: Option<C>,
+    voc: GasIndexAlgorithm,
+    nox: GasIndexAlgorithm,
+    conditioning: u8,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Sgp4x<I> {
+    /// Sensor of given model on bus, compensated by default conditions.
+    pub fn new(i2c: I, variant: Variant) -> Self {
+        Self {
+            i2c,
+            variant,
+            compensation: None,
+            voc: GasIndexAlgorithm::new(AlgorithmType::Voc),
+            nox: GasIndexAlgorithm::new(AlgorithmType::Nox),
+            conditioning: CONDITIONING_SAMPLES,
+            last: None,
+        }
+    }
+}
+
+impl<I: I2c, C: Humidity + Temperature> Sgp4x<I, C> {
+    /// Compensate measurements by readings of other sensor, e.g. BME280 or SHT4x.
+    pub fn with_compensation<D: Humidity + Temperature>(self, sensor: D) -> Sgp4x<I, D> {
+        Sgp4x {
+            i2c: self.i2c,
+            variant: self.variant,
+            compensation: Some(sensor),
+            voc: self.voc,
+            nox: self.nox,
+            conditioning: self.conditioning,
+            last: self.last,
+        }
+    }
+
+    /// Release I2C bus and compensation sensor.
+    pub fn release(self) -> (I, Option<C>) {
+        (self.i2c, self.compensation)
+    }
+
+    /// Sensor model.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// VOC index algorithm, e.g. to save or tune its state.
+    pub fn voc_algorithm(&mut self) -> &mut GasIndexAlgorithm {
+        &mut self.voc
+    }
+
+    /// NOx index algorithm.
+    pub fn nox_algorithm(&mut self) -> &mut GasIndexAlgorithm {
+        &mut self.nox
+    }
+
+    /// Measure raw ticks compensated by humidity and temperature in units of
+    /// sensor traits, `None` stands for default conditions.
+    ///
+    /// SGP41 runs NOx pixel conditioning instead for the first samples.
+    pub async fn measure_raw(
+        &mut self,
+        humidity: Option<u16>,
+        temperature: Option<i16>,
+    ) -> Result<RawSignals, SensirionError<I::Error>> {
+        let args = [humidity_ticks(humidity), temperature_ticks(temperature)];
+        let mut words = [0; 2];
+        match self.variant {
+            Variant::Sgp40 => {
+                self.read_words(CMD_MEASURE_SGP40, &args, 30, &mut words[..1])
+                    .await?;
+                Ok(RawSignals {
+                    voc: words[0],
+                    nox: None,
+                })
+            }
+            Variant::Sgp41 if self.conditioning > 0 => {
+                self.read_words(CMD_CONDITIONING, &args, 50, &mut words[..1])
+                    .await?;
+                self.conditioning -= 1;
+                Ok(RawSignals {
+                    voc: words[0],
+                    nox: None,
+                })
+            }
+            Variant::Sgp41 => {
+                self.read_words(CMD_MEASURE_SGP41, &args, 50, &mut words)
+                    .await?;
+                Ok(RawSignals {
+                    voc: words[0],
+                    nox: Some(words[1]),
+                })
+            }
+        }
+    }
+
+    /// Measure compensated raw ticks and process them by algorithms, should be
+    /// called every second.
+    pub async fn process(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        let (humidity, temperature) = match &mut self.compensation {
+            Some(sensor) => (sensor.humidity().await, sensor.temperature().await),
+            None => (None, None),
+        };
+        let raw = self.measure_raw(humidity, temperature).await?;
+        let index = |index: i32| u16::try_from(index).ok().filter(|&i| i > 0);
+        let reading = Reading {
+            voc_index: index(self.voc.process(raw.voc.into())),
+            nox_index: raw.nox.and_then(|nox| index(self.nox.process(nox.into()))),
+            raw,
+        };
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Run built-in self test of hotplates.
+    pub async fn self_test(&mut self) -> Result<(), SensirionError<I::Error>> {
+        let mut result = [0];
+        self.read_words(CMD_SELF_TEST, &[], 320, &mut result)
+            .await?;
+        if result[0] != SELF_TEST_OK {
+            return Err(SensirionError::Device);
+        }
+        Ok(())
+    }
+
+    /// Switch hotplates off until the next measurement, SGP41 NOx pixel is
+    /// conditioned again then.
+    pub async fn heater_off(&mut self) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, ADDRESS, CMD_HEATER_OFF, &[]).await?;
+        self.conditioning = CONDITIONING_SAMPLES;
+        self.last = None;
+        Timer::after_millis(1).await;
+        Ok(())
+    }
+
+    /// 48-bit serial number.
+    pub async fn serial_number(&mut self) -> Result<u64, SensirionError<I::Error>> {
+        let mut words = [0; 3];
+        self.read_words(CMD_SERIAL, &[], 1, &mut words).await?;
+        Ok(words
+            .iter()
+            .fold(0, |serial, &w| serial << 16 | u64::from(w)))
+    }
+
+    async fn read_words(
+        &mut self,
+        command: u16,
+        args: &[u16],
+        delay_ms: u64,
+        words: &mut [u16],
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, ADDRESS, command, args).await?;
+        Timer::after_millis(delay_ms).await;
+        sensirion::read_response(&mut self.i2c, ADDRESS, words).await
+    }
+}
+
+impl<I: I2c, C: Humidity + Temperature> VolatileOrganicCompounds for Sgp4x<I, C> {
+    async fn voc_index(&mut self) -> Option<u16> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => reading.voc_index,
+            _ => self.process().await.ok()?.voc_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, recorded};
+    use embassy_futures::block_on;
+
+    /// VOC ticks 30000 with CRC.
+    const VOC: [u8; 3] = [0x75, 0x30, 0x08];
+    /// NOx ticks 16000 with CRC.
+    const NOX: [u8; 3] = [0x3e, 0x80, 0x24];
+    /// Default arguments of 50 % and 25 °C with CRC.
+    const DEFAULT_ARGS: [u8; 6] = [0x80, 0x00, 0xa2, 0x66, 0x66, 0x93];
+
+    /// Compensation sensor reporting 60 % and 30 °C.
+    struct Climate;
+
+    impl Humidity for Climate {
+        async fn humidity(&mut self) -> Option<u16> {
+            Some(600)
+        }
+    }
+
+    impl Temperature for Climate {
+        async fn temperature(&mut self) -> Option<i16> {
+            Some(300)
+        }
+    }
+
+    #[test]
+    fn compensation_ticks() {
+        assert_eq!(humidity_ticks(None), 0x8000);
+        assert_eq!(temperature_ticks(None), 0x6666);
+        assert_eq!(humidity_ticks(Some(0)), 0);
+        assert_eq!(humidity_ticks(Some(500)), 0x7fff);
+        assert_eq!(humidity_ticks(Some(1000)), 0xffff);
+        assert_eq!(humidity_ticks(Some(1500)), 0xffff);
+        assert_eq!(temperature_ticks(Some(250)), 0x6666);
+        assert_eq!(temperature_ticks(Some(-450)), 0);
+        assert_eq!(temperature_ticks(Some(-600)), 0);
+        assert_eq!(temperature_ticks(Some(1300)), 0xffff);
+        assert_eq!(temperature_ticks(Some(i16::MAX)), 0xffff);
+    }
+
+    #[test]
+    fn sgp40_measure() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&VOC, &VOC]);
+        let mut sgp = Sgp4x::new(I2cBus::new(&rx), Variant::Sgp40);
+        block_on(async {
+            let raw = RawSignals {
+                voc: 30000,
+                nox: None,
+            };
+            assert_eq!(sgp.measure_raw(None, None).await, Ok(raw));
+            assert_eq!(sgp.measure_raw(Some(500), Some(-450)).await, Ok(raw));
+        });
+        let (bus, _) = sgp.release();
+        assert_eq!(bus.writes[0].0, ADDRESS);
+        let mut default = [0x26, 0x0f, 0, 0, 0, 0, 0, 0];
+        default[2..].copy_from_slice(&DEFAULT_ARGS);
+        assert_eq!(bus.writes[0].1[..], default);
+        assert_eq!(
+            bus.writes[1].1[..],
+            [0x26, 0x0f, 0x7f, 0xff, 0x8f, 0x00, 0x00, 0x81]
+        );
+    }
+
+    #[test]
+    fn sgp41_conditioning() {
+        let rx: heapless::Vec<u8, 64> = recorded(&[
+            &VOC, &VOC, &VOC, &VOC, &VOC, &VOC, &VOC, &VOC, &VOC, &VOC, &VOC, &NOX, &VOC,
+        ]);
+        let mut sgp = Sgp4x::new(I2cBus::new(&rx), Variant::Sgp41);
+        block_on(async {
+            // NOx pixel is conditioned by the first 10 samples.
+            for _ in 0..CONDITIONING_SAMPLES {
+                let raw = sgp.measure_raw(None, None).await.unwrap();
+                assert_eq!(raw.nox, None);
+            }
+            let raw = sgp.measure_raw(None, None).await.unwrap();
+            assert_eq!(
+                raw,
+                RawSignals {
+                    voc: 30000,
+                    nox: Some(16000),
+                }
+            );
+            // Heater off starts conditioning again.
+            sgp.heater_off().await.unwrap();
+            let raw = sgp.measure_raw(None, None).await.unwrap();
+            assert_eq!(raw.nox, None);
+        });
+        let (bus, _) = sgp.release();
+        let commands = bus.commands();
+        assert!(commands[..10].iter().all(|&c| c == 0x2612));
+        assert_eq!(commands[10..], [0x2619, 0x3615, 0x2612]);
+        // Conditioning and measurement take compensation arguments too.
+        assert_eq!(bus.writes[0].1[2..], DEFAULT_ARGS);
+        assert_eq!(bus.writes[10].1[2..], DEFAULT_ARGS);
+        assert_eq!(bus.writes[11].1.len(), 2);
+    }
+
+    #[test]
+    fn compensated_process() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&VOC, &VOC]);
+        let mut sgp = Sgp4x::new(I2cBus::new(&rx), Variant::Sgp40).with_compensation(Climate);
+        block_on(async {
+            // Indices are 0 during initial blackout.
+            let reading = sgp.process().await.unwrap();
+            assert_eq!(reading.voc_index, None);
+            assert_eq!(reading.nox_index, None);
+            assert_eq!(reading.raw.voc, 30000);
+            // Fresh reading is reused by trait method.
+            assert_eq!(sgp.voc_index().await, None);
+        });
+        let (bus, _) = sgp.release();
+        assert_eq!(bus.writes.len(), 1);
+        assert_eq!(
+            bus.writes[0].1[..],
+            [0x26, 0x0f, 0x99, 0x99, 0xbe, 0x6d, 0xb6, 0x8d]
+        );
+    }
+
+    #[test]
+    fn self_test() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&[0xd4, 0x00, 0xc6], &[0x4b, 0x00, 0x12]]);
+        let mut sgp = Sgp4x::new(I2cBus::new(&rx), Variant::Sgp41);
+        block_on(async {
+            assert_eq!(sgp.self_test().await, Ok(()));
+            // Failed VOC or NOx pixel test bits.
+            assert_eq!(sgp.self_test().await, Err(SensirionError::Device));
+        });
+        let (bus, _) = sgp.release();
+        assert_eq!(bus.commands()[..], [0x280e, 0x280e]);
+        assert!(bus.writes.iter().all(|(_, bytes)| bytes.len() == 2));
+    }
+
+    #[test]
+    fn serial_number() {
+        let rx = [0x12, 0x34, 0x37, 0x56, 0x78, 0x7d, 0x9a, 0xbc, 0xe0];
+        let mut sgp = Sgp4x::new(I2cBus::new(&rx), Variant::Sgp40);
+        block_on(async {
+            assert_eq!(sgp.serial_number().await, Ok(0x1234_5678_9abc));
+            assert_eq!(sgp.serial_number().await.ok(), None);
+        });
+        assert_eq!(sgp.release().0.commands()[..], [0x3682, 0x3682]);
+    }
+}
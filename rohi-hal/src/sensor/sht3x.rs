@@ -0,0 +1,304 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion SHT30 / SHT31 / SHT35 humidity and temperature sensors over I2C,
+//! address `0x44` or `0x45` when ADDR pin is high.
+//!
+//! Commands and words follow [common protocol](super::sensirion). Driver uses
+//! single shot measurement without clock stretching, response is temperature
+//! and humidity word, both scaled to `0..65535`. Built-in heater warms sensor
+//! by a few degrees to recover from condensation or to check it, readings
+//! are off while heater is on.
+//!
+//! ```ignore
+//! let mut sht = Sht3x::new(i2c).with_repeatability(Repeatability::Medium);
+//! info!("SHT3x serial {:08x}", sht.serial_number().await?);
+//! let reading = sht.measure().await?;
+//! info!("{} °C/10, {} %/10", reading.temperature, reading.humidity);
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::sensirion::{self, Repeatability, SensirionError};
+use super::{Humidity, Temperature};
+
+/// I2C address of sensor with ADDR pin low.
+pub const ADDRESS: u8 = 0x44;
+/// I2C address of sensor with ADDR pin high.
+pub const ADDRESS_ALT: u8 = 0x45;
+
+/// Reading is reused by trait methods within this period.
+const FRESH: Duration = Duration::from_secs(1);
+
+const CMD_MEASURE_HIGH: u16 = 0x2400;
+const CMD_MEASURE_MEDIUM: u16 = 0x240b;
+const CMD_MEASURE_LOW: u16 = 0x2416;
+const CMD_HEATER_ON: u16 = 0x306d;
+const CMD_HEATER_OFF: u16 = 0x3066;
+const CMD_STATUS: u16 = 0xf32d;
+const CMD_CLEAR_STATUS: u16 = 0x3041;
+const CMD_SERIAL: u16 = 0x3780;
+const CMD_RESET: u16 = 0x30a2;
+
+/// Bits of status register.
+pub mod status {
+    /// At least one alert is pending.
+    pub const ALERT: u16 = 1 << 15;
+    /// Heater is on.
+    pub const HEATER: u16 = 1 << 13;
+    /// Humidity tracking alert.
+    pub const HUMIDITY_ALERT: u16 = 1 << 11;
+    /// Temperature tracking alert.
+    pub const TEMPERATURE_ALERT: u16 = 1 << 10;
+    /// Reset was detected since status was cleared.
+    pub const RESET: u16 = 1 << 4;
+    /// The last command wasn't processed.
+    pub const COMMAND: u16 = 1 << 1;
+    /// Checksum of the last write was wrong.
+    pub const CHECKSUM: u16 = 1 << 0;
+}
+
+/// Measured values in units of sensor traits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// Temperature in tenths of degrees Celsius.
+    pub temperature: i16,
+    /// Relative humidity in tenths of a percent.
+    pub humidity: u16,
+}
+
+/// SHT3x sensor.
+pub struct Sht3x<I> {
+    i2c: I,
+    address: u8,
+    repeatability: Repeatability,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Sht3x<I> {
+    /// Sensor at default address measuring with high repeatability.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            address: ADDRESS,
+            repeatability: Repeatability::High,
+            last: None,
+        }
+    }
+
+    /// Other address, e.g. [`ADDRESS_ALT`].
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Other repeatability of measurements.
+    pub fn with_repeatability(mut self, repeatability: Repeatability) -> Self {
+        self.repeatability = repeatability;
+        self
+    }
+
+    /// Release I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Measure temperature and humidity once.
+    pub async fn measure(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        // Longest measurement durations are 15.5, 6.5 and 4.5 ms.
+        let (command, duration) = match self.repeatability {
+            Repeatability::High => (CMD_MEASURE_HIGH, 16),
+            Repeatability::Medium => (CMD_MEASURE_MEDIUM, 7),
+            Repeatability::Low => (CMD_MEASURE_LOW, 5),
+        };
+        let mut words = [0; 2];
+        self.read_words(command, Duration::from_millis(duration), &mut words)
+            .await?;
+        let reading = Reading {
+            temperature: sensirion::temperature(words[0]),
+            humidity: sensirion::humidity(words[1]),
+        };
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Switch heater on or off, it stays on until switched off or reset.
+    pub async fn set_heater(&mut self, enabled: bool) -> Result<(), SensirionError<I::Error>> {
+        let command = if enabled {
+            CMD_HEATER_ON
+        } else {
+            CMD_HEATER_OFF
+        };
+        self.last = None;
+        self.write(command).await
+    }
+
+    /// Status register, see [`status`] bits.
+    pub async fn status(&mut self) -> Result<u16, SensirionError<I::Error>> {
+        let mut status = [0];
+        self.read_words(CMD_STATUS, Duration::from_millis(1), &mut status)
+            .await?;
+        Ok(status[0])
+    }
+
+    /// Clear alert and reset bits of status register.
+    pub async fn clear_status(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_CLEAR_STATUS).await
+    }
+
+    /// 32-bit serial number.
+    pub async fn serial_number(&mut self) -> Result<u32, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(CMD_SERIAL, Duration::from_millis(1), &mut words)
+            .await?;
+        Ok(u32::from(words[0]) << 16 | u32::from(words[1]))
+    }
+
+    /// Restart sensor, heater is switched off.
+    pub async fn reset(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.last = None;
+        self.write(CMD_RESET).await?;
+        Timer::after_millis(2).await;
+        Ok(())
+    }
+
+    /// Last reading if it is fresh, new one otherwise.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => self.measure().await.ok(),
+        }
+    }
+
+    async fn write(&mut self, command: u16) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, self.address, command, &[]).await?;
+        Timer::after_millis(1).await;
+        Ok(())
+    }
+
+    async fn read_words(
+        &mut self,
+        command: u16,
+        delay: Duration,
+        words: &mut [u16],
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::read(&mut self.i2c, self.address, command, delay, words).await
+    }
+}
+
+impl<I: I2c> Temperature for Sht3x<I> {
+    async fn temperature(&mut self) -> Option<i16> {
+        self.latest().await.map(|r| r.temperature)
+    }
+}
+
+impl<I: I2c> Humidity for Sht3x<I> {
+    async fn humidity(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.humidity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, recorded};
+    use embassy_futures::block_on;
+    use embedded_hal_async::i2c::{ErrorKind, NoAcknowledgeSource};
+
+    /// 25 °C and 50 %.
+    const MEASUREMENT: [u8; 6] = [0x66, 0x66, 0x93, 0x80, 0x00, 0xa2];
+    const READING: Reading = Reading {
+        temperature: 250,
+        humidity: 500,
+    };
+
+    #[test]
+    fn measure() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&MEASUREMENT, &MEASUREMENT]);
+        let mut sht = Sht3x::new(I2cBus::new(&rx)).with_address(ADDRESS_ALT);
+        block_on(async {
+            assert_eq!(sht.measure().await, Ok(READING));
+            sht.repeatability = Repeatability::Low;
+            assert_eq!(sht.measure().await, Ok(READING));
+            assert_eq!(
+                sht.measure().await,
+                Err(SensirionError::I2c(ErrorKind::NoAcknowledge(
+                    NoAcknowledgeSource::Data
+                )))
+            );
+        });
+        let bus = sht.release();
+        assert_eq!(bus.commands()[..], [0x2400, 0x2416, 0x2416]);
+        assert!(
+            bus.writes
+                .iter()
+                .all(|(address, _)| *address == ADDRESS_ALT)
+        );
+    }
+
+    #[test]
+    fn sensor_traits() {
+        let rx: heapless::Vec<u8, 16> = recorded(&[&MEASUREMENT, &MEASUREMENT]);
+        let mut sht = Sht3x::new(I2cBus::new(&rx)).with_repeatability(Repeatability::Medium);
+        block_on(async {
+            // Reading is reused until heater changes it.
+            assert_eq!(sht.temperature().await, Some(250));
+            assert_eq!(sht.humidity().await, Some(500));
+            sht.set_heater(true).await.unwrap();
+            assert_eq!(sht.humidity().await, Some(500));
+        });
+        assert_eq!(sht.release().commands()[..], [0x240b, 0x306d, 0x240b]);
+    }
+
+    #[test]
+    fn serial_number_and_status() {
+        let rx: heapless::Vec<u8, 16> =
+            recorded(&[&[0x12, 0x34, 0x37, 0x56, 0x78, 0x7d], &[0x20, 0x10, 0x1e]]);
+        let mut sht = Sht3x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sht.serial_number().await, Ok(0x1234_5678));
+            assert_eq!(sht.status().await, Ok(status::HEATER | status::RESET));
+        });
+        assert_eq!(sht.release().commands()[..], [0x3780, 0xf32d]);
+    }
+
+    #[test]
+    fn commands() {
+        let mut sht = Sht3x::new(I2cBus::new(&[]));
+        block_on(async {
+            sht.set_heater(true).await.unwrap();
+            sht.set_heater(false).await.unwrap();
+            sht.clear_status().await.unwrap();
+            sht.reset().await.unwrap();
+        });
+        let bus = sht.release();
+        assert_eq!(bus.commands()[..], [0x306d, 0x3066, 0x3041, 0x30a2]);
+        assert!(bus.writes.iter().all(|(_, bytes)| bytes.len() == 2));
+    }
+
+    #[test]
+    fn corrupted_reading() {
+        let mut rx = MEASUREMENT;
+        rx[4] ^= 0x01;
+        let mut sht = Sht3x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sht.measure().await, Err(SensirionError::Crc));
+            assert_eq!(sht.humidity().await, None);
+        });
+    }
+}
@@ -0,0 +1,346 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion SHT40 / SHT41 / SHT45 humidity and temperature sensors over I2C,
+//! address `0x44`, other addresses are ordered as separate parts.
+//!
+//! Unlike the other Sensirion sensors, commands are single bytes, responses
+//! follow [common protocol](super::sensirion). Response to measurement is
+//! temperature and humidity word, both scaled to `0..65535`, humidity range
+//! is `-6..119 %` and it is clipped to `0..100 %`.
+//!
+//! Heater is switched on for 0.1 s or 1 s by a single command, measurement
+//! taken at the end of heating is returned. It removes condensation and
+//! creep in high humidity, heater duty cycle should be kept below 10 %.
+//!
+//! ```ignore
+//! let mut sht = Sht4x::new(i2c);
+//! info!("SHT4x serial {:08x}", sht.serial_number().await?);
+//! if sht.measure().await?.humidity > 950 {
+//!     sht.heat(HeaterPower::High, HeaterDuration::Long).await?;
+//! }
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::sensirion::{self, Repeatability, SensirionError};
+use super::{Humidity, Temperature};
+
+/// I2C address of SHT4x-A parts.
+pub const ADDRESS: u8 = 0x44;
+
+/// Reading is reused by trait methods within this period.
+const FRESH: Duration = Duration::from_secs(1);
+
+const CMD_MEASURE_HIGH: u8 = 0xfd;
+const CMD_MEASURE_MEDIUM: u8 = 0xf6;
+const CMD_MEASURE_LOW: u8 = 0xe0;
+const CMD_SERIAL: u8 = 0x89;
+const CMD_RESET: u8 = 0x94;
+
+/// Heater power.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaterPower {
+    /// 20 mW.
+    Low,
+    /// 110 mW.
+    Medium,
+    /// 200 mW.
+    High,
+}
+
+/// Heating time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeaterDuration {
+    /// 0.1 s.
+    Short,
+    /// 1 s.
+    Long,
+}
+
+/// Measured values in units of sensor traits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Reading {
+    /// Temperature in tenths of degrees Celsius.
+    pub temperature: i16,
+    /// Relative humidity in tenths of a percent.
+    pub humidity: u16,
+}
+
+impl Reading {
+    fn decode(words: &[u16; 2]) -> Self {
+        let humidity = (1250 * i32::from(words[1]) + 32767) / 65535 - 60;
+        Self {
+            temperature: sensirion::temperature(words[0]),
+            humidity: humidity.clamp(0, 1000) as u16,
+        }
+    }
+}
+
+/// SHT4x sensor.
+pub struct Sht4x<I> {
+    i2c: I,
+    address: u8,
+    repeatability: Repeatability,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Sht4x<I> {
+    /// Sensor at default address measuring with high repeatability.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            address: ADDRESS,
+            repeatability: Repeatability::High,
+            last: None,
+        }
+    }
+
+    /// Other address, `0x45` or `0x46` of SHT4x-B and SHT4x-C parts.
+    pub fn with_address(mut self, address: u8) -> Self {
+        self.address = address;
+        self
+    }
+
+    /// Other repeatability of measurements.
+    pub fn with_repeatability(mut self, repeatability: Repeatability) -> Self {
+        self.repeatability = repeatability;
+        self
+    }
+
+    /// Release I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Measure temperature and humidity once.
+    pub async fn measure(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        // Longest measurement durations are 8.3, 4.5 and 1.6 ms.
+        let (command, duration) = match self.repeatability {
+            Repeatability::High => (CMD_MEASURE_HIGH, Duration::from_millis(9)),
+            Repeatability::Medium => (CMD_MEASURE_MEDIUM, Duration::from_millis(5)),
+            Repeatability::Low => (CMD_MEASURE_LOW, Duration::from_millis(2)),
+        };
+        let reading = self.read_reading(command, duration).await?;
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Heat sensor and measure with high repeatability at the end.
+    ///
+    /// Returned reading is taken on hot sensor, it isn't used by trait methods.
+    pub async fn heat(
+        &mut self,
+        power: HeaterPower,
+        duration: HeaterDuration,
+    ) -> Result<Reading, SensirionError<I::Error>> {
+        let command = match (power, duration) {
+            (HeaterPower::High, HeaterDuration::Long) => 0x39,
+            (HeaterPower::High, HeaterDuration::Short) => 0x32,
+            (HeaterPower::Medium, HeaterDuration::Long) => 0x2f,
+            (HeaterPower::Medium, HeaterDuration::Short) => 0x24,
+            (HeaterPower::Low, HeaterDuration::Long) => 0x1e,
+            (HeaterPower::Low, HeaterDuration::Short) => 0x15,
+        };
+        let time = match duration {
+            HeaterDuration::Long => Duration::from_millis(1100),
+            HeaterDuration::Short => Duration::from_millis(110),
+        };
+        self.last = None;
+        self.read_reading(command, time).await
+    }
+
+    /// 32-bit serial number.
+    pub async fn serial_number(&mut self) -> Result<u32, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(CMD_SERIAL, Duration::from_millis(1), &mut words)
+            .await?;
+        Ok(u32::from(words[0]) << 16 | u32::from(words[1]))
+    }
+
+    /// Restart sensor.
+    pub async fn reset(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.last = None;
+        self.i2c
+            .write(self.address, &[CMD_RESET])
+            .await
+            .map_err(SensirionError::I2c)?;
+        Timer::after_millis(1).await;
+        Ok(())
+    }
+
+    /// Last reading if it is fresh, new one otherwise.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => self.measure().await.ok(),
+        }
+    }
+
+    async fn read_reading(
+        &mut self,
+        command: u8,
+        delay: Duration,
+    ) -> Result<Reading, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(command, delay, &mut words).await?;
+        Ok(Reading::decode(&words))
+    }
+
+    async fn read_words(
+        &mut self,
+        command: u8,
+        delay: Duration,
+        words: &mut [u16],
+    ) -> Result<(), SensirionError<I::Error>> {
+        self.i2c
+            .write(self.address, &[command])
+            .await
+            .map_err(SensirionError::I2c)?;
+        Timer::after(delay).await;
+        sensirion::read_response(&mut self.i2c, self.address, words).await
+    }
+}
+
+impl<I: I2c> Temperature for Sht4x<I> {
+    async fn temperature(&mut self) -> Option<i16> {
+        self.latest().await.map(|r| r.temperature)
+    }
+}
+
+impl<I: I2c> Humidity for Sht4x<I> {
+    async fn humidity(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| r.humidity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, recorded};
+    use embassy_futures::block_on;
+
+    /// 25 °C and 56.5 %.
+    const MEASUREMENT: [u8; 6] = [0x66, 0x66, 0x93, 0x80, 0x00, 0xa2];
+    const READING: Reading = Reading {
+        temperature: 250,
+        humidity: 565,
+    };
+
+    /// Single byte commands of writes.
+    fn commands(bus: &I2cBus) -> heapless::Vec<u8, 32> {
+        bus.writes
+            .iter()
+            .map(|(address, bytes)| {
+                assert_eq!((*address, bytes.len()), (ADDRESS, 1));
+                bytes[0]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn decode() {
+        assert_eq!(Reading::decode(&[0x6666, 0x8000]), READING);
+        // Humidity range -6..119 % is clamped.
+        assert_eq!(
+            Reading::decode(&[0x0000, 0x0000]),
+            Reading {
+                temperature: -450,
+                humidity: 0,
+            }
+        );
+        assert_eq!(
+            Reading::decode(&[0xffff, 0xffff]),
+            Reading {
+                temperature: 1300,
+                humidity: 1000,
+            }
+        );
+        assert_eq!(Reading::decode(&[0x6666, 0x1000]).humidity, 18);
+    }
+
+    #[test]
+    fn measure() {
+        let rx: heapless::Vec<u8, 24> = recorded(&[&MEASUREMENT, &MEASUREMENT, &MEASUREMENT]);
+        let mut sht = Sht4x::new(I2cBus::new(&rx));
+        block_on(async {
+            for repeatability in [
+                Repeatability::High,
+                Repeatability::Medium,
+                Repeatability::Low,
+            ] {
+                sht.repeatability = repeatability;
+                assert_eq!(sht.measure().await, Ok(READING));
+            }
+        });
+        assert_eq!(commands(&sht.release())[..], [0xfd, 0xf6, 0xe0]);
+    }
+
+    #[test]
+    fn heat() {
+        let rx: heapless::Vec<u8, 24> = recorded(&[&MEASUREMENT, &MEASUREMENT, &MEASUREMENT]);
+        let mut sht = Sht4x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sht.temperature().await, Some(250));
+            // Hot reading isn't reused by trait methods.
+            let hot = sht.heat(HeaterPower::Low, HeaterDuration::Short).await;
+            assert_eq!(hot, Ok(READING));
+            assert_eq!(sht.humidity().await, Some(565));
+        });
+        assert_eq!(commands(&sht.release())[..], [0xfd, 0x15, 0xfd]);
+    }
+
+    #[test]
+    fn heater_commands() {
+        let rx: heapless::Vec<u8, 36> = recorded(&[&MEASUREMENT[..]; 6]);
+        let mut sht = Sht4x::new(I2cBus::new(&rx));
+        block_on(async {
+            for power in [HeaterPower::High, HeaterPower::Medium, HeaterPower::Low] {
+                for duration in [HeaterDuration::Long, HeaterDuration::Short] {
+                    assert_eq!(sht.heat(power, duration).await, Ok(READING));
+                }
+            }
+        });
+        assert_eq!(
+            commands(&sht.release())[..],
+            [0x39, 0x32, 0x2f, 0x24, 0x1e, 0x15]
+        );
+    }
+
+    #[test]
+    fn serial_number_and_reset() {
+        let rx = [0x12, 0x34, 0x37, 0x56, 0x78, 0x7d];
+        let mut sht = Sht4x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sht.serial_number().await, Ok(0x1234_5678));
+            sht.reset().await.unwrap();
+        });
+        assert_eq!(commands(&sht.release())[..], [0x89, 0x94]);
+    }
+
+    #[test]
+    fn corrupted_reading() {
+        let mut rx = MEASUREMENT;
+        rx[1] ^= 0x01;
+        let mut sht = Sht4x::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sht.measure().await, Err(SensirionError::Crc));
+        });
+        assert_eq!(commands(&sht.release())[..], [0xfd]);
+    }
+}
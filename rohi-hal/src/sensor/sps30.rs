@@ -0,0 +1,422 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Sensirion SPS30 particulate matter sensor over I2C, address `0x69`.
+//!
+//! Commands and words follow [common protocol](super::sensirion). Sensor
+//! measures once a second after start command and reports ten big endian
+//! floats: mass concentrations of PM1.0, PM2.5, PM4.0 and PM10 in µg/m³,
+//! number concentrations of PM0.5, PM1.0, PM2.5, PM4.0 and PM10 in #/cm³ and
+//! typical particle size in µm. First values are ready about a second after
+//! start, fan cleaning runs for 10 s and readings are skipped meanwhile.
+//!
+//! UART (SHDLC) interface of sensor is not supported, SEL pin should be
+//! grounded to select I2C.
+//!
+//! ```ignore
+//! let mut sps = Sps30::new(i2c);
+//! info!("SPS30 {:?}", sps.info().await?);
+//! sps.start().await?;
+//! let reading = sps.read().await?;
+//! info!("PM2.5 {} µg/m³, typical size {} µm", reading.pm2_5, reading.typical_size);
+//! ```
+
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal_async::i2c::I2c;
+
+use super::ParticulateMatter;
+use super::sensirion::{self, DeviceInfo, SensirionError};
+
+/// I2C address of sensor.
+pub const ADDRESS: u8 = 0x69;
+
+/// Time to execute command before the next one or response.
+const EXECUTION: Duration = Duration::from_millis(20);
+/// Wait for values, sensor measures once a second.
+const READY_TIMEOUT: Duration = Duration::from_millis(1500);
+/// Period of polling data-ready flag.
+const READY_POLL: Duration = Duration::from_millis(100);
+/// Reading is reused by trait methods within this period.
+const FRESH: Duration = Duration::from_secs(2);
+
+const CMD_START: u16 = 0x0010;
+const CMD_STOP: u16 = 0x0104;
+const CMD_DATA_READY: u16 = 0x0202;
+const CMD_READ_VALUES: u16 = 0x0300;
+const CMD_SLEEP: u16 = 0x1001;
+const CMD_WAKE: u16 = 0x1103;
+const CMD_FAN_CLEANING: u16 = 0x5607;
+const CMD_CLEANING_INTERVAL: u16 = 0x8004;
+const CMD_PRODUCT_TYPE: u16 = 0xd002;
+const CMD_SERIAL: u16 = 0xd033;
+const CMD_FIRMWARE: u16 = 0xd100;
+const CMD_STATUS: u16 = 0xd206;
+const CMD_RESET: u16 = 0xd304;
+
+/// Argument of start command selecting big endian float output.
+const FLOAT_FORMAT: u16 = 0x0300;
+
+/// Bits of device status register.
+pub mod status {
+    /// Fan speed is out of range.
+    pub const SPEED: u32 = 1 << 21;
+    /// Laser current is out of range.
+    pub const LASER: u32 = 1 << 5;
+    /// Fan is blocked or broken.
+    pub const FAN: u32 = 1 << 4;
+}
+
+/// Measured values.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Reading {
+    /// PM1.0 in µg/m³.
+    pub pm1_0: f32,
+    /// PM2.5 in µg/m³.
+    pub pm2_5: f32,
+    /// PM4.0 in µg/m³.
+    pub pm4_0: f32,
+    /// PM10 in µg/m³.
+    pub pm10: f32,
+    /// Number of particles in cm³ of PM0.5, PM1.0, PM2.5, PM4.0 and PM10.
+    pub counts: [f32; 5],
+    /// Typical particle size in µm.
+    pub typical_size: f32,
+}
+
+impl Reading {
+    fn decode(words: &[u16; 20]) -> Self {
+        let float = |i: usize| sensirion::float(&words[2 * i..]);
+        Self {
+            pm1_0: float(0),
+            pm2_5: float(1),
+            pm4_0: float(2),
+            pm10: float(3),
+            counts: [float(4), float(5), float(6), float(7), float(8)],
+            typical_size: float(9),
+        }
+    }
+}
+
+/// Concentration in tenths of µg/m³, negative and NaN values are zero.
+fn tenths(value: f32) -> u16 {
+    (value * 10.0 + 0.5) as u16
+}
+
+/// SPS30 sensor.
+pub struct Sps30<I> {
+    i2c: I,
+    measuring: bool,
+    last: Option<(Instant, Reading)>,
+}
+
+impl<I: I2c> Sps30<I> {
+    /// Sensor on given bus, it is idle after power up.
+    pub fn new(i2c: I) -> Self {
+        Self {
+            i2c,
+            measuring: false,
+            last: None,
+        }
+    }
+
+    /// Release I2C bus.
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    /// Measurement is started.
+    pub fn is_measuring(&self) -> bool {
+        self.measuring
+    }
+
+    /// Start fan and measurement.
+    pub async fn start(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_START, &[FLOAT_FORMAT]).await?;
+        self.measuring = true;
+        Ok(())
+    }
+
+    /// Stop measurement, sensor returns to idle mode.
+    pub async fn stop(&mut self) -> Result<(), SensirionError<I::Error>> {
+        self.write(CMD_STOP, &[]).await?;
+        self.measuring = false;
+        self.last = None;
+        Ok(())
+    }
+
+    /// New values are ready.
+    pub async fn is_ready(&mut self) -> Result<bool, SensirionError<I::Error>> {
+        let mut flag = [0];
+        self.read_words(CMD_DATA_READY, &mut flag).await?;
+        Ok(flag[0] & 0xff == 1)
+    }
+
+    /// Wait for new values and read them, measurement should be started.
+    pub async fn read(&mut self) -> Result<Reading, SensirionError<I::Error>> {
+        if !self.measuring {
+            return Err(SensirionError::NotReady);
+        }
+        let deadline = Instant::now() + READY_TIMEOUT;
+        while !self.is_ready().await? {
+            if Instant::now() >= deadline {
+                return Err(SensirionError::NotReady);
+            }
+            Timer::after(READY_POLL).await;
+        }
+        let mut words = [0; 20];
+        self.read_words(CMD_READ_VALUES, &mut words).await?;
+        let reading = Reading::decode(&words);
+        self.last = Some((Instant::now(), reading));
+        Ok(reading)
+    }
+
+    /// Run fan at maximal speed for 10 s, measurement should be started.
+    pub async fn start_fan_cleaning(&mut self) -> Result<(), SensirionError<I::Error>> {
+        if !self.measuring {
+            return Err(SensirionError::NotReady);
+        }
+        self.write(CMD_FAN_CLEANING, &[]).await
+    }
+
+    /// Period of automatic fan cleaning in seconds, zero when disabled.
+    pub async fn cleaning_interval(&mut self) -> Result<u32, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(CMD_CLEANING_INTERVAL, &mut words).await?;
+        Ok(u32::from(words[0]) << 16 | u32::from(words[1]))
+    }
+
+    /// Set period of automatic fan cleaning in seconds, zero disables it.
+    ///
+    /// Default period is a week, new one is applied after reset or power cycle.
+    pub async fn set_cleaning_interval(
+        &mut self,
+        seconds: u32,
+    ) -> Result<(), SensirionError<I::Error>> {
+        let words = [(seconds >> 16) as u16, seconds as u16];
+        self.write(CMD_CLEANING_INTERVAL, &words).await
+    }
+
+    /// Product type, serial number and firmware version.
+    pub async fn info(&mut self) -> Result<DeviceInfo, SensirionError<I::Error>> {
+        let mut words = [0; 16];
+        self.read_words(CMD_PRODUCT_TYPE, &mut words[..4]).await?;
+        let product = sensirion::string(&words[..4]);
+        self.read_words(CMD_SERIAL, &mut words).await?;
+        let serial = sensirion::string(&words);
+        self.read_words(CMD_FIRMWARE, &mut words[..1]).await?;
+        let [major, minor] = words[0].to_be_bytes();
+        Ok(DeviceInfo {
+            product,
+            serial,
+            firmware: (major, minor),
+        })
+    }
+
+    /// Device status register, see [`status`] bits.
+    pub async fn status(&mut self) -> Result<u32, SensirionError<I::Error>> {
+        let mut words = [0; 2];
+        self.read_words(CMD_STATUS, &mut words).await?;
+        Ok(u32::from(words[0]) << 16 | u32::from(words[1]))
+    }
+
+    /// Stop measurement and switch off fan, laser and interface.
+    pub async fn sleep(&mut self) -> Result<(), SensirionError<I::Error>> {
+        if self.measuring {
+            self.stop().await?;
+        }
+        self.write(CMD_SLEEP, &[]).await
+    }
+
+    /// Wake up from sleep to idle mode.
+    pub async fn wake(&mut self) -> Result<(), SensirionError<I::Error>> {
+        // The first command only enables interface of sleeping sensor and
+        // isn't acknowledged, the second one wakes sensor up.
+        _ = sensirion::write(&mut self.i2c, ADDRESS, CMD_WAKE, &[]).await;
+        self.write(CMD_WAKE, &[]).await
+    }
+
+    /// Restart sensor, it is idle after reset.
+    pub async fn reset(&mut self) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, ADDRESS, CMD_RESET, &[]).await?;
+        self.measuring = false;
+        self.last = None;
+        Timer::after_millis(100).await;
+        Ok(())
+    }
+
+    /// Last reading if it is fresh, new one otherwise, measurement is started on demand.
+    async fn latest(&mut self) -> Option<Reading> {
+        match self.last {
+            Some((at, reading)) if at.elapsed() < FRESH => Some(reading),
+            _ => {
+                if !self.measuring {
+                    self.start().await.ok()?;
+                }
+                self.read().await.ok()
+            }
+        }
+    }
+
+    async fn write(&mut self, command: u16, args: &[u16]) -> Result<(), SensirionError<I::Error>> {
+        sensirion::write(&mut self.i2c, ADDRESS, command, args).await?;
+        Timer::after(EXECUTION).await;
+        Ok(())
+    }
+
+    async fn read_words(
+        &mut self,
+        command: u16,
+        words: &mut [u16],
+    ) -> Result<(), SensirionError<I::Error>> {
+        sensirion::read(&mut self.i2c, ADDRESS, command, EXECUTION, words).await
+    }
+}
+
+/// Mass concentrations in tenths of µg/m³.
+impl<I: I2c> ParticulateMatter for Sps30<I> {
+    async fn pm10(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| tenths(r.pm10))
+    }
+
+    async fn pm25(&mut self) -> Option<u16> {
+        self.latest().await.map(|r| tenths(r.pm2_5))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sensor::fake::{I2cBus, null_words, recorded};
+    use embassy_futures::block_on;
+
+    const READY: [u8; 3] = [0x00, 0x01, 0xb0];
+    const NOT_READY: [u8; 3] = [0x00, 0x00, 0x81];
+    /// Floats 3.5, 5.25, 6.0, 6.5, 20.0, 25.5, 26.0, 26.125, 26.25 and 0.5.
+    const VALUES: [u8; 60] = [
+        0x40, 0x60, 0xb3, 0x00, 0x00, 0x81, 0x40, 0xa8, 0x4d, 0x00, 0x00, 0x81, 0x40, 0xc0, 0x4f,
+        0x00, 0x00, 0x81, 0x40, 0xd0, 0x0c, 0x00, 0x00, 0x81, 0x41, 0xa0, 0x00, 0x00, 0x00, 0x81,
+        0x41, 0xcc, 0xc6, 0x00, 0x00, 0x81, 0x41, 0xd0, 0xf8, 0x00, 0x00, 0x81, 0x41, 0xd1, 0xc9,
+        0x00, 0x00, 0x81, 0x41, 0xd2, 0x9a, 0x00, 0x00, 0x81, 0x3f, 0x00, 0xaa, 0x00, 0x00, 0x81,
+    ];
+    const READING: Reading = Reading {
+        pm1_0: 3.5,
+        pm2_5: 5.25,
+        pm4_0: 6.0,
+        pm10: 6.5,
+        counts: [20.0, 25.5, 26.0, 26.125, 26.25],
+        typical_size: 0.5,
+    };
+
+    #[test]
+    fn measurement() {
+        let rx: heapless::Vec<u8, 80> = recorded(&[&NOT_READY, &READY, &VALUES]);
+        let mut sps = Sps30::new(I2cBus::new(&rx));
+        block_on(async {
+            assert_eq!(sps.read().await, Err(SensirionError::NotReady));
+            sps.start().await.unwrap();
+            assert_eq!(sps.read().await, Ok(READING));
+            sps.stop().await.unwrap();
+            assert!(!sps.is_measuring());
+        });
+        let bus = sps.release();
+        assert_eq!(bus.commands()[..], [0x0010, 0x0202, 0x0202, 0x0300, 0x0104]);
+        // Start in float format.
+        assert_eq!(bus.writes[0].1, [0x00, 0x10, 0x03, 0x00, 0xac]);
+        assert!(bus.writes.iter().all(|(address, _)| *address == ADDRESS));
+    }
+
+    #[test]
+    fn corrupted_values() {
+        let mut rx: heapless::Vec<u8, 80> = recorded(&[&READY, &VALUES]);
+        rx[3 + 7] ^= 0x01;
+        let mut sps = Sps30::new(I2cBus::new(&rx));
+        block_on(async {
+            sps.start().await.unwrap();
+            assert_eq!(sps.read().await, Err(SensirionError::Crc));
+        });
+    }
+
+    #[test]
+    fn particulate_matter() {
+        let rx: heapless::Vec<u8, 80> = recorded(&[&READY, &VALUES]);
+        let mut sps = Sps30::new(I2cBus::new(&rx));
+        block_on(async {
+            // Measurement is started on demand, fresh reading is reused.
+            assert_eq!(sps.pm25().await, Some(53));
+            assert_eq!(sps.pm10().await, Some(65));
+        });
+        assert_eq!(sps.release().commands()[..], [0x0010, 0x0202, 0x0300]);
+    }
+
+    #[test]
+    fn device_info() {
+        let rx: heapless::Vec<u8, 80> = recorded(&[
+            &[
+                0x30, 0x30, 0xf6, 0x30, 0x38, 0x4f, 0x30, 0x30, 0xf6, 0x30, 0x30, 0xf6,
+            ],
+            &[
+                0x39, 0x45, 0x38, 0x37, 0x42, 0xc2, 0x33, 0x41, 0x12, 0x31, 0x43, 0xa9, 0x30, 0x34,
+                0x32, 0x42, 0x31, 0x25, 0x44, 0x36, 0xe8, 0x41, 0x32, 0x5b,
+            ],
+            &null_words::<24>(),
+            &[0x02, 0x01, 0x69],
+        ]);
+        let mut sps = Sps30::new(I2cBus::new(&rx));
+        let info = block_on(sps.info()).unwrap();
+        assert_eq!(info.product, "00080000");
+        assert_eq!(info.serial, "9E7B3A1C04B1D6A2");
+        assert_eq!(info.firmware, (2, 1));
+        assert_eq!(sps.release().commands()[..], [0xd002, 0xd033, 0xd100]);
+    }
+
+    #[test]
+    fn sleep_and_wake() {
+        let mut sps = Sps30::new(I2cBus::new(&[]));
+        block_on(async {
+            sps.start().await.unwrap();
+            sps.sleep().await.unwrap();
+            assert!(!sps.is_measuring());
+        });
+        let mut bus = sps.release();
+        assert_eq!(bus.commands()[..], [0x0010, 0x0104, 0x1001]);
+        // The first wake command of sleeping sensor isn't acknowledged.
+        bus.writes.clear();
+        bus.nack = 1;
+        let mut sps = Sps30::new(bus);
+        block_on(sps.wake()).unwrap();
+        assert_eq!(sps.release().commands()[..], [0x1103]);
+    }
+
+    #[test]
+    fn cleaning_interval() {
+        let mut sps = Sps30::new(I2cBus::new(&[0x00, 0x09, 0x09, 0x3a, 0x80, 0xa7]));
+        block_on(async {
+            assert_eq!(
+                sps.start_fan_cleaning().await,
+                Err(SensirionError::NotReady)
+            );
+            sps.set_cleaning_interval(604_800).await.unwrap();
+            assert_eq!(sps.cleaning_interval().await, Ok(604_800));
+        });
+        let bus = sps.release();
+        assert_eq!(
+            bus.writes[0].1,
+            [0x80, 0x04, 0x00, 0x09, 0x09, 0x3a, 0x80, 0xa7]
+        );
+        assert_eq!(bus.commands()[..], [0x8004, 0x8004]);
+    }
+}
@@ -0,0 +1,307 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Persistent key-value store on dedicated flash partition.
+//!
+//! Store is built on [sequential-storage](https://crates.io/crates/sequential-storage)
+//! map: items are appended to sectors cyclically, so flash wears evenly, and
+//! every item is protected by CRC. Single item write is atomic, after power loss
+//! either previous or new value is read. Settings which should change together,
+//! like WiFi SSID and password, are better kept in one item.
+//!
+//! Values are typed by [`Key`] and encoded with [postcard](https://crates.io/crates/postcard).
+//!
+//! ```ignore
+//! const DEVICE_NAME: Key<String<32>> = Key::new(1);
+//!
+//! let partition = Partition::find(&FLASH, "config").await?;
+//! let mut store = KvStore::new(partition);
+//! store.set(&DEVICE_NAME, &name).await?;
+//! let name = store.get(&DEVICE_NAME).await?;
+//! ```
+
+/// Offline measurements buffer with store-and-forward.
+pub mod buffer;
+pub use buffer::MeasurementBuffer;
+
+use core::marker::PhantomData;
+use embedded_storage_async::nor_flash::MultiwriteNorFlash;
+use sequential_storage::cache::{Cache, Uncached};
+use sequential_storage::map::{MapConfig, MapStorage};
+use serde::{Serialize, de::DeserializeOwned};
+
+/// Maximal size of encoded key and value.
+pub const KV_BUF_SIZE: usize = 1024;
+
+/// Typed key of stored value.
+pub struct Key<T> {
+    id: u16,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    /// Key with unique identifier, it should never be reused for values of other type.
+    ///
+    /// Identifiers below `0x100` are reserved by SDK, see [`crate::config`].
+    pub const fn new(id: u16) -> Self {
+        Self {
+            id,
+            _value: PhantomData,
+        }
+    }
+
+    /// Key identifier.
+    pub const fn id(&self) -> u16 {
+        self.id
+    }
+}
+
+/// Key-value store errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StoreError<E> {
+    /// Flash access failure.
+    Flash(E),
+    /// No space left even after compaction.
+    Full,
+    /// Stored data is corrupted beyond repair, store should be cleared.
+    Corrupted,
+    /// Value is larger than [`KV_BUF_SIZE`].
+    TooLarge,
+    /// Stored value doesn't match type of key.
+    Serialization,
+}
+
+impl<E> From<sequential_storage::Error<E>> for StoreError<E> {
+    fn from(e: sequential_storage::Error<E>) -> Self {
+        match e {
+            sequential_storage::Error::Storage { value, .. } => Self::Flash(value),
+            sequential_storage::Error::FullStorage => Self::Full,
+            sequential_storage::Error::BufferTooSmall(_)
+            | sequential_storage::Error::ItemTooBig => Self::TooLarge,
+            sequential_storage::Error::SerializationError(_) => Self::Serialization,
+            _ => Self::Corrupted,
+        }
+    }
+}
+
+type KvCache = Cache<Uncached, Uncached, Uncached, u16>;
+
+/// Wear-leveled key-value store.
+pub struct KvStore<S: MultiwriteNorFlash> {
+    map: MapStorage<u16, S, KvCache>,
+    buf: [u8; KV_BUF_SIZE],
+}
+
+impl<S: MultiwriteNorFlash> KvStore<S> {
+    /// Store occupying whole given flash, e.g. [`Partition`](crate::flash::Partition).
+    ///
+    /// Panics when flash is smaller than two sectors.
+    pub fn new(flash: S) -> Self {
+        let range = 0..flash.capacity() as u32;
+        Self {
+            map: MapStorage::new(flash, MapConfig::new(range), KvCache::new_uncached()),
+            buf: [0; KV_BUF_SIZE],
+        }
+    }
+
+    /// Latest value stored with given key.
+    pub async fn get<T: DeserializeOwned>(
+        &mut self,
+        key: &Key<T>,
+    ) -> Result<Option<T>, StoreError<S::Error>> {
+        match self.map.fetch_item::<&[u8]>(&mut self.buf, &key.id).await? {
+            Some(bytes) => postcard::from_bytes(bytes)
+                .map(Some)
+                .map_err(|_| StoreError::Serialization),
+            None => Ok(None),
+        }
+    }
+
+    /// Atomically replace value stored with given key.
+    pub async fn set<T: Serialize>(
+        &mut self,
+        key: &Key<T>,
+        value: &T,
+    ) -> Result<(), StoreError<S::Error>> {
+        let mut encoded = [0u8; KV_BUF_SIZE];
+        let encoded: &[u8] =
+            postcard::to_slice(value, &mut encoded).map_err(|_| StoreError::TooLarge)?;
+        Ok(self
+            .map
+            .store_item(&mut self.buf, &key.id, &encoded)
+            .await?)
+    }
+
+    /// Remove value stored with given key.
+    pub async fn remove<T>(&mut self, key: &Key<T>) -> Result<(), StoreError<S::Error>> {
+        Ok(self.map.remove_item(&mut self.buf, &key.id).await?)
+    }
+
+    /// Erase all stored values.
+    pub async fn clear(&mut self) -> Result<(), StoreError<S::Error>> {
+        Ok(self.map.erase_all().await?)
+    }
+
+    /// Release underlying flash.
+    pub fn into_inner(self) -> S {
+        self.map.destroy().0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_futures::block_on;
+    use heapless::String;
+
+    use super::*;
+    use crate::flash::{RamFlash, SECTOR_SIZE};
+
+    type Flash = RamFlash<{ 4 * SECTOR_SIZE }>;
+
+    const NAME: Key<String<32>> = Key::new(0x100);
+    const COUNTER: Key<u32> = Key::new(0x101);
+
+    fn name(name: &str) -> String<32> {
+        String::try_from(name).unwrap()
+    }
+
+    /// Reopen store on the same memory, as after restart.
+    fn reopen(store: KvStore<Flash>) -> KvStore<Flash> {
+        KvStore::new(store.into_inner())
+    }
+
+    #[test]
+    fn set_get_remove_clear() {
+        block_on(async {
+            let mut store = KvStore::new(Flash::new());
+            assert_eq!(store.get(&NAME).await, Ok(None));
+
+            store.set(&NAME, &name("altruist")).await.unwrap();
+            store.set(&COUNTER, &7).await.unwrap();
+            store.set(&NAME, &name("balcony")).await.unwrap();
+            assert_eq!(store.get(&NAME).await, Ok(Some(name("balcony"))));
+            assert_eq!(store.get(&COUNTER).await, Ok(Some(7)));
+
+            let mut store = reopen(store);
+            assert_eq!(store.get(&NAME).await, Ok(Some(name("balcony"))));
+
+            store.remove(&NAME).await.unwrap();
+            assert_eq!(store.get(&NAME).await, Ok(None));
+            assert_eq!(store.get(&COUNTER).await, Ok(Some(7)));
+
+            store.clear().await.unwrap();
+            assert_eq!(store.get(&COUNTER).await, Ok(None));
+        })
+    }
+
+    #[test]
+    fn too_large_value() {
+        block_on(async {
+            let mut store = KvStore::new(Flash::new());
+            let key: Key<heapless::Vec<u8, KV_BUF_SIZE>> = Key::new(0x102);
+            let value = heapless::Vec::from_slice(&[0; KV_BUF_SIZE]).unwrap();
+            assert_eq!(store.set(&key, &value).await, Err(StoreError::TooLarge));
+        })
+    }
+
+    #[test]
+    fn rewrites_wrap_around_partition() {
+        block_on(async {
+            let mut store = KvStore::new(Flash::new());
+            store.set(&NAME, &name("altruist")).await.unwrap();
+            for counter in 0..5000 {
+                store.set(&COUNTER, &counter).await.unwrap();
+            }
+            let mut store = reopen(store);
+            assert_eq!(store.get(&COUNTER).await, Ok(Some(4999)));
+            // Rarely changed value is moved along while sectors are reclaimed.
+            assert_eq!(store.get(&NAME).await, Ok(Some(name("altruist"))));
+
+            let flash = store.into_inner();
+            assert!(flash.erases() > 4, "{} erases", flash.erases());
+        })
+    }
+
+    #[test]
+    fn power_loss_at_every_byte_of_write() {
+        block_on(async {
+            let mut store = KvStore::new(Flash::new());
+            store.set(&NAME, &name("old name")).await.unwrap();
+            store.set(&COUNTER, &1).await.unwrap();
+            let base = store.into_inner();
+
+            for cut in 0.. {
+                let mut flash = base.clone();
+                flash.lose_power_after(cut);
+                let mut store = KvStore::new(flash);
+                let result = store.set(&NAME, &name("new name")).await;
+
+                let mut flash = store.into_inner();
+                let interrupted = !flash.is_powered();
+                assert_eq!(result.is_err(), interrupted);
+                flash.restore_power();
+
+                let mut store = KvStore::new(flash);
+                let value = store.get(&NAME).await.unwrap().unwrap();
+                assert_eq!(store.get(&COUNTER).await, Ok(Some(1)));
+                if !interrupted {
+                    assert_eq!(value, name("new name"));
+                    break;
+                }
+                assert!(
+                    value == name("old name") || value == name("new name"),
+                    "{value} after power loss at byte {cut}"
+                );
+                // Store stays writable after repair.
+                store.set(&NAME, &name("next")).await.unwrap();
+                assert_eq!(reopen(store).get(&NAME).await, Ok(Some(name("next"))));
+            }
+        })
+    }
+
+    #[test]
+    fn repeated_power_loss_during_compaction() {
+        block_on(async {
+            let mut store = KvStore::new(Flash::new());
+            store.set(&NAME, &name("altruist")).await.unwrap();
+            store.set(&COUNTER, &0).await.unwrap();
+            let mut flash = store.into_inner();
+
+            for counter in 1..3000u32 {
+                // Every write is interrupted at other place, then repeated.
+                flash.lose_power_after(counter as usize % 37);
+                let mut store = KvStore::new(flash);
+                let interrupted = store.set(&COUNTER, &counter).await.is_err();
+                flash = store.into_inner();
+                flash.restore_power();
+
+                let mut store = KvStore::new(flash);
+                let value = store.get(&COUNTER).await.unwrap();
+                assert!(
+                    value == Some(counter - 1) || value == Some(counter),
+                    "{value:?} after {counter} written"
+                );
+                if interrupted {
+                    store.set(&COUNTER, &counter).await.unwrap();
+                }
+                assert_eq!(store.get(&NAME).await, Ok(Some(name("altruist"))));
+                flash = store.into_inner();
+            }
+            assert!(flash.erases() > 4, "{} erases", flash.erases());
+        })
+    }
+}
@@ -0,0 +1,346 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Flash-backed FIFO of measurements taken while uploads are impossible.
+//!
+//! Built on [sequential-storage](https://crates.io/crates/sequential-storage) queue:
+//! records are postcard encoded [`Measurement`]s with Unix time they were taken at,
+//! so buffered readings are never reported as live ones. Records are protected by
+//! CRC, sector states act as head and tail pointers and are repaired after power
+//! loss, so the buffer is always consistent, at most the record being written is
+//! lost. When buffer is full the oldest records are evicted.
+//!
+//! Uploaders [`drain`](MeasurementBuffer::drain) buffer in order once connectivity
+//! returns, record is removed only after successful upload, so it could be sent
+//! twice when device restarts in between. Records which couldn't be decoded, e.g.
+//! written by older firmware, are dropped.
+
+use embedded_storage_async::nor_flash::MultiwriteNorFlash;
+use sequential_storage::cache::{Cache, Uncached};
+use sequential_storage::queue::{QueueConfig, QueueStorage};
+use serde::{Deserialize, Serialize};
+
+use super::StoreError;
+use crate::sensor::Measurement;
+
+/// Maximal size of encoded record.
+const RECORD_MAX_SIZE: usize = 64;
+
+/// Buffered measurement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// Unix time in seconds measurement was taken at.
+    pub time: u64,
+    pub measurement: Measurement,
+}
+
+type BufferCache = Cache<Uncached, Uncached, Uncached, ()>;
+
+/// Errors of [`MeasurementBuffer::drain`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrainError<S, U> {
+    /// Buffer access failure.
+    Store(StoreError<S>),
+    /// Upload failure, record is kept in buffer.
+    Upload(U),
+}
+
+impl<S, U> From<StoreError<S>> for DrainError<S, U> {
+    fn from(e: StoreError<S>) -> Self {
+        Self::Store(e)
+    }
+}
+
+/// Measurements ring buffer in flash.
+pub struct MeasurementBuffer<S: MultiwriteNorFlash> {
+    queue: QueueStorage<S, BufferCache>,
+}
+
+impl<S: MultiwriteNorFlash> MeasurementBuffer<S> {
+    /// Buffer occupying first `capacity` bytes of flash, e.g. [`Partition`](crate::flash::Partition).
+    ///
+    /// Capacity is limited by flash size and should be a multiple of sector size,
+    /// panics when it is less than two sectors.
+    pub fn new(flash: S, capacity: u32) -> Self {
+        let range = 0..capacity.min(flash.capacity() as u32);
+        Self {
+            queue: QueueStorage::new(flash, QueueConfig::new(range), BufferCache::new_uncached()),
+        }
+    }
+
+    /// Append measurement taken at given Unix time in seconds, the oldest ones
+    /// are evicted when buffer is full.
+    pub async fn push(
+        &mut self,
+        time: u64,
+        measurement: &Measurement,
+    ) -> Result<(), StoreError<S::Error>> {
+        let record = Record {
+            time,
+            measurement: *measurement,
+        };
+        let mut data = [0u8; RECORD_MAX_SIZE];
+        let data = postcard::to_slice(&record, &mut data).map_err(|_| StoreError::TooLarge)?;
+        Ok(self.queue.push(data, true).await?)
+    }
+
+    /// The oldest buffered measurement.
+    pub async fn peek(&mut self) -> Result<Option<Record>, StoreError<S::Error>> {
+        let mut record = [0u8; RECORD_MAX_SIZE];
+        match self.queue.peek(&mut record).await? {
+            Some(record) => decode(record).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Take the oldest buffered measurement.
+    pub async fn pop(&mut self) -> Result<Option<Record>, StoreError<S::Error>> {
+        let mut record = [0u8; RECORD_MAX_SIZE];
+        match self.queue.pop(&mut record).await? {
+            Some(record) => decode(record).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Upload buffered measurements oldest first, stops on the first upload failure.
+    ///
+    /// Returns count of uploaded measurements.
+    pub async fn drain<E>(
+        &mut self,
+        mut upload: impl AsyncFnMut(&Record) -> Result<(), E>,
+    ) -> Result<usize, DrainError<S::Error, E>> {
+        let mut uploaded = 0;
+        loop {
+            let record = match self.peek().await {
+                Ok(Some(record)) => record,
+                Ok(None) => return Ok(uploaded),
+                Err(StoreError::Serialization) => {
+                    self.discard().await?;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            upload(&record).await.map_err(DrainError::Upload)?;
+            self.discard().await?;
+            uploaded += 1;
+        }
+    }
+
+    /// Remove the oldest record without decoding it.
+    async fn discard(&mut self) -> Result<(), StoreError<S::Error>> {
+        let mut data = [0u8; RECORD_MAX_SIZE];
+        self.queue.pop(&mut data).await?;
+        Ok(())
+    }
+
+    /// Buffer has no records.
+    pub async fn is_empty(&mut self) -> Result<bool, StoreError<S::Error>> {
+        let mut data = [0u8; RECORD_MAX_SIZE];
+        Ok(self.queue.peek(&mut data).await?.is_none())
+    }
+
+    /// Drop all buffered measurements.
+    pub async fn clear(&mut self) -> Result<(), StoreError<S::Error>> {
+        Ok(self.queue.erase_all().await?)
+    }
+
+    /// Release underlying flash.
+    pub fn into_inner(self) -> S {
+        self.queue.destroy().0
+    }
+}
+
+fn decode<E>(record: &[u8]) -> Result<Record, StoreError<E>> {
+    postcard::from_bytes(record).map_err(|_| StoreError::Serialization)
+}
+
+#[cfg(test)]
+mod tests {
+    use embassy_futures::block_on;
+    use heapless::Vec;
+
+    use super::*;
+    use crate::flash::{RamFlash, SECTOR_SIZE};
+
+    type Flash = RamFlash<{ 4 * SECTOR_SIZE }>;
+
+    const CAPACITY: u32 = 4 * SECTOR_SIZE as u32;
+
+    fn measurement(i: u64) -> Measurement {
+        Measurement {
+            pm10: Some(i as u16),
+            pm25: Some(i as u16 / 2),
+            temperature: Some(-(i as i16)),
+            ..Measurement::new(i * 1000)
+        }
+    }
+
+    /// Reopen buffer on the same memory, as after restart.
+    fn reopen(buffer: MeasurementBuffer<Flash>) -> MeasurementBuffer<Flash> {
+        MeasurementBuffer::new(buffer.into_inner(), CAPACITY)
+    }
+
+    /// Upload every record, returns their times.
+    async fn drain_all(buffer: &mut MeasurementBuffer<Flash>) -> Vec<u64, 1024> {
+        let mut times = Vec::new();
+        buffer
+            .drain(async |r| {
+                assert_eq!(r.measurement, measurement(r.time));
+                times.push(r.time).map_err(|_| ())
+            })
+            .await
+            .unwrap();
+        times
+    }
+
+    #[test]
+    fn push_drain_in_order() {
+        block_on(async {
+            let mut buffer = MeasurementBuffer::new(Flash::new(), CAPACITY);
+            assert_eq!(buffer.is_empty().await, Ok(true));
+            for i in 1..=10 {
+                buffer.push(i, &measurement(i)).await.unwrap();
+            }
+            let mut buffer = reopen(buffer);
+            assert_eq!(
+                buffer.peek().await,
+                Ok(Some(Record {
+                    time: 1,
+                    measurement: measurement(1)
+                }))
+            );
+            assert_eq!(
+                drain_all(&mut buffer).await,
+                (1..=10).collect::<Vec<_, 10>>()
+            );
+            assert_eq!(buffer.is_empty().await, Ok(true));
+        })
+    }
+
+    #[test]
+    fn failed_upload_keeps_records() {
+        block_on(async {
+            let mut buffer = MeasurementBuffer::new(Flash::new(), CAPACITY);
+            for i in 1..=5 {
+                buffer.push(i, &measurement(i)).await.unwrap();
+            }
+            let result = buffer
+                .drain(async |r| if r.time < 3 { Ok(()) } else { Err("offline") })
+                .await;
+            assert_eq!(result, Err(DrainError::Upload("offline")));
+            assert_eq!(drain_all(&mut reopen(buffer)).await, [3, 4, 5]);
+        })
+    }
+
+    #[test]
+    fn oldest_records_are_evicted() {
+        block_on(async {
+            let mut buffer = MeasurementBuffer::new(Flash::new(), CAPACITY);
+            for i in 1..=1000 {
+                buffer.push(i, &measurement(i)).await.unwrap();
+            }
+            let times = drain_all(&mut buffer).await;
+            assert!(times.len() < 1000, "{} records kept", times.len());
+            // The newest records are kept in order.
+            assert_eq!(times.last(), Some(&1000));
+            assert!(times.windows(2).all(|w| w[1] == w[0] + 1));
+        })
+    }
+
+    #[test]
+    fn undecodable_records_are_dropped() {
+        block_on(async {
+            let mut buffer = MeasurementBuffer::new(Flash::new(), CAPACITY);
+            buffer.push(1, &measurement(1)).await.unwrap();
+            buffer.queue.push(&[0xff; 3], false).await.unwrap();
+            buffer.push(2, &measurement(2)).await.unwrap();
+            assert_eq!(drain_all(&mut buffer).await, [1, 2]);
+            assert_eq!(buffer.is_empty().await, Ok(true));
+        })
+    }
+
+    #[test]
+    fn power_loss_at_every_byte_of_push() {
+        block_on(async {
+            let mut buffer = MeasurementBuffer::new(Flash::new(), CAPACITY);
+            for i in 1..=3 {
+                buffer.push(i, &measurement(i)).await.unwrap();
+            }
+            let base = buffer.into_inner();
+
+            for cut in 0.. {
+                let mut flash = base.clone();
+                flash.lose_power_after(cut);
+                let mut buffer = MeasurementBuffer::new(flash, CAPACITY);
+                let result = buffer.push(4, &measurement(4)).await;
+
+                let mut flash = buffer.into_inner();
+                let interrupted = !flash.is_powered();
+                assert_eq!(result.is_err(), interrupted);
+                flash.restore_power();
+
+                let mut buffer = MeasurementBuffer::new(flash, CAPACITY);
+                if !interrupted {
+                    assert_eq!(drain_all(&mut buffer).await, [1, 2, 3, 4]);
+                    break;
+                }
+                // Half written record is lost, buffer stays usable.
+                buffer.push(5, &measurement(5)).await.unwrap();
+                let times = drain_all(&mut reopen(buffer)).await;
+                assert!(
+                    times == [1, 2, 3, 5] || times == [1, 2, 3, 4, 5],
+                    "{times:?} after power loss at byte {cut}"
+                );
+            }
+        })
+    }
+
+    #[test]
+    fn power_loss_at_every_byte_of_pop() {
+        block_on(async {
+            let mut buffer = MeasurementBuffer::new(Flash::new(), CAPACITY);
+            for i in 1..=3 {
+                buffer.push(i, &measurement(i)).await.unwrap();
+            }
+            let base = buffer.into_inner();
+
+            for cut in 0.. {
+                let mut flash = base.clone();
+                flash.lose_power_after(cut);
+                let mut buffer = MeasurementBuffer::new(flash, CAPACITY);
+                let result = buffer.drain(async |_| Ok::<_, ()>(())).await;
+
+                let mut flash = buffer.into_inner();
+                let interrupted = !flash.is_powered();
+                assert_eq!(result.is_err(), interrupted);
+                flash.restore_power();
+
+                let mut buffer = MeasurementBuffer::new(flash, CAPACITY);
+                let times = drain_all(&mut buffer).await;
+                if !interrupted {
+                    assert!(times.is_empty());
+                    break;
+                }
+                // Record being removed is either kept or gone, the rest are intact.
+                assert!(
+                    [&[1, 2, 3][..], &[2, 3], &[3], &[]].contains(&&times[..]),
+                    "{times:?} after power loss at byte {cut}"
+                );
+            }
+        })
+    }
+}
@@ -0,0 +1,98 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Chip services used by network layer.
+//!
+//! Without `esp32c3` feature host stand-ins are used, so protocol code can be
+//! tested with `cargo test-host`. Host [`Rng`] is NOT a secure random source.
+
+#[cfg(feature = "esp32c3")]
+pub use esp_hal::rng::Rng;
+
+/// Free heap memory in bytes.
+#[cfg(feature = "esp32c3")]
+pub fn heap_free() -> usize {
+    esp_alloc::HEAP.free()
+}
+
+/// Restart chip.
+#[cfg(feature = "esp32c3")]
+pub fn software_reset() -> ! {
+    esp_hal::system::software_reset()
+}
+
+#[cfg(not(feature = "esp32c3"))]
+pub use host::*;
+
+#[cfg(not(feature = "esp32c3"))]
+mod host {
+    use core::sync::atomic::{AtomicU32, Ordering};
+
+    static STATE: AtomicU32 = AtomicU32::new(0x2545_f491);
+
+    /// Xorshift generator with the same interface as `esp_hal::rng::Rng`.
+    #[derive(Clone, Copy, Debug, Default)]
+    pub struct Rng;
+
+    impl Rng {
+        pub fn new() -> Self {
+            Self
+        }
+
+        pub fn random(&self) -> u32 {
+            let mut x = STATE.load(Ordering::Relaxed);
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            STATE.store(x, Ordering::Relaxed);
+            x
+        }
+
+        pub fn read(&mut self, buf: &mut [u8]) {
+            for chunk in buf.chunks_mut(4) {
+                chunk.copy_from_slice(&self.random().to_ne_bytes()[..chunk.len()]);
+            }
+        }
+    }
+
+    impl rand_core::RngCore for Rng {
+        fn next_u32(&mut self) -> u32 {
+            self.random()
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            (self.random() as u64) << 32 | self.random() as u64
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            self.read(dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.read(dest);
+            Ok(())
+        }
+    }
+
+    pub fn heap_free() -> usize {
+        0
+    }
+
+    pub fn software_reset() -> ! {
+        panic!("software reset")
+    }
+}
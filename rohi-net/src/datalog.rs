@@ -0,0 +1,489 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Publishing sensor readings into Robonomics Datalog.
+//!
+//! [`DatalogPublisher`] averages measurements taken during upload period and
+//! records them on chain with `datalog.record` extrinsic signed by device
+//! account. Account nonce and chain parameters are cached between records and
+//! refreshed when node rejects extrinsic as outdated, e.g. after runtime upgrade.
+//!
+//! Records are put into [`MeasurementBuffer`] first and removed only after node
+//! accepted extrinsic, so readings survive connectivity loss and restarts.
+//! Buffering requires [`WALL_CLOCK`], until it is synchronized records are
+//! published right away or lost. Records carry Unix time of measurement as
+//! `time`, it is `null` while clock isn't synchronized.
+//! Account should have funds to pay fees, otherwise publishing is postponed.
+//!
+//! Records are public, with [`DatalogPublisher::with_encryption`] they are
+//! sealed for device owner, see [`rohi_hal::encryption`].
+//!
+//! ```ignore
+//! static AGGREGATE: SharedAggregate = Mutex::new(Aggregate::new());
+//!
+//! // Measurement loop.
+//! AGGREGATE.lock().await.add(&measurement);
+//!
+//! // Publisher task.
+//! let mut publisher = DatalogPublisher::new(rpc, pair).with_format(Format::Cbor);
+//! publisher.run(&AGGREGATE, &mut buffer, Duration::from_secs(300)).await
+//! ```
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::{Duration, Timer};
+use embedded_storage_async::nor_flash::MultiwriteNorFlash;
+use heapless::{String, Vec};
+use log::{info, warn};
+use rohi_hal::config::Location;
+use rohi_hal::encryption::Envelope;
+use rohi_hal::extrinsic::{Call, ChainInfo, ExtrinsicBuilder, RECORD_MAX_LEN};
+use rohi_hal::identity::Keypair;
+use rohi_hal::sensor::{Aggregate, Measurement};
+use rohi_hal::storage::MeasurementBuffer;
+use rohi_hal::storage::buffer::DrainError;
+
+use crate::chip::Rng;
+use crate::http::json;
+use crate::rpc::{Hash, INVALID_TRANSACTION, PRIORITY_TOO_LOW, RpcClient, RpcError};
+use crate::sensors_social::{self, Station};
+use crate::sntp::WALL_CLOCK;
+
+/// Measurements accumulated between records, shared with measurement loop.
+pub type SharedAggregate = Mutex<CriticalSectionRawMutex, Aggregate>;
+
+/// Count of submission attempts when node rejects extrinsic as outdated.
+pub const MAX_ATTEMPTS: usize = 3;
+
+/// Reason reported by runtime when account can't pay fees.
+const NO_FUNDS_REASON: &str = "Inability to pay";
+
+/// Encoding of datalog record.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// JSON object, see [`json::write_record`].
+    #[default]
+    Json,
+    /// CBOR map with the same keys and units as JSON.
+    Cbor,
+    /// sensors.social payload of station, see [`sensors_social::write_payload`].
+    SensorsSocial(Station),
+}
+
+/// Datalog publishing errors.
+#[derive(Debug)]
+pub enum DatalogError {
+    /// Node is unreachable or rejected extrinsic.
+    Rpc(RpcError),
+    /// Account has no funds to pay fees.
+    NoFunds,
+    /// Encoded record or extrinsic is too large.
+    TooLarge,
+}
+
+impl From<RpcError> for DatalogError {
+    fn from(e: RpcError) -> Self {
+        Self::Rpc(e)
+    }
+}
+
+/// Publisher of measurements into Datalog of device account.
+pub struct DatalogPublisher<'a> {
+    rpc: RpcClient<'a>,
+    pair: Keypair,
+    format: Format,
+    envelope: Option<Envelope>,
+    chain: Option<ChainInfo>,
+    nonce: Option<u32>,
+}
+
+impl<'a> DatalogPublisher<'a> {
+    /// Publisher of JSON records signed by `pair`.
+    pub fn new(rpc: RpcClient<'a>, pair: Keypair) -> Self {
+        Self {
+            rpc,
+            pair,
+            format: Format::Json,
+            envelope: None,
+            chain: None,
+            nonce: None,
+        }
+    }
+
+    /// Records encoding.
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Seal records for device owner.
+    pub fn with_encryption(mut self, envelope: Envelope) -> Self {
+        self.envelope = Some(envelope);
+        self
+    }
+
+    /// Record measurement on chain, returns extrinsic hash once it is accepted into pool.
+    pub async fn record(&mut self, measurement: &Measurement) -> Result<Hash, DatalogError> {
+        let time = WALL_CLOCK.unix_time(measurement.timestamp);
+        self.record_at(measurement, time).await
+    }
+
+    /// Record measurement taken at given Unix time in seconds.
+    async fn record_at(
+        &mut self,
+        measurement: &Measurement,
+        time: Option<u64>,
+    ) -> Result<Hash, DatalogError> {
+        let record = encode(self.format, measurement, time).ok_or(DatalogError::TooLarge)?;
+        let Some(envelope) = &self.envelope else {
+            return self.submit(&Call::Datalog(&record)).await;
+        };
+        let mut sealed = [0u8; RECORD_MAX_LEN];
+        let len = envelope
+            .seal(&mut Rng::new(), &record, &mut sealed)
+            .map_err(|_| DatalogError::TooLarge)?;
+        self.submit(&Call::Datalog(&sealed[..len])).await
+    }
+
+    /// Buffer measurement and publish all buffered records, returns count of published ones.
+    ///
+    /// Measurement is published right away when [`WALL_CLOCK`] isn't synchronized
+    /// or buffer fails, it is lost if publishing fails then.
+    pub async fn publish<S: MultiwriteNorFlash>(
+        &mut self,
+        measurement: &Measurement,
+        buffer: &mut MeasurementBuffer<S>,
+    ) -> Result<usize, DrainError<S::Error, DatalogError>> {
+        let Some(time) = WALL_CLOCK.unix_time(measurement.timestamp) else {
+            // Buffered record would be published later as live one.
+            return self
+                .record_at(measurement, None)
+                .await
+                .map(|_| 1)
+                .map_err(DrainError::Upload);
+        };
+        if let Err(e) = buffer.push(time, measurement).await {
+            warn!("[Datalog] > Unable to buffer record: {:?}", e);
+            return self
+                .record_at(measurement, Some(time))
+                .await
+                .map(|_| 1)
+                .map_err(DrainError::Upload);
+        }
+        buffer
+            .drain(async |r| {
+                self.record_at(&r.measurement, Some(r.time))
+                    .await
+                    .map(|_| ())
+            })
+            .await
+    }
+
+    /// Every `period` take averaged measurements, buffer and publish them.
+    pub async fn run<S: MultiwriteNorFlash>(
+        &mut self,
+        aggregate: &SharedAggregate,
+        buffer: &mut MeasurementBuffer<S>,
+        period: Duration,
+    ) -> ! {
+        loop {
+            Timer::after(period).await;
+            let Some(measurement) = aggregate.lock().await.take() else {
+                continue;
+            };
+            match self.publish(&measurement, buffer).await {
+                Ok(count) => info!("[Datalog] > {} records published", count),
+                Err(DrainError::Upload(DatalogError::NoFunds)) => warn!(
+                    "[Datalog] > Account {} can't pay fees, publishing postponed",
+                    self.pair.public()
+                ),
+                Err(e) => warn!("[Datalog] > Publishing failed: {:?}", e),
+            }
+        }
+    }
+
+    /// Sign and submit call, outdated nonce or chain parameters are refreshed and retried.
+    async fn submit(&mut self, call: &Call<'_>) -> Result<Hash, DatalogError> {
+        let mut attempt = 1;
+        loop {
+            let chain = match self.chain {
+                Some(chain) => chain,
+                None => *self.chain.insert(self.rpc.chain_info().await?),
+            };
+            let nonce = match self.nonce {
+                Some(nonce) => nonce,
+                None => self.rpc.account_next_index(&self.pair.public()).await?,
+            };
+            let extrinsic = ExtrinsicBuilder::new(&chain, nonce)
+                .sign(&self.pair, call)
+                .map_err(|_| DatalogError::TooLarge)?;
+
+            // Nonce is fetched again after any failure, extrinsic could reach pool anyway.
+            self.nonce = None;
+            match self.rpc.submit_extrinsic(&extrinsic).await {
+                Ok(hash) => {
+                    self.nonce = Some(nonce + 1);
+                    return Ok(hash);
+                }
+                Err(RpcError::Rpc { code, data })
+                    if code == INVALID_TRANSACTION && data.starts_with(NO_FUNDS_REASON) =>
+                {
+                    return Err(DatalogError::NoFunds);
+                }
+                Err(RpcError::Rpc { code, data })
+                    if (code == INVALID_TRANSACTION || code == PRIORITY_TOO_LOW)
+                        && attempt < MAX_ATTEMPTS =>
+                {
+                    warn!("[Datalog] > Extrinsic rejected ({}), retrying", data);
+                    self.chain = None;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+}
+
+/// Encode measurement taken at given Unix time as datalog record.
+fn encode(format: Format, m: &Measurement, time: Option<u64>) -> Option<Vec<u8, RECORD_MAX_LEN>> {
+    match format {
+        Format::Json => {
+            let mut text: String<RECORD_MAX_LEN> = String::new();
+            json::write_record(&mut text, m, time).ok()?;
+            Some(text.into_bytes())
+        }
+        Format::Cbor => {
+            let mut cbor = Vec::new();
+            write_cbor(&mut cbor, m, time).ok()?;
+            Some(cbor)
+        }
+        Format::SensorsSocial(station) => {
+            let mut text: String<RECORD_MAX_LEN> = String::new();
+            if sensors_social::write_payload(&mut text, &station, m, time).is_err() {
+                // Full payload with location may not fit into record, keep values only.
+                let station = Station {
+                    location: Location::default(),
+                    ..station
+                };
+                text.clear();
+                sensors_social::write_payload(&mut text, &station, m, time).ok()?;
+            }
+            Some(text.into_bytes())
+        }
+    }
+}
+
+/// Render measurement taken at given Unix time as CBOR map, values are floats
+/// in units of JSON format.
+fn write_cbor<const N: usize>(
+    out: &mut Vec<u8, N>,
+    m: &Measurement,
+    time: Option<u64>,
+) -> Result<(), ()> {
+    let tenths = |v: Option<i32>| v.map(|v| v as f32 / 10.0);
+    let fields = [
+        ("pm10", tenths(m.pm10.map(Into::into))),
+        ("pm25", tenths(m.pm25.map(Into::into))),
+        ("temperature", tenths(m.temperature.map(Into::into))),
+        ("humidity", tenths(m.humidity.map(Into::into))),
+        ("pressure", m.pressure.map(|v| v as f32)),
+        ("noise", tenths(m.noise.map(Into::into))),
+    ];
+
+    // Map of time and fields.
+    cbor_head(out, 5, fields.len() as u64 + 1)?;
+    cbor_text(out, "time")?;
+    match time {
+        Some(time) => cbor_head(out, 0, time)?,
+        None => out.push(0xf6).map_err(drop)?,
+    }
+    for (key, value) in fields {
+        cbor_text(out, key)?;
+        match value {
+            Some(value) => {
+                out.push(0xfa).map_err(drop)?;
+                out.extend_from_slice(&value.to_be_bytes()).map_err(drop)?;
+            }
+            None => out.push(0xf6).map_err(drop)?,
+        }
+    }
+    Ok(())
+}
+
+/// CBOR data item head with major type and argument.
+fn cbor_head<const N: usize>(out: &mut Vec<u8, N>, major: u8, arg: u64) -> Result<(), ()> {
+    let major = major << 5;
+    match arg {
+        0..24 => out.push(major | arg as u8).map_err(drop),
+        24..0x100 => out
+            .extend_from_slice(&[major | 24, arg as u8])
+            .map_err(drop),
+        0x100..0x1_0000 => {
+            out.push(major | 25).map_err(drop)?;
+            out.extend_from_slice(&(arg as u16).to_be_bytes())
+                .map_err(drop)
+        }
+        0x1_0000..0x1_0000_0000 => {
+            out.push(major | 26).map_err(drop)?;
+            out.extend_from_slice(&(arg as u32).to_be_bytes())
+                .map_err(drop)
+        }
+        _ => {
+            out.push(major | 27).map_err(drop)?;
+            out.extend_from_slice(&arg.to_be_bytes()).map_err(drop)
+        }
+    }
+}
+
+fn cbor_text<const N: usize>(out: &mut Vec<u8, N>, text: &str) -> Result<(), ()> {
+    cbor_head(out, 3, text.len() as u64)?;
+    out.extend_from_slice(text.as_bytes()).map_err(drop)
+}
+
+#[cfg(test)]
+mod tests {
+    use rohi_hal::board::ChipId;
+
+    use super::*;
+
+    const TIME: u64 = 1_700_000_000;
+
+    fn measurement() -> Measurement {
+        Measurement {
+            pm10: Some(123),
+            pm25: Some(45),
+            temperature: Some(-35),
+            pressure: Some(100_934),
+            noise: Some(456),
+            ..Measurement::new(60_000)
+        }
+    }
+
+    #[test]
+    fn cbor_map() {
+        let mut cbor: Vec<u8, 128> = Vec::new();
+        write_cbor(&mut cbor, &measurement(), Some(TIME)).unwrap();
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0xa7,
+            0x64, b't', b'i', b'm', b'e', 0x1a, 0x65, 0x53, 0xf1, 0x00,
+            0x64, b'p', b'm', b'1', b'0', 0xfa, 0x41, 0x44, 0xcc, 0xcd,
+            0x64, b'p', b'm', b'2', b'5', 0xfa, 0x40, 0x90, 0x00, 0x00,
+            0x6b, b't', b'e', b'm', b'p', b'e', b'r', b'a', b't', b'u', b'r', b'e',
+            0xfa, 0xc0, 0x60, 0x00, 0x00,
+            0x68, b'h', b'u', b'm', b'i', b'd', b'i', b't', b'y', 0xf6,
+            0x68, b'p', b'r', b'e', b's', b's', b'u', b'r', b'e', 0xfa, 0x47, 0xc5, 0x23, 0x00,
+            0x65, b'n', b'o', b'i', b's', b'e', 0xfa, 0x42, 0x36, 0x66, 0x66,
+        ];
+        assert_eq!(cbor[..], expected[..]);
+
+        // Unknown time is null.
+        cbor.clear();
+        write_cbor(&mut cbor, &Measurement::new(60_000), None).unwrap();
+        assert_eq!(
+            cbor[..11],
+            [
+                0xa7, 0x64, b't', b'i', b'm', b'e', 0xf6, 0x64, b'p', b'm', b'1'
+            ]
+        );
+
+        let mut small: Vec<u8, 32> = Vec::new();
+        assert!(write_cbor(&mut small, &measurement(), Some(TIME)).is_err());
+    }
+
+    #[test]
+    fn cbor_head_arguments() {
+        let head = |major, arg| {
+            let mut out: Vec<u8, 9> = Vec::new();
+            cbor_head(&mut out, major, arg).unwrap();
+            out
+        };
+        assert_eq!(head(0, 0)[..], [0x00]);
+        assert_eq!(head(0, 23)[..], [0x17]);
+        assert_eq!(head(0, 24)[..], [0x18, 24]);
+        assert_eq!(head(0, 255)[..], [0x18, 0xff]);
+        assert_eq!(head(0, 256)[..], [0x19, 0x01, 0x00]);
+        assert_eq!(head(0, 65535)[..], [0x19, 0xff, 0xff]);
+        assert_eq!(head(0, 65536)[..], [0x1a, 0x00, 0x01, 0x00, 0x00]);
+        assert_eq!(head(0, 0xffff_ffff)[..], [0x1a, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            head(0, 0x1_0000_0000)[..],
+            [0x1b, 0, 0, 0, 0x01, 0, 0, 0, 0]
+        );
+        // Major type is in high bits.
+        assert_eq!(head(3, 4)[..], [0x64]);
+        assert_eq!(head(5, 24)[..], [0xb8, 24]);
+    }
+
+    #[test]
+    fn record_formats() {
+        let json = encode(Format::Json, &measurement(), Some(TIME)).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&json).unwrap(),
+            r#"{"time":1700000000,"pm10":12.3,"pm25":4.5,"temperature":-3.5,"humidity":null,"pressure":100934,"noise":45.6}"#
+        );
+        let json = encode(Format::Json, &measurement(), None).unwrap();
+        assert!(json.starts_with(br#"{"time":null,"pm10":12.3,"#));
+
+        let cbor = encode(Format::Cbor, &measurement(), Some(TIME)).unwrap();
+        let mut expected: Vec<u8, 128> = Vec::new();
+        write_cbor(&mut expected, &measurement(), Some(TIME)).unwrap();
+        assert_eq!(cbor, expected);
+
+        let station = Station::new(
+            ChipId([0x24, 0x0a, 0xc4, 0x12, 0x34, 0x56]),
+            "rohi-test/1.0",
+            Location::default(),
+        );
+        let payload = encode(Format::SensorsSocial(station), &measurement(), Some(TIME)).unwrap();
+        let mut expected: String<RECORD_MAX_LEN> = String::new();
+        sensors_social::write_payload(&mut expected, &station, &measurement(), Some(TIME)).unwrap();
+        assert_eq!(payload, expected.into_bytes());
+        assert!(
+            core::str::from_utf8(&payload)
+                .unwrap()
+                .contains(r#""timestamp":1700000000,"#)
+        );
+
+        // Location is dropped when full payload doesn't fit into record.
+        let m = Measurement {
+            humidity: Some(605),
+            noise: Some(452),
+            ..measurement()
+        };
+        let located = Station::new(
+            ChipId([0x24, 0x0a, 0xc4, 0x12, 0x34, 0x56]),
+            "ROHI-altruist-0.1.0",
+            Location {
+                latitude: 55.751244,
+                longitude: -37.618423,
+                altitude: 156.5,
+            },
+        );
+        let mut full: String<1024> = String::new();
+        sensors_social::write_payload(&mut full, &located, &m, Some(TIME)).unwrap();
+        assert!(full.len() > RECORD_MAX_LEN);
+        let payload = encode(Format::SensorsSocial(located), &m, Some(TIME)).unwrap();
+        let mut expected: String<RECORD_MAX_LEN> = String::new();
+        let unlocated = Station {
+            location: Location::default(),
+            ..located
+        };
+        sensors_social::write_payload(&mut expected, &unlocated, &m, Some(TIME)).unwrap();
+        assert_eq!(payload, expected.into_bytes());
+    }
+}
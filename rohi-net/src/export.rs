@@ -0,0 +1,492 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Exporting measurements to user's own servers.
+//!
+//! [`Exporter`] follows [`ExportConfig`], so target could be changed at runtime:
+//!
+//! - InfluxDB line protocol posted to `/write?db=` of InfluxDB 1.x or to
+//!   `/api/v2/write?org=&bucket=` of InfluxDB 2.x, authenticated by
+//!   `Authorization: Token` header;
+//! - JSON body rendered from template and posted to any URL.
+//!
+//! Line has `air_quality` measurement, `device` tag, float fields in units
+//! of [`write_measurement`](crate::http::json::write_measurement) and Unix time
+//! of measurement in seconds. Time is omitted until [`WALL_CLOCK`] is
+//! synchronized, server assigns time of write then:
+//!
+//! ```text
+//! air_quality,device=altruist\ kitchen pm10=12.3,pm25=7.1,pressure=100934.0 1700000000
+//! ```
+//!
+//! Webhook template placeholders are `{device}`, `{timestamp}` and names of
+//! measurement fields, e.g. `{pm25}`. Timestamp is Unix time in seconds, absent
+//! values and timestamp of unsynchronized clock are rendered as `null`, device
+//! name is JSON escaped, other text of template is kept as is:
+//!
+//! ```text
+//! {"station":"{device}","dust":{"pm10":{pm10},"pm25":{pm25}}}
+//! ```
+//!
+//! ```ignore
+//! Exporter::new(client, &config.export, &config.name)
+//!     .export(&measurement)
+//!     .await?;
+//! ```
+
+use core::fmt::{self, Display, Formatter, Write};
+use edge_http::Method;
+use heapless::String;
+use log::{debug, warn};
+use rohi_hal::config::{ExportConfig, ExportKind};
+use rohi_hal::sensor::Measurement;
+
+use crate::http::HttpClient;
+use crate::http::client::ClientError;
+use crate::http::json::{Escaped, Nullable, Tenths};
+use crate::sntp::WALL_CLOCK;
+
+/// InfluxDB measurement of exported lines.
+pub const MEASUREMENT: &str = "air_quality";
+
+/// Maximal length of rendered body.
+pub const BODY_MAX_LEN: usize = 512;
+
+/// Maximal length of write URL with query.
+pub const URL_MAX_LEN: usize = 256;
+
+/// Webhook body used when template is empty.
+pub const DEFAULT_TEMPLATE: &str = concat!(
+    r#"{"device":"{device}","timestamp":{timestamp},"pm10":{pm10},"pm25":{pm25},"#,
+    r#""temperature":{temperature},"humidity":{humidity},"pressure":{pressure},"noise":{noise}}"#
+);
+
+/// Line protocol element, escaped by its own rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Element {
+    /// Measurement name, commas and spaces are escaped, newlines become spaces.
+    Measurement,
+    /// Tag key, tag value or field key, commas, equal signs and spaces are
+    /// escaped, newlines become spaces.
+    Key,
+    /// String field value, double quotes and backslashes are escaped.
+    String,
+}
+
+impl Element {
+    /// Character is escaped by backslash in this element.
+    fn is_escaped(self, c: char) -> bool {
+        match self {
+            Self::Measurement => matches!(c, ',' | ' ' | '\n' | '\r'),
+            Self::Key => matches!(c, ',' | '=' | ' ' | '\n' | '\r'),
+            Self::String => matches!(c, '"' | '\\'),
+        }
+    }
+}
+
+/// Text escaped as line protocol element.
+pub struct LineEscaped<'a>(pub &'a str, pub Element);
+
+impl Display for LineEscaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut chars = self.0.chars().peekable();
+        while let Some(c) = chars.next() {
+            let escape = match c {
+                // Backslash in names escapes the next character, so it is doubled
+                // before escaped characters and at the end, e.g. before separator.
+                '\\' if self.1 != Element::String => {
+                    chars.peek().is_none_or(|&next| self.1.is_escaped(next))
+                }
+                c => self.1.is_escaped(c),
+            };
+            if escape {
+                f.write_char('\\')?;
+            }
+            match c {
+                // Lines are separated by newlines, they can't be escaped in names.
+                '\n' | '\r' if self.1 != Element::String => f.write_char(' ')?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Text percent encoded as URL query value.
+pub struct QueryEncoded<'a>(pub &'a str);
+
+impl Display for QueryEncoded<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for b in self.0.bytes() {
+            match b {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    f.write_char(b as char)?
+                }
+                b => write!(f, "%{:02X}", b)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Measurement fields in units of JSON format, floats or `None` when absent.
+fn fields(m: &Measurement) -> [(&'static str, Option<Tenths>); 6] {
+    let tenths = |v: Option<i32>| v.map(Tenths);
+    [
+        ("pm10", tenths(m.pm10.map(Into::into))),
+        ("pm25", tenths(m.pm25.map(Into::into))),
+        ("temperature", tenths(m.temperature.map(Into::into))),
+        ("humidity", tenths(m.humidity.map(Into::into))),
+        (
+            "pressure",
+            tenths(m.pressure.map(|v| v.saturating_mul(10) as i32)),
+        ),
+        ("noise", tenths(m.noise.map(Into::into))),
+    ]
+}
+
+/// Render measurement taken at given Unix time in seconds as line of InfluxDB
+/// line protocol with `device` tag.
+///
+/// Returns `false` and writes nothing when measurement has no values, such line
+/// is rejected by InfluxDB.
+pub fn write_line<W: Write>(
+    w: &mut W,
+    device: &str,
+    m: &Measurement,
+    time: Option<u64>,
+) -> Result<bool, fmt::Error> {
+    let fields = fields(m);
+    if fields.iter().all(|(_, value)| value.is_none()) {
+        return Ok(false);
+    }
+    write!(
+        w,
+        "{},device={}",
+        LineEscaped(MEASUREMENT, Element::Measurement),
+        LineEscaped(device, Element::Key)
+    )?;
+    let mut separator = ' ';
+    for (key, value) in fields {
+        if let Some(value) = value {
+            write!(w, "{}{}={}", separator, key, value)?;
+            separator = ',';
+        }
+    }
+    if let Some(time) = time {
+        write!(w, " {}", time)?;
+    }
+    w.write_char('\n')?;
+    Ok(true)
+}
+
+/// Render webhook body of measurement taken at given Unix time in seconds from
+/// template, see module documentation for placeholders.
+pub fn write_template<W: Write>(
+    w: &mut W,
+    template: &str,
+    device: &str,
+    m: &Measurement,
+    time: Option<u64>,
+) -> fmt::Result {
+    let fields = fields(m);
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let (text, tail) = rest.split_at(start);
+        w.write_str(text)?;
+        let name = tail[1..]
+            .find('}')
+            .map(|end| &tail[1..end + 1])
+            .filter(|name| {
+                !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+            });
+        let Some(name) = name else {
+            w.write_char('{')?;
+            rest = &tail[1..];
+            continue;
+        };
+        match name {
+            "device" => write!(w, "{}", Escaped(device))?,
+            "timestamp" => write!(w, "{}", Nullable(time.as_ref()))?,
+            name => match fields.iter().find(|(key, _)| *key == name) {
+                Some((_, value)) => write!(w, "{}", Nullable(value.as_ref()))?,
+                // Unknown placeholders are kept, e.g. JSON braces around names.
+                None => write!(w, "{{{}}}", name)?,
+            },
+        }
+        rest = &tail[name.len() + 2..];
+    }
+    w.write_str(rest)
+}
+
+/// Write endpoint of InfluxDB target, line time is in seconds.
+fn write_influx_url<W: Write>(w: &mut W, config: &ExportConfig) -> fmt::Result {
+    let base = config.url.trim_end_matches('/');
+    let database = QueryEncoded(&config.database);
+    match config.kind {
+        ExportKind::InfluxV1 => write!(w, "{}/write?db={}&precision=s", base, database),
+        _ => write!(
+            w,
+            "{}/api/v2/write?org={}&bucket={}&precision=s",
+            base,
+            QueryEncoded(&config.org),
+            database
+        ),
+    }
+}
+
+/// Exporter of measurements to target of [`ExportConfig`].
+#[derive(Clone, Copy)]
+pub struct Exporter<'a> {
+    http: HttpClient,
+    config: &'a ExportConfig,
+    device: &'a str,
+}
+
+impl<'a> Exporter<'a> {
+    /// Exporter of `device` readings, device name is used as tag or `{device}` placeholder.
+    pub fn new(http: HttpClient, config: &'a ExportConfig, device: &'a str) -> Self {
+        Self {
+            http,
+            config,
+            device,
+        }
+    }
+
+    /// Export is configured.
+    pub fn is_enabled(&self) -> bool {
+        self.config.kind != ExportKind::None
+    }
+
+    /// Post measurement to configured target, nothing is done when export is disabled.
+    pub async fn export(&self, m: &Measurement) -> Result<(), ClientError> {
+        let config = self.config;
+        let mut url: String<URL_MAX_LEN> = String::new();
+        let mut body: String<BODY_MAX_LEN> = String::new();
+        let time = WALL_CLOCK.unix_time(m.timestamp);
+        let content_type = match config.kind {
+            ExportKind::None => return Ok(()),
+            ExportKind::InfluxV1 | ExportKind::InfluxV2 => {
+                if !write_line(&mut body, self.device, m, time)
+                    .map_err(|_| ClientError::BufferOverflow)?
+                {
+                    return Ok(());
+                }
+                write_influx_url(&mut url, config).map_err(|_| ClientError::InvalidUrl)?;
+                "text/plain; charset=utf-8"
+            }
+            ExportKind::Webhook => {
+                let template = if config.template.is_empty() {
+                    DEFAULT_TEMPLATE
+                } else {
+                    &config.template
+                };
+                write_template(&mut body, template, self.device, m, time)
+                    .map_err(|_| ClientError::BufferOverflow)?;
+                _ = url.push_str(config.url.trim_end_matches('/'));
+                "application/json"
+            }
+        };
+
+        let mut auth: String<104> = String::new();
+        let mut headers: heapless::Vec<(&str, &str), 2> = heapless::Vec::new();
+        _ = headers.push(("Content-Type", content_type));
+        if config.kind != ExportKind::Webhook && !config.token.is_empty() {
+            _ = write!(auth, "Token {}", config.token);
+            _ = headers.push(("Authorization", &auth));
+        }
+        let mut buf = [0u8; 512];
+        let response = self
+            .http
+            .request(Method::Post, &url, &headers, body.as_bytes(), &mut buf)
+            .await?;
+        if !response.is_success() {
+            warn!(
+                "[Export] > {:?} rejected with status {}",
+                config.kind, response.status
+            );
+            return Err(ClientError::Status(response.status));
+        }
+        debug!("[Export] > Exported: {}", body);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unix time of measurement.
+    const TIME: u64 = 1_700_000_000;
+
+    fn escaped(text: &str, element: Element) -> String<64> {
+        let mut escaped = String::new();
+        write!(escaped, "{}", LineEscaped(text, element)).unwrap();
+        escaped
+    }
+
+    fn measurement() -> Measurement {
+        Measurement {
+            pm10: Some(123),
+            pm25: Some(71),
+            temperature: Some(-35),
+            pressure: Some(100934),
+            ..Measurement::new(60000)
+        }
+    }
+
+    fn template(template: &str, device: &str, m: &Measurement) -> String<BODY_MAX_LEN> {
+        let mut body = String::new();
+        write_template(&mut body, template, device, m, Some(TIME)).unwrap();
+        body
+    }
+
+    #[test]
+    fn line_escaped_names() {
+        let measurement = Element::Measurement;
+        assert_eq!(
+            escaped("air quality,x=1", measurement),
+            r"air\ quality\,x=1"
+        );
+        assert_eq!(escaped("air\nquality\r", measurement), r"air\ quality\ ");
+        assert_eq!(escaped(r"dust\", measurement), r"dust\\");
+        let key = Element::Key;
+        assert_eq!(escaped("altruist kitchen", key), r"altruist\ kitchen");
+        assert_eq!(escaped("a,b=c", key), r"a\,b\=c");
+        assert_eq!(escaped("first\nsecond", key), r"first\ second");
+        // Backslash is kept unless it escapes the next character.
+        assert_eq!(escaped(r"C:\temp", key), r"C:\temp");
+        assert_eq!(escaped(r"kitchen\", key), r"kitchen\\");
+        assert_eq!(escaped(r"a\,b\ c\=d", key), r"a\\\,b\\\ c\\\=d");
+        assert_eq!(escaped(r"a\\", key), r"a\\\");
+    }
+
+    #[test]
+    fn line_escaped_string() {
+        let string = Element::String;
+        assert_eq!(escaped(r#"say "hi" \ bye"#, string), r#"say \"hi\" \\ bye"#);
+        assert_eq!(escaped("a, b=c\n", string), "a, b=c\n");
+        assert_eq!(escaped(r"end\", string), r"end\\");
+    }
+
+    #[test]
+    fn line_protocol() {
+        let mut line: String<BODY_MAX_LEN> = String::new();
+        assert!(write_line(&mut line, "altruist kitchen", &measurement(), Some(TIME)).unwrap());
+        assert_eq!(
+            line,
+            "air_quality,device=altruist\\ kitchen pm10=12.3,pm25=7.1,temperature=-3.5,pressure=100934.0 1700000000\n"
+        );
+        // Server assigns time until clock is synchronized.
+        line.clear();
+        assert!(write_line(&mut line, "kitchen\\", &measurement(), None).unwrap());
+        assert!(line.starts_with("air_quality,device=kitchen\\\\ pm10=12.3,"));
+        assert!(line.ends_with(",pressure=100934.0\n"));
+        line.clear();
+        assert!(!write_line(&mut line, "kitchen", &Measurement::new(0), Some(TIME)).unwrap());
+        assert!(line.is_empty());
+    }
+
+    #[test]
+    fn influx_urls() {
+        let mut config = ExportConfig {
+            kind: ExportKind::InfluxV1,
+            url: "http://influx.local:8086/".try_into().unwrap(),
+            database: "air quality".try_into().unwrap(),
+            org: "home&co".try_into().unwrap(),
+            ..ExportConfig::default()
+        };
+        let mut url: String<URL_MAX_LEN> = String::new();
+        write_influx_url(&mut url, &config).unwrap();
+        assert_eq!(
+            url,
+            "http://influx.local:8086/write?db=air%20quality&precision=s"
+        );
+        config.kind = ExportKind::InfluxV2;
+        url.clear();
+        write_influx_url(&mut url, &config).unwrap();
+        assert_eq!(
+            url,
+            "http://influx.local:8086/api/v2/write?org=home%26co&bucket=air%20quality&precision=s"
+        );
+    }
+
+    #[test]
+    fn query_encoded() {
+        let mut query: String<64> = String::new();
+        write!(query, "{}", QueryEncoded("my db/ü&x=1 ~a-b_c.d")).unwrap();
+        assert_eq!(query, "my%20db%2F%C3%BC%26x%3D1%20~a-b_c.d");
+        query.clear();
+        write!(query, "{}", QueryEncoded("Sensors_2025")).unwrap();
+        assert_eq!(query, "Sensors_2025");
+    }
+
+    #[test]
+    fn default_template() {
+        assert_eq!(
+            template(DEFAULT_TEMPLATE, "altruist \"kitchen\"", &measurement()),
+            concat!(
+                r#"{"device":"altruist \"kitchen\"","timestamp":1700000000,"pm10":12.3,"pm25":7.1,"#,
+                r#""temperature":-3.5,"humidity":null,"pressure":100934.0,"noise":null}"#
+            )
+        );
+        // Timestamp is unknown until clock is synchronized, uptime isn't rendered.
+        let mut body: String<BODY_MAX_LEN> = String::new();
+        write_template(&mut body, DEFAULT_TEMPLATE, "kitchen", &measurement(), None).unwrap();
+        assert!(body.starts_with(r#"{"device":"kitchen","timestamp":null,"pm10":12.3,"#));
+    }
+
+    #[test]
+    fn template_placeholders() {
+        let m = measurement();
+        assert_eq!(
+            template(r#"{"dust":{"pm25":{pm25}}}"#, "kitchen", &m),
+            r#"{"dust":{"pm25":7.1}}"#
+        );
+        // Unknown and malformed placeholders are kept as is.
+        assert_eq!(
+            template(r#"{"co2":{co2},"pm10":{pm10},"x":{}}"#, "kitchen", &m),
+            r#"{"co2":{co2},"pm10":12.3,"x":{}}"#
+        );
+        assert_eq!(
+            template("{device {pm 10} {noise}", "kitchen", &m),
+            "{device {pm 10} null"
+        );
+        // Unterminated placeholders.
+        assert_eq!(
+            template(r#"{"pm10":{pm10"#, "kitchen", &m),
+            r#"{"pm10":{pm10"#
+        );
+        assert_eq!(template("{", "kitchen", &m), "{");
+        assert_eq!(template("{device}{", "kitchen", &m), "kitchen{");
+    }
+
+    #[test]
+    fn template_overflow() {
+        let mut body: String<16> = String::new();
+        assert!(
+            write_template(
+                &mut body,
+                DEFAULT_TEMPLATE,
+                "kitchen",
+                &measurement(),
+                Some(TIME)
+            )
+            .is_err()
+        );
+    }
+}
@@ -16,3 +16,51 @@
 //
 ///////////////////////////////////////////////////////////////////////////////
 //! HTTP server & client implementation for embedded devices.
+//!
+//! Server side is based on [edge-http](https://crates.io/crates/edge-http):
+//! any [`Handler`](edge_http::io::server::Handler) could be served by [`HttpServer`],
+//! several handlers are combined with [`Route`].
+
+/// Local REST API for device status and configuration.
+pub mod api;
+pub use api::ApiHandler;
+
+/// Authorization of requests changing device state.
+pub mod auth;
+pub use auth::Authorize;
+
+/// Static assets embedded into firmware, e.g. web dashboard.
+pub mod asset;
+pub use asset::{Asset, AssetHandler};
+
+/// Minimal HTTP client for plain `http://` endpoints.
+pub mod client;
+pub use client::HttpClient;
+
+/// JSON rendering helpers for sensor data.
+pub mod json;
+
+/// Prometheus metrics exporter.
+pub mod metrics;
+pub use metrics::{Metrics, MetricsHandler};
+
+/// Over-the-air firmware update endpoint.
+#[cfg(feature = "esp32c3")]
+pub mod ota;
+#[cfg(feature = "esp32c3")]
+pub use ota::OtaHandler;
+
+/// Request routing between handlers.
+pub mod route;
+pub use route::{NotFound, Route};
+
+/// HTTP server running on top of network stack.
+pub mod server;
+pub use server::HttpServer;
+
+/// WebSocket protocol support and live sensor readings stream.
+pub mod ws;
+pub use ws::WsReadingsHandler;
+
+#[cfg(test)]
+mod fake;
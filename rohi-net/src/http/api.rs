@@ -0,0 +1,631 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Local REST API for device status and configuration.
+//!
+//! | Method | Path                  | Description                              |
+//! |--------|-----------------------|------------------------------------------|
+//! | GET    | `/api/status`         | Firmware, vitals, network and uploads    |
+//! | GET    | `/api/sensors`        | Latest sensors measurement               |
+//! | GET    | `/api/config`         | Current device configuration             |
+//! | PUT    | `/api/config`         | Validate and persist new configuration   |
+//! | POST   | `/api/reboot`         | Restart device                           |
+//! | POST   | `/api/factory-reset`  | Wipe configuration and restart device    |
+//!
+//! Request and response bodies are JSON, errors are reported as `{"error":"..."}`.
+//! Secrets of configuration are never sent to clients, see [`Secrets`]. Changing
+//! configuration, reboot and factory reset require [device password](super::auth).
+
+use core::fmt::{Debug, Display, Write as _};
+use edge_http::Method;
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::{Connection, Handler};
+use edge_nal::TcpSplit;
+use edge_nal::io::{Read, Write};
+use embassy_time::{Instant, Timer};
+use heapless::String;
+use log::{info, warn};
+use rohi_hal::config::DeviceConfig;
+use rohi_hal::sensor::Measurement;
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::auth::{Authorize, rejection};
+use super::json;
+use super::route::path_only;
+use crate::NETWORK_STATS;
+
+/// Size of request and response body buffers.
+pub const API_BUF_SIZE: usize = 2048;
+
+const ENDPOINTS: [&str; 5] = [
+    "/api/status",
+    "/api/sensors",
+    "/api/config",
+    "/api/reboot",
+    "/api/factory-reset",
+];
+
+/// Configuration document with secrets hidden from API clients.
+pub trait Secrets {
+    /// Hide secrets of configuration sent to client.
+    fn redact(&mut self);
+
+    /// Take secrets hidden from client from `current` configuration.
+    fn keep_secrets(&mut self, current: &Self);
+}
+
+impl Secrets for DeviceConfig {
+    fn redact(&mut self) {
+        DeviceConfig::redact(self)
+    }
+
+    fn keep_secrets(&mut self, current: &Self) {
+        DeviceConfig::keep_secrets(self, current)
+    }
+}
+
+/// Device operations exposed through REST API, implemented by firmware.
+#[allow(async_fn_in_trait)]
+pub trait Device: Authorize {
+    /// Device configuration document.
+    type Config: Serialize + DeserializeOwned + Secrets;
+
+    /// Firmware name and version, e.g. `altruist-sensors-social 0.1.0`.
+    fn firmware(&self) -> &str;
+
+    /// Latest sensors measurement.
+    fn measurement(&self) -> Option<Measurement>;
+
+    /// Current device configuration.
+    async fn config(&self) -> Self::Config;
+
+    /// Validate and persist new configuration, error message is reported to client.
+    async fn set_config(&self, config: Self::Config) -> Result<(), &'static str>;
+
+    /// Wipe persisted configuration, device is restarted after that.
+    async fn factory_reset(&self);
+
+    /// State of measurements upload, `None` when device doesn't upload anything.
+    fn uploads(&self) -> Option<UploadStatus> {
+        None
+    }
+
+    /// Restart device.
+    fn reboot(&self) -> ! {
+        crate::chip::software_reset()
+    }
+}
+
+/// Response of `GET /api/status`.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Status<'a> {
+    pub firmware: &'a str,
+    /// Time since boot in seconds.
+    pub uptime: u64,
+    /// Free heap memory in bytes.
+    pub heap_free: usize,
+    pub network: NetworkStatus,
+    pub uploads: Option<UploadStatus>,
+}
+
+/// Network part of [`Status`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct NetworkStatus {
+    pub rssi: Option<i32>,
+    pub reconnects: u32,
+    pub dhcp_leases: u32,
+}
+
+/// Uploads part of [`Status`].
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UploadStatus {
+    /// Count of successful uploads.
+    pub uploads: u32,
+    /// Count of failed uploads.
+    pub errors: u32,
+    /// Uptime in seconds of the last successful upload.
+    pub last_success: Option<u64>,
+    /// Reason of the last failure, cleared by successful upload.
+    pub last_error: Option<&'static str>,
+}
+
+/// Error response body.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApiError<'a> {
+    pub error: &'a str,
+}
+
+/// HTTP handler of REST API, usually routed on `/api` prefix.
+pub struct ApiHandler<'a, D> {
+    device: &'a D,
+}
+
+impl<'a, D: Device> ApiHandler<'a, D> {
+    /// Create handler for given device.
+    pub const fn new(device: &'a D) -> Self {
+        Self { device }
+    }
+
+    /// Current configuration with secrets hidden.
+    async fn redacted_config(&self) -> D::Config {
+        let mut config = self.device.config().await;
+        config.redact();
+        config
+    }
+
+    fn status(&self) -> Status<'_> {
+        Status {
+            firmware: self.device.firmware(),
+            uptime: Instant::now().as_secs(),
+            heap_free: crate::chip::heap_free(),
+            network: NetworkStatus {
+                rssi: NETWORK_STATS.rssi(),
+                reconnects: NETWORK_STATS.reconnects(),
+                dhcp_leases: NETWORK_STATS.dhcp_leases(),
+            },
+            uploads: self.device.uploads(),
+        }
+    }
+}
+
+impl<D: Device> Handler for ApiHandler<'_, D> {
+    type Error<E>
+        = HttpError<E>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        _task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        let headers = connection.headers()?;
+        let method = headers.method;
+        let mut buf = [0u8; API_BUF_SIZE];
+
+        let path = path_only(headers.path);
+        if matches!(method, Method::Put | Method::Post)
+            && ENDPOINTS.contains(&path)
+            && let Some((status, error)) = rejection(self.device, connection).await?
+        {
+            warn!("[API] > Unauthorized request to {}", path);
+            return respond_error(connection, status, error, &mut buf).await;
+        }
+
+        match (method, path) {
+            (Method::Get, "/api/status") => {
+                respond_serialized(connection, 200, &self.status(), &mut buf).await
+            }
+            (Method::Get, "/api/sensors") => match self.device.measurement() {
+                Some(measurement) => {
+                    let mut body: String<API_BUF_SIZE> = String::new();
+                    _ = json::write_measurement(&mut body, &measurement);
+                    respond_json(connection, 200, body.as_bytes()).await
+                }
+                None => respond_error(connection, 503, "no measurements yet", &mut buf).await,
+            },
+            (Method::Get, "/api/config") => {
+                let config = self.redacted_config().await;
+                respond_serialized(connection, 200, &config, &mut buf).await
+            }
+            (Method::Put, "/api/config") => {
+                let Some(len) = read_body(connection, &mut buf).await? else {
+                    return respond_error(connection, 413, "request too large", &mut buf).await;
+                };
+                let mut config = match serde_json_core::from_slice::<D::Config>(&buf[..len]) {
+                    Ok((config, _)) => config,
+                    Err(_) => {
+                        return respond_error(connection, 400, "malformed config", &mut buf).await;
+                    }
+                };
+                config.keep_secrets(&self.device.config().await);
+                match self.device.set_config(config).await {
+                    Ok(()) => {
+                        info!("[API] > Configuration updated");
+                        let config = self.redacted_config().await;
+                        respond_serialized(connection, 200, &config, &mut buf).await
+                    }
+                    Err(e) => respond_error(connection, 422, e, &mut buf).await,
+                }
+            }
+            (Method::Post, "/api/reboot") => {
+                respond_json(connection, 202, b"{}").await?;
+                restart(connection, self.device).await
+            }
+            (Method::Post, "/api/factory-reset") => {
+                warn!("[API] > Factory reset requested");
+                self.device.factory_reset().await;
+                respond_json(connection, 202, b"{}").await?;
+                restart(connection, self.device).await
+            }
+            (_, path) if ENDPOINTS.contains(&path) => {
+                respond_error(connection, 405, "method not allowed", &mut buf).await
+            }
+            _ => respond_error(connection, 404, "not found", &mut buf).await,
+        }
+    }
+}
+
+/// Complete response and restart device after short delay to let client get it.
+async fn restart<T, const N: usize, D>(
+    connection: &mut Connection<'_, T, N>,
+    device: &D,
+) -> Result<(), HttpError<T::Error>>
+where
+    T: Read + Write,
+    D: Device,
+{
+    connection.complete().await?;
+    info!("[API] > Restarting device");
+    Timer::after_millis(500).await;
+    device.reboot()
+}
+
+/// Read request body into buffer, `None` when body doesn't fit.
+pub(super) async fn read_body<T, const N: usize>(
+    connection: &mut Connection<'_, T, N>,
+    buf: &mut [u8],
+) -> Result<Option<usize>, HttpError<T::Error>>
+where
+    T: Read + Write,
+{
+    let mut len = 0;
+    loop {
+        if len == buf.len() {
+            // Body filling buffer exactly is complete when nothing follows.
+            let mut probe = [0u8; 1];
+            return match connection.read(&mut probe).await? {
+                0 => Ok(Some(len)),
+                _ => Ok(None),
+            };
+        }
+        match connection.read(&mut buf[len..]).await? {
+            0 => return Ok(Some(len)),
+            n => len += n,
+        }
+    }
+}
+
+async fn respond_serialized<T, const N: usize, S>(
+    connection: &mut Connection<'_, T, N>,
+    status: u16,
+    body: &S,
+    buf: &mut [u8],
+) -> Result<(), HttpError<T::Error>>
+where
+    T: Read + Write,
+    S: Serialize,
+{
+    match serde_json_core::to_slice(body, buf) {
+        Ok(len) => respond_json(connection, status, &buf[..len]).await,
+        Err(_) => {
+            warn!("[API] > Response doesn't fit into buffer");
+            respond_json(connection, 500, br#"{"error":"response too large"}"#).await
+        }
+    }
+}
+
+pub(super) async fn respond_error<T, const N: usize>(
+    connection: &mut Connection<'_, T, N>,
+    status: u16,
+    error: &str,
+    buf: &mut [u8],
+) -> Result<(), HttpError<T::Error>>
+where
+    T: Read + Write,
+{
+    respond_serialized(connection, status, &ApiError { error }, buf).await
+}
+
+pub(super) async fn respond_json<T, const N: usize>(
+    connection: &mut Connection<'_, T, N>,
+    status: u16,
+    body: &[u8],
+) -> Result<(), HttpError<T::Error>>
+where
+    T: Read + Write,
+{
+    let mut length: String<10> = String::new();
+    _ = write!(length, "{}", body.len());
+    connection
+        .initiate_response(
+            status,
+            None,
+            &[
+                ("Content-Type", "application/json"),
+                ("Content-Length", &length),
+            ],
+        )
+        .await?;
+    connection.write_all(body).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::auth;
+    use crate::http::fake::Socket;
+    use core::cell::{Cell, RefCell};
+    use embassy_futures::block_on;
+    use heapless::Vec;
+
+    const PASSWORD: &str = "device-password";
+    /// Factory defaults with WiFi network and device password.
+    const CONFIG: &str = concat!(
+        r#"{"name":"altruist","network":{"ssid":"home","password":"********"},"#,
+        r#""sensors":{"pm":true,"climate":true,"noise":true,"temperature_offset":0},"#,
+        r#""upload":{"sensors_social":"","robonomics":"","sensor_community":false,"madavi":false},"#,
+        r#""location":{"latitude":0.0,"longitude":0.0,"altitude":0.0},"#,
+        r#""intervals":{"measure_secs":10,"upload_secs":300},"#,
+        r#""export":{"kind":"none","url":"","database":"","org":"","token":"","template":""},"#,
+        r#""admin_password":"********"}"#
+    );
+
+    struct FakeDevice {
+        config: RefCell<DeviceConfig>,
+        measurement: Option<Measurement>,
+        reset: Cell<bool>,
+    }
+
+    impl FakeDevice {
+        fn new() -> Self {
+            let mut config = DeviceConfig::default();
+            config.network.ssid = "home".try_into().unwrap();
+            config.network.password = "wifi-password".try_into().unwrap();
+            config.admin_password = PASSWORD.try_into().unwrap();
+            Self {
+                config: RefCell::new(config),
+                measurement: None,
+                reset: Cell::new(false),
+            }
+        }
+    }
+
+    impl Authorize for FakeDevice {
+        async fn authorize(&self, token: Option<&str>) -> bool {
+            token.is_some_and(|token| auth::verify(token, PASSWORD))
+        }
+    }
+
+    impl Device for FakeDevice {
+        type Config = DeviceConfig;
+
+        fn firmware(&self) -> &str {
+            "altruist-sensors-social 0.1.0"
+        }
+
+        fn measurement(&self) -> Option<Measurement> {
+            self.measurement
+        }
+
+        async fn config(&self) -> DeviceConfig {
+            self.config.borrow().clone()
+        }
+
+        async fn set_config(&self, config: DeviceConfig) -> Result<(), &'static str> {
+            config.validate()?;
+            *self.config.borrow_mut() = config;
+            Ok(())
+        }
+
+        async fn factory_reset(&self) {
+            self.reset.set(true);
+        }
+    }
+
+    /// Status code and body of response to raw request.
+    fn request(device: &FakeDevice, request: &[u8]) -> (u16, String<API_BUF_SIZE>) {
+        let mut socket = Socket::new(request);
+        let mut buf = [0u8; 1024];
+        block_on(async {
+            let mut connection = Connection::<_, 8>::new(&mut buf, &mut socket)
+                .await
+                .unwrap();
+            ApiHandler::new(device)
+                .handle(0, &mut connection)
+                .await
+                .unwrap();
+            connection.complete().await.unwrap();
+        });
+        let response = core::str::from_utf8(socket.written()).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.try_into().unwrap())
+    }
+
+    /// Raw request with given token and body.
+    fn raw(method: &str, path: &str, token: Option<&str>, body: &[u8]) -> Vec<u8, 4096> {
+        let mut head: String<256> = String::new();
+        write!(head, "{} {} HTTP/1.1\r\nHost: rohi\r\n", method, path).unwrap();
+        if let Some(token) = token {
+            write!(head, "Authorization: Bearer {}\r\n", token).unwrap();
+        }
+        write!(head, "Content-Length: {}\r\n\r\n", body.len()).unwrap();
+        let mut raw = Vec::from_slice(head.as_bytes()).unwrap();
+        raw.extend_from_slice(body).unwrap();
+        raw
+    }
+
+    /// [`CONFIG`] with `from` replaced by `to`.
+    fn config_with(from: &str, to: &str) -> String<API_BUF_SIZE> {
+        let (head, tail) = CONFIG.split_once(from).unwrap();
+        let mut config = String::new();
+        write!(config, "{head}{to}{tail}").unwrap();
+        config
+    }
+
+    fn get(device: &FakeDevice, path: &str) -> (u16, String<API_BUF_SIZE>) {
+        request(device, &raw("GET", path, None, b""))
+    }
+
+    fn put_config(
+        device: &FakeDevice,
+        token: Option<&str>,
+        body: &[u8],
+    ) -> (u16, String<API_BUF_SIZE>) {
+        request(device, &raw("PUT", "/api/config", token, body))
+    }
+
+    #[test]
+    fn status_json() {
+        let mut status = Status {
+            firmware: "altruist-sensors-social 0.1.0",
+            uptime: 3600,
+            heap_free: 51200,
+            network: NetworkStatus {
+                rssi: Some(-67),
+                reconnects: 1,
+                dhcp_leases: 0,
+            },
+            uploads: Some(UploadStatus {
+                uploads: 12,
+                errors: 1,
+                last_success: Some(3540),
+                last_error: Some("connection failed"),
+            }),
+        };
+        let mut buf = [0u8; 512];
+        let len = serde_json_core::to_slice(&status, &mut buf).unwrap();
+        assert_eq!(
+            core::str::from_utf8(&buf[..len]).unwrap(),
+            concat!(
+                r#"{"firmware":"altruist-sensors-social 0.1.0","uptime":3600,"heap_free":51200,"#,
+                r#""network":{"rssi":-67,"reconnects":1,"dhcp_leases":0},"#,
+                r#""uploads":{"uploads":12,"errors":1,"last_success":3540,"last_error":"connection failed"}}"#
+            )
+        );
+        status.network.rssi = None;
+        status.uploads = None;
+        let len = serde_json_core::to_slice(&status, &mut buf).unwrap();
+        let json = core::str::from_utf8(&buf[..len]).unwrap();
+        assert!(json.ends_with(
+            r#""network":{"rssi":null,"reconnects":1,"dhcp_leases":0},"uploads":null}"#
+        ));
+
+        let (code, body) = get(&FakeDevice::new(), "/api/status");
+        assert_eq!(code, 200);
+        assert!(body.starts_with(r#"{"firmware":"altruist-sensors-social 0.1.0","uptime":"#));
+    }
+
+    #[test]
+    fn config_is_redacted() {
+        let (code, body) = get(&FakeDevice::new(), "/api/config");
+        assert_eq!(code, 200);
+        assert_eq!(body, CONFIG);
+    }
+
+    #[test]
+    fn config_update() {
+        let device = FakeDevice::new();
+        let update = config_with("\"altruist\"", "\"kitchen\"");
+        let (code, body) = put_config(&device, Some(PASSWORD), update.as_bytes());
+        assert_eq!((code, body.as_str()), (200, update.as_str()));
+        // Secrets sent back as placeholders are kept.
+        let config = device.config.borrow();
+        assert_eq!(config.name, "kitchen");
+        assert_eq!(config.network.password, "wifi-password");
+        assert_eq!(config.admin_password, PASSWORD);
+    }
+
+    #[test]
+    fn request_bodies() {
+        let device = FakeDevice::new();
+        // Body filling buffer exactly is accepted, longer one is refused.
+        let mut body: Vec<u8, { API_BUF_SIZE + 1 }> = Vec::from_slice(CONFIG.as_bytes()).unwrap();
+        body.resize(API_BUF_SIZE, b' ').unwrap();
+        assert_eq!(put_config(&device, Some(PASSWORD), &body).0, 200);
+        body.push(b' ').unwrap();
+        let (code, error) = put_config(&device, Some(PASSWORD), &body);
+        assert_eq!(
+            (code, error.as_str()),
+            (413, r#"{"error":"request too large"}"#)
+        );
+
+        let truncated = &CONFIG.as_bytes()[..CONFIG.len() - 1];
+        let (code, error) = put_config(&device, Some(PASSWORD), truncated);
+        assert_eq!(
+            (code, error.as_str()),
+            (400, r#"{"error":"malformed config"}"#)
+        );
+        let (code, _) = put_config(&device, Some(PASSWORD), br#"{"name":"kitchen"}"#);
+        assert_eq!(code, 400);
+        let invalid = config_with(r#""measure_secs":10"#, r#""measure_secs":0"#);
+        let (code, error) = put_config(&device, Some(PASSWORD), invalid.as_bytes());
+        assert_eq!(
+            (code, error.as_str()),
+            (422, r#"{"error":"measure interval should be positive"}"#)
+        );
+        assert_eq!(device.config.borrow().intervals.measure_secs, 10);
+    }
+
+    #[test]
+    fn authorization() {
+        let device = FakeDevice::new();
+        let update = config_with("\"altruist\"", "\"kitchen\"");
+        let (code, error) = put_config(&device, None, update.as_bytes());
+        assert_eq!(
+            (code, error.as_str()),
+            (401, r#"{"error":"device password required"}"#)
+        );
+        let (code, error) = put_config(&device, Some("device-passwor"), update.as_bytes());
+        assert_eq!(
+            (code, error.as_str()),
+            (403, r#"{"error":"wrong device password"}"#)
+        );
+        assert_eq!(device.config.borrow().name, "altruist");
+
+        let reset = raw("POST", "/api/factory-reset", Some("wrong"), b"");
+        assert_eq!(request(&device, &reset).0, 403);
+        let reboot = raw("POST", "/api/reboot", None, b"");
+        assert_eq!(request(&device, &reboot).0, 401);
+        assert!(!device.reset.get());
+
+        assert!(auth::verify(PASSWORD, PASSWORD));
+        assert!(!auth::verify("device-passwore", PASSWORD));
+        assert!(!auth::verify("device-password-", PASSWORD));
+        assert!(!auth::verify("", PASSWORD));
+    }
+
+    #[test]
+    fn sensors_and_routes() {
+        let mut device = FakeDevice::new();
+        let (code, error) = get(&device, "/api/sensors");
+        assert_eq!(
+            (code, error.as_str()),
+            (503, r#"{"error":"no measurements yet"}"#)
+        );
+        device.measurement = Some(Measurement {
+            pm10: Some(123),
+            temperature: Some(-35),
+            ..Measurement::new(60_000)
+        });
+        let (code, body) = get(&device, "/api/sensors?fresh");
+        assert_eq!(code, 200);
+        assert_eq!(
+            body,
+            r#"{"timestamp":60000,"pm10":12.3,"pm25":null,"temperature":-3.5,"humidity":null,"pressure":null,"noise":null}"#
+        );
+
+        let delete = raw("DELETE", "/api/config", Some(PASSWORD), b"");
+        assert_eq!(request(&device, &delete).0, 405);
+        assert_eq!(get(&device, "/api/reboot").0, 405);
+        assert_eq!(get(&device, "/api/unknown").0, 404);
+    }
+}
@@ -0,0 +1,121 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Static assets embedded into firmware image.
+//!
+//! Assets are gzip compressed at build time and served as is with
+//! `Content-Encoding: gzip`. Browser revalidates cached copy using `ETag`,
+//! so unchanged asset costs only `304 Not Modified` response.
+
+use core::fmt::{Debug, Display, Write as _};
+use edge_http::Method;
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::{Connection, Handler};
+use edge_nal::TcpSplit;
+use edge_nal::io::{Read, Write};
+use heapless::String;
+
+use super::route::path_only;
+
+/// Precompressed static asset.
+pub struct Asset {
+    /// Request path, e.g. `/index.html`.
+    pub path: &'static str,
+    pub content_type: &'static str,
+    /// Gzip compressed content.
+    pub gzip: &'static [u8],
+    /// Quoted entity tag which changes together with content.
+    pub etag: &'static str,
+}
+
+/// HTTP handler serving collection of static assets.
+///
+/// Request of `/` is served by asset with `/index.html` path.
+pub struct AssetHandler<'a> {
+    assets: &'a [Asset],
+}
+
+impl<'a> AssetHandler<'a> {
+    /// Create handler for given assets.
+    pub const fn new(assets: &'a [Asset]) -> Self {
+        Self { assets }
+    }
+
+    fn find(&self, path: &str) -> Option<&Asset> {
+        let path = match path_only(path) {
+            "/" => "/index.html",
+            path => path,
+        };
+        self.assets.iter().find(|asset| asset.path == path)
+    }
+}
+
+impl Handler for AssetHandler<'_> {
+    type Error<E>
+        = HttpError<E>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        _task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        let headers = connection.headers()?;
+        if headers.method != Method::Get {
+            return connection
+                .initiate_response(405, Some("Method Not Allowed"), &[("Allow", "GET")])
+                .await;
+        }
+        let Some(asset) = self.find(headers.path) else {
+            return connection
+                .initiate_response(404, Some("Not Found"), &[])
+                .await;
+        };
+
+        if headers.headers.get("If-None-Match") == Some(asset.etag) {
+            return connection
+                .initiate_response(
+                    304,
+                    Some("Not Modified"),
+                    &[("ETag", asset.etag), ("Cache-Control", "no-cache")],
+                )
+                .await;
+        }
+
+        let mut length: String<10> = String::new();
+        _ = write!(length, "{}", asset.gzip.len());
+        connection
+            .initiate_response(
+                200,
+                Some("OK"),
+                &[
+                    ("Content-Type", asset.content_type),
+                    ("Content-Encoding", "gzip"),
+                    ("Content-Length", &length),
+                    ("ETag", asset.etag),
+                    ("Cache-Control", "no-cache"),
+                    ("Vary", "Accept-Encoding"),
+                ],
+            )
+            .await?;
+        connection.write_all(asset.gzip).await
+    }
+}
@@ -0,0 +1,73 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Authorization of requests changing device state.
+//!
+//! Clients pass device password as bearer token:
+//!
+//! ```sh
+//! curl -X POST -H "Authorization: Bearer $PASSWORD" http://192.168.42.1/api/reboot
+//! ```
+//!
+//! Requests without token are answered with 401, requests with wrong one with 403.
+
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::Connection;
+use edge_nal::io::{Read, Write};
+
+/// Header carrying bearer token.
+pub const AUTHORIZATION_HEADER: &str = "Authorization";
+
+/// Check of device credential, implemented by firmware.
+#[allow(async_fn_in_trait)]
+pub trait Authorize {
+    /// Request with given bearer token is allowed to change device state,
+    /// `token` is `None` when request isn't authorized at all.
+    async fn authorize(&self, token: Option<&str>) -> bool;
+}
+
+/// Compare token with password in constant time.
+pub fn verify(token: &str, password: &str) -> bool {
+    let (token, password) = (token.as_bytes(), password.as_bytes());
+    let diff = token
+        .iter()
+        .zip(password)
+        .fold(0, |diff, (a, b)| diff | (a ^ b));
+    diff == 0 && token.len() == password.len()
+}
+
+/// Status and message of response to request not accepted by `auth`:
+/// 401 without bearer token and 403 with rejected one, `None` when accepted.
+pub(super) async fn rejection<A, T, const N: usize>(
+    auth: &A,
+    connection: &Connection<'_, T, N>,
+) -> Result<Option<(u16, &'static str)>, HttpError<T::Error>>
+where
+    A: Authorize,
+    T: Read + Write,
+{
+    let token = connection
+        .headers()?
+        .headers
+        .get(AUTHORIZATION_HEADER)
+        .and_then(|value| value.strip_prefix("Bearer "));
+    Ok(match (auth.authorize(token).await, token) {
+        (true, _) => None,
+        (false, None) => Some((401, "device password required")),
+        (false, Some(_)) => Some((403, "wrong device password")),
+    })
+}
@@ -0,0 +1,371 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Minimal HTTP/1.1 client for plain `http://` endpoints.
+//!
+//! Besides plain requests, client opens WebSocket connections to `ws://`
+//! endpoints, see [`HttpClient::websocket`].
+
+use core::fmt::Write as _;
+use core::net::{IpAddr, SocketAddr};
+use edge_http::Method;
+use edge_http::io::ErrorKind;
+use edge_http::io::client::Connection;
+use edge_http::ws::{MAX_BASE64_KEY_LEN, MAX_BASE64_KEY_RESPONSE_LEN, NONCE_LEN};
+use edge_nal::io::{Read, Write};
+use edge_nal::{AddrType, Close, Dns as _, TcpShutdown};
+use edge_nal_embassy::{Dns, Tcp, TcpBuffers, TcpSocket};
+use embassy_net::Stack;
+use heapless::{String, Vec};
+
+use crate::NETWORK_STATS;
+use crate::chip::Rng;
+
+/// Count of requests performed concurrently.
+pub const CLIENT_SOCKETS: usize = 2;
+
+/// Size of response headers buffer for each request.
+pub const CLIENT_BUF_SIZE: usize = 2048;
+
+/// Maximal count of headers in request and response.
+pub const CLIENT_MAX_HEADERS: usize = 16;
+
+type SocketBuffers = TcpBuffers<CLIENT_SOCKETS, 1024, 1024>;
+
+/// Socket of WebSocket connection opened by [`HttpClient::websocket`].
+pub type ClientSocket<'d> = TcpSocket<'d, CLIENT_SOCKETS, 1024, 1024>;
+
+/// HTTP client errors.
+#[derive(Debug)]
+pub enum ClientError {
+    /// URL is not in `http://host[:port]/path` or `ws://host[:port]/path` form.
+    InvalidUrl,
+    /// Host name could not be resolved.
+    Dns,
+    /// Request has more than [`CLIENT_MAX_HEADERS`] headers.
+    TooManyHeaders,
+    /// Response body does not fit into buffer.
+    BufferOverflow,
+    /// Server responded with non-success status code.
+    Status(u16),
+    /// Connection or protocol error.
+    Http(ErrorKind),
+}
+
+impl<E: edge_nal::io::Error> From<edge_http::io::Error<E>> for ClientError {
+    fn from(e: edge_http::io::Error<E>) -> Self {
+        Self::Http(e.erase())
+    }
+}
+
+/// Streaming download errors.
+#[derive(Debug)]
+pub enum DownloadError<E> {
+    /// Request failed.
+    Client(ClientError),
+    /// Body consumer rejected received data.
+    Sink(E),
+}
+
+impl<E> From<ClientError> for DownloadError<E> {
+    fn from(e: ClientError) -> Self {
+        Self::Client(e)
+    }
+}
+
+/// Parsed `http://host[:port]/path` or `ws://host[:port]/path` URL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Url<'a> {
+    /// Host name or IP address.
+    pub host: &'a str,
+    /// TCP port, 80 by default.
+    pub port: u16,
+    /// Path with query string, `/` when absent.
+    pub path: &'a str,
+}
+
+impl<'a> Url<'a> {
+    /// Parse URL, only `http` and `ws` schemes are supported.
+    pub fn parse(url: &'a str) -> Result<Self, ClientError> {
+        let rest = url
+            .strip_prefix("http://")
+            .or_else(|| url.strip_prefix("ws://"))
+            .ok_or(ClientError::InvalidUrl)?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| ClientError::InvalidUrl)?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(ClientError::InvalidUrl);
+        }
+        Ok(Self { host, port, path })
+    }
+}
+
+/// Response of buffered request.
+#[derive(Debug)]
+pub struct Response<'b> {
+    /// HTTP status code.
+    pub status: u16,
+    /// Response body.
+    pub body: &'b [u8],
+}
+
+impl Response<'_> {
+    /// Status code is `2xx`.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Chunk of streamed response.
+pub enum Chunk<'b> {
+    /// Response started, value of `Content-Length` header when present.
+    Start(Option<u64>),
+    /// Next part of response body.
+    Data(&'b [u8]),
+}
+
+/// HTTP client on top of network stack.
+///
+/// Socket buffers are statically allocated, so only one instance could be created;
+/// the client is cheap to copy and could be shared between tasks.
+#[derive(Clone, Copy)]
+pub struct HttpClient {
+    stack: Stack<'static>,
+    buffers: &'static SocketBuffers,
+}
+
+impl HttpClient {
+    /// New HTTP client using given network stack.
+    pub fn new(stack: Stack<'static>) -> Self {
+        Self {
+            stack,
+            buffers: mk_static!(SocketBuffers, SocketBuffers::new()),
+        }
+    }
+
+    /// Network stack used by client.
+    pub fn stack(&self) -> Stack<'static> {
+        self.stack
+    }
+
+    /// Perform request and read whole response body into `response` buffer.
+    pub async fn request<'b>(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+        response: &'b mut [u8],
+    ) -> Result<Response<'b>, ClientError> {
+        self.request_parts(method, url, headers, &[body], response)
+            .await
+    }
+
+    /// Same as [`HttpClient::request`] with body sent from several parts,
+    /// e.g. multipart form around payload without copying it.
+    pub async fn request_parts<'b>(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[&[u8]],
+        response: &'b mut [u8],
+    ) -> Result<Response<'b>, ClientError> {
+        let url = Url::parse(url)?;
+        let addr = self.resolve(&url).await?;
+        let tcp = Tcp::new(self.stack, self.buffers);
+        let mut buf = [0u8; CLIENT_BUF_SIZE];
+        let mut connection: Connection<_, CLIENT_MAX_HEADERS> =
+            Connection::new(&mut buf, &tcp, addr);
+        send_request(&mut connection, method, &url, headers, body).await?;
+
+        let status = connection.headers()?.code;
+        NETWORK_STATS.http_exchange();
+        let mut len = 0;
+        loop {
+            if len == response.len() {
+                // Response filling buffer exactly is complete when nothing follows.
+                let mut probe = [0u8; 1];
+                if connection.read(&mut probe).await? != 0 {
+                    return Err(ClientError::BufferOverflow);
+                }
+                break;
+            }
+            match connection.read(&mut response[len..]).await? {
+                0 => break,
+                n => len += n,
+            }
+        }
+        connection.close().await?;
+        Ok(Response {
+            status,
+            body: &response[..len],
+        })
+    }
+
+    /// Perform `GET` request and pass response body to `sink` chunk by chunk.
+    ///
+    /// Non-success status code is reported as [`ClientError::Status`].
+    pub async fn download<E>(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+        sink: impl AsyncFnMut(Chunk<'_>) -> Result<(), E>,
+    ) -> Result<(), DownloadError<E>> {
+        self.stream(Method::Get, url, headers, &[], sink).await
+    }
+
+    /// Perform request and pass response body to `sink` chunk by chunk,
+    /// for responses which don't fit into memory.
+    ///
+    /// Non-success status code is reported as [`ClientError::Status`].
+    pub async fn stream<E>(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+        mut sink: impl AsyncFnMut(Chunk<'_>) -> Result<(), E>,
+    ) -> Result<(), DownloadError<E>> {
+        let url = Url::parse(url)?;
+        let addr = self.resolve(&url).await?;
+        let tcp = Tcp::new(self.stack, self.buffers);
+        let mut buf = [0u8; CLIENT_BUF_SIZE];
+        let mut connection: Connection<_, CLIENT_MAX_HEADERS> =
+            Connection::new(&mut buf, &tcp, addr);
+        send_request(&mut connection, method, &url, headers, &[body]).await?;
+
+        let response = connection.headers().map_err(ClientError::from)?;
+        NETWORK_STATS.http_exchange();
+        if !(200..300).contains(&response.code) {
+            return Err(ClientError::Status(response.code).into());
+        }
+        sink(Chunk::Start(response.headers.content_len()))
+            .await
+            .map_err(DownloadError::Sink)?;
+
+        let mut chunk = [0u8; 1024];
+        loop {
+            match connection
+                .read(&mut chunk)
+                .await
+                .map_err(ClientError::from)?
+            {
+                0 => break,
+                n => sink(Chunk::Data(&chunk[..n]))
+                    .await
+                    .map_err(DownloadError::Sink)?,
+            }
+        }
+        connection.close().await.map_err(ClientError::from)?;
+        Ok(())
+    }
+
+    /// Open WebSocket connection and run `session` on it, connection is closed after that.
+    ///
+    /// Upgrade refused by server is reported as [`ClientError::Status`], frames are
+    /// exchanged with [`super::ws::send_masked_frame`] and [`super::ws::recv_message`].
+    pub async fn websocket<R>(
+        &self,
+        url: &str,
+        session: impl AsyncFnOnce(&mut ClientSocket<'_>) -> R,
+    ) -> Result<R, ClientError> {
+        let url = Url::parse(url)?;
+        let addr = self.resolve(&url).await?;
+        let tcp = Tcp::new(self.stack, self.buffers);
+        let mut buf = [0u8; CLIENT_BUF_SIZE];
+        let mut connection: Connection<_, CLIENT_MAX_HEADERS> =
+            Connection::new(&mut buf, &tcp, addr);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        Rng::new().read(&mut nonce);
+        let mut nonce_buf = [0u8; MAX_BASE64_KEY_LEN];
+        connection
+            .initiate_ws_upgrade_request(
+                Some(url.host),
+                None,
+                url.path,
+                None,
+                &nonce,
+                &mut nonce_buf,
+            )
+            .await?;
+        connection.initiate_response().await?;
+        let mut key_buf = [0u8; MAX_BASE64_KEY_RESPONSE_LEN];
+        if !connection.is_ws_upgrade_accepted(&nonce, &mut key_buf)? {
+            return Err(ClientError::Status(connection.headers()?.code));
+        }
+
+        let (mut socket, _) = connection.release();
+        let result = session(&mut socket).await;
+        _ = socket.close(Close::Both).await;
+        Ok(result)
+    }
+
+    async fn resolve(&self, url: &Url<'_>) -> Result<SocketAddr, ClientError> {
+        let ip = match url.host.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => Dns::new(self.stack)
+                .get_host_by_name(url.host, AddrType::IPv4)
+                .await
+                .map_err(|_| ClientError::Dns)?,
+        };
+        Ok(SocketAddr::new(ip, url.port))
+    }
+}
+
+async fn send_request<T, const N: usize>(
+    connection: &mut Connection<'_, T, N>,
+    method: Method,
+    url: &Url<'_>,
+    headers: &[(&str, &str)],
+    body: &[&[u8]],
+) -> Result<(), ClientError>
+where
+    T: edge_nal::TcpConnect,
+{
+    let body_len: usize = body.iter().map(|part| part.len()).sum();
+    let mut content_len = String::<10>::new();
+    _ = write!(content_len, "{}", body_len);
+
+    let mut all: Vec<(&str, &str), CLIENT_MAX_HEADERS> = Vec::new();
+    all.push(("Host", url.host))
+        .map_err(|_| ClientError::TooManyHeaders)?;
+    all.push(("Connection", "close"))
+        .map_err(|_| ClientError::TooManyHeaders)?;
+    if method != Method::Get || body_len > 0 {
+        all.push(("Content-Length", &content_len))
+            .map_err(|_| ClientError::TooManyHeaders)?;
+    }
+    all.extend_from_slice(headers)
+        .map_err(|_| ClientError::TooManyHeaders)?;
+
+    connection
+        .initiate_request(true, method, url.path, &all)
+        .await?;
+    for part in body {
+        connection.write_all(part).await?;
+    }
+    connection.initiate_response().await?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Fake TCP socket of protocol tests.
+
+use core::convert::Infallible;
+use edge_nal::io::{ErrorType, Read, Write};
+use edge_nal::{Readable, TcpSplit};
+use heapless::Vec;
+
+/// Bytes sent by peer, stream ends after them.
+pub struct Rx<'a>(pub &'a [u8]);
+
+/// Bytes written by device.
+pub struct Tx(pub Vec<u8, 4096>);
+
+/// Socket replaying recorded bytes of peer and keeping written ones.
+pub struct Socket<'a> {
+    pub rx: Rx<'a>,
+    pub tx: Tx,
+}
+
+impl<'a> Socket<'a> {
+    pub fn new(rx: &'a [u8]) -> Self {
+        Self {
+            rx: Rx(rx),
+            tx: Tx(Vec::new()),
+        }
+    }
+
+    /// Written bytes.
+    pub fn written(&self) -> &[u8] {
+        &self.tx.0
+    }
+}
+
+impl ErrorType for Rx<'_> {
+    type Error = Infallible;
+}
+
+impl Read for Rx<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        let len = buf.len().min(self.0.len());
+        buf[..len].copy_from_slice(&self.0[..len]);
+        self.0 = &self.0[len..];
+        Ok(len)
+    }
+}
+
+impl Readable for Rx<'_> {
+    async fn readable(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl ErrorType for Tx {
+    type Error = Infallible;
+}
+
+impl Write for Tx {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        self.0
+            .extend_from_slice(buf)
+            .expect("too many written bytes");
+        Ok(buf.len())
+    }
+}
+
+impl ErrorType for Socket<'_> {
+    type Error = Infallible;
+}
+
+impl Read for Socket<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        self.rx.read(buf).await
+    }
+}
+
+impl Readable for Socket<'_> {
+    async fn readable(&mut self) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+impl Write for Socket<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        self.tx.write(buf).await
+    }
+}
+
+impl<'a> TcpSplit for Socket<'a> {
+    type Read<'s>
+        = &'s mut Rx<'a>
+    where
+        Self: 's;
+    type Write<'s>
+        = &'s mut Tx
+    where
+        Self: 's;
+
+    fn split(&mut self) -> (Self::Read<'_>, Self::Write<'_>) {
+        (&mut self.rx, &mut self.tx)
+    }
+}
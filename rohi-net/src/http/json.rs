@@ -0,0 +1,95 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Minimal JSON rendering for sensor data without heap allocations.
+
+use core::fmt::{self, Display, Formatter, Write};
+use rohi_hal::sensor::Measurement;
+
+/// Fixed point value in tenths, rendered as decimal number, e.g. `-1.5`.
+pub struct Tenths(pub i32);
+
+impl Display for Tenths {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let abs = self.0.unsigned_abs();
+        write!(f, "{}{}.{}", sign, abs / 10, abs % 10)
+    }
+}
+
+/// Optional value rendered as JSON `null` when absent.
+pub struct Nullable<T>(pub Option<T>);
+
+impl<T: Display> Display for Nullable<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => f.write_str("null"),
+        }
+    }
+}
+
+/// Text escaped to be placed between quotes of JSON string.
+pub struct Escaped<'a>(pub &'a str);
+
+impl Display for Escaped<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Render measurement as JSON object.
+///
+/// Particulate matter is reported in µg/m³, temperature in °C, humidity in %,
+/// pressure in Pa and noise in dBA.
+pub fn write_measurement<W: Write>(w: &mut W, m: &Measurement) -> fmt::Result {
+    write!(w, r#"{{"timestamp":{},"#, m.timestamp)?;
+    write_values(w, m)?;
+    w.write_char('}')
+}
+
+/// Render measurement taken at given Unix time in seconds as JSON object,
+/// values are the same as in [`write_measurement`], time is `null` when unknown.
+pub fn write_record<W: Write>(w: &mut W, m: &Measurement, time: Option<u64>) -> fmt::Result {
+    write!(w, r#"{{"time":{},"#, Nullable(time))?;
+    write_values(w, m)?;
+    w.write_char('}')
+}
+
+fn write_values<W: Write>(w: &mut W, m: &Measurement) -> fmt::Result {
+    write!(
+        w,
+        r#""pm10":{},"pm25":{},"temperature":{},"humidity":{},"pressure":{},"noise":{}"#,
+        Nullable(m.pm10.map(|v| Tenths(v.into()))),
+        Nullable(m.pm25.map(|v| Tenths(v.into()))),
+        Nullable(m.temperature.map(|v| Tenths(v.into()))),
+        Nullable(m.humidity.map(|v| Tenths(v.into()))),
+        Nullable(m.pressure),
+        Nullable(m.noise.map(|v| Tenths(v.into()))),
+    )
+}
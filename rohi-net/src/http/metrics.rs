@@ -0,0 +1,464 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Device metrics in [Prometheus text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/).
+//!
+//! Application updates [`Metrics`] with fresh measurements and events,
+//! [`MetricsHandler`] renders them together with device vitals on scrape.
+
+use core::cell::Cell;
+use core::fmt::{self, Debug, Display, Write as _};
+use edge_http::Method;
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::{Connection, Handler};
+use edge_nal::TcpSplit;
+use edge_nal::io::{Read, Write};
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use embassy_time::Instant;
+use heapless::String;
+use log::warn;
+use rohi_hal::sensor::Measurement;
+
+use super::api::UploadStatus;
+use super::json::Tenths;
+use crate::NETWORK_STATS;
+
+/// Content type of Prometheus text format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Size of rendered metrics buffer.
+const METRICS_BUF_SIZE: usize = 2048;
+
+#[derive(Clone, Copy, Default)]
+struct Counters {
+    sensor_errors: u32,
+    uploads: UploadStatus,
+}
+
+/// Application metrics registry.
+pub struct Metrics {
+    measurement: Mutex<CriticalSectionRawMutex, Cell<Option<Measurement>>>,
+    counters: Mutex<CriticalSectionRawMutex, Cell<Counters>>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Empty metrics registry, usually placed in `static`.
+    pub const fn new() -> Self {
+        Self {
+            measurement: Mutex::new(Cell::new(None)),
+            counters: Mutex::new(Cell::new(Counters {
+                sensor_errors: 0,
+                uploads: UploadStatus {
+                    uploads: 0,
+                    errors: 0,
+                    last_success: None,
+                    last_error: None,
+                },
+            })),
+        }
+    }
+
+    /// Set latest sensors measurement.
+    pub fn update(&self, measurement: Measurement) {
+        self.measurement.lock(|cell| cell.set(Some(measurement)));
+    }
+
+    /// Latest sensors measurement.
+    pub fn measurement(&self) -> Option<Measurement> {
+        self.measurement.lock(Cell::get)
+    }
+
+    /// Count sensor read failure.
+    pub fn sensor_error(&self) {
+        self.count(|c| c.sensor_errors += 1);
+    }
+
+    /// Count data upload result.
+    pub fn upload(&self, success: bool) {
+        if !success {
+            return self.upload_error("upload failed");
+        }
+        self.count(|c| {
+            c.uploads.uploads += 1;
+            c.uploads.last_success = Some(Instant::now().as_secs());
+            c.uploads.last_error = None;
+        });
+    }
+
+    /// Count failed upload, `reason` is reported by device status.
+    pub fn upload_error(&self, reason: &'static str) {
+        self.count(|c| {
+            c.uploads.errors += 1;
+            c.uploads.last_error = Some(reason);
+        });
+    }
+
+    /// Uploads state for device status.
+    pub fn upload_status(&self) -> UploadStatus {
+        self.counters.lock(Cell::get).uploads
+    }
+
+    fn count(&self, f: impl FnOnce(&mut Counters)) {
+        self.counters.lock(|cell| {
+            let mut counters = cell.get();
+            f(&mut counters);
+            cell.set(counters);
+        });
+    }
+
+    /// Render all metrics in Prometheus text format.
+    pub fn render<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+        self.render_with(w, &Vitals::now())
+    }
+
+    fn render_with<W: fmt::Write>(&self, w: &mut W, vitals: &Vitals) -> fmt::Result {
+        if let Some(m) = self.measurement() {
+            let tenths = |v: Option<u16>| v.map(|v| Tenths(v.into()));
+            gauge(w, "rohi_pm10_ug_m3", "PM10 concentration.", tenths(m.pm10))?;
+            gauge(w, "rohi_pm25_ug_m3", "PM2.5 concentration.", tenths(m.pm25))?;
+            gauge(
+                w,
+                "rohi_temperature_celsius",
+                "Air temperature.",
+                m.temperature.map(|v| Tenths(v.into())),
+            )?;
+            gauge(
+                w,
+                "rohi_humidity_percent",
+                "Relative humidity.",
+                tenths(m.humidity),
+            )?;
+            gauge(
+                w,
+                "rohi_pressure_pascals",
+                "Atmospheric pressure.",
+                m.pressure,
+            )?;
+            gauge(w, "rohi_noise_dba", "Ambient noise level.", tenths(m.noise))?;
+        }
+
+        gauge(
+            w,
+            "rohi_uptime_seconds",
+            "Time since boot.",
+            Some(vitals.uptime),
+        )?;
+        gauge(
+            w,
+            "rohi_heap_free_bytes",
+            "Free heap memory.",
+            Some(vitals.heap_free),
+        )?;
+        gauge(
+            w,
+            "rohi_wifi_rssi_dbm",
+            "WiFi signal strength.",
+            vitals.rssi,
+        )?;
+        gauge(
+            w,
+            "rohi_dhcp_leases",
+            "Addresses leased by DHCP server.",
+            Some(vitals.dhcp_leases),
+        )?;
+
+        let c = self.counters.lock(Cell::get);
+        counter(
+            w,
+            "rohi_wifi_reconnects_total",
+            "WiFi restarts.",
+            vitals.reconnects,
+        )?;
+        counter(
+            w,
+            "rohi_sensor_errors_total",
+            "Sensor read failures.",
+            c.sensor_errors,
+        )?;
+        counter(
+            w,
+            "rohi_uploads_total",
+            "Successful data uploads.",
+            c.uploads.uploads,
+        )?;
+        counter(
+            w,
+            "rohi_upload_errors_total",
+            "Failed data uploads.",
+            c.uploads.errors,
+        )
+    }
+}
+
+/// Device and network state rendered with application metrics.
+struct Vitals {
+    uptime: u64,
+    heap_free: usize,
+    rssi: Option<i32>,
+    dhcp_leases: u32,
+    reconnects: u32,
+}
+
+impl Vitals {
+    fn now() -> Self {
+        Self {
+            uptime: Instant::now().as_secs(),
+            heap_free: crate::chip::heap_free(),
+            rssi: NETWORK_STATS.rssi(),
+            dhcp_leases: NETWORK_STATS.dhcp_leases(),
+            reconnects: NETWORK_STATS.reconnects(),
+        }
+    }
+}
+
+fn gauge<W: fmt::Write, V: Display>(
+    w: &mut W,
+    name: &str,
+    help: &str,
+    value: Option<V>,
+) -> fmt::Result {
+    match value {
+        Some(value) => metric(w, name, help, "gauge", value),
+        None => Ok(()),
+    }
+}
+
+fn counter<W: fmt::Write>(w: &mut W, name: &str, help: &str, value: u32) -> fmt::Result {
+    metric(w, name, help, "counter", value)
+}
+
+fn metric<W: fmt::Write, V: Display>(
+    w: &mut W,
+    name: &str,
+    help: &str,
+    kind: &str,
+    value: V,
+) -> fmt::Result {
+    write!(
+        w,
+        "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+    )
+}
+
+/// HTTP handler exposing [`Metrics`] for Prometheus scrapes.
+pub struct MetricsHandler<'a> {
+    metrics: &'a Metrics,
+}
+
+impl<'a> MetricsHandler<'a> {
+    /// Create handler rendering given metrics registry.
+    pub const fn new(metrics: &'a Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+impl Handler for MetricsHandler<'_> {
+    type Error<E>
+        = HttpError<E>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        _task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        if connection.headers()?.method != Method::Get {
+            return connection
+                .initiate_response(405, Some("Method Not Allowed"), &[("Allow", "GET")])
+                .await;
+        }
+
+        respond::<METRICS_BUF_SIZE, _, N>(connection, self.metrics).await
+    }
+}
+
+/// Respond with metrics rendered into buffer of `SIZE` bytes.
+async fn respond<const SIZE: usize, T, const N: usize>(
+    connection: &mut Connection<'_, T, N>,
+    metrics: &Metrics,
+) -> Result<(), HttpError<T::Error>>
+where
+    T: Read + Write,
+{
+    let mut body: String<SIZE> = String::new();
+    if metrics.render(&mut body).is_err() {
+        // Truncated text would be scraped as valid series.
+        warn!("[HTTP] > Metrics don't fit into buffer");
+        return connection
+            .initiate_response(
+                500,
+                Some("Internal Server Error"),
+                &[("Content-Length", "0")],
+            )
+            .await;
+    }
+    let mut length: String<10> = String::new();
+    _ = write!(length, "{}", body.len());
+
+    connection
+        .initiate_response(
+            200,
+            Some("OK"),
+            &[("Content-Type", CONTENT_TYPE), ("Content-Length", &length)],
+        )
+        .await?;
+    connection.write_all(body.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::fake::Socket;
+    use embassy_futures::block_on;
+
+    const VITALS: Vitals = Vitals {
+        uptime: 3600,
+        heap_free: 51200,
+        rssi: Some(-67),
+        dhcp_leases: 2,
+        reconnects: 1,
+    };
+
+    const EXPOSITION: &str = "\
+# HELP rohi_pm10_ug_m3 PM10 concentration.
+# TYPE rohi_pm10_ug_m3 gauge
+rohi_pm10_ug_m3 12.3
+# HELP rohi_pm25_ug_m3 PM2.5 concentration.
+# TYPE rohi_pm25_ug_m3 gauge
+rohi_pm25_ug_m3 4.5
+# HELP rohi_temperature_celsius Air temperature.
+# TYPE rohi_temperature_celsius gauge
+rohi_temperature_celsius -3.5
+# HELP rohi_pressure_pascals Atmospheric pressure.
+# TYPE rohi_pressure_pascals gauge
+rohi_pressure_pascals 100934
+# HELP rohi_noise_dba Ambient noise level.
+# TYPE rohi_noise_dba gauge
+rohi_noise_dba 52.7
+# HELP rohi_uptime_seconds Time since boot.
+# TYPE rohi_uptime_seconds gauge
+rohi_uptime_seconds 3600
+# HELP rohi_heap_free_bytes Free heap memory.
+# TYPE rohi_heap_free_bytes gauge
+rohi_heap_free_bytes 51200
+# HELP rohi_wifi_rssi_dbm WiFi signal strength.
+# TYPE rohi_wifi_rssi_dbm gauge
+rohi_wifi_rssi_dbm -67
+# HELP rohi_dhcp_leases Addresses leased by DHCP server.
+# TYPE rohi_dhcp_leases gauge
+rohi_dhcp_leases 2
+# HELP rohi_wifi_reconnects_total WiFi restarts.
+# TYPE rohi_wifi_reconnects_total counter
+rohi_wifi_reconnects_total 1
+# HELP rohi_sensor_errors_total Sensor read failures.
+# TYPE rohi_sensor_errors_total counter
+rohi_sensor_errors_total 2
+# HELP rohi_uploads_total Successful data uploads.
+# TYPE rohi_uploads_total counter
+rohi_uploads_total 1
+# HELP rohi_upload_errors_total Failed data uploads.
+# TYPE rohi_upload_errors_total counter
+rohi_upload_errors_total 1
+";
+
+    fn metrics() -> Metrics {
+        let metrics = Metrics::new();
+        metrics.update(Measurement {
+            pm10: Some(123),
+            pm25: Some(45),
+            temperature: Some(-35),
+            pressure: Some(100_934),
+            noise: Some(527),
+            ..Measurement::new(60_000)
+        });
+        metrics.sensor_error();
+        metrics.sensor_error();
+        metrics.upload(true);
+        metrics.upload_error("no network");
+        metrics
+    }
+
+    /// Response to scrape with metrics rendered into buffer of `SIZE` bytes.
+    fn scrape<const SIZE: usize>(metrics: &Metrics) -> heapless::Vec<u8, 4096> {
+        let mut socket = Socket::new(b"GET /metrics HTTP/1.1\r\nHost: rohi\r\n\r\n");
+        let mut buf = [0u8; 512];
+        block_on(async {
+            let mut connection = Connection::<_, 8>::new(&mut buf, &mut socket)
+                .await
+                .unwrap();
+            respond::<SIZE, _, 8>(&mut connection, metrics)
+                .await
+                .unwrap();
+            connection.complete().await.unwrap();
+        });
+        socket.tx.0
+    }
+
+    #[test]
+    fn exposition() {
+        let mut text: String<METRICS_BUF_SIZE> = String::new();
+        metrics().render_with(&mut text, &VITALS).unwrap();
+        assert_eq!(text, EXPOSITION);
+        assert_eq!(metrics().upload_status().last_error, Some("no network"));
+    }
+
+    #[test]
+    fn without_measurement() {
+        let mut text: String<METRICS_BUF_SIZE> = String::new();
+        let vitals = Vitals {
+            rssi: None,
+            ..VITALS
+        };
+        Metrics::new().render_with(&mut text, &vitals).unwrap();
+        assert!(text.starts_with("# HELP rohi_uptime_seconds "));
+        assert!(!text.contains("rohi_wifi_rssi_dbm"));
+        assert!(text.ends_with("\nrohi_upload_errors_total 0\n"));
+    }
+
+    #[test]
+    fn scrape_response() {
+        let response = scrape::<METRICS_BUF_SIZE>(&metrics());
+        let response = core::str::from_utf8(&response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains("\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8"));
+        let mut length: String<32> = String::new();
+        write!(length, "Content-Length: {}", body.len()).unwrap();
+        assert!(head.contains(length.as_str()));
+        assert!(body.starts_with("# HELP rohi_pm10_ug_m3 "));
+    }
+
+    #[test]
+    fn overflow_is_failed() {
+        // Truncated exposition isn't sent.
+        let response = scrape::<256>(&metrics());
+        let response = core::str::from_utf8(&response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+        assert!(response.contains("\r\nContent-Length: 0\r\n"));
+        assert!(response.ends_with("\r\n\r\n"));
+    }
+}
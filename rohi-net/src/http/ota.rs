@@ -0,0 +1,317 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Over-the-air firmware update endpoint.
+//!
+//! | Method | Path         | Description                                        |
+//! |--------|--------------|----------------------------------------------------|
+//! | GET    | `/ota`       | State of running image                             |
+//! | POST   | `/ota`       | Upload image as request body (push mode)           |
+//! | POST   | `/ota/pull`  | Download image from `{"url":..,"signature":..}`    |
+//!
+//! Uploaded image should be accompanied with `X-Image-Signature` header containing
+//! hex encoded [`ImageSignature`] produced by `rohi-sign`. Device restarts into new
+//! image after successful update, see [`rohi_hal::ota`] for the rollback procedure.
+//! Updates are refused when firmware is built without [`UpdatePolicy`] and
+//! require [device password](super::auth). Only one update runs at a time, other
+//! requests are answered with `409 Conflict` meanwhile.
+//!
+//! ```sh
+//! curl -X POST --data-binary @firmware.bin \
+//!     -H "Authorization: Bearer $PASSWORD" \
+//!     -H "X-Image-Signature: $(xxd -p firmware.bin.sig | tr -d '\n')" \
+//!     http://192.168.42.1/ota
+//! ```
+
+use core::fmt::{Debug, Display, Write as _};
+use edge_http::Method;
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::{Connection, Handler};
+use edge_nal::TcpSplit;
+use edge_nal::io::{Read, Write};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::mutex::Mutex;
+use embassy_time::Timer;
+use heapless::String;
+use log::{info, warn};
+use rohi_hal::flash::SharedFlash;
+use rohi_hal::ota::{ImageSignature, Ota, OtaError, SIGNATURE_LEN, UpdatePolicy};
+use serde::Deserialize;
+
+use super::api::{read_body, respond_error, respond_json};
+use super::auth::{Authorize, rejection};
+use super::client::{Chunk, DownloadError, HttpClient};
+use super::route::path_only;
+
+/// Header with hex encoded signature of uploaded image.
+pub const SIGNATURE_HEADER: &str = "X-Image-Signature";
+
+const UPDATES_DISABLED: &str = "firmware updates disabled";
+
+/// Size of chunks image is streamed by.
+const CHUNK_SIZE: usize = 1024;
+
+/// Body of `POST /ota/pull` request.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PullRequest<'a> {
+    /// Image location, only `http://` is supported.
+    pub url: &'a str,
+    /// Hex encoded image signature.
+    pub signature: &'a str,
+}
+
+/// Image download errors.
+pub type PullError = DownloadError<OtaError>;
+
+/// Parse hex encoded image signature.
+pub fn parse_signature(hex: &str) -> Option<ImageSignature> {
+    let hex = hex.trim().as_bytes();
+    if hex.len() != SIGNATURE_LEN * 2 {
+        return None;
+    }
+    let mut bytes = [0u8; SIGNATURE_LEN];
+    for (byte, pair) in bytes.iter_mut().zip(hex.chunks(2)) {
+        let pair = core::str::from_utf8(pair).ok()?;
+        *byte = u8::from_str_radix(pair, 16).ok()?;
+    }
+    Some(ImageSignature::from_bytes(&bytes))
+}
+
+/// Download image from URL into inactive slot and select it for the next boot.
+///
+/// Flash is locked for every chunk only, so other tasks aren't blocked by slow download.
+pub async fn pull(
+    client: &HttpClient,
+    flash: &SharedFlash,
+    url: &str,
+    signature: &ImageSignature,
+    policy: &UpdatePolicy,
+) -> Result<(), PullError> {
+    info!("[OTA] > Downloading image from {}", url);
+    let mut writer = None;
+    client
+        .download(url, &[], async |chunk| match chunk {
+            Chunk::Start(len) => {
+                // Image size is required to check it fits into slot.
+                let len = len.ok_or(OtaError::SizeMismatch)? as usize;
+                if writer.is_some() {
+                    return Err(OtaError::SizeMismatch);
+                }
+                writer = Some(Ota::new(&mut *flash.lock().await).begin(len)?);
+                Ok(())
+            }
+            Chunk::Data(data) => writer
+                .as_mut()
+                .ok_or(OtaError::SizeMismatch)?
+                .write(&mut *flash.lock().await, data),
+        })
+        .await?;
+    let writer = writer.ok_or(DownloadError::Sink(OtaError::SizeMismatch))?;
+    writer
+        .finish(&mut *flash.lock().await, signature, policy)
+        .map_err(DownloadError::Sink)
+}
+
+/// HTTP status code reported for update error.
+fn error_status(e: &OtaError) -> (u16, &'static str) {
+    match e {
+        OtaError::TooLarge => (413, "image too large"),
+        OtaError::SizeMismatch => (422, "image size mismatch"),
+        OtaError::InvalidImage => (422, "invalid image"),
+        OtaError::BadSignature => (403, "untrusted image signature"),
+        OtaError::Downgrade => (403, "security version downgrade"),
+        OtaError::Partition(_) => (500, "no OTA partitions"),
+        OtaError::Flash(_) => (500, "flash failure"),
+    }
+}
+
+/// HTTP handler of firmware updates, usually routed on `/ota` prefix.
+pub struct OtaHandler<'a, A> {
+    flash: &'a SharedFlash,
+    client: HttpClient,
+    policy: Option<UpdatePolicy>,
+    auth: &'a A,
+    /// Held while image is written, concurrent updates would interleave in one slot.
+    updating: Mutex<CriticalSectionRawMutex, ()>,
+}
+
+impl<'a, A: Authorize> OtaHandler<'a, A> {
+    /// Create handler writing images into given flash, `client` is used in pull mode
+    /// and updates are allowed to clients accepted by `auth`.
+    ///
+    /// Without `policy` all updates are refused.
+    pub const fn new(
+        flash: &'a SharedFlash,
+        client: HttpClient,
+        policy: Option<UpdatePolicy>,
+        auth: &'a A,
+    ) -> Self {
+        Self {
+            flash,
+            client,
+            policy,
+            auth,
+            updating: Mutex::new(()),
+        }
+    }
+
+    /// Stream request body into inactive slot.
+    async fn push<T, const N: usize>(
+        &self,
+        connection: &mut Connection<'_, T, N>,
+        size: usize,
+        signature: &ImageSignature,
+        policy: &UpdatePolicy,
+    ) -> Result<Result<(), OtaError>, HttpError<T::Error>>
+    where
+        T: Read + Write,
+    {
+        info!("[OTA] > Receiving {} bytes image", size);
+        let mut writer = match Ota::new(&mut *self.flash.lock().await).begin(size) {
+            Ok(writer) => writer,
+            Err(e) => return Ok(Err(e)),
+        };
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            match connection.read(&mut chunk).await? {
+                0 => break,
+                n => {
+                    // Flash is locked per chunk, not while waiting for client.
+                    let mut flash = self.flash.lock().await;
+                    if let Err(e) = writer.write(&mut *flash, &chunk[..n]) {
+                        return Ok(Err(e));
+                    }
+                }
+            }
+        }
+        Ok(writer.finish(&mut *self.flash.lock().await, signature, policy))
+    }
+}
+
+impl<A: Authorize> Handler for OtaHandler<'_, A> {
+    type Error<E>
+        = HttpError<E>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        _task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        let headers = connection.headers()?;
+        let mut buf = [0u8; CHUNK_SIZE];
+
+        let path = path_only(headers.path);
+        if headers.method == Method::Post
+            && matches!(path, "/ota" | "/ota/pull")
+            && let Some((status, error)) = rejection(self.auth, connection).await?
+        {
+            warn!("[OTA] > Unauthorized request to {}", path);
+            return respond_error(connection, status, error, &mut buf).await;
+        }
+        let _updating = match headers.method {
+            Method::Post if matches!(path, "/ota" | "/ota/pull") => {
+                let Ok(updating) = self.updating.try_lock() else {
+                    warn!("[OTA] > Update is already in progress");
+                    return respond_error(connection, 409, "update in progress", &mut buf).await;
+                };
+                Some(updating)
+            }
+            _ => None,
+        };
+
+        let result = match (headers.method, path) {
+            (Method::Get, "/ota") => {
+                let state = Ota::new(&mut *self.flash.lock().await).state();
+                let mut body: String<64> = String::new();
+                _ = match state {
+                    Ok(Some(state)) => write!(body, r#"{{"state":"{:?}"}}"#, state),
+                    Ok(None) => write!(body, r#"{{"state":null}}"#),
+                    Err(e) => {
+                        let (status, error) = error_status(&e);
+                        return respond_error(connection, status, error, &mut buf).await;
+                    }
+                };
+                return respond_json(connection, 200, body.as_bytes()).await;
+            }
+            (Method::Post, "/ota") => {
+                let Some(size) = headers.headers.content_len() else {
+                    return respond_error(connection, 411, "length required", &mut buf).await;
+                };
+                let Some(signature) = headers
+                    .headers
+                    .get(SIGNATURE_HEADER)
+                    .and_then(parse_signature)
+                else {
+                    return respond_error(connection, 400, "image signature required", &mut buf)
+                        .await;
+                };
+                let Some(policy) = &self.policy else {
+                    return respond_error(connection, 403, UPDATES_DISABLED, &mut buf).await;
+                };
+                self.push(connection, size as usize, &signature, policy)
+                    .await?
+            }
+            (Method::Post, "/ota/pull") => {
+                let Some(len) = read_body(connection, &mut buf).await? else {
+                    return respond_error(connection, 413, "request too large", &mut buf).await;
+                };
+                let Ok((request, _)) = serde_json_core::from_slice::<PullRequest>(&buf[..len])
+                else {
+                    return respond_error(connection, 400, "malformed request", &mut buf).await;
+                };
+                let Some(signature) = parse_signature(request.signature) else {
+                    return respond_error(connection, 400, "malformed signature", &mut buf).await;
+                };
+                let Some(policy) = &self.policy else {
+                    return respond_error(connection, 403, UPDATES_DISABLED, &mut buf).await;
+                };
+                match pull(&self.client, self.flash, request.url, &signature, policy).await {
+                    Ok(()) => Ok(()),
+                    Err(DownloadError::Sink(e)) => Err(e),
+                    Err(DownloadError::Client(e)) => {
+                        warn!("[OTA] > Download failed: {:?}", e);
+                        return respond_error(connection, 502, "download failed", &mut buf).await;
+                    }
+                }
+            }
+            (_, "/ota") | (_, "/ota/pull") => {
+                return respond_error(connection, 405, "method not allowed", &mut buf).await;
+            }
+            _ => return respond_error(connection, 404, "not found", &mut buf).await,
+        };
+
+        match result {
+            Ok(()) => {
+                respond_json(connection, 200, b"{}").await?;
+                connection.complete().await?;
+                info!("[OTA] > Restarting into new image");
+                Timer::after_millis(500).await;
+                crate::chip::software_reset()
+            }
+            Err(e) => {
+                warn!("[OTA] > Update failed: {:?}", e);
+                let (status, error) = error_status(&e);
+                respond_error(connection, status, error, &mut buf).await
+            }
+        }
+    }
+}
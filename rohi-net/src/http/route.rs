@@ -0,0 +1,125 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Request routing between several handlers served by single [`HttpServer`](super::HttpServer).
+
+use core::fmt::{Debug, Display};
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::{Connection, Handler};
+use edge_nal::TcpSplit;
+use edge_nal::io::{Read, Write};
+
+/// Check that request path is equal to prefix or nested under it.
+/// Query string is not taken into account.
+pub fn path_matches(path: &str, prefix: &str) -> bool {
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['/', '?']) || prefix.ends_with('/'),
+        None => false,
+    }
+}
+
+/// Request path without query string.
+pub fn path_only(path: &str) -> &str {
+    path.split_once('?').map_or(path, |(path, _)| path)
+}
+
+/// Handler responding `404 Not Found` to any request, terminates chain of routes.
+pub struct NotFound;
+
+impl Handler for NotFound {
+    type Error<E>
+        = HttpError<E>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        _task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        connection
+            .initiate_response(404, Some("Not Found"), &[])
+            .await
+    }
+}
+
+/// Error of handler in chain of routes.
+#[derive(Debug)]
+pub enum RouteError<A, B> {
+    /// Error of route handler.
+    Handler(A),
+    /// Error of next routes in chain.
+    Rest(B),
+}
+
+/// Dispatch requests with path under `prefix` to `handler`, other requests to `rest`.
+///
+/// Routes are chained to serve several handlers by one server:
+///
+/// ```ignore
+/// let handler = Route::new("/ws", ws, Route::new("/metrics", metrics, NotFound));
+/// ```
+pub struct Route<'a, H, R> {
+    prefix: &'a str,
+    handler: H,
+    rest: R,
+}
+
+impl<'a, H, R> Route<'a, H, R> {
+    /// New route for given path prefix.
+    pub const fn new(prefix: &'a str, handler: H, rest: R) -> Self {
+        Self {
+            prefix,
+            handler,
+            rest,
+        }
+    }
+}
+
+impl<H: Handler, R: Handler> Handler for Route<'_, H, R> {
+    type Error<E>
+        = RouteError<H::Error<E>, R::Error<E>>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        let matched = connection
+            .headers()
+            .is_ok_and(|headers| path_matches(headers.path, self.prefix));
+        if matched {
+            self.handler
+                .handle(task_id, connection)
+                .await
+                .map_err(RouteError::Handler)
+        } else {
+            self.rest
+                .handle(task_id, connection)
+                .await
+                .map_err(RouteError::Rest)
+        }
+    }
+}
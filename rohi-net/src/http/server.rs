@@ -0,0 +1,83 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! HTTP server running on top of embassy network stack.
+
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use edge_http::io::server::{Handler, Server};
+use edge_nal::TcpBind;
+use edge_nal_embassy::{Tcp, TcpBuffers};
+use embassy_net::Stack;
+use embassy_time::Timer;
+use log::{info, warn};
+
+/// Count of HTTP connections served concurrently.
+pub const HTTP_TASKS: usize = 4;
+
+/// Size of request buffer for each connection.
+pub const HTTP_BUF_SIZE: usize = 2048;
+
+/// Maximal count of headers in request.
+pub const HTTP_MAX_HEADERS: usize = 16;
+
+/// Idle keep-alive connections are closed after this timeout.
+const KEEPALIVE_TIMEOUT_MS: u32 = 30_000;
+
+type ServerBuffers = Server<HTTP_TASKS, HTTP_BUF_SIZE, HTTP_MAX_HEADERS>;
+type SocketBuffers = TcpBuffers<HTTP_TASKS, 1024, 1024>;
+
+/// HTTP server instance.
+///
+/// Server buffers are statically allocated, so only one instance could be created.
+pub struct HttpServer {
+    stack: Stack<'static>,
+    port: u16,
+    server: &'static mut ServerBuffers,
+    buffers: &'static SocketBuffers,
+}
+
+impl HttpServer {
+    /// New HTTP server listening given port on network stack.
+    pub fn new(stack: Stack<'static>, port: u16) -> Self {
+        Self {
+            stack,
+            port,
+            server: mk_static!(ServerBuffers, ServerBuffers::new()),
+            buffers: mk_static!(SocketBuffers, SocketBuffers::new()),
+        }
+    }
+
+    /// Serve incoming requests with given handler, never returns.
+    pub async fn run<H: Handler>(self, handler: H) -> ! {
+        info!("[HTTP] > Server started on port {}", self.port);
+        let tcp = Tcp::new(self.stack, self.buffers);
+        let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, self.port));
+        loop {
+            match tcp.bind(addr).await {
+                Ok(acceptor) => {
+                    _ = self
+                        .server
+                        .run(Some(KEEPALIVE_TIMEOUT_MS), acceptor, &handler)
+                        .await
+                        .inspect_err(|e| warn!("[HTTP] > Server error: {:?}", e));
+                }
+                Err(e) => warn!("[HTTP] > Unable to bind socket: {:?}", e),
+            }
+            Timer::after_secs(3).await;
+        }
+    }
+}
@@ -0,0 +1,683 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! WebSocket protocol (RFC 6455) support.
+//!
+//! Upgrade handshake is done by edge-http, this module provides frame encoding
+//! and decoding, control frames handling and [`WsReadingsHandler`] which streams
+//! sensor measurements as JSON text frames to every connected client.
+//!
+//! Client side connections are opened by [`HttpClient::websocket`](super::HttpClient::websocket),
+//! client frames are sent with [`send_masked_frame`] and received with [`recv_message`].
+
+use core::cell::Cell;
+use core::fmt::{Debug, Display};
+use edge_http::io::Error as HttpError;
+use edge_http::io::server::{Connection, Handler};
+use edge_http::ws::MAX_BASE64_KEY_RESPONSE_LEN;
+use edge_nal::TcpSplit;
+use edge_nal::io::{Read, ReadExactError, Write};
+use embassy_futures::select::{Either, select};
+use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, NoopRawMutex};
+use embassy_sync::mutex::Mutex;
+use embassy_sync::pubsub::{DynSubscriber, PubSubChannel};
+use embassy_time::{Duration, Instant, with_deadline};
+use heapless::String;
+use log::{info, warn};
+use rohi_hal::sensor::Measurement;
+
+use crate::chip::Rng;
+
+use super::json;
+use super::server::HTTP_TASKS;
+
+/// Maximal payload length of control frames.
+pub const MAX_CONTROL_PAYLOAD_LEN: usize = 125;
+
+/// Count of WebSocket clients served at the same time.
+/// One HTTP task is always kept free for plain requests.
+pub const WS_MAX_CLIENTS: usize = HTTP_TASKS - 1;
+
+/// Server pings clients with this interval, client which doesn't answer
+/// until the next ping is disconnected.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Normal closure status code.
+pub const CLOSE_NORMAL: u16 = 1000;
+/// Protocol error status code.
+pub const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+
+/// Broadcast channel for sensor readings consumed by [`WsReadingsHandler`].
+///
+/// Measurements should be published with `immediate_publisher()`, slow clients
+/// just miss outdated readings.
+pub type ReadingsChannel =
+    PubSubChannel<CriticalSectionRawMutex, Measurement, 2, WS_MAX_CLIENTS, 1>;
+
+/// WebSocket frame kind.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameType {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl FrameType {
+    fn from_opcode(opcode: u8) -> Option<Self> {
+        match opcode {
+            0x0 => Some(Self::Continuation),
+            0x1 => Some(Self::Text),
+            0x2 => Some(Self::Binary),
+            0x8 => Some(Self::Close),
+            0x9 => Some(Self::Ping),
+            0xA => Some(Self::Pong),
+            _ => None,
+        }
+    }
+
+    fn opcode(self) -> u8 {
+        match self {
+            Self::Continuation => 0x0,
+            Self::Text => 0x1,
+            Self::Binary => 0x2,
+            Self::Close => 0x8,
+            Self::Ping => 0x9,
+            Self::Pong => 0xA,
+        }
+    }
+
+    /// Control frames are used to manage connection: close, ping and pong.
+    pub fn is_control(self) -> bool {
+        matches!(self, Self::Close | Self::Ping | Self::Pong)
+    }
+}
+
+/// WebSocket connection errors.
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Connection closed in the middle of frame.
+    Incomplete,
+    /// Malformed frame received.
+    Invalid,
+    /// Frame payload does not fit into buffer.
+    BufferOverflow,
+    /// Peer doesn't answer ping.
+    Timeout,
+    /// HTTP handshake failure.
+    Http(HttpError<E>),
+    /// Socket I/O error.
+    Io(E),
+}
+
+impl<E> From<ReadExactError<E>> for Error<E> {
+    fn from(e: ReadExactError<E>) -> Self {
+        match e {
+            ReadExactError::UnexpectedEof => Self::Incomplete,
+            ReadExactError::Other(e) => Self::Io(e),
+        }
+    }
+}
+
+impl<E> From<HttpError<E>> for Error<E> {
+    fn from(e: HttpError<E>) -> Self {
+        Self::Http(e)
+    }
+}
+
+/// WebSocket frame header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameHeader {
+    pub frame_type: FrameType,
+    /// Final fragment of message.
+    pub fin: bool,
+    pub payload_len: u64,
+    /// Masking key, mandatory for frames sent by client.
+    pub mask_key: Option<[u8; 4]>,
+}
+
+impl FrameHeader {
+    /// Maximal length of serialized header.
+    pub const MAX_LEN: usize = 14;
+
+    /// Header of single unmasked frame with given payload length, as sent by server.
+    pub fn new(frame_type: FrameType, payload_len: usize) -> Self {
+        Self {
+            frame_type,
+            fin: true,
+            payload_len: payload_len as u64,
+            mask_key: None,
+        }
+    }
+
+    /// Read frame header from socket.
+    pub async fn recv<R: Read>(read: &mut R) -> Result<Self, Error<R::Error>> {
+        let mut head = [0u8; 2];
+        read.read_exact(&mut head).await?;
+
+        // Extensions are not negotiated, so reserved bits must be zero.
+        if head[0] & 0x70 != 0 {
+            return Err(Error::Invalid);
+        }
+        let fin = head[0] & 0x80 != 0;
+        let frame_type = FrameType::from_opcode(head[0] & 0x0F).ok_or(Error::Invalid)?;
+
+        let payload_len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0u8; 2];
+                read.read_exact(&mut len).await?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                read.read_exact(&mut len).await?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        if frame_type.is_control() && (!fin || payload_len > MAX_CONTROL_PAYLOAD_LEN as u64) {
+            return Err(Error::Invalid);
+        }
+
+        let mask_key = if head[1] & 0x80 != 0 {
+            let mut key = [0u8; 4];
+            read.read_exact(&mut key).await?;
+            Some(key)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            frame_type,
+            fin,
+            payload_len,
+            mask_key,
+        })
+    }
+
+    /// Serialize header into buffer, returns count of bytes written.
+    pub fn serialize(&self, buf: &mut [u8; Self::MAX_LEN]) -> usize {
+        buf[0] = if self.fin { 0x80 } else { 0 } | self.frame_type.opcode();
+        let mask_bit = if self.mask_key.is_some() { 0x80 } else { 0 };
+        let mut len = 2;
+        if self.payload_len < 126 {
+            buf[1] = mask_bit | self.payload_len as u8;
+        } else if self.payload_len <= u16::MAX as u64 {
+            buf[1] = mask_bit | 126;
+            buf[2..4].copy_from_slice(&(self.payload_len as u16).to_be_bytes());
+            len += 2;
+        } else {
+            buf[1] = mask_bit | 127;
+            buf[2..10].copy_from_slice(&self.payload_len.to_be_bytes());
+            len += 8;
+        }
+        if let Some(key) = self.mask_key {
+            buf[len..len + 4].copy_from_slice(&key);
+            len += 4;
+        }
+        len
+    }
+
+    /// Write frame header into socket.
+    pub async fn send<W: Write>(&self, write: &mut W) -> Result<(), Error<W::Error>> {
+        let mut buf = [0u8; Self::MAX_LEN];
+        let len = self.serialize(&mut buf);
+        write.write_all(&buf[..len]).await.map_err(Error::Io)
+    }
+
+    /// Read frame payload into buffer and unmask it when needed.
+    pub async fn recv_payload<'a, R: Read>(
+        &self,
+        read: &mut R,
+        buf: &'a mut [u8],
+    ) -> Result<&'a [u8], Error<R::Error>> {
+        let len = usize::try_from(self.payload_len)
+            .ok()
+            .filter(|len| *len <= buf.len())
+            .ok_or(Error::BufferOverflow)?;
+        let payload = &mut buf[..len];
+        read.read_exact(payload).await?;
+        if let Some(key) = self.mask_key {
+            apply_mask(payload, key);
+        }
+        Ok(payload)
+    }
+
+    /// Read frame payload and drop it.
+    pub async fn skip_payload<R: Read>(&self, read: &mut R) -> Result<(), Error<R::Error>> {
+        let mut buf = [0u8; 64];
+        let mut left = self.payload_len;
+        while left > 0 {
+            let chunk = left.min(buf.len() as u64) as usize;
+            read.read_exact(&mut buf[..chunk]).await?;
+            left -= chunk as u64;
+        }
+        Ok(())
+    }
+}
+
+/// Mask or unmask payload in place, the operation is symmetric.
+pub fn apply_mask(payload: &mut [u8], key: [u8; 4]) {
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= key[i % 4];
+    }
+}
+
+/// Send single unmasked frame with given payload.
+pub async fn send_frame<W: Write>(
+    write: &mut W,
+    frame_type: FrameType,
+    payload: &[u8],
+) -> Result<(), Error<W::Error>> {
+    FrameHeader::new(frame_type, payload.len())
+        .send(write)
+        .await?;
+    write.write_all(payload).await.map_err(Error::Io)?;
+    write.flush().await.map_err(Error::Io)
+}
+
+/// Send close frame with given status code.
+pub async fn send_close<W: Write>(write: &mut W, code: u16) -> Result<(), Error<W::Error>> {
+    send_frame(write, FrameType::Close, &code.to_be_bytes()).await
+}
+
+/// Send single masked frame with given payload, as client does.
+pub async fn send_masked_frame<W: Write>(
+    write: &mut W,
+    frame_type: FrameType,
+    payload: &[u8],
+) -> Result<(), Error<W::Error>> {
+    let key = Rng::new().random().to_ne_bytes();
+    let header = FrameHeader {
+        mask_key: Some(key),
+        ..FrameHeader::new(frame_type, payload.len())
+    };
+    header.send(write).await?;
+
+    // Chunk length is multiple of 4, so every chunk starts with first byte of key.
+    let mut buf = [0u8; 64];
+    for chunk in payload.chunks(buf.len()) {
+        let masked = &mut buf[..chunk.len()];
+        masked.copy_from_slice(chunk);
+        apply_mask(masked, key);
+        write.write_all(masked).await.map_err(Error::Io)?;
+    }
+    write.flush().await.map_err(Error::Io)
+}
+
+/// Receive next data message as client, pings are answered on the way.
+///
+/// Returns `None` when server closed connection. Fragmented messages are not supported.
+pub async fn recv_message<'a, S: Read + Write>(
+    socket: &mut S,
+    buf: &'a mut [u8],
+) -> Result<Option<&'a [u8]>, Error<S::Error>> {
+    loop {
+        let header = FrameHeader::recv(socket).await?;
+        match header.frame_type {
+            FrameType::Text | FrameType::Binary if header.fin => {
+                let len = header.recv_payload(socket, buf).await?.len();
+                return Ok(Some(&buf[..len]));
+            }
+            FrameType::Ping => {
+                let mut ping = [0u8; MAX_CONTROL_PAYLOAD_LEN];
+                let payload = header.recv_payload(socket, &mut ping).await?;
+                send_masked_frame(socket, FrameType::Pong, payload).await?;
+            }
+            FrameType::Close => {
+                header.skip_payload(socket).await?;
+                send_masked_frame(socket, FrameType::Close, &CLOSE_NORMAL.to_be_bytes()).await?;
+                return Ok(None);
+            }
+            FrameType::Pong => header.skip_payload(socket).await?,
+            _ => return Err(Error::Invalid),
+        }
+    }
+}
+
+/// HTTP handler streaming sensor readings to WebSocket clients.
+///
+/// Every measurement published into [`ReadingsChannel`] is sent to all clients
+/// as JSON text frame, see [`json::write_measurement`] for format.
+pub struct WsReadingsHandler<'a> {
+    channel: &'a ReadingsChannel,
+}
+
+impl<'a> WsReadingsHandler<'a> {
+    /// Create handler streaming readings of given channel.
+    pub const fn new(channel: &'a ReadingsChannel) -> Self {
+        Self { channel }
+    }
+}
+
+impl Handler for WsReadingsHandler<'_> {
+    type Error<E>
+        = Error<E>
+    where
+        E: Debug;
+
+    async fn handle<T, const N: usize>(
+        &self,
+        task_id: impl Display + Copy,
+        connection: &mut Connection<'_, T, N>,
+    ) -> Result<(), Self::Error<T::Error>>
+    where
+        T: Read + Write + TcpSplit,
+    {
+        if !connection.is_ws_upgrade_request()? {
+            connection
+                .initiate_response(400, Some("Bad Request"), &[])
+                .await?;
+            return Ok(());
+        }
+        let Ok(subscriber) = self.channel.dyn_subscriber() else {
+            warn!("[WS] > Too many clients, connection refused");
+            connection
+                .initiate_response(503, Some("Service Unavailable"), &[])
+                .await?;
+            return Ok(());
+        };
+
+        let mut key_buf = [0u8; MAX_BASE64_KEY_RESPONSE_LEN];
+        connection
+            .initiate_ws_upgrade_response(&mut key_buf)
+            .await?;
+        connection.complete().await?;
+        info!("[WS] > Task {}: client connected", task_id);
+
+        let (mut rx, tx) = connection.unbind()?.split();
+        let tx = Mutex::<NoopRawMutex, _>::new(tx);
+        let awaiting_pong = Cell::new(false);
+        let result = match select(
+            receive_loop(&mut rx, &tx, &awaiting_pong),
+            send_loop(subscriber, &tx, &awaiting_pong),
+        )
+        .await
+        {
+            Either::First(result) | Either::Second(result) => result,
+        };
+        if let Err(Error::Timeout) = result {
+            warn!("[WS] > Task {}: client doesn't answer ping", task_id);
+        }
+        info!("[WS] > Task {}: client disconnected", task_id);
+        result
+    }
+}
+
+/// Handle control frames of client until connection closed.
+async fn receive_loop<R, W>(
+    rx: &mut R,
+    tx: &Mutex<NoopRawMutex, W>,
+    awaiting_pong: &Cell<bool>,
+) -> Result<(), Error<R::Error>>
+where
+    R: Read,
+    W: Write<Error = R::Error>,
+{
+    let mut buf = [0u8; MAX_CONTROL_PAYLOAD_LEN];
+    loop {
+        let header = FrameHeader::recv(rx).await?;
+        if header.mask_key.is_none() {
+            send_close(&mut *tx.lock().await, CLOSE_PROTOCOL_ERROR).await?;
+            return Err(Error::Invalid);
+        }
+        if !header.frame_type.is_control() {
+            // Stream is one-way, data frames from client are ignored.
+            header.skip_payload(rx).await?;
+            continue;
+        }
+
+        let payload = header.recv_payload(rx, &mut buf).await?;
+        match header.frame_type {
+            FrameType::Ping => send_frame(&mut *tx.lock().await, FrameType::Pong, payload).await?,
+            FrameType::Pong => awaiting_pong.set(false),
+            FrameType::Close => {
+                // Echo status code back to complete closing handshake.
+                let code = match payload {
+                    [hi, lo, ..] => u16::from_be_bytes([*hi, *lo]),
+                    _ => CLOSE_NORMAL,
+                };
+                return send_close(&mut *tx.lock().await, code).await;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Send published readings to client and ping it periodically, fails when
+/// the previous ping isn't answered.
+async fn send_loop<W: Write>(
+    mut subscriber: DynSubscriber<'_, Measurement>,
+    tx: &Mutex<NoopRawMutex, W>,
+    awaiting_pong: &Cell<bool>,
+) -> Result<(), Error<W::Error>> {
+    let mut ping_at = Instant::now() + PING_INTERVAL;
+    loop {
+        let Ok(measurement) = with_deadline(ping_at, subscriber.next_message_pure()).await else {
+            if awaiting_pong.replace(true) {
+                return Err(Error::Timeout);
+            }
+            send_frame(&mut *tx.lock().await, FrameType::Ping, &[]).await?;
+            ping_at += PING_INTERVAL;
+            continue;
+        };
+
+        let mut text: String<192> = String::new();
+        if json::write_measurement(&mut text, &measurement).is_err() {
+            warn!("[WS] > Measurement doesn't fit into frame buffer");
+            continue;
+        }
+        send_frame(&mut *tx.lock().await, FrameType::Text, text.as_bytes()).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::fake::{Rx, Socket, Tx};
+    use embassy_futures::block_on;
+
+    const KEY: [u8; 4] = [0x37, 0xfa, 0x21, 0x3d];
+    /// Examples of RFC 6455 section 5.7, "Hello" unmasked and masked.
+    const TEXT: [u8; 7] = [0x81, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+    const MASKED_TEXT: [u8; 11] = [
+        0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+    ];
+    const PING: [u8; 7] = [0x89, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f];
+    const MASKED_PING: [u8; 11] = [
+        0x89, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+    ];
+
+    /// Header and unmasked payload of the next frame.
+    fn frame<'a>(read: &mut Rx, buf: &'a mut [u8]) -> (FrameHeader, &'a [u8]) {
+        block_on(async {
+            let header = FrameHeader::recv(read).await.unwrap();
+            (header, header.recv_payload(read, buf).await.unwrap())
+        })
+    }
+
+    fn serialized(header: &FrameHeader) -> heapless::Vec<u8, { FrameHeader::MAX_LEN }> {
+        let mut buf = [0u8; FrameHeader::MAX_LEN];
+        let len = header.serialize(&mut buf);
+        heapless::Vec::from_slice(&buf[..len]).unwrap()
+    }
+
+    #[test]
+    fn rfc_examples() {
+        let mut buf = [0u8; 16];
+        let (header, payload) = frame(&mut Rx(&MASKED_TEXT), &mut buf);
+        assert_eq!(
+            header,
+            FrameHeader {
+                frame_type: FrameType::Text,
+                fin: true,
+                payload_len: 5,
+                mask_key: Some(KEY),
+            }
+        );
+        assert_eq!(payload, b"Hello");
+        assert_eq!(serialized(&header)[..], MASKED_TEXT[..6]);
+
+        let header = FrameHeader::new(FrameType::Text, 5);
+        assert_eq!(serialized(&header)[..], TEXT[..2]);
+        let mut hello = *b"Hello";
+        apply_mask(&mut hello, KEY);
+        assert_eq!(hello, MASKED_TEXT[6..]);
+        apply_mask(&mut hello, KEY);
+        assert_eq!(&hello, b"Hello");
+    }
+
+    #[test]
+    fn payload_lengths() {
+        let lengths: [(usize, &[u8]); 4] = [
+            (125, &[0x82, 0x7d]),
+            (126, &[0x82, 0x7e, 0x00, 0x7e]),
+            (65535, &[0x82, 0x7e, 0xff, 0xff]),
+            (65536, &[0x82, 0x7f, 0, 0, 0, 0, 0, 0x01, 0x00, 0x00]),
+        ];
+        for (len, bytes) in lengths {
+            let header = FrameHeader::new(FrameType::Binary, len);
+            assert_eq!(serialized(&header)[..], *bytes, "{len}");
+            let received = block_on(FrameHeader::recv(&mut Rx(bytes))).unwrap();
+            assert_eq!(received, header);
+        }
+        // Masking key follows 64-bit length.
+        let header = FrameHeader {
+            mask_key: Some(KEY),
+            ..FrameHeader::new(FrameType::Binary, 65536)
+        };
+        let head = serialized(&header);
+        assert_eq!(head.len(), FrameHeader::MAX_LEN);
+        assert_eq!(head[1], 0xff);
+        assert_eq!(head[10..], KEY);
+    }
+
+    #[test]
+    fn invalid_frames() {
+        let invalid: [&[u8]; 5] = [
+            // Reserved bits.
+            &[0xc1, 0x00],
+            // Unknown opcode.
+            &[0x83, 0x00],
+            // Fragmented control frames.
+            &[0x09, 0x00],
+            &[0x08, 0x02, 0x03, 0xe8],
+            // Control frame longer than 125 bytes.
+            &[0x89, 0x7e, 0x00, 0x7e],
+        ];
+        for bytes in invalid {
+            let result = block_on(FrameHeader::recv(&mut Rx(bytes)));
+            assert!(matches!(result, Err(Error::Invalid)), "{bytes:02x?}");
+        }
+        let result = block_on(FrameHeader::recv(&mut Rx(&[0x82, 0x7e, 0x01])));
+        assert!(matches!(result, Err(Error::Incomplete)));
+        let result = block_on(FrameHeader::recv(&mut Rx(&TEXT[..1])));
+        assert!(matches!(result, Err(Error::Incomplete)));
+
+        let mut rx = Rx(&TEXT);
+        let mut buf = [0u8; 4];
+        let result = block_on(async {
+            let header = FrameHeader::recv(&mut rx).await.unwrap();
+            header.recv_payload(&mut rx, &mut buf).await.map(|_| ())
+        });
+        assert!(matches!(result, Err(Error::BufferOverflow)));
+    }
+
+    #[test]
+    fn masked_frames() {
+        let mut payload = [0u8; 150];
+        payload
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, b)| *b = i as u8);
+        let mut tx = Tx(heapless::Vec::new());
+        block_on(send_masked_frame(&mut tx, FrameType::Binary, &payload)).unwrap();
+        assert_eq!(tx.0.len(), 4 + 4 + payload.len());
+
+        let mut buf = [0u8; 256];
+        let (header, received) = frame(&mut Rx(&tx.0), &mut buf);
+        assert_eq!(header.frame_type, FrameType::Binary);
+        assert!(header.fin && header.mask_key.is_some());
+        assert_eq!(received, payload);
+    }
+
+    #[test]
+    fn client_messages() {
+        let rx: heapless::Vec<u8, 32> = [&PING[..], &[0x8a, 0x00], &TEXT]
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        let mut socket = Socket::new(&rx);
+        let mut buf = [0u8; 16];
+        let message = block_on(recv_message(&mut socket, &mut buf)).unwrap();
+        assert_eq!(message, Some(&b"Hello"[..]));
+        // Ping is answered by masked pong with the same payload.
+        let mut pong = [0u8; 16];
+        let (header, payload) = frame(&mut Rx(socket.written()), &mut pong);
+        assert_eq!(header.frame_type, FrameType::Pong);
+        assert!(header.mask_key.is_some());
+        assert_eq!(payload, b"Hello");
+
+        // Closing handshake is completed.
+        let mut socket = Socket::new(&[0x88, 0x02, 0x03, 0xe9]);
+        assert_eq!(block_on(recv_message(&mut socket, &mut buf)).unwrap(), None);
+        let (header, payload) = frame(&mut Rx(socket.written()), &mut pong);
+        assert_eq!(header.frame_type, FrameType::Close);
+        assert_eq!(payload, CLOSE_NORMAL.to_be_bytes());
+
+        // Fragmented messages aren't supported.
+        let mut socket = Socket::new(&[0x01, 0x03, 0x48, 0x65, 0x6c, 0x80, 0x02, 0x6c, 0x6f]);
+        let result = block_on(recv_message(&mut socket, &mut buf));
+        assert!(matches!(result, Err(Error::Invalid)));
+        let mut socket = Socket::new(&TEXT[..4]);
+        let result = block_on(recv_message(&mut socket, &mut buf));
+        assert!(matches!(result, Err(Error::Incomplete)));
+    }
+
+    #[test]
+    fn server_control_frames() {
+        // Ping, pong, ignored text and close with status 1001.
+        let rx: heapless::Vec<u8, 64> = [
+            &MASKED_PING[..],
+            &[0x8a, 0x80, 0x37, 0xfa, 0x21, 0x3d],
+            &MASKED_TEXT,
+            &[0x88, 0x82, 0x37, 0xfa, 0x21, 0x3d, 0x34, 0x13],
+        ]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect();
+        let tx = Mutex::<NoopRawMutex, _>::new(Tx(heapless::Vec::new()));
+        let awaiting_pong = Cell::new(true);
+        block_on(receive_loop(&mut Rx(&rx), &tx, &awaiting_pong)).unwrap();
+        assert!(!awaiting_pong.get());
+        let tx = tx.into_inner();
+        assert_eq!(tx.0[..7], [0x8a, 0x05, 0x48, 0x65, 0x6c, 0x6c, 0x6f]);
+        assert_eq!(tx.0[7..], [0x88, 0x02, 0x03, 0xe9]);
+    }
+
+    #[test]
+    fn unmasked_client_frame() {
+        let tx = Mutex::<NoopRawMutex, _>::new(Tx(heapless::Vec::new()));
+        let result = block_on(receive_loop(&mut Rx(&PING), &tx, &Cell::new(false)));
+        assert!(matches!(result, Err(Error::Invalid)));
+        assert_eq!(tx.into_inner().0, [0x88, 0x02, 0x03, 0xea]);
+    }
+}
@@ -0,0 +1,271 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Uploading payloads to IPFS.
+//!
+//! [`IpfsUploader`] posts payload as multipart form to `/api/v0/add` of IPFS
+//! node or to compatible pinning gateway. CID is computed on device before
+//! upload and compared with one returned by node, so it is safe to reference
+//! it on chain even when upload fails and is retried later.
+//!
+//! Only plain HTTP is supported, gateways behind HTTPS need local proxy.
+//!
+//! ```ignore
+//! let ipfs = IpfsUploader::new(client, "http://127.0.0.1:5001/api/v0/add")
+//!     .with_version(Version::V1);
+//! let cid = ipfs.upload(&payload).await?;
+//! ```
+
+use core::fmt::Write as _;
+use edge_http::Method;
+use heapless::String;
+use log::{debug, warn};
+use rohi_hal::ipfs::{Cid, CidError, Version};
+use serde::Deserialize;
+
+use crate::http::HttpClient;
+use crate::http::client::ClientError;
+
+/// Maximal length of endpoint URL with query.
+pub const URL_MAX_LEN: usize = 256;
+
+const BOUNDARY: &str = "rohi-ipfs-payload";
+const FORM_HEAD: &str = concat!(
+    "--rohi-ipfs-payload\r\n",
+    "Content-Disposition: form-data; name=\"file\"; filename=\"payload\"\r\n",
+    "Content-Type: application/octet-stream\r\n\r\n"
+);
+const FORM_TAIL: &str = "\r\n--rohi-ipfs-payload--\r\n";
+
+/// IPFS upload errors.
+#[derive(Debug)]
+pub enum IpfsError {
+    /// Request failed.
+    Client(ClientError),
+    /// Payload is too large to be added as single level file.
+    Cid(CidError),
+    /// Endpoint URL is too long.
+    Overflow,
+    /// Response holds no CID.
+    InvalidResponse,
+    /// Node stored payload under different CID, e.g. gateway ignores CID version.
+    CidMismatch(Cid),
+}
+
+impl From<ClientError> for IpfsError {
+    fn from(e: ClientError) -> Self {
+        Self::Client(e)
+    }
+}
+
+impl From<CidError> for IpfsError {
+    fn from(e: CidError) -> Self {
+        Self::Cid(e)
+    }
+}
+
+/// Uploader of payloads to IPFS HTTP API.
+#[derive(Clone, Copy)]
+pub struct IpfsUploader<'a> {
+    http: HttpClient,
+    url: &'a str,
+    auth: Option<&'a str>,
+    version: Version,
+}
+
+impl<'a> IpfsUploader<'a> {
+    /// Uploader to `http://` endpoint accepting multipart `file` field,
+    /// e.g. `http://127.0.0.1:5001/api/v0/add`.
+    pub fn new(http: HttpClient, url: &'a str) -> Self {
+        Self {
+            http,
+            url,
+            auth: None,
+            version: Version::V0,
+        }
+    }
+
+    /// Value of `Authorization` header required by pinning gateway.
+    pub fn with_auth(mut self, auth: &'a str) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Version of CIDs, CIDv1 is requested from node by `cid-version=1` query.
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// CID which payload gets once uploaded.
+    pub fn cid(&self, payload: &[u8]) -> Result<Cid, IpfsError> {
+        Ok(Cid::of(self.version, payload)?)
+    }
+
+    /// Upload and pin payload, returns its CID confirmed by node.
+    pub async fn upload(&self, payload: &[u8]) -> Result<Cid, IpfsError> {
+        let cid = self.cid(payload)?;
+
+        let url = endpoint(self.url, self.version)?;
+        let mut content_type: String<64> = String::new();
+        _ = write!(content_type, "multipart/form-data; boundary={}", BOUNDARY);
+        let mut headers: heapless::Vec<(&str, &str), 2> = heapless::Vec::new();
+        _ = headers.push(("Content-Type", &content_type));
+        if let Some(auth) = self.auth {
+            _ = headers.push(("Authorization", auth));
+        }
+
+        let mut buf = [0u8; 512];
+        let response = self
+            .http
+            .request_parts(Method::Post, &url, &headers, &form(payload), &mut buf)
+            .await?;
+        if !response.is_success() {
+            warn!("[IPFS] > Upload failed with status {}", response.status);
+            return Err(ClientError::Status(response.status).into());
+        }
+
+        added_cid(response.body, &cid)?;
+        debug!("[IPFS] > Uploaded {} bytes as {}", payload.len(), cid);
+        Ok(cid)
+    }
+}
+
+/// Endpoint URL with CID version query.
+fn endpoint(url: &str, version: Version) -> Result<String<URL_MAX_LEN>, IpfsError> {
+    let mut endpoint: String<URL_MAX_LEN> = String::new();
+    endpoint.push_str(url).map_err(|_| IpfsError::Overflow)?;
+    if version == Version::V1 {
+        let separator = if url.contains('?') { '&' } else { '?' };
+        write!(endpoint, "{}cid-version=1", separator).map_err(|_| IpfsError::Overflow)?;
+    }
+    Ok(endpoint)
+}
+
+/// Parts of multipart form body with payload as `file` field.
+fn form(payload: &[u8]) -> [&[u8]; 3] {
+    [FORM_HEAD.as_bytes(), payload, FORM_TAIL.as_bytes()]
+}
+
+/// Check CID in response of node against `expected` one.
+fn added_cid(body: &[u8], expected: &Cid) -> Result<(), IpfsError> {
+    let (added, _): (AddResponse, _) =
+        serde_json_core::from_slice(body).map_err(|_| IpfsError::InvalidResponse)?;
+    let stored = added
+        .hash
+        .or(added.ipfs_hash)
+        .and_then(|hash| Cid::parse(hash).ok())
+        .ok_or(IpfsError::InvalidResponse)?;
+    if stored != *expected {
+        return Err(IpfsError::CidMismatch(stored));
+    }
+    Ok(())
+}
+
+/// Response of `/api/v0/add` or pinning gateway.
+#[derive(Deserialize)]
+struct AddResponse<'a> {
+    #[serde(rename = "Hash")]
+    hash: Option<&'a str>,
+    #[serde(rename = "IpfsHash")]
+    ipfs_hash: Option<&'a str>,
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use super::*;
+
+    const HELLO_V0: &str = "QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN";
+    const HELLO_V1: &str = "bafkreicysg23kiwv34eg2d7qweipxwosdo2py4ldv42nbauguluen5v6am";
+
+    #[test]
+    fn endpoint_query() {
+        let add = "http://127.0.0.1:5001/api/v0/add";
+        assert_eq!(endpoint(add, Version::V0).unwrap(), add);
+        assert_eq!(
+            endpoint(add, Version::V1).unwrap(),
+            "http://127.0.0.1:5001/api/v0/add?cid-version=1"
+        );
+        assert_eq!(
+            endpoint("http://127.0.0.1:5001/api/v0/add?pin=true", Version::V1).unwrap(),
+            "http://127.0.0.1:5001/api/v0/add?pin=true&cid-version=1"
+        );
+
+        let mut long: String<URL_MAX_LEN> = String::new();
+        while long.push('a').is_ok() {}
+        assert!(endpoint(&long, Version::V0).is_ok());
+        assert!(matches!(
+            endpoint(&long, Version::V1),
+            Err(IpfsError::Overflow)
+        ));
+    }
+
+    #[test]
+    fn multipart_body() {
+        let mut body: Vec<u8, 256> = Vec::new();
+        for part in form(b"hello\n") {
+            body.extend_from_slice(part).unwrap();
+        }
+        assert_eq!(
+            core::str::from_utf8(&body).unwrap(),
+            concat!(
+                "--rohi-ipfs-payload\r\n",
+                "Content-Disposition: form-data; name=\"file\"; filename=\"payload\"\r\n",
+                "Content-Type: application/octet-stream\r\n",
+                "\r\n",
+                "hello\n",
+                "\r\n",
+                "--rohi-ipfs-payload--\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn add_responses() {
+        let v0 = Cid::of(Version::V0, b"hello\n").unwrap();
+        // Kubo `/api/v0/add`.
+        let kubo = br#"{"Name":"payload","Hash":"QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN","Size":"14"}"#;
+        assert!(added_cid(kubo, &v0).is_ok());
+        // Pinata-like gateway.
+        let gateway = br#"{"IpfsHash":"QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXEN","PinSize":14,"Timestamp":"2025-01-01T00:00:00Z"}"#;
+        assert!(added_cid(gateway, &v0).is_ok());
+
+        // Gateway ignoring requested CID version.
+        let v1 = Cid::of(Version::V1, b"hello\n").unwrap();
+        assert_eq!(v1, Cid::parse(HELLO_V1).unwrap());
+        match added_cid(kubo, &v1) {
+            Err(IpfsError::CidMismatch(stored)) => {
+                assert_eq!(stored, Cid::parse(HELLO_V0).unwrap())
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        for body in [
+            &br#"{"Name":"payload","Size":"14"}"#[..],
+            br#"{"Hash":"QmZULkCELmmk5XNfCgTnCyFgAVxBRBXyDHGGMVoLFLiXE0"}"#,
+            br#"{"Hash":"QmZULkCELmmk5XNfCgTnCyFgAVx"#,
+            b"Internal Server Error",
+        ] {
+            assert!(matches!(
+                added_cid(body, &v0),
+                Err(IpfsError::InvalidResponse)
+            ));
+        }
+    }
+}
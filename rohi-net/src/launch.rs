@@ -0,0 +1,268 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Receiving Robonomics `launch` commands.
+//!
+//! [`LaunchSubscriber`] follows finalized blocks and looks for `launch.launch`
+//! extrinsics targeting device account, sent directly or through RWS
+//! subscription. Found commands are passed to every registered [`LaunchHandler`].
+//! With [`LaunchSubscriber::with_owner`] only commands dispatched on behalf of
+//! owner are taken: signed by owner or paid by owner's RWS subscription.
+//!
+//! Decoding runtime events requires chain metadata, so extrinsics are decoded
+//! instead. Direct launch call has no checks in runtime, so every included
+//! extrinsic is a delivered command. Calls through RWS are dispatched only when
+//! subscription has free quota, which can't be seen in block, so they are
+//! passed to handlers as well.
+//!
+//! ```ignore
+//! let mut subscriber = LaunchSubscriber::new(rpc, pair.public()).with_owner(owner);
+//! let mut on_launch = |launch: &Launch| info!("Launch: {}", launch.param_cid());
+//! subscriber.run(&mut [&mut on_launch], Duration::from_secs(12)).await
+//! ```
+
+use embassy_time::{Duration, Timer};
+use heapless::Vec;
+use log::{debug, info, warn};
+use rohi_hal::extrinsic::Launch;
+use rohi_hal::identity::AccountId;
+
+use crate::rpc::{RpcClient, RpcError};
+
+/// Most blocks processed after connectivity loss, older commands are dropped.
+pub const MAX_CATCH_UP: u64 = 64;
+
+/// Most commands to device taken from one block, the rest is dropped.
+pub const MAX_BLOCK_LAUNCHES: usize = 16;
+
+/// Commands to device found in block.
+type BlockLaunches = Vec<Launch, MAX_BLOCK_LAUNCHES>;
+
+/// Consumer of launch commands.
+pub trait LaunchHandler {
+    fn handle(&mut self, launch: &Launch);
+}
+
+impl<F: FnMut(&Launch)> LaunchHandler for F {
+    fn handle(&mut self, launch: &Launch) {
+        self(launch)
+    }
+}
+
+/// Follower of finalized blocks dispatching launch commands to device.
+pub struct LaunchSubscriber<'a> {
+    rpc: RpcClient<'a>,
+    device: AccountId,
+    owner: Option<AccountId>,
+    last: Option<u64>,
+}
+
+impl<'a> LaunchSubscriber<'a> {
+    /// Subscriber of commands sent to `device` account by anyone.
+    pub fn new(rpc: RpcClient<'a>, device: AccountId) -> Self {
+        Self {
+            rpc,
+            device,
+            owner: None,
+            last: None,
+        }
+    }
+
+    /// Accept commands of `owner` only, see [`Launch::origin`].
+    pub fn with_owner(mut self, owner: AccountId) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Process finalized blocks since last call, returns count of dispatched commands.
+    ///
+    /// First call starts with current finalized block. Commands are dispatched
+    /// only once the whole block is received, failed block is retried on next call.
+    pub async fn poll(
+        &mut self,
+        handlers: &mut [&mut dyn LaunchHandler],
+    ) -> Result<usize, RpcError> {
+        let head = self.rpc.finalized_head().await?;
+        let head_number = self.rpc.block_number(&head).await?;
+        let mut next = match self.last {
+            Some(last) => last + 1,
+            None => head_number,
+        };
+        if head_number.saturating_sub(next) >= MAX_CATCH_UP {
+            warn!(
+                "[Launch] > Blocks {}..{} skipped",
+                next,
+                head_number + 1 - MAX_CATCH_UP
+            );
+            next = head_number + 1 - MAX_CATCH_UP;
+        }
+
+        let mut count = 0;
+        for number in next..=head_number {
+            let hash = match number {
+                number if number == head_number => head,
+                number => self.rpc.block_hash(Some(number)).await?,
+            };
+            debug!("[Launch] > Processing block #{}", number);
+            let mut launches = BlockLaunches::new();
+            self.rpc
+                .block_extrinsics(&hash, collector(self.device, self.owner, &mut launches))
+                .await?;
+            for launch in &launches {
+                info!(
+                    "[Launch] > Command from {} in block #{}",
+                    launch.origin(),
+                    number
+                );
+                for handler in handlers.iter_mut() {
+                    handler.handle(launch);
+                }
+            }
+            count += launches.len();
+            self.last = Some(number);
+        }
+        Ok(count)
+    }
+
+    /// Every `period` dispatch commands from new finalized blocks.
+    pub async fn run(&mut self, handlers: &mut [&mut dyn LaunchHandler], period: Duration) -> ! {
+        loop {
+            if let Err(e) = self.poll(handlers).await {
+                warn!("[Launch] > Unable to fetch blocks: {:?}", e);
+            }
+            Timer::after(period).await;
+        }
+    }
+}
+
+/// Block extrinsics consumer keeping commands sent to `device` on behalf of `owner`.
+fn collector(
+    device: AccountId,
+    owner: Option<AccountId>,
+    launches: &mut BlockLaunches,
+) -> impl FnMut(&[u8]) + '_ {
+    move |extrinsic| {
+        let Some(launch) = Launch::decode(extrinsic) else {
+            return;
+        };
+        if launch.robot != device || owner.is_some_and(|owner| owner != launch.origin()) {
+            return;
+        }
+        if launches.push(launch).is_err() {
+            warn!(
+                "[Launch] > Command from {} dropped, too many in block",
+                launch.origin()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::ExtrinsicScanner;
+
+    const DEVICE: AccountId = AccountId([0x11; 32]);
+    /// Signer of commands, ed25519 `//robonomics` account of RFC 8032 test seed.
+    const SENDER: AccountId = AccountId([
+        0xe9, 0x6b, 0x6c, 0x28, 0x17, 0xfc, 0x85, 0xd5, 0x28, 0x67, 0x43, 0xe5, 0xcd, 0x97, 0xac,
+        0x3f, 0x70, 0x29, 0x67, 0x01, 0x30, 0x8c, 0x82, 0x5c, 0xb8, 0xd6, 0xa7, 0x26, 0x3c, 0xa9,
+        0xac, 0xff,
+    ]);
+    /// Owner of RWS subscription paying for command.
+    const SUBSCRIPTION: AccountId = AccountId([0x22; 32]);
+
+    /// Hand-made `chain_getBlock` response with timestamp inherent, datalog
+    /// record, command to other robot, direct and RWS commands to device. Header
+    /// hashes are placeholder patterns, not taken from a node.
+    const BLOCK: &str = concat!(
+        r#"{"jsonrpc":"2.0","result":{"block":{"header":{"parentHash":"0x1f3c5b9a0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b","number":"0x3e8","#,
+        r#""stateRoot":"0x8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f","extrinsicsRoot":"0x3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b","#,
+        r#""digest":{"logs":["0x066175726120a4d1a10800000000"]}},"extrinsics":["0x280402000b50e2e0b09401","#,
+        r#""0xdd018400e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff00ff3d0757b4446d4e320832312d950ce6eae43f3e2bfa04e1a455e7422533269431a19f977b765784699ff64fce09c66131eeabf9b8d08be35be046c635183502000000003300347b22706d3235223a31322e357d","#,
+        r#""0xa5028400e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff0063c16e33585c176d010ec10db89d1955e00580cbbba27ed6b1241ce9f57ab1d1f185a84253c1a4f8f526fc2106a902683593c27a6411430e03ebe7c726f8010100240000340055555555555555555555555555555555555555555555555555555555555555556666666666666666666666666666666666666666666666666666666666666666","#,
+        r#""0xb5028400e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff00023d65646d3e6bff343263adf6ce037803b93b3cae26a8b7958a6f7c61b7626ecc6172905978894eb2c143dba5cf44425aff8f94c5056c733ba33174e6bf340b85021c02093d0000340011111111111111111111111111111111111111111111111111111111111111113333333333333333333333333333333333333333333333333333333333333333","#,
+        r#""0x31038400e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff00017817914959fa6871a78fedc47f9d61048e36589a0e60676771d7411c3811b04eb390236f3d3bd2f596b405a7f06b558f6fa26c49c5208122cb1ae4583c7002850220000037002222222222222222222222222222222222222222222222222222222222222222340011111111111111111111111111111111111111111111111111111111111111113333333333333333333333333333333333333333333333333333333333333333"]},"#,
+        r#""justifications":null},"id":1}"#,
+    );
+
+    fn scan(block: &[u8], owner: Option<AccountId>) -> (Result<(), RpcError>, BlockLaunches) {
+        let mut launches = BlockLaunches::new();
+        let mut scanner = ExtrinsicScanner::new(collector(DEVICE, owner, &mut launches));
+        block.iter().for_each(|&c| scanner.feed(c));
+        let result = scanner.finish();
+        (result, launches)
+    }
+
+    #[test]
+    fn commands_to_device() {
+        let (result, launches) = scan(BLOCK.as_bytes(), None);
+        assert!(result.is_ok());
+        let direct = Launch {
+            sender: SENDER,
+            robot: DEVICE,
+            param: [0x33; 32],
+            subscription: None,
+        };
+        let rws = Launch {
+            subscription: Some(SUBSCRIPTION),
+            ..direct
+        };
+        assert_eq!(launches[..], [direct, rws]);
+    }
+
+    #[test]
+    fn commands_of_owner() {
+        // RWS call is dispatched on behalf of subscription owner, not signer.
+        let (_, launches) = scan(BLOCK.as_bytes(), Some(SENDER));
+        assert_eq!(launches.len(), 1);
+        assert_eq!(launches[0].subscription, None);
+        let (_, launches) = scan(BLOCK.as_bytes(), Some(SUBSCRIPTION));
+        assert_eq!(launches.len(), 1);
+        assert_eq!(launches[0].origin(), SUBSCRIPTION);
+        let (result, launches) = scan(BLOCK.as_bytes(), Some(AccountId([0x44; 32])));
+        assert!(result.is_ok());
+        assert!(launches.is_empty());
+    }
+
+    #[test]
+    fn truncated_block_is_failed() {
+        // Connection lost after direct command, before RWS one.
+        let cut = BLOCK.find("0x3103").unwrap() + 10;
+        let (result, launches) = scan(&BLOCK.as_bytes()[..cut], None);
+        assert!(matches!(result, Err(RpcError::InvalidResponse)));
+        // Collected command is not dispatched by `poll`, block is retried.
+        assert_eq!(launches.len(), 1);
+    }
+
+    #[test]
+    fn too_many_commands() {
+        let direct = BLOCK.find("\"0xb502").unwrap();
+        let item = &BLOCK[direct..BLOCK.find("\"0x3103").unwrap()];
+        let mut block: heapless::String<8192> = heapless::String::new();
+        block
+            .push_str(r#"{"result":{"block":{"extrinsics":["#)
+            .unwrap();
+        for _ in 0..MAX_BLOCK_LAUNCHES + 2 {
+            block.push_str(item).unwrap();
+        }
+        block.push_str(r#""0x00"]}}}"#).unwrap();
+        let (result, launches) = scan(block.as_bytes(), None);
+        assert!(result.is_ok());
+        assert_eq!(launches.len(), MAX_BLOCK_LAUNCHES);
+    }
+}
@@ -26,9 +26,53 @@
 //! Same as other ROHI SDK crates this is **async-only**. It based on [embassy-net](https://crates.io/crates/embassy-net)
 //! as low level networking and uses [edge-http](https://crates.io/crates/edge-http) for HTTP.
 
+macro_rules! mk_static {
+    ($t:ty,$val:expr) => {{
+        static STATIC_CELL: static_cell::StaticCell<$t> = static_cell::StaticCell::new();
+        #[deny(unused_attributes)]
+        let x = STATIC_CELL.uninit().write(($val));
+        x
+    }};
+}
+
+/// Chip services used by network layer.
+mod chip;
+
+/// Publishing sensor readings into Robonomics Datalog.
+pub mod datalog;
+pub use datalog::DatalogPublisher;
+
+/// Exporting measurements to user's own servers.
+pub mod export;
+pub use export::Exporter;
+
 /// HTTP server and client support.
 pub mod http;
 
+/// Uploading payloads to IPFS.
+pub mod ipfs;
+pub use ipfs::IpfsUploader;
+
+/// Receiving Robonomics `launch` commands.
+pub mod launch;
+pub use launch::LaunchSubscriber;
+
+/// Uploading measurements to sensor.community and Madavi.
+pub mod sensor_community;
+pub use sensor_community::SensorCommunityUploader;
+
+/// Submitting measurements to sensors.social.
+pub mod sensors_social;
+pub use sensors_social::SensorsSocialUploader;
+
+/// Wall clock synchronized over SNTP.
+pub mod sntp;
+pub use sntp::WALL_CLOCK;
+
+/// Substrate JSON-RPC client for Robonomics nodes.
+pub mod rpc;
+pub use rpc::RpcClient;
+
 /// Entry point for networking.
 pub mod network;
 pub use network::*;
@@ -17,37 +17,129 @@
 ///////////////////////////////////////////////////////////////////////////////
 //! Embedded networking for Robonomics Open Hardware.
 
+use core::cell::{Cell, RefCell};
 use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use edge_dhcp::{
+    DhcpOption, MessageType, Options, Packet,
     io::{self, DEFAULT_SERVER_PORT},
     server::{Server, ServerOptions},
 };
-use edge_nal::UdpBind;
+use edge_nal::{UdpBind, UdpReceive, UdpSend};
 use edge_nal_embassy::{Udp, UdpBuffers};
-use embassy_executor::Spawner;
-use embassy_net::{Ipv4Cidr, Runner, Stack, StackResources, StaticConfigV4};
-use embassy_time::Timer;
-use esp_hal::{peripherals::WIFI, rng::Rng};
-use esp_radio::{
-    Controller,
-    wifi::{
-        AccessPointConfig, Interfaces, ModeConfig, WifiApState, WifiController, WifiDevice,
-        WifiEvent,
+use embassy_net::{Ipv4Cidr, Stack};
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use embassy_time::{Instant, Timer};
+use heapless::{LinearMap, String};
+use log::{info, warn};
+#[cfg(feature = "esp32c3")]
+use {
+    embassy_executor::Spawner,
+    embassy_futures::select::{Either, select},
+    embassy_net::{Runner, StackResources, StaticConfigV4},
+    embassy_time::Duration,
+    esp_hal::{peripherals::WIFI, rng::Rng},
+    esp_radio::{
+        Controller,
+        wifi::{
+            AccessPointConfig, AuthMethod, ClientConfig, Interfaces, ModeConfig, WifiApState,
+            WifiController, WifiDevice, WifiEvent, WifiStaState,
+        },
     },
 };
-use heapless::String;
-use log::{info, warn};
 
-macro_rules! mk_static {
-    ($t:ty,$val:expr) => {{
-        static STATIC_CELL: static_cell::StaticCell<$t> = static_cell::StaticCell::new();
-        #[deny(unused_attributes)]
-        let x = STATIC_CELL.uninit().write(($val));
-        x
-    }};
+/// Period of signal strength refresh while connected to access point.
+#[cfg(feature = "esp32c3")]
+const RSSI_PERIOD: Duration = Duration::from_secs(10);
+
+/// Most addresses leased by DHCP server at once.
+const DHCP_LEASES: usize = 64;
+
+/// Expiration time in seconds since boot of leased addresses.
+type LeaseTable = LinearMap<Ipv4Addr, u64, DHCP_LEASES>;
+
+/// Statistics of network started by [`Network::start_wifi`].
+pub static NETWORK_STATS: NetworkStats = NetworkStats::new();
+
+/// Network counters updated by background network tasks.
+pub struct NetworkStats {
+    reconnects: Mutex<CriticalSectionRawMutex, Cell<u32>>,
+    dhcp_leases: Mutex<CriticalSectionRawMutex, RefCell<LeaseTable>>,
+    rssi: Mutex<CriticalSectionRawMutex, Cell<Option<i32>>>,
+    http_exchanges: Mutex<CriticalSectionRawMutex, Cell<u32>>,
+}
+
+impl NetworkStats {
+    const fn new() -> Self {
+        Self {
+            reconnects: Mutex::new(Cell::new(0)),
+            dhcp_leases: Mutex::new(RefCell::new(LinearMap::new())),
+            rssi: Mutex::new(Cell::new(None)),
+            http_exchanges: Mutex::new(Cell::new(0)),
+        }
+    }
+
+    /// Count of WiFi restarts since boot.
+    pub fn reconnects(&self) -> u32 {
+        self.reconnects.lock(Cell::get)
+    }
+
+    /// Count of addresses leased by DHCP server and not expired yet.
+    pub fn dhcp_leases(&self) -> u32 {
+        let now = Instant::now().as_secs();
+        self.dhcp_leases
+            .lock(|leases| leases.borrow().values().filter(|&&e| e > now).count() as u32)
+    }
+
+    /// Follow leases of DHCP server after request, `acked` address is leased till `expires`.
+    fn update_leases<F, const N: usize>(
+        &self,
+        server: &Server<F, N>,
+        acked: Option<(Ipv4Addr, u64)>,
+    ) {
+        self.dhcp_leases.lock(|leases| {
+            let mut leases = leases.borrow_mut();
+            // Released and reassigned addresses are gone from server table.
+            let mut active = LeaseTable::new();
+            for (ip, expires) in leases.iter() {
+                if server.leases.contains_key(ip) {
+                    _ = active.insert(*ip, *expires);
+                }
+            }
+            if let Some((ip, expires)) = acked {
+                _ = active.insert(ip, expires);
+            }
+            *leases = active;
+        });
+    }
+
+    /// Signal strength of upstream access point in dBm, `None` in AP mode.
+    pub fn rssi(&self) -> Option<i32> {
+        self.rssi.lock(Cell::get)
+    }
+
+    /// Count of HTTP responses received by device from remote servers.
+    ///
+    /// Requests served by device aren't counted: client on its own access point
+    /// doesn't prove that upstream network is reachable.
+    pub fn http_exchanges(&self) -> u32 {
+        self.http_exchanges.lock(Cell::get)
+    }
+
+    /// Count HTTP response received from remote server.
+    pub(crate) fn http_exchange(&self) {
+        self.http_exchanges
+            .lock(|cell| cell.set(cell.get().saturating_add(1)));
+    }
+
+    /// Network is proven to work: link and address are up and at least one
+    /// HTTP response was received from remote server.
+    pub fn is_ready(&self, stack: Stack<'_>) -> bool {
+        stack.is_link_up() && stack.is_config_up() && self.http_exchanges() > 0
+    }
 }
 
 /// General network service interface.
+#[cfg(feature = "esp32c3")]
 pub struct Network {
     wifi_controller: WifiController<'static>,
     wifi_interfaces: Interfaces<'static>,
@@ -57,8 +149,15 @@ pub struct Network {
 pub enum WifiConfig {
     /// Access point with given SSID and IP.
     Ap { ssid: String<32>, ip: Ipv4Cidr },
+    /// Station connected to given network, address is obtained by DHCP.
+    /// Open network is used when password is empty.
+    Sta {
+        ssid: String<32>,
+        password: String<64>,
+    },
 }
 
+#[cfg(feature = "esp32c3")]
 impl Network {
     /// New network instance.
     pub fn new(wifi: WIFI<'static>) -> Self {
@@ -72,7 +171,9 @@ impl Network {
     }
 
     /// Spawn background network services like dhcp, wifi, etc.
-    pub fn start_wifi(self, config: WifiConfig, spawner: &Spawner) {
+    ///
+    /// Returns network stack to be used by application level services.
+    pub fn start_wifi(self, config: WifiConfig, spawner: &Spawner) -> Stack<'static> {
         match config {
             WifiConfig::Ap { ssid, ip } => {
                 info!(
@@ -100,11 +201,33 @@ impl Network {
                     .ok();
                 spawner.spawn(ap_network_task(runner)).ok();
                 spawner.spawn(dhcp_server_task(stack, ip.address())).ok();
+                stack
+            }
+            WifiConfig::Sta { ssid, password } => {
+                info!("[Network] > Start WiFi station with config: SSID({})", ssid);
+
+                let rng = Rng::new();
+                let ip_config = embassy_net::Config::dhcpv4(Default::default());
+                let seed = (rng.random() as u64) << 32 | rng.random() as u64;
+
+                let (stack, runner) = embassy_net::new(
+                    self.wifi_interfaces.sta,
+                    ip_config,
+                    mk_static!(StackResources<8>, StackResources::<8>::new()),
+                    seed,
+                );
+
+                spawner
+                    .spawn(sta_connect_task(self.wifi_controller, ssid, password))
+                    .ok();
+                spawner.spawn(ap_network_task(runner)).ok();
+                stack
             }
         }
     }
 }
 
+#[cfg(feature = "esp32c3")]
 #[embassy_executor::task]
 pub async fn ap_setup_task(mut controller: WifiController<'static>, ssid: String<32>) {
     info!("[Network] > Wifi AP setup task started");
@@ -113,6 +236,9 @@ pub async fn ap_setup_task(mut controller: WifiController<'static>, ssid: String
     loop {
         if esp_radio::wifi::ap_state() == WifiApState::Started {
             controller.wait_for_event(WifiEvent::ApStop).await;
+            NETWORK_STATS
+                .reconnects
+                .lock(|reconnects| reconnects.set(reconnects.get() + 1));
             Timer::after_secs(5).await
         }
         if !matches!(controller.is_started(), Ok(true)) {
@@ -123,6 +249,63 @@ pub async fn ap_setup_task(mut controller: WifiController<'static>, ssid: String
     }
 }
 
+#[cfg(feature = "esp32c3")]
+#[embassy_executor::task]
+pub async fn sta_connect_task(
+    mut controller: WifiController<'static>,
+    ssid: String<32>,
+    password: String<64>,
+) {
+    info!("[Network] > Wifi station connect task started");
+    let auth_method = if password.is_empty() {
+        AuthMethod::None
+    } else {
+        AuthMethod::Wpa2Personal
+    };
+    let config = ModeConfig::Client(
+        ClientConfig::default()
+            .with_ssid(ssid.as_str().into())
+            .with_password(password.as_str().into())
+            .with_auth_method(auth_method),
+    );
+    let update_rssi = |controller: &WifiController<'static>| {
+        let rssi = controller.rssi().ok();
+        NETWORK_STATS.rssi.lock(|cell| cell.set(rssi));
+    };
+    loop {
+        if esp_radio::wifi::sta_state() == WifiStaState::Connected {
+            let disconnected = controller.wait_for_event(WifiEvent::StaDisconnected);
+            if let Either::Second(_) = select(disconnected, Timer::after(RSSI_PERIOD)).await {
+                update_rssi(&controller);
+                continue;
+            }
+            warn!("[Network] > Wifi disconnected");
+            NETWORK_STATS.rssi.lock(|cell| cell.set(None));
+            NETWORK_STATS
+                .reconnects
+                .lock(|reconnects| reconnects.set(reconnects.get() + 1));
+            Timer::after_secs(5).await
+        }
+        if !matches!(controller.is_started(), Ok(true)) {
+            controller.set_config(&config).unwrap();
+            controller.start_async().await.unwrap();
+            info!("[Network] > Wifi started!");
+        }
+        match controller.connect_async().await {
+            Ok(()) => {
+                info!("[Network] > Wifi connected to {}", ssid);
+                update_rssi(&controller);
+            }
+            Err(e) => {
+                warn!("[Network] > Wifi connection failed: {:?}", e);
+                Timer::after_secs(5).await
+            }
+        }
+    }
+}
+
+/// Network stack runner, serves both access point and station interfaces.
+#[cfg(feature = "esp32c3")]
 #[embassy_executor::task]
 pub async fn ap_network_task(mut runner: Runner<'static, WifiDevice<'static>>) {
     info!("[Network] > Wifi AP network task started");
@@ -144,15 +327,57 @@ pub async fn dhcp_server_task(stack: Stack<'static>, ip: Ipv4Addr) {
         .await
         .unwrap();
 
+    let mut server = Server::<_, DHCP_LEASES>::new_with_et(ip);
+    let options = ServerOptions::new(ip, Some(&mut gw_buf));
     loop {
-        _ = io::server::run(
-            &mut Server::<_, 64>::new_with_et(ip),
-            &ServerOptions::new(ip, Some(&mut gw_buf)),
-            &mut bound_socket,
-            &mut buf,
-        )
-        .await
-        .inspect_err(|_| warn!("[Network] > DHCP server error"));
-        Timer::after_secs(3).await;
+        if let Err(e) = serve_dhcp(&mut server, &options, &mut bound_socket, &mut buf).await {
+            warn!("[Network] > DHCP server error: {:?}", e);
+            Timer::after_secs(3).await;
+        }
+    }
+}
+
+/// Request loop of [`io::server::run`], leases are followed by [`NETWORK_STATS`].
+async fn serve_dhcp<T, F, const N: usize>(
+    server: &mut Server<F, N>,
+    options: &ServerOptions<'_>,
+    socket: &mut T,
+    buf: &mut [u8],
+) -> Result<(), io::Error<T::Error>>
+where
+    T: UdpReceive + UdpSend,
+    F: FnMut() -> u64,
+{
+    loop {
+        let (len, remote) = socket.receive(buf).await.map_err(io::Error::Io)?;
+        let Ok(request) = Packet::decode(&buf[..len]) else {
+            continue;
+        };
+        let mut opt_buf = Options::buf();
+        let reply = server.handle_request(&mut opt_buf, options, &request);
+
+        let acked = reply.as_ref().and_then(|reply| {
+            let ack = reply
+                .options
+                .iter()
+                .any(|o| matches!(o, DhcpOption::MessageType(MessageType::Ack)));
+            let expires = (server.now)() + u64::from(options.lease_duration_secs);
+            ack.then_some((reply.yiaddr, expires))
+        });
+        NETWORK_STATS.update_leases(server, acked);
+
+        if let Some(reply) = reply {
+            // Clients without address get broadcast reply.
+            let remote = match remote {
+                SocketAddr::V4(addr) if request.broadcast || addr.ip().is_unspecified() => {
+                    SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::BROADCAST, addr.port()))
+                }
+                remote => remote,
+            };
+            socket
+                .send(remote, reply.encode(buf)?)
+                .await
+                .map_err(io::Error::Io)?;
+        }
     }
 }
@@ -0,0 +1,759 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Substrate JSON-RPC client for Robonomics nodes.
+//!
+//! Plain requests are sent over HTTP, extrinsic status is watched through
+//! WebSocket subscription. Substrate nodes serve both protocols on the same
+//! port, so single `http://host:9944` URL is enough.
+//!
+//! ```ignore
+//! let rpc = RpcClient::new(client, "http://127.0.0.1:9944");
+//! let chain = rpc.chain_info().await?;
+//! let nonce = rpc.account_next_index(&pair.public()).await?;
+//! let extrinsic = ExtrinsicBuilder::new(&chain, nonce).sign(&pair, &call)?;
+//! let status = rpc.submit_and_watch(&extrinsic, |status| {
+//!     info!("Extrinsic status: {:?}", status);
+//!     !matches!(status, TxStatus::InBlock(_))
+//! }).await?;
+//! ```
+
+use core::convert::Infallible;
+use core::fmt::{self, Write as _};
+use edge_http::Method;
+use heapless::String;
+use log::{debug, warn};
+use rohi_hal::extrinsic::{ChainInfo, EXTRINSIC_MAX_LEN, Extrinsic};
+use rohi_hal::identity::AccountId;
+use serde::de::{self, DeserializeOwned, IgnoredAny, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::http::HttpClient;
+use crate::http::client::{Chunk, ClientError, DownloadError};
+use crate::http::ws::{FrameType, recv_message, send_masked_frame};
+
+/// Size of response buffer, enough for runtime version with all APIs listed.
+pub const RPC_RESPONSE_SIZE: usize = 2048;
+
+/// Size of request buffer, enough for hex encoded extrinsic.
+pub const RPC_REQUEST_SIZE: usize = EXTRINSIC_MAX_LEN * 2 + 128;
+
+/// Invalid transaction, e.g. bad signature, outdated nonce or no funds for fees.
+pub const INVALID_TRANSACTION: i32 = 1010;
+/// Transaction with the same nonce is already in pool.
+pub const PRIORITY_TOO_LOW: i32 = 1014;
+
+/// Longest block extrinsic passed by [`RpcClient::block_extrinsics`], enough for commands.
+pub const BLOCK_EXTRINSIC_MAX_LEN: usize = 512;
+
+/// Hash of block or extrinsic.
+pub type Hash = [u8; 32];
+
+/// JSON-RPC client errors.
+#[derive(Debug)]
+pub enum RpcError {
+    /// HTTP request failed.
+    Client(ClientError),
+    /// WebSocket connection failed.
+    WebSocket,
+    /// Request does not fit into buffer.
+    Overflow,
+    /// Response is not a valid JSON-RPC response.
+    InvalidResponse,
+    /// Node rejected request, `data` holds the reason when it fits.
+    Rpc { code: i32, data: String<64> },
+    /// Connection closed before extrinsic reached final state.
+    Closed,
+}
+
+impl From<ClientError> for RpcError {
+    fn from(e: ClientError) -> Self {
+        Self::Client(e)
+    }
+}
+
+impl From<fmt::Error> for RpcError {
+    fn from(_: fmt::Error) -> Self {
+        Self::Overflow
+    }
+}
+
+impl From<serde_json_core::de::Error> for RpcError {
+    fn from(_: serde_json_core::de::Error) -> Self {
+        Self::InvalidResponse
+    }
+}
+
+/// Versions of running runtime.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeVersion {
+    pub spec_version: u32,
+    pub transaction_version: u32,
+}
+
+/// Status of submitted extrinsic, see `author_submitAndWatchExtrinsic`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Waiting for previous nonces.
+    Future,
+    /// Ready to be included into block.
+    Ready,
+    /// Sent to other nodes.
+    Broadcast,
+    /// Included into block.
+    InBlock(Hash),
+    /// Block with extrinsic is retracted.
+    Retracted(Hash),
+    /// Block with extrinsic is not finalized for too long, watching stopped.
+    FinalityTimeout(Hash),
+    /// Block with extrinsic is finalized.
+    Finalized(Hash),
+    /// Replaced by other extrinsic with the same nonce.
+    Usurped(Hash),
+    /// Dropped from pool because of limits.
+    Dropped,
+    /// Became invalid, e.g. fees could not be paid anymore.
+    Invalid,
+}
+
+impl TxStatus {
+    /// Node stops sending updates after final status.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            Self::FinalityTimeout(_)
+                | Self::Finalized(_)
+                | Self::Usurped(_)
+                | Self::Dropped
+                | Self::Invalid
+        )
+    }
+}
+
+/// JSON-RPC client of Substrate node.
+#[derive(Clone, Copy)]
+pub struct RpcClient<'a> {
+    http: HttpClient,
+    url: &'a str,
+}
+
+impl<'a> RpcClient<'a> {
+    /// Client of node with given `http://` endpoint.
+    pub fn new(http: HttpClient, url: &'a str) -> Self {
+        Self { http, url }
+    }
+
+    /// Next nonce of account including extrinsics in pool, `system_accountNextIndex`.
+    pub async fn account_next_index(&self, account: &AccountId) -> Result<u32, RpcError> {
+        let mut params: String<64> = String::new();
+        write!(params, "[\"{}\"]", account)?;
+        self.call("system_accountNextIndex", &params).await
+    }
+
+    /// Hash of block with given number or the best block, `chain_getBlockHash`.
+    pub async fn block_hash(&self, number: Option<u64>) -> Result<Hash, RpcError> {
+        let mut params: String<24> = String::new();
+        match number {
+            Some(number) => write!(params, "[{}]", number)?,
+            None => params.push_str("[]").map_err(|_| RpcError::Overflow)?,
+        }
+        let hash: Option<HexHash> = self.call("chain_getBlockHash", &params).await?;
+        hash.map(|h| h.0).ok_or(RpcError::InvalidResponse)
+    }
+
+    /// Hash of the last finalized block, `chain_getFinalizedHead`.
+    pub async fn finalized_head(&self) -> Result<Hash, RpcError> {
+        let hash: HexHash = self.call("chain_getFinalizedHead", "[]").await?;
+        Ok(hash.0)
+    }
+
+    /// Number of block with given hash, `chain_getHeader`.
+    pub async fn block_number(&self, hash: &Hash) -> Result<u64, RpcError> {
+        let params = hash_params(hash)?;
+        let header: Option<Header> = self.call("chain_getHeader", &params).await?;
+        header.map(|h| h.number.0).ok_or(RpcError::InvalidResponse)
+    }
+
+    /// Pass every encoded extrinsic of block to `on_extrinsic`, `chain_getBlock`.
+    ///
+    /// Block is parsed while received, so it doesn't have to fit into memory.
+    /// Extrinsics longer than [`BLOCK_EXTRINSIC_MAX_LEN`] are skipped.
+    pub async fn block_extrinsics(
+        &self,
+        hash: &Hash,
+        on_extrinsic: impl FnMut(&[u8]),
+    ) -> Result<(), RpcError> {
+        let params = hash_params(hash)?;
+        let request = request("chain_getBlock", &params)?;
+        let mut scanner = ExtrinsicScanner::new(on_extrinsic);
+        self.http
+            .stream(
+                Method::Post,
+                self.url,
+                &[("Content-Type", "application/json")],
+                request.as_bytes(),
+                async |chunk| {
+                    if let Chunk::Data(data) = chunk {
+                        data.iter().for_each(|&c| scanner.feed(c));
+                    }
+                    Ok::<_, Infallible>(())
+                },
+            )
+            .await
+            .map_err(|e| match e {
+                DownloadError::Client(e) => RpcError::Client(e),
+            })?;
+        scanner.finish()
+    }
+
+    /// Versions of the best block runtime, `state_getRuntimeVersion`.
+    pub async fn runtime_version(&self) -> Result<RuntimeVersion, RpcError> {
+        self.call("state_getRuntimeVersion", "[]").await
+    }
+
+    /// Chain parameters required to sign extrinsics.
+    pub async fn chain_info(&self) -> Result<ChainInfo, RpcError> {
+        let genesis_hash = self.block_hash(Some(0)).await?;
+        let version = self.runtime_version().await?;
+        Ok(ChainInfo {
+            genesis_hash,
+            spec_version: version.spec_version,
+            transaction_version: version.transaction_version,
+        })
+    }
+
+    /// Submit extrinsic to pool and return its hash, `author_submitExtrinsic`.
+    pub async fn submit_extrinsic(&self, extrinsic: &Extrinsic) -> Result<Hash, RpcError> {
+        let params = extrinsic_params(extrinsic)?;
+        let hash: HexHash = self.call("author_submitExtrinsic", &params).await?;
+        Ok(hash.0)
+    }
+
+    /// Submit extrinsic and track its status, `author_submitAndWatchExtrinsic`.
+    ///
+    /// Every status update is passed to `on_status`, watching stops when it returns
+    /// `false` or final status is reached. Last received status is returned.
+    pub async fn submit_and_watch(
+        &self,
+        extrinsic: &Extrinsic,
+        mut on_status: impl FnMut(&TxStatus) -> bool,
+    ) -> Result<TxStatus, RpcError> {
+        let params = extrinsic_params(extrinsic)?;
+        let request = request("author_submitAndWatchExtrinsic", &params)?;
+        self.http
+            .websocket(self.url, async |socket| {
+                send_masked_frame(socket, FrameType::Text, request.as_bytes())
+                    .await
+                    .map_err(|_| RpcError::WebSocket)?;
+                let mut buf = [0u8; RPC_RESPONSE_SIZE];
+                loop {
+                    let message = recv_message(socket, &mut buf)
+                        .await
+                        .map_err(|_| RpcError::WebSocket)?
+                        .ok_or(RpcError::Closed)?;
+                    let Some(status) = parse_notification(message)? else {
+                        // Subscription confirmation.
+                        continue;
+                    };
+                    debug!("[RPC] > Extrinsic status: {:?}", status);
+                    if !on_status(&status) || status.is_final() {
+                        return Ok(status);
+                    }
+                }
+            })
+            .await?
+    }
+
+    /// Perform single JSON-RPC request over HTTP.
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: &str) -> Result<T, RpcError> {
+        let request = request(method, params)?;
+        let mut buf = [0u8; RPC_RESPONSE_SIZE];
+        let response = self
+            .http
+            .request(
+                Method::Post,
+                self.url,
+                &[("Content-Type", "application/json")],
+                request.as_bytes(),
+                &mut buf,
+            )
+            .await?;
+        if !response.is_success() {
+            return Err(ClientError::Status(response.status).into());
+        }
+        parse_response(response.body)
+    }
+}
+
+/// Result of JSON-RPC response.
+fn parse_response<T: DeserializeOwned>(body: &[u8]) -> Result<T, RpcError> {
+    let (response, _): (Response<T>, _) = serde_json_core::from_slice(body)?;
+    match response {
+        Response {
+            error: Some(error), ..
+        } => Err(error.into()),
+        Response {
+            result: Some(result),
+            ..
+        } => Ok(result),
+        // `null` result is deserialized as `None`, so it is let to caller.
+        _ => serde_json_core::from_str("null")
+            .map(|(result, _)| result)
+            .map_err(|_| RpcError::InvalidResponse),
+    }
+}
+
+/// Extrinsic status of subscription notification, `None` for subscription confirmation.
+fn parse_notification(message: &[u8]) -> Result<Option<TxStatus>, RpcError> {
+    let (message, _): (WsMessage, _) = serde_json_core::from_slice(message)?;
+    if let Some(error) = message.error {
+        return Err(error.into());
+    }
+    Ok(message
+        .params
+        .map(|notification| notification.result.into()))
+}
+
+fn request(method: &str, params: &str) -> Result<String<RPC_REQUEST_SIZE>, RpcError> {
+    let mut request = String::new();
+    write!(
+        request,
+        "{{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"{}\",\"params\":{}}}",
+        method, params
+    )?;
+    Ok(request)
+}
+
+fn extrinsic_params(extrinsic: &Extrinsic) -> Result<String<RPC_REQUEST_SIZE>, RpcError> {
+    let mut params = String::new();
+    params.push_str("[\"0x").map_err(|_| RpcError::Overflow)?;
+    for byte in extrinsic {
+        write!(params, "{:02x}", byte)?;
+    }
+    params.push_str("\"]").map_err(|_| RpcError::Overflow)?;
+    Ok(params)
+}
+
+fn hash_params(hash: &Hash) -> Result<String<72>, RpcError> {
+    let mut params = String::new();
+    params.push_str("[\"0x").map_err(|_| RpcError::Overflow)?;
+    for byte in hash {
+        write!(params, "{:02x}", byte)?;
+    }
+    params.push_str("\"]").map_err(|_| RpcError::Overflow)?;
+    Ok(params)
+}
+
+#[derive(Deserialize)]
+struct Response<'a, T> {
+    result: Option<T>,
+    #[serde(borrow)]
+    error: Option<ErrorObject<'a>>,
+}
+
+#[derive(Deserialize)]
+struct ErrorObject<'a> {
+    code: i32,
+    message: &'a str,
+    data: Option<&'a str>,
+}
+
+impl From<ErrorObject<'_>> for RpcError {
+    fn from(e: ErrorObject<'_>) -> Self {
+        warn!("[RPC] > Error {}: {} {:?}", e.code, e.message, e.data);
+        let mut data = String::new();
+        for c in e.data.unwrap_or(e.message).chars() {
+            if data.push(c).is_err() {
+                break;
+            }
+        }
+        Self::Rpc { code: e.code, data }
+    }
+}
+
+/// Subscription confirmation or notification.
+#[derive(Deserialize)]
+struct WsMessage<'a> {
+    #[serde(borrow)]
+    error: Option<ErrorObject<'a>>,
+    params: Option<Notification>,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    result: RawStatus,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum RawStatus {
+    Future,
+    Ready,
+    Broadcast(IgnoredAny),
+    InBlock(HexHash),
+    Retracted(HexHash),
+    FinalityTimeout(HexHash),
+    Finalized(HexHash),
+    Usurped(HexHash),
+    Dropped,
+    Invalid,
+}
+
+impl From<RawStatus> for TxStatus {
+    fn from(status: RawStatus) -> Self {
+        match status {
+            RawStatus::Future => Self::Future,
+            RawStatus::Ready => Self::Ready,
+            RawStatus::Broadcast(_) => Self::Broadcast,
+            RawStatus::InBlock(hash) => Self::InBlock(hash.0),
+            RawStatus::Retracted(hash) => Self::Retracted(hash.0),
+            RawStatus::FinalityTimeout(hash) => Self::FinalityTimeout(hash.0),
+            RawStatus::Finalized(hash) => Self::Finalized(hash.0),
+            RawStatus::Usurped(hash) => Self::Usurped(hash.0),
+            RawStatus::Dropped => Self::Dropped,
+            RawStatus::Invalid => Self::Invalid,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Header {
+    number: HexNumber,
+}
+
+/// `0x` prefixed hex encoded number.
+struct HexNumber(u64);
+
+impl<'de> Deserialize<'de> for HexNumber {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HexVisitor;
+
+        impl Visitor<'_> for HexVisitor {
+            type Value = HexNumber;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("0x prefixed hex number")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<HexNumber, E> {
+                let hex = v
+                    .strip_prefix("0x")
+                    .ok_or_else(|| E::custom("no 0x prefix"))?;
+                u64::from_str_radix(hex, 16)
+                    .map(HexNumber)
+                    .map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(HexVisitor)
+    }
+}
+
+/// Position of [`ExtrinsicScanner`] in `chain_getBlock` response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ScanState {
+    /// Looking for extrinsics array, value is count of matched key bytes.
+    Key(usize),
+    /// Inside of array, between items.
+    Array,
+    /// Inside of `0x` prefixed item, value is count of prefix bytes read.
+    Prefix(u8),
+    /// Decoding hex digits of item.
+    Item,
+    /// Item doesn't fit into buffer, skipping to its end.
+    Skip,
+    /// Array is over.
+    Done,
+}
+
+/// Streaming parser picking hex strings of `"extrinsics":[...]` array.
+///
+/// Extrinsics are plain hex strings, so no general JSON parsing is needed.
+pub(crate) struct ExtrinsicScanner<F> {
+    state: ScanState,
+    buf: heapless::Vec<u8, BLOCK_EXTRINSIC_MAX_LEN>,
+    high: Option<u8>,
+    on_extrinsic: F,
+}
+
+impl<F: FnMut(&[u8])> ExtrinsicScanner<F> {
+    const KEY: &[u8] = b"\"extrinsics\":[";
+
+    pub(crate) fn new(on_extrinsic: F) -> Self {
+        Self {
+            state: ScanState::Key(0),
+            buf: heapless::Vec::new(),
+            high: None,
+            on_extrinsic,
+        }
+    }
+
+    pub(crate) fn feed(&mut self, c: u8) {
+        self.state = match (self.state, c) {
+            (ScanState::Key(n), c) if Self::KEY[n] == c => match n + 1 {
+                n if n == Self::KEY.len() => ScanState::Array,
+                n => ScanState::Key(n),
+            },
+            (ScanState::Key(_), c) if Self::KEY[0] == c => ScanState::Key(1),
+            (ScanState::Key(_), _) => ScanState::Key(0),
+            (ScanState::Array, b'"') => {
+                self.buf.clear();
+                self.high = None;
+                ScanState::Prefix(0)
+            }
+            (ScanState::Array, b']') => ScanState::Done,
+            (ScanState::Array, _) => ScanState::Array,
+            (ScanState::Prefix(0), b'0') => ScanState::Prefix(1),
+            (ScanState::Prefix(1), b'x') => ScanState::Item,
+            (ScanState::Prefix(_), b'"') | (ScanState::Skip, b'"') => ScanState::Array,
+            (ScanState::Prefix(_), _) | (ScanState::Skip, _) => ScanState::Skip,
+            (ScanState::Item, b'"') => {
+                if self.high.is_none() {
+                    (self.on_extrinsic)(&self.buf);
+                }
+                ScanState::Array
+            }
+            (ScanState::Item, c) => match (hex_digit(c), self.high) {
+                (Some(low), Some(high)) => {
+                    self.high = None;
+                    match self.buf.push(high << 4 | low) {
+                        Ok(()) => ScanState::Item,
+                        Err(_) => ScanState::Skip,
+                    }
+                }
+                (Some(high), None) => {
+                    self.high = Some(high);
+                    ScanState::Item
+                }
+                (None, _) => ScanState::Skip,
+            },
+            (ScanState::Done, _) => ScanState::Done,
+        };
+    }
+}
+
+impl<F> ExtrinsicScanner<F> {
+    /// Check that the whole array was received.
+    pub(crate) fn finish(self) -> Result<(), RpcError> {
+        match self.state {
+            ScanState::Done => Ok(()),
+            _ => Err(RpcError::InvalidResponse),
+        }
+    }
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+/// `0x` prefixed hex encoded hash.
+struct HexHash(Hash);
+
+impl<'de> Deserialize<'de> for HexHash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct HexVisitor;
+
+        impl Visitor<'_> for HexVisitor {
+            type Value = HexHash;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("0x prefixed 32 bytes hex string")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<HexHash, E> {
+                let hex = v
+                    .strip_prefix("0x")
+                    .filter(|hex| hex.len() == 64)
+                    .ok_or_else(|| E::custom("bad hash length"))?;
+                let mut hash = [0u8; 32];
+                for (byte, pair) in hash.iter_mut().zip(hex.as_bytes().chunks(2)) {
+                    let pair = core::str::from_utf8(pair).map_err(E::custom)?;
+                    *byte = u8::from_str_radix(pair, 16).map_err(E::custom)?;
+                }
+                Ok(HexHash(hash))
+            }
+        }
+
+        deserializer.deserialize_str(HexVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use heapless::Vec;
+
+    use super::*;
+
+    const HASH: &str = "0x5d4ef47b2d54d2ba0f6fb8b6c6e8e46c0ae3e1fdfd0e6c8e1a7a0d9e2c8f1b3a";
+
+    /// Hand-made `chain_getBlock` response with timestamp inherent, launch
+    /// command, too long and malformed extrinsics. It is not recorded from a
+    /// node: header hashes are placeholder patterns, only extrinsics are encoded
+    /// like on chain.
+    const BLOCK: &[u8] = br#"{"jsonrpc":"2.0","result":{"block":{"header":{"parentHash":"0x1f3c5b9a0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b","number":"0x1b2","stateRoot":"0x8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f","extrinsicsRoot":"0x3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b8a7f9e1d3c5b7a9f0e2d4c6b","digest":{"logs":["0x066175726120a4d1a10800000000","0x05617572610101c2e8"]}},"extrinsics":["0x280402000b50e2e0b09401","0xb5028400e96b6c2817fc85d5286743e5cd97ac3f70296701308c825cb8d6a7263ca9acff00023d65646d3e6bff343263adf6ce037803b93b3cae26a8b7958a6f7c61b7626ecc6172905978894eb2c143dba5cf44425aff8f94c5056c733ba33174e6bf340b85021c02093d0000340011111111111111111111111111111111111111111111111111111111111111113333333333333333333333333333333333333333333333333333333333333333","0xzz","0x123","ab"]},"justifications":null},"id":1}"#;
+
+    fn scan(chunks: impl Iterator<Item = u8>) -> (ScanState, Vec<Vec<u8, 256>, 8>) {
+        let mut extrinsics = Vec::new();
+        let mut scanner = ExtrinsicScanner::new(|e: &[u8]| {
+            extrinsics.push(Vec::from_slice(e).unwrap()).unwrap();
+        });
+        chunks.for_each(|c| scanner.feed(c));
+        let state = scanner.state;
+        (state, extrinsics)
+    }
+
+    #[test]
+    fn block_extrinsics() {
+        let (state, extrinsics) = scan(BLOCK.iter().copied());
+        assert_eq!(state, ScanState::Done);
+        assert_eq!(extrinsics.len(), 2);
+        assert_eq!(
+            extrinsics[0][..],
+            [
+                0x28, 0x04, 0x02, 0x00, 0x0b, 0x50, 0xe2, 0xe0, 0xb0, 0x94, 0x01
+            ]
+        );
+        // Compact length prefix 0x02b5 >> 2 and 173 bytes of launch call.
+        assert_eq!(extrinsics[1].len(), 175);
+        assert_eq!(extrinsics[1][..4], [0xb5, 0x02, 0x84, 0x00]);
+    }
+
+    #[test]
+    fn long_extrinsics_are_skipped() {
+        let long = BLOCK_EXTRINSIC_MAX_LEN + 1;
+        let prefix = br#"{"result":{"block":{"extrinsics":["0x"#.iter();
+        let item = core::iter::repeat_n(b'a', 2 * long);
+        let suffix = br#"","0x00"]}}}"#.iter();
+        let (state, extrinsics) = scan(prefix.chain(suffix.clone()).copied());
+        assert_eq!(state, ScanState::Done);
+        assert_eq!(extrinsics.len(), 2);
+        assert!(extrinsics[0].is_empty());
+
+        let prefix = br#"{"result":{"block":{"extrinsics":["0x"#.iter().copied();
+        let (state, extrinsics) = scan(prefix.chain(item).chain(suffix.copied()));
+        assert_eq!(state, ScanState::Done);
+        assert_eq!(extrinsics.len(), 1);
+        assert_eq!(extrinsics[0][..], [0]);
+    }
+
+    #[test]
+    fn truncated_block() {
+        // Connection lost in the middle of launch extrinsic.
+        let cut = BLOCK.windows(6).position(|w| w == b"0xb502").unwrap() + 100;
+        let (state, extrinsics) = scan(BLOCK[..cut].iter().copied());
+        assert_ne!(state, ScanState::Done);
+        assert_eq!(extrinsics.len(), 1);
+        let (state, _) = scan(br#"{"jsonrpc":"2.0","result":null,"id":1}"#.iter().copied());
+        assert_eq!(state, ScanState::Key(0));
+    }
+
+    #[test]
+    fn extrinsic_updates() {
+        let mut quoted: String<68> = String::new();
+        write!(quoted, "\"{}\"", HASH).unwrap();
+        let (HexHash(hash), _) = serde_json_core::from_str(&quoted).unwrap();
+        let status = |result: &str| {
+            let mut message: String<256> = String::new();
+            write!(
+                message,
+                "{{\"jsonrpc\":\"2.0\",\"method\":\"author_extrinsicUpdate\",\
+                 \"params\":{{\"subscription\":\"fXbDeRRIGAqyqRFl\",\"result\":{}}}}}",
+                result
+            )
+            .unwrap();
+            parse_notification(message.as_bytes()).unwrap()
+        };
+        let subscribed = br#"{"jsonrpc":"2.0","result":"fXbDeRRIGAqyqRFl","id":1}"#;
+        assert_eq!(parse_notification(subscribed).unwrap(), None);
+        assert_eq!(status("\"future\""), Some(TxStatus::Future));
+        assert_eq!(status("\"ready\""), Some(TxStatus::Ready));
+        assert_eq!(
+            status(
+                "{\"broadcast\":[\"12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp\",\
+                 \"12D3KooWHdiAxVd8uMQR1hGWXccidmfCwLqcMpGwR6QcTP6QRMuD\"]}"
+            ),
+            Some(TxStatus::Broadcast)
+        );
+        let mut hashed: String<96> = String::new();
+        for (variant, expected) in [
+            ("inBlock", TxStatus::InBlock(hash)),
+            ("retracted", TxStatus::Retracted(hash)),
+            ("finalityTimeout", TxStatus::FinalityTimeout(hash)),
+            ("finalized", TxStatus::Finalized(hash)),
+            ("usurped", TxStatus::Usurped(hash)),
+        ] {
+            hashed.clear();
+            write!(hashed, "{{\"{}\":\"{}\"}}", variant, HASH).unwrap();
+            assert_eq!(status(&hashed), Some(expected));
+        }
+        assert_eq!(status("\"dropped\""), Some(TxStatus::Dropped));
+        assert_eq!(status("\"invalid\""), Some(TxStatus::Invalid));
+    }
+
+    #[test]
+    fn extrinsic_rejected() {
+        let message = br#"{"jsonrpc":"2.0","error":{"code":1010,"message":"Invalid Transaction","data":"Transaction has a bad signature"},"id":1}"#;
+        match parse_notification(message) {
+            Err(RpcError::Rpc { code, data }) => {
+                assert_eq!(code, INVALID_TRANSACTION);
+                assert_eq!(data, "Transaction has a bad signature");
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn responses() {
+        let nonce = br#"{"jsonrpc":"2.0","result":7,"id":1}"#;
+        assert_eq!(parse_response::<u32>(nonce).unwrap(), 7);
+
+        let version = br#"{"jsonrpc":"2.0","result":{"specName":"robonomics","implName":"robonomics-airalab","authoringVersion":1,"specVersion":41,"implVersion":1,"apis":[["0xdf6acb689907609b",5],["0x37e397fc7c91f5e4",2]],"transactionVersion":2,"stateVersion":1},"id":1}"#;
+        assert_eq!(
+            parse_response::<RuntimeVersion>(version).unwrap(),
+            RuntimeVersion {
+                spec_version: 41,
+                transaction_version: 2,
+            }
+        );
+
+        let header = br#"{"jsonrpc":"2.0","result":{"parentHash":"0x1f3c","number":"0x1b2","digest":{"logs":[]}},"id":1}"#;
+        let header: Option<Header> = parse_response(header).unwrap();
+        assert_eq!(header.map(|h| h.number.0), Some(0x1b2));
+    }
+
+    #[test]
+    fn null_result() {
+        let null = br#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+        // Unknown block is not an error for node.
+        assert!(parse_response::<Option<HexHash>>(null).unwrap().is_none());
+        assert!(parse_response::<Option<Header>>(null).unwrap().is_none());
+        // But it is for values always present.
+        assert!(matches!(
+            parse_response::<HexHash>(null),
+            Err(RpcError::InvalidResponse)
+        ));
+        assert!(matches!(
+            parse_response::<u32>(br#"{"jsonrpc":"2.0","id":1}"#),
+            Err(RpcError::InvalidResponse)
+        ));
+        assert!(matches!(
+            parse_response::<u32>(b"Internal Server Error"),
+            Err(RpcError::InvalidResponse)
+        ));
+    }
+}
@@ -0,0 +1,415 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Uploading measurements to sensor.community (Luftdaten) and Madavi.
+//!
+//! Requests are the same as airrohr firmware sends. Station is identified by
+//! `X-Sensor: esp32-<chip id>` header, see [`ChipId`], and should be registered
+//! on sensor.community with this ID first.
+//!
+//! sensor.community expects one request per sensor, type of sensor is given by
+//! `X-Pin` header and value names are not prefixed:
+//!
+//! ```text
+//! POST /v1/push-sensor-data/ HTTP/1.1
+//! X-Pin: 1
+//! X-Sensor: esp32-7a5b3c71bf10
+//!
+//! {"software_version":"ROHI-altruist-0.1.0","sensordatavalues":[
+//!   {"value_type":"P1","value":"12.3"},{"value_type":"P2","value":"7.1"}]}
+//! ```
+//!
+//! Madavi takes all values in a single request with prefixed names, e.g.
+//! `SDS_P1` or `BME280_temperature`, together with `esp8266id`.
+//!
+//! ```ignore
+//! let uploader = SensorCommunityUploader::new(client, Service::SensorCommunity, ChipId::read(), "ROHI-altruist-0.1.0");
+//! let sent = uploader.upload(&measurement).await?;
+//! ```
+
+use core::fmt::{self, Write};
+use edge_http::Method;
+use heapless::String;
+use log::{debug, warn};
+use rohi_hal::board::ChipId;
+use rohi_hal::sensor::Measurement;
+
+use crate::http::HttpClient;
+use crate::http::client::ClientError;
+use crate::http::json::Tenths;
+
+/// Push endpoint of sensor.community, HTTPS is not supported by [`HttpClient`].
+pub const SENSOR_COMMUNITY_URL: &str = "http://api.sensor.community/v1/push-sensor-data/";
+
+/// Push endpoint of Madavi.
+pub const MADAVI_URL: &str = "http://api-rrd.madavi.de/data.php";
+
+/// Maximal length of rendered payload.
+pub const PAYLOAD_MAX_LEN: usize = 512;
+
+/// Upload destination.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Service {
+    /// sensor.community map, one request per sensor.
+    SensorCommunity,
+    /// Madavi graphs, single request with all values.
+    Madavi,
+}
+
+impl Service {
+    /// Default endpoint URL.
+    pub fn url(self) -> &'static str {
+        match self {
+            Self::SensorCommunity => SENSOR_COMMUNITY_URL,
+            Self::Madavi => MADAVI_URL,
+        }
+    }
+}
+
+/// Sensor kinds known by sensor.community, readings of Altruist board are
+/// reported as these sensors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sensor {
+    /// Particulate matter, `P1` is PM10 and `P2` is PM2.5.
+    Sds011,
+    /// Temperature, humidity and pressure.
+    Bme280,
+    /// Noise level.
+    Dnms,
+}
+
+impl Sensor {
+    pub const ALL: [Sensor; 3] = [Self::Sds011, Self::Bme280, Self::Dnms];
+
+    /// Value of `X-Pin` header.
+    pub fn pin(self) -> &'static str {
+        match self {
+            Self::Sds011 => "1",
+            Self::Bme280 => "11",
+            Self::Dnms => "15",
+        }
+    }
+
+    /// Prefix of value names in Madavi payload.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Sds011 => "SDS_",
+            Self::Bme280 => "BME280_",
+            Self::Dnms => "DNMS_",
+        }
+    }
+
+    /// Names and values of sensor readings in tenths, `None` when absent.
+    fn values(self, m: &Measurement) -> [(&'static str, Option<i32>); 3] {
+        match self {
+            Self::Sds011 => [
+                ("P1", m.pm10.map(Into::into)),
+                ("P2", m.pm25.map(Into::into)),
+                ("", None),
+            ],
+            Self::Bme280 => [
+                ("temperature", m.temperature.map(Into::into)),
+                ("humidity", m.humidity.map(Into::into)),
+                ("pressure", m.pressure.map(|v| v.saturating_mul(10) as i32)),
+            ],
+            Self::Dnms => [
+                ("noise_LAeq", m.noise.map(Into::into)),
+                ("", None),
+                ("", None),
+            ],
+        }
+    }
+
+    /// Sensor has readings in measurement.
+    pub fn is_present(self, m: &Measurement) -> bool {
+        self.values(m).iter().any(|(_, value)| value.is_some())
+    }
+}
+
+/// Value of `X-Sensor` header, e.g. `esp32-7a5b3c71bf10`.
+pub fn sensor_id(chip_id: ChipId) -> String<24> {
+    let mut id = String::new();
+    _ = write!(id, "esp32-{}", chip_id);
+    id
+}
+
+/// Render sensor.community payload with readings of one `sensor`.
+pub fn write_sensor_payload<W: Write>(
+    w: &mut W,
+    software: &str,
+    sensor: Sensor,
+    m: &Measurement,
+) -> fmt::Result {
+    write!(
+        w,
+        r#"{{"software_version":"{}","sensordatavalues":["#,
+        software
+    )?;
+    write_values(w, sensor, "", m, &mut "")?;
+    w.write_str("]}")
+}
+
+/// Render Madavi payload with readings of all sensors.
+pub fn write_madavi_payload<W: Write>(
+    w: &mut W,
+    chip_id: ChipId,
+    software: &str,
+    m: &Measurement,
+) -> fmt::Result {
+    write!(
+        w,
+        r#"{{"esp8266id":"{}","software_version":"{}","sensordatavalues":["#,
+        chip_id, software
+    )?;
+    let mut separator = "";
+    for sensor in Sensor::ALL {
+        write_values(w, sensor, sensor.prefix(), m, &mut separator)?;
+    }
+    w.write_str("]}")
+}
+
+/// Render present values of sensor as `sensordatavalues` items.
+fn write_values<W: Write>(
+    w: &mut W,
+    sensor: Sensor,
+    prefix: &str,
+    m: &Measurement,
+    separator: &mut &str,
+) -> fmt::Result {
+    for (name, value) in sensor.values(m) {
+        if let Some(value) = value {
+            write!(
+                w,
+                r#"{}{{"value_type":"{}{}","value":"{}"}}"#,
+                separator,
+                prefix,
+                name,
+                Tenths(value)
+            )?;
+            *separator = ",";
+        }
+    }
+    Ok(())
+}
+
+/// Request headers, `X-Pin` is given for sensor.community only.
+fn headers<'h>(sensor_id: &'h str, pin: Option<&'h str>) -> heapless::Vec<(&'h str, &'h str), 3> {
+    let mut headers = heapless::Vec::new();
+    _ = headers.push(("Content-Type", "application/json"));
+    _ = headers.push(("X-Sensor", sensor_id));
+    if let Some(pin) = pin {
+        _ = headers.push(("X-Pin", pin));
+    }
+    headers
+}
+
+/// Uploader of measurements to sensor.community or Madavi.
+#[derive(Clone, Copy)]
+pub struct SensorCommunityUploader<'a> {
+    http: HttpClient,
+    service: Service,
+    url: &'a str,
+    chip_id: ChipId,
+    software: &'a str,
+}
+
+impl<'a> SensorCommunityUploader<'a> {
+    /// Uploader to default endpoint of `service`, `software` is firmware name and version.
+    pub fn new(http: HttpClient, service: Service, chip_id: ChipId, software: &'a str) -> Self {
+        Self {
+            http,
+            service,
+            url: service.url(),
+            chip_id,
+            software,
+        }
+    }
+
+    /// Other `http://` endpoint, e.g. local proxy.
+    pub fn with_url(mut self, url: &'a str) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// Post measurement, returns count of sent requests.
+    ///
+    /// Sensors without readings are skipped, upload stops on the first failure.
+    pub async fn upload(&self, m: &Measurement) -> Result<usize, ClientError> {
+        let sensor_id = sensor_id(self.chip_id);
+        let mut payload: String<PAYLOAD_MAX_LEN> = String::new();
+        match self.service {
+            Service::SensorCommunity => {
+                let mut sent = 0;
+                for sensor in Sensor::ALL.into_iter().filter(|s| s.is_present(m)) {
+                    payload.clear();
+                    write_sensor_payload(&mut payload, self.software, sensor, m)
+                        .map_err(|_| ClientError::BufferOverflow)?;
+                    self.post(&payload, &sensor_id, Some(sensor.pin())).await?;
+                    sent += 1;
+                }
+                Ok(sent)
+            }
+            Service::Madavi => {
+                write_madavi_payload(&mut payload, self.chip_id, self.software, m)
+                    .map_err(|_| ClientError::BufferOverflow)?;
+                self.post(&payload, &sensor_id, None).await?;
+                Ok(1)
+            }
+        }
+    }
+
+    async fn post(
+        &self,
+        payload: &str,
+        sensor_id: &str,
+        pin: Option<&str>,
+    ) -> Result<(), ClientError> {
+        let headers = headers(sensor_id, pin);
+        // sensor.community echoes stored values back.
+        let mut buf = [0u8; 1024];
+        let response = self
+            .http
+            .request(
+                Method::Post,
+                self.url,
+                &headers,
+                payload.as_bytes(),
+                &mut buf,
+            )
+            .await?;
+        if !response.is_success() {
+            warn!(
+                "[SensorCommunity] > {:?} rejected upload with status {}",
+                self.service, response.status
+            );
+            return Err(ClientError::Status(response.status));
+        }
+        debug!("[SensorCommunity] > Uploaded: {}", payload);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOFTWARE: &str = "ROHI-altruist-0.1.0";
+    const CHIP_ID: ChipId = ChipId([0x10, 0xbf, 0x71, 0x3c, 0x5b, 0x7a]);
+
+    fn measurement() -> Measurement {
+        Measurement {
+            pm10: Some(123),
+            pm25: Some(71),
+            temperature: Some(-35),
+            humidity: Some(605),
+            pressure: Some(101325),
+            noise: Some(452),
+            ..Measurement::new(0)
+        }
+    }
+
+    fn sensor_payload(sensor: Sensor, m: &Measurement) -> String<PAYLOAD_MAX_LEN> {
+        let mut payload = String::new();
+        write_sensor_payload(&mut payload, SOFTWARE, sensor, m).unwrap();
+        payload
+    }
+
+    #[test]
+    fn sensor_payloads() {
+        let m = measurement();
+        assert_eq!(
+            sensor_payload(Sensor::Sds011, &m),
+            r#"{"software_version":"ROHI-altruist-0.1.0","sensordatavalues":[{"value_type":"P1","value":"12.3"},{"value_type":"P2","value":"7.1"}]}"#
+        );
+        assert_eq!(
+            sensor_payload(Sensor::Bme280, &m),
+            r#"{"software_version":"ROHI-altruist-0.1.0","sensordatavalues":[{"value_type":"temperature","value":"-3.5"},{"value_type":"humidity","value":"60.5"},{"value_type":"pressure","value":"101325.0"}]}"#
+        );
+        assert_eq!(
+            sensor_payload(Sensor::Dnms, &m),
+            r#"{"software_version":"ROHI-altruist-0.1.0","sensordatavalues":[{"value_type":"noise_LAeq","value":"45.2"}]}"#
+        );
+    }
+
+    #[test]
+    fn absent_values_are_skipped() {
+        let m = Measurement {
+            pm25: Some(71),
+            ..Measurement::new(0)
+        };
+        assert!(Sensor::Sds011.is_present(&m));
+        assert!(!Sensor::Bme280.is_present(&m));
+        assert!(!Sensor::Dnms.is_present(&m));
+        assert_eq!(
+            sensor_payload(Sensor::Sds011, &m),
+            r#"{"software_version":"ROHI-altruist-0.1.0","sensordatavalues":[{"value_type":"P2","value":"7.1"}]}"#
+        );
+        let mut payload: String<PAYLOAD_MAX_LEN> = String::new();
+        write_madavi_payload(&mut payload, CHIP_ID, SOFTWARE, &Measurement::new(0)).unwrap();
+        assert_eq!(
+            payload,
+            r#"{"esp8266id":"7a5b3c71bf10","software_version":"ROHI-altruist-0.1.0","sensordatavalues":[]}"#
+        );
+    }
+
+    #[test]
+    fn madavi_payload() {
+        let mut payload: String<PAYLOAD_MAX_LEN> = String::new();
+        write_madavi_payload(&mut payload, CHIP_ID, SOFTWARE, &measurement()).unwrap();
+        assert_eq!(
+            payload,
+            concat!(
+                r#"{"esp8266id":"7a5b3c71bf10","software_version":"ROHI-altruist-0.1.0","sensordatavalues":["#,
+                r#"{"value_type":"SDS_P1","value":"12.3"},{"value_type":"SDS_P2","value":"7.1"},"#,
+                r#"{"value_type":"BME280_temperature","value":"-3.5"},{"value_type":"BME280_humidity","value":"60.5"},"#,
+                r#"{"value_type":"BME280_pressure","value":"101325.0"},{"value_type":"DNMS_noise_LAeq","value":"45.2"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn request_headers() {
+        let id = sensor_id(CHIP_ID);
+        assert_eq!(id, "esp32-7a5b3c71bf10");
+        let pins = Sensor::ALL.map(Sensor::pin);
+        assert_eq!(pins, ["1", "11", "15"]);
+        assert_eq!(
+            headers(&id, Some(Sensor::Bme280.pin()))[..],
+            [
+                ("Content-Type", "application/json"),
+                ("X-Sensor", "esp32-7a5b3c71bf10"),
+                ("X-Pin", "11"),
+            ]
+        );
+        // Madavi takes all sensors in one request.
+        assert_eq!(
+            headers(&id, None)[..],
+            [
+                ("Content-Type", "application/json"),
+                ("X-Sensor", "esp32-7a5b3c71bf10"),
+            ]
+        );
+        // Chip ID is not zero padded, as in airrohr.
+        assert_eq!(sensor_id(ChipId([1, 0, 0, 0, 2, 0])), "esp32-21");
+    }
+
+    #[test]
+    fn payload_overflow() {
+        let mut payload: String<64> = String::new();
+        assert!(write_madavi_payload(&mut payload, CHIP_ID, SOFTWARE, &measurement()).is_err());
+    }
+}
@@ -0,0 +1,270 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Submitting measurements to sensors.social.
+//!
+//! sensors.social gateway accepts airrohr firmware JSON: chip ID, software
+//! version and list of named values, extended with station coordinates and Unix
+//! time of measurement once device clock is synchronized.
+//!
+//! ```json
+//! {"esp8266id":"7a5b3c71bf10","software_version":"altruist-0.1.0","timestamp":1735689600,"sensordatavalues":[
+//!   {"value_type":"SDS_P1","value":"12.3"},{"value_type":"SDS_P2","value":"7.1"},
+//!   {"value_type":"GPS_lat","value":"59.934280"},{"value_type":"GPS_lon","value":"30.335099"}]}
+//! ```
+//!
+//! The same payload is published into datalog by [`Format::SensorsSocial`](crate::datalog::Format).
+//! Only plain HTTP is supported, gateways behind HTTPS need local proxy.
+//!
+//! ```ignore
+//! let station = Station::new(ChipId::read(), "altruist-0.1.0", config.location);
+//! SensorsSocialUploader::new(client, &config.upload.sensors_social, station)
+//!     .upload(&measurement, WALL_CLOCK.unix_time(measurement.timestamp))
+//!     .await?;
+//! ```
+
+use core::fmt::{self, Write};
+use edge_http::Method;
+use heapless::String;
+use log::{debug, warn};
+use rohi_hal::board::ChipId;
+use rohi_hal::config::Location;
+use rohi_hal::sensor::Measurement;
+
+use crate::http::HttpClient;
+use crate::http::client::ClientError;
+use crate::http::json::Tenths;
+
+/// Maximal length of rendered payload.
+/// Room for payload with every value and location, the longest one is 549 bytes.
+pub const PAYLOAD_MAX_LEN: usize = 640;
+
+/// Measuring station reported together with values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Station {
+    pub chip_id: ChipId,
+    /// Firmware name and version.
+    pub software: &'static str,
+    /// Coordinates, omitted from payload when not set.
+    pub location: Location,
+}
+
+impl Station {
+    pub fn new(chip_id: ChipId, software: &'static str, location: Location) -> Self {
+        Self {
+            chip_id,
+            software,
+            location,
+        }
+    }
+}
+
+/// Render measurement taken at given Unix time in seconds as sensors.social payload.
+///
+/// Particulate matter is reported in µg/m³, temperature in °C, humidity in %,
+/// pressure in Pa, noise in dBA and coordinates in degrees and meters.
+pub fn write_payload<W: Write>(
+    w: &mut W,
+    station: &Station,
+    m: &Measurement,
+    time: Option<u64>,
+) -> fmt::Result {
+    write!(
+        w,
+        r#"{{"esp8266id":"{}","software_version":"{}","#,
+        station.chip_id, station.software
+    )?;
+    if let Some(time) = time {
+        write!(w, r#""timestamp":{},"#, time)?;
+    }
+    w.write_str(r#""sensordatavalues":["#)?;
+    let mut separator = "";
+    let mut value = |w: &mut W, name: &str, value: &dyn fmt::Display| {
+        let result = write!(
+            w,
+            r#"{}{{"value_type":"{}","value":"{}"}}"#,
+            separator, name, value
+        );
+        separator = ",";
+        result
+    };
+    if let Some(pm10) = m.pm10 {
+        value(w, "SDS_P1", &Tenths(pm10.into()))?;
+    }
+    if let Some(pm25) = m.pm25 {
+        value(w, "SDS_P2", &Tenths(pm25.into()))?;
+    }
+    if let Some(temperature) = m.temperature {
+        value(w, "BME280_temperature", &Tenths(temperature.into()))?;
+    }
+    if let Some(humidity) = m.humidity {
+        value(w, "BME280_humidity", &Tenths(humidity.into()))?;
+    }
+    if let Some(pressure) = m.pressure {
+        value(w, "BME280_pressure", &pressure)?;
+    }
+    if let Some(noise) = m.noise {
+        value(w, "noise_LAeq", &Tenths(noise.into()))?;
+    }
+    let location = &station.location;
+    if location.is_set() {
+        value(w, "GPS_lat", &format_args!("{:.6}", location.latitude))?;
+        value(w, "GPS_lon", &format_args!("{:.6}", location.longitude))?;
+        value(w, "GPS_height", &format_args!("{:.1}", location.altitude))?;
+    }
+    w.write_str("]}")
+}
+
+/// Uploader of measurements to sensors.social gateway.
+#[derive(Clone, Copy)]
+pub struct SensorsSocialUploader<'a> {
+    http: HttpClient,
+    url: &'a str,
+    station: Station,
+}
+
+impl<'a> SensorsSocialUploader<'a> {
+    /// Uploader to `http://` gateway endpoint.
+    pub fn new(http: HttpClient, url: &'a str, station: Station) -> Self {
+        Self { http, url, station }
+    }
+
+    /// Post measurement taken at given Unix time in seconds, succeeds when gateway
+    /// accepted it.
+    pub async fn upload(&self, m: &Measurement, time: Option<u64>) -> Result<(), ClientError> {
+        let mut payload: String<PAYLOAD_MAX_LEN> = String::new();
+        write_payload(&mut payload, &self.station, m, time)
+            .map_err(|_| ClientError::BufferOverflow)?;
+
+        let headers = [("Content-Type", "application/json")];
+        let mut buf = [0u8; 256];
+        let response = self
+            .http
+            .request(
+                Method::Post,
+                self.url,
+                &headers,
+                payload.as_bytes(),
+                &mut buf,
+            )
+            .await?;
+        if !response.is_success() {
+            warn!(
+                "[SensorsSocial] > Upload failed with status {}",
+                response.status
+            );
+            return Err(ClientError::Status(response.status));
+        }
+        debug!("[SensorsSocial] > Uploaded: {}", payload);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOFTWARE: &str = "ROHI-altruist-0.1.0";
+    const CHIP_ID: ChipId = ChipId([0x10, 0xbf, 0x71, 0x3c, 0x5b, 0x7a]);
+    const LOCATION: Location = Location {
+        latitude: 55.751244,
+        longitude: -37.618423,
+        altitude: 156.5,
+    };
+
+    fn measurement() -> Measurement {
+        Measurement {
+            pm10: Some(123),
+            pm25: Some(71),
+            temperature: Some(-35),
+            humidity: Some(605),
+            pressure: Some(101325),
+            noise: Some(452),
+            ..Measurement::new(0)
+        }
+    }
+
+    fn payload(location: Location, m: &Measurement, time: Option<u64>) -> String<PAYLOAD_MAX_LEN> {
+        let station = Station::new(CHIP_ID, SOFTWARE, location);
+        let mut payload = String::new();
+        write_payload(&mut payload, &station, m, time).unwrap();
+        payload
+    }
+
+    #[test]
+    fn full_measurement() {
+        assert_eq!(
+            payload(LOCATION, &measurement(), Some(1_735_689_600)),
+            concat!(
+                r#"{"esp8266id":"7a5b3c71bf10","software_version":"ROHI-altruist-0.1.0","timestamp":1735689600,"sensordatavalues":["#,
+                r#"{"value_type":"SDS_P1","value":"12.3"},{"value_type":"SDS_P2","value":"7.1"},"#,
+                r#"{"value_type":"BME280_temperature","value":"-3.5"},{"value_type":"BME280_humidity","value":"60.5"},"#,
+                r#"{"value_type":"BME280_pressure","value":"101325"},{"value_type":"noise_LAeq","value":"45.2"},"#,
+                r#"{"value_type":"GPS_lat","value":"55.751244"},{"value_type":"GPS_lon","value":"-37.618423"},"#,
+                r#"{"value_type":"GPS_height","value":"156.5"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn absent_values_are_skipped() {
+        let m = Measurement {
+            pm25: Some(71),
+            humidity: Some(0),
+            ..Measurement::new(0)
+        };
+        assert_eq!(
+            payload(LOCATION, &m, Some(1_735_689_600)),
+            concat!(
+                r#"{"esp8266id":"7a5b3c71bf10","software_version":"ROHI-altruist-0.1.0","timestamp":1735689600,"sensordatavalues":["#,
+                r#"{"value_type":"SDS_P2","value":"7.1"},{"value_type":"BME280_humidity","value":"0.0"},"#,
+                r#"{"value_type":"GPS_lat","value":"55.751244"},{"value_type":"GPS_lon","value":"-37.618423"},"#,
+                r#"{"value_type":"GPS_height","value":"156.5"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn unset_location_and_time() {
+        // Location is unset, timestamp is unknown before clock is synced.
+        assert_eq!(
+            payload(Location::default(), &Measurement::new(0), None),
+            r#"{"esp8266id":"7a5b3c71bf10","software_version":"ROHI-altruist-0.1.0","sensordatavalues":[]}"#
+        );
+        // Location with zero latitude is set.
+        let equator = Location {
+            latitude: 0.0,
+            longitude: 10.0,
+            altitude: 0.0,
+        };
+        assert!(
+            payload(equator, &Measurement::new(0), None).ends_with(
+                r#"{"value_type":"GPS_lat","value":"0.000000"},{"value_type":"GPS_lon","value":"10.000000"},{"value_type":"GPS_height","value":"0.0"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn payload_overflow() {
+        let station = Station::new(CHIP_ID, SOFTWARE, LOCATION);
+        let mut payload: String<128> = String::new();
+        assert!(write_payload(&mut payload, &station, &measurement(), None).is_err());
+        // Full payload fits into upload buffer.
+        let mut payload: String<PAYLOAD_MAX_LEN> = String::new();
+        write_payload(&mut payload, &station, &measurement(), Some(u64::MAX)).unwrap();
+    }
+}
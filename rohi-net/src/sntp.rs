@@ -0,0 +1,190 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Wall clock synchronized over SNTP.
+//!
+//! Device has no battery backed clock, measurements are timestamped in
+//! milliseconds since boot. [`sntp_task`] queries time server periodically and
+//! [`WALL_CLOCK`] converts these timestamps into Unix time, so readings kept
+//! offline are reported with the time they were taken at.
+//!
+//! ```ignore
+//! spawner.spawn(sntp_task(stack)).ok();
+//! if let Some(time) = WALL_CLOCK.unix_time(measurement.timestamp) {
+//!     buffer.push(time, &measurement).await?;
+//! }
+//! ```
+
+use core::cell::Cell;
+use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use edge_nal::{AddrType, Dns as _, UdpBind, UdpReceive, UdpSend};
+use edge_nal_embassy::{Dns, Udp, UdpBuffers};
+use embassy_net::Stack;
+use embassy_sync::blocking_mutex::{Mutex, raw::CriticalSectionRawMutex};
+use embassy_time::{Duration, Instant, Timer, with_timeout};
+use log::{info, warn};
+
+/// Default time server.
+pub const NTP_SERVER: &str = "pool.ntp.org";
+
+const NTP_PORT: u16 = 123;
+
+/// Seconds between NTP era start in 1900 and Unix epoch.
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Length of SNTP packet without extensions.
+const PACKET_LEN: usize = 48;
+
+/// Period of clock synchronization, drift of crystal is a few seconds a day.
+const SYNC_PERIOD: Duration = Duration::from_secs(6 * 3600);
+
+/// Retry period while clock isn't synchronized.
+const RETRY_PERIOD: Duration = Duration::from_secs(60);
+
+/// Time server should respond within this timeout.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Clock synchronized by [`sntp_task`].
+pub static WALL_CLOCK: WallClock = WallClock::new();
+
+/// SNTP query errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SntpError {
+    /// Unable to resolve server name.
+    Dns,
+    /// Unable to send or receive packet.
+    Network,
+    /// Server didn't respond in time.
+    Timeout,
+    /// Response is malformed or server is not synchronized.
+    InvalidResponse,
+}
+
+/// Unix time of device boot, known once clock is synchronized.
+pub struct WallClock {
+    boot_ms: Mutex<CriticalSectionRawMutex, Cell<Option<u64>>>,
+}
+
+impl WallClock {
+    const fn new() -> Self {
+        Self {
+            boot_ms: Mutex::new(Cell::new(None)),
+        }
+    }
+
+    /// Synchronize clock, `unix_ms` is current Unix time in milliseconds.
+    pub fn set(&self, unix_ms: u64) {
+        let boot_ms = unix_ms.saturating_sub(Instant::now().as_millis());
+        self.boot_ms.lock(|cell| cell.set(Some(boot_ms)));
+    }
+
+    /// Clock is synchronized.
+    pub fn is_set(&self) -> bool {
+        self.boot_ms.lock(Cell::get).is_some()
+    }
+
+    /// Unix time in seconds of given milliseconds since boot, `None` until synchronized.
+    pub fn unix_time(&self, uptime_ms: u64) -> Option<u64> {
+        let boot_ms = self.boot_ms.lock(Cell::get)?;
+        Some((boot_ms + uptime_ms) / 1000)
+    }
+
+    /// Current Unix time in seconds, `None` until synchronized.
+    pub fn now(&self) -> Option<u64> {
+        self.unix_time(Instant::now().as_millis())
+    }
+}
+
+/// Query current Unix time in milliseconds from given server.
+pub async fn query(stack: Stack<'_>, server: &str) -> Result<u64, SntpError> {
+    let ip = match server.parse::<Ipv4Addr>() {
+        Ok(ip) => ip.into(),
+        Err(_) => Dns::new(stack)
+            .get_host_by_name(server, AddrType::IPv4)
+            .await
+            .map_err(|_| SntpError::Dns)?,
+    };
+    let buffers = UdpBuffers::<1, PACKET_LEN, PACKET_LEN, 1>::new();
+    let udp = Udp::new(stack, &buffers);
+    let mut socket = udp
+        .bind(SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0)))
+        .await
+        .map_err(|_| SntpError::Network)?;
+
+    let mut packet = [0u8; PACKET_LEN];
+    packet[0] = request_header();
+    let server = SocketAddr::new(ip, NTP_PORT);
+    socket
+        .send(server, &packet)
+        .await
+        .map_err(|_| SntpError::Network)?;
+    let (len, from) = with_timeout(RESPONSE_TIMEOUT, socket.receive(&mut packet))
+        .await
+        .map_err(|_| SntpError::Timeout)?
+        .map_err(|_| SntpError::Network)?;
+    if from != server {
+        return Err(SntpError::InvalidResponse);
+    }
+    parse_response(&packet[..len])
+}
+
+/// Leap indicator 0, version 4, client mode.
+const fn request_header() -> u8 {
+    (4 << 3) | 3
+}
+
+/// Unix time in milliseconds of server transmit timestamp.
+pub fn parse_response(packet: &[u8]) -> Result<u64, SntpError> {
+    let packet: &[u8; PACKET_LEN] = packet
+        .get(..PACKET_LEN)
+        .and_then(|p| p.try_into().ok())
+        .ok_or(SntpError::InvalidResponse)?;
+    let mode = packet[0] & 0x07;
+    let leap = packet[0] >> 6;
+    let stratum = packet[1];
+    // Server mode, clock is synchronized and isn't kiss-o'-death.
+    if mode != 4 || leap == 3 || stratum == 0 || stratum > 15 {
+        return Err(SntpError::InvalidResponse);
+    }
+    let seconds = u64::from(u32::from_be_bytes(packet[40..44].try_into().unwrap()));
+    let fraction = u64::from(u32::from_be_bytes(packet[44..48].try_into().unwrap()));
+    let seconds = seconds
+        .checked_sub(NTP_UNIX_OFFSET)
+        .ok_or(SntpError::InvalidResponse)?;
+    Ok(seconds * 1000 + ((fraction * 1000) >> 32))
+}
+
+/// Keep [`WALL_CLOCK`] synchronized with [`NTP_SERVER`].
+#[embassy_executor::task]
+pub async fn sntp_task(stack: Stack<'static>) {
+    loop {
+        stack.wait_config_up().await;
+        match query(stack, NTP_SERVER).await {
+            Ok(unix_ms) => {
+                if !WALL_CLOCK.is_set() {
+                    info!("[SNTP] > Clock synchronized, Unix time {}", unix_ms / 1000);
+                }
+                WALL_CLOCK.set(unix_ms);
+                Timer::after(SYNC_PERIOD).await;
+            }
+            Err(e) => {
+                warn!("[SNTP] > Unable to get time: {:?}", e);
+                Timer::after(RETRY_PERIOD).await;
+            }
+        }
+    }
+}
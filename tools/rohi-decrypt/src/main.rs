@@ -0,0 +1,301 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Owner side of `rohi_hal::encryption`.
+//!
+//! Owner key is ed25519 seed of Robonomics account, device is given by its
+//! ed25519 account. Sealed payload is `nonce (24) | tag (16) | ciphertext` of
+//! NaCl `crypto_box`, raw or hex encoded as stored in datalog.
+//!
+//! ```sh
+//! rohi-decrypt keygen owner.key
+//! rohi-decrypt decrypt owner.key 4Fg...device record.hex
+//! ```
+
+use std::{env, fs, process};
+
+use blake2::{Blake2b512, Digest};
+use crypto_box::aead::{Aead, generic_array::GenericArray};
+use crypto_box::{ChaChaBox, PublicKey, SalsaBox, SecretKey};
+use ed25519_dalek::{SigningKey, VerifyingKey};
+
+/// Robonomics network address prefix.
+const SS58_PREFIX: u8 = 32;
+const NONCE_LEN: usize = 24;
+
+const USAGE: &str = "\
+Usage:
+    rohi-decrypt keygen <key-file>
+    rohi-decrypt pubkey <key-file>
+    rohi-decrypt decrypt <key-file> <device> <file> [--cipher xchacha20|xsalsa20]
+    rohi-decrypt encrypt <key-file> <device> <file> [--cipher xchacha20|xsalsa20]
+
+Device is SS58 address or hex public key of ed25519 account.";
+
+#[derive(Clone, Copy)]
+enum Cipher {
+    XChaCha20Poly1305,
+    XSalsa20Poly1305,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["keygen", key] => keygen(key),
+        ["pubkey", key] => read_key(key).map(|key| print_public(&key.verifying_key())),
+        [
+            command @ ("decrypt" | "encrypt"),
+            key,
+            device,
+            file,
+            rest @ ..,
+        ] => {
+            let cipher = match rest {
+                [] | ["--cipher", "xchacha20"] => Ok(Cipher::XChaCha20Poly1305),
+                ["--cipher", "xsalsa20"] => Ok(Cipher::XSalsa20Poly1305),
+                _ => Err(USAGE.to_string()),
+            };
+            cipher.and_then(|cipher| match *command {
+                "decrypt" => decrypt(key, device, file, cipher),
+                _ => encrypt(key, device, file, cipher),
+            })
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
+
+/// Generate new owner key and print its account.
+fn keygen(path: &str) -> Result<(), String> {
+    if fs::exists(path).unwrap_or(true) {
+        return Err(format!("{path} already exists"));
+    }
+    let mut seed = [0u8; 32];
+    getrandom::fill(&mut seed).map_err(|e| format!("no entropy: {e}"))?;
+    let key = SigningKey::from_bytes(&seed);
+    fs::write(path, hex(&seed) + "\n").map_err(|e| format!("{path}: {e}"))?;
+    eprintln!("Secret key is saved to {path}, keep it private.");
+    eprintln!("Configure device owner as:");
+    print_public(&key.verifying_key());
+    Ok(())
+}
+
+/// Open payload sealed by device and write it to stdout.
+fn decrypt(key: &str, device: &str, file: &str, cipher: Cipher) -> Result<(), String> {
+    let sealed = read_payload(file)?;
+    let plain = open(&read_key(key)?, &device_key(device)?, &sealed, cipher)
+        .map_err(|e| format!("{file}: {e}"))?;
+    print!("{}", String::from_utf8_lossy(&plain));
+    Ok(())
+}
+
+/// Open `nonce | tag | ciphertext` sealed by device for owner key.
+fn open(
+    key: &SigningKey,
+    device: &PublicKey,
+    sealed: &[u8],
+    cipher: Cipher,
+) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN + 16 {
+        return Err("payload is too short".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = GenericArray::from_slice(nonce);
+    let secret = secret_key(key);
+    match cipher {
+        Cipher::XChaCha20Poly1305 => ChaChaBox::new(device, &secret).decrypt(nonce, ciphertext),
+        Cipher::XSalsa20Poly1305 => SalsaBox::new(device, &secret).decrypt(nonce, ciphertext),
+    }
+    .map_err(|_| "payload is corrupted or sealed for other keys".to_string())
+}
+
+/// Seal payload for device and print it hex encoded.
+fn encrypt(key: &str, device: &str, file: &str, cipher: Cipher) -> Result<(), String> {
+    let plain = fs::read(file).map_err(|e| format!("{file}: {e}"))?;
+    let (secret, public) = (secret_key(&read_key(key)?), device_key(device)?);
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::fill(&mut nonce).map_err(|e| format!("no entropy: {e}"))?;
+    let nonce = GenericArray::from(nonce);
+    let sealed = match cipher {
+        Cipher::XChaCha20Poly1305 => ChaChaBox::new(&public, &secret).encrypt(&nonce, &plain[..]),
+        Cipher::XSalsa20Poly1305 => SalsaBox::new(&public, &secret).encrypt(&nonce, &plain[..]),
+    }
+    .map_err(|_| "encryption failed".to_string())?;
+    println!("0x{}{}", hex(&nonce), hex(&sealed));
+    Ok(())
+}
+
+/// X25519 secret of ed25519 key, same as libsodium `crypto_sign_ed25519_sk_to_curve25519`.
+fn secret_key(key: &SigningKey) -> SecretKey {
+    SecretKey::from(key.to_scalar_bytes())
+}
+
+/// X25519 public key of ed25519 device account.
+fn device_key(device: &str) -> Result<PublicKey, String> {
+    let public = match from_hex(device) {
+        Some(public) => public,
+        None => from_ss58(device).ok_or(format!("{device}: malformed account"))?,
+    };
+    let public: [u8; 32] = public
+        .try_into()
+        .map_err(|_| format!("{device}: malformed account"))?;
+    let key = VerifyingKey::from_bytes(&public)
+        .map_err(|_| format!("{device}: not an ed25519 public key"))?;
+    Ok(PublicKey::from(key.to_montgomery().to_bytes()))
+}
+
+fn print_public(key: &VerifyingKey) {
+    println!("{}", to_ss58(key.as_bytes()));
+    println!("0x{}", hex(key.as_bytes()));
+}
+
+/// Payload file, raw bytes or `0x` prefixed hex text.
+fn read_payload(path: &str) -> Result<Vec<u8>, String> {
+    let content = fs::read(path).map_err(|e| format!("{path}: {e}"))?;
+    Ok(std::str::from_utf8(&content)
+        .ok()
+        .and_then(|text| from_hex(text.trim()))
+        .unwrap_or(content))
+}
+
+fn read_key(path: &str) -> Result<SigningKey, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let seed: [u8; 32] = from_hex(content.trim())
+        .and_then(|seed| seed.try_into().ok())
+        .ok_or(format!("{path}: malformed key"))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+fn ss58_checksum(data: &[u8]) -> [u8; 2] {
+    let hash = Blake2b512::new()
+        .chain_update(b"SS58PRE")
+        .chain_update(data)
+        .finalize();
+    [hash[0], hash[1]]
+}
+
+fn to_ss58(public: &[u8; 32]) -> String {
+    let mut data = vec![SS58_PREFIX];
+    data.extend_from_slice(public);
+    let checksum = ss58_checksum(&data);
+    data.extend_from_slice(&checksum);
+    bs58::encode(data).into_string()
+}
+
+fn from_ss58(address: &str) -> Option<Vec<u8>> {
+    let data = bs58::decode(address).into_vec().ok()?;
+    // One byte prefixes only, as used by Robonomics and Polkadot networks.
+    if data.len() != 35 || data[0] >= 64 {
+        return None;
+    }
+    let (body, checksum) = data.split_at(33);
+    (ss58_checksum(body) == checksum).then(|| body[1..].to_vec())
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| {
+            text.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test vector of README, produced by libsodium `crypto_box_easy`.
+    const OWNER: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+    const DEVICE_SEED: &str = "202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f";
+    const DEVICE: &str = "0x29acbae141bccaf0b22e1a94d34d0bc7361e526d0bfe12c89794bc9322966dd7";
+    const PAYLOAD: &str = r#"{"pm25":12.3,"temperature":21.5}"#;
+    const XCHACHA20: &str = "0x070707070707070707070707070707070707070707070707\
+                             b17ef4fc70af68ff1793dadd5e749a9105db20a98519417b\
+                             dfc0240e9c498cf2667a6e4a5e2f5edb2ad77a2abc6fe95d";
+    const XSALSA20: &str = "0x070707070707070707070707070707070707070707070707\
+                            fd7ebc185d5cd5fd66e9ee879d88006049336732ea67dee6\
+                            ce6f9c309678fa2deaf8c33c35c76e86f4148b29d1c75195";
+
+    fn key(seed: &str) -> SigningKey {
+        SigningKey::from_bytes(&from_hex(seed).unwrap().try_into().unwrap())
+    }
+
+    #[test]
+    fn device_account() {
+        assert_eq!(
+            format!("0x{}", hex(key(DEVICE_SEED).verifying_key().as_bytes())),
+            DEVICE
+        );
+        let address = to_ss58(key(DEVICE_SEED).verifying_key().as_bytes());
+        let public = device_key(&address).unwrap();
+        assert_eq!(public, device_key(DEVICE).unwrap());
+    }
+
+    #[test]
+    fn open_libsodium_vectors() {
+        let device = device_key(DEVICE).unwrap();
+        for (cipher, vector) in [
+            (Cipher::XChaCha20Poly1305, XCHACHA20),
+            (Cipher::XSalsa20Poly1305, XSALSA20),
+        ] {
+            let plain = open(&key(OWNER), &device, &from_hex(vector).unwrap(), cipher).unwrap();
+            assert_eq!(plain, PAYLOAD.as_bytes());
+        }
+    }
+
+    #[test]
+    fn corrupted_payload() {
+        let device = device_key(DEVICE).unwrap();
+        let sealed = from_hex(XCHACHA20).unwrap();
+        assert!(open(&key(OWNER), &device, &sealed, Cipher::XSalsa20Poly1305).is_err());
+        assert!(
+            open(
+                &key(DEVICE_SEED),
+                &device,
+                &sealed,
+                Cipher::XChaCha20Poly1305
+            )
+            .is_err()
+        );
+        assert!(
+            open(
+                &key(OWNER),
+                &device,
+                &sealed[..NONCE_LEN + 15],
+                Cipher::XChaCha20Poly1305
+            )
+            .is_err()
+        );
+        let mut sealed = sealed;
+        sealed[NONCE_LEN] ^= 1;
+        assert!(open(&key(OWNER), &device, &sealed, Cipher::XChaCha20Poly1305).is_err());
+    }
+}
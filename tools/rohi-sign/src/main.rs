@@ -0,0 +1,176 @@
+///////////////////////////////////////////////////////////////////////////////
+//
+//  Copyright 2025 Akagi Engineering <admin@akagi.dev>
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//      http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+//
+///////////////////////////////////////////////////////////////////////////////
+//! Firmware image signing tool.
+//!
+//! Produces detached signatures accepted by `rohi_hal::ota`: little endian
+//! security version followed by ed25519 signature of
+//! `"ROHI-OTA" | security version (u32 LE) | image size (u32 LE) | SHA-256(image)`.
+//!
+//! ```sh
+//! rohi-sign keygen ota.key
+//! rohi-sign sign ota.key firmware.bin --security-version 1
+//! ```
+
+use std::{env, fs, process};
+
+use ed25519_dalek::{Signer, SigningKey};
+use sha2::{Digest, Sha256};
+
+const SIGNATURE_MAGIC: &[u8; 8] = b"ROHI-OTA";
+
+const USAGE: &str = "\
+Usage:
+    rohi-sign keygen <key-file>
+    rohi-sign pubkey <key-file>
+    rohi-sign sign <key-file> <image> --security-version <N>";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["keygen", key] => keygen(key),
+        ["pubkey", key] => {
+            read_key(key).map(|key| println!("{}", hex(key.verifying_key().as_bytes())))
+        }
+        ["sign", _, _] => Err(format!(
+            "security version is required, it should not be lower than one of running firmware\n{USAGE}"
+        )),
+        ["sign", key, image, "--security-version", version] => match version.parse() {
+            Ok(version) => sign(key, image, version),
+            Err(_) => Err(format!("invalid security version: {version}")),
+        },
+        _ => Err(USAGE.to_string()),
+    };
+    if let Err(e) = result {
+        eprintln!("{e}");
+        process::exit(1);
+    }
+}
+
+/// Generate new signing key and print its public part.
+fn keygen(path: &str) -> Result<(), String> {
+    if fs::exists(path).unwrap_or(true) {
+        return Err(format!("{path} already exists"));
+    }
+    let mut seed = [0u8; 32];
+    getrandom::fill(&mut seed).map_err(|e| format!("no entropy: {e}"))?;
+    let key = SigningKey::from_bytes(&seed);
+    fs::write(path, hex(&seed) + "\n").map_err(|e| format!("{path}: {e}"))?;
+    eprintln!("Secret key is saved to {path}, keep it private.");
+    eprintln!("Build firmware with OTA_PUBLIC_KEY set to:");
+    println!("{}", hex(key.verifying_key().as_bytes()));
+    Ok(())
+}
+
+/// Sign image and write detached signature next to it.
+fn sign(key: &str, image: &str, security_version: u32) -> Result<(), String> {
+    let key = read_key(key)?;
+    let data = fs::read(image).map_err(|e| format!("{image}: {e}"))?;
+    let signature =
+        signature(&key, &data, security_version).ok_or_else(|| format!("{image} is too large"))?;
+    let path = format!("{image}.sig");
+    fs::write(&path, &signature).map_err(|e| format!("{path}: {e}"))?;
+    eprintln!("Signature is saved to {path}");
+    println!("{}", hex(&signature));
+    Ok(())
+}
+
+/// Detached signature of image, `None` when image size doesn't fit `u32`.
+fn signature(key: &SigningKey, data: &[u8], security_version: u32) -> Option<Vec<u8>> {
+    let size = u32::try_from(data.len()).ok()?;
+
+    let mut message = Vec::with_capacity(48);
+    message.extend_from_slice(SIGNATURE_MAGIC);
+    message.extend_from_slice(&security_version.to_le_bytes());
+    message.extend_from_slice(&size.to_le_bytes());
+    message.extend_from_slice(&Sha256::digest(data));
+
+    let mut signature = security_version.to_le_bytes().to_vec();
+    signature.extend_from_slice(&key.sign(&message).to_bytes());
+    Some(signature)
+}
+
+fn read_key(path: &str) -> Result<SigningKey, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let content = content.trim();
+    let seed: Vec<u8> = (0..content.len())
+        .step_by(2)
+        .map(|i| {
+            content
+                .get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+        })
+        .collect::<Option<_>>()
+        .ok_or(format!("{path}: malformed key"))?;
+    let seed: [u8; 32] = seed
+        .try_into()
+        .map_err(|_| format!("{path}: malformed key"))?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rohi_hal::ota::{ImageSignature, OtaError, SIGNATURE_LEN, UpdatePolicy};
+
+    /// Head of ESP application image.
+    const IMAGE: &[u8] = &[
+        0xe9, 0x05, 0x02, 0x2f, 0x4e, 0x1c, 0x38, 0x40, 0xee, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x20, 0x00, 0x00, 0x3c, 0x7c, 0x3a,
+        0x01, 0x00, 0x32, 0x54, 0xcd, 0xab,
+    ];
+
+    const SEED: [u8; 32] = [0x42; 32];
+
+    fn verify(data: &[u8], signature: &[u8], security_version: u32) -> Result<(), OtaError> {
+        let policy = UpdatePolicy {
+            public_key: SigningKey::from_bytes(&SEED).verifying_key().to_bytes(),
+            security_version,
+        };
+        let signature = ImageSignature::from_bytes(signature.try_into().unwrap());
+        let digest: [u8; 32] = Sha256::digest(data).into();
+        policy.verify(data.len() as u32, &digest, &signature)
+    }
+
+    #[test]
+    fn accepted_by_device() {
+        let key = SigningKey::from_bytes(&SEED);
+        let signature = signature(&key, IMAGE, 1).unwrap();
+        assert_eq!(signature.len(), SIGNATURE_LEN);
+        assert_eq!(signature[..4], [1, 0, 0, 0]);
+        assert_eq!(verify(IMAGE, &signature, 1), Ok(()));
+        assert_eq!(verify(IMAGE, &signature, 2), Err(OtaError::Downgrade));
+        assert_eq!(
+            verify(&IMAGE[1..], &signature, 1),
+            Err(OtaError::BadSignature)
+        );
+    }
+
+    #[test]
+    fn key_file() {
+        let path = env::temp_dir().join(format!("rohi-sign-{}.key", process::id()));
+        fs::write(&path, hex(&SEED) + "\n").unwrap();
+        let key = read_key(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(key.unwrap().to_bytes(), SEED);
+    }
+}